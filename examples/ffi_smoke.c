@@ -0,0 +1,66 @@
+/*
+ * ffi_smoke.c -- Exercises the C API in include/mazegen.h.
+ *
+ * Build the library with the "ffi" feature, then compile and link against it, e.g.,
+ *
+ *     cargo build --features ffi
+ *     cc -std=c99 -Wall -Werror -Iinclude examples/ffi_smoke.c \
+ *         -Ltarget/debug -lmazegen -o ffi_smoke
+ *     LD_LIBRARY_PATH=target/debug ./ffi_smoke
+ *
+ * The ffi module's tests do this automatically when a C compiler is available.  Exits
+ * with status 0 on success, and 1 with a message on the first failure.
+ */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "mazegen.h"
+
+#define CHECK(cond)                                                    \
+    do {                                                               \
+        if (!(cond)) {                                                 \
+            fprintf(stderr, "%s:%d: failed: %s\n", __FILE__, __LINE__, \
+                    #cond);                                            \
+            return 1;                                                  \
+        }                                                              \
+    } while (0)
+
+int main(void) {
+    static const uint8_t png_signature[] = {0x89, 'P', 'N', 'G'};
+    uint32_t rows = 0, cols = 0;
+    size_t len = 99;
+
+    MazeHandle *maze = mazegen_generate(5, 7, MAZEGEN_BACKTRACKER, 42);
+    CHECK(maze != NULL);
+
+    mazegen_dims(maze, &rows, &cols);
+    CHECK(rows == 5 && cols == 7);
+
+    /* A perfect maze: every cell links to at least one neighbor. */
+    for (uint32_t cell = 0; cell < rows * cols; cell++) {
+        CHECK(mazegen_is_linked(maze, cell, MAZEGEN_NORTH) ||
+              mazegen_is_linked(maze, cell, MAZEGEN_SOUTH) ||
+              mazegen_is_linked(maze, cell, MAZEGEN_EAST) ||
+              mazegen_is_linked(maze, cell, MAZEGEN_WEST));
+    }
+
+    const uint32_t *path = mazegen_solve(maze, 0, 34, &len);
+    CHECK(path != NULL && len >= 11);
+    CHECK(path[0] == 0 && path[len - 1] == 34);
+
+    const uint8_t *png = mazegen_render_png(maze, &len);
+    CHECK(png != NULL && len > sizeof(png_signature));
+    CHECK(memcmp(png, png_signature, sizeof(png_signature)) == 0);
+
+    /* Freed and NULL handles fail, and freeing them again does nothing. */
+    mazegen_free(maze);
+    mazegen_free(maze);
+    mazegen_free(NULL);
+    mazegen_dims(maze, &rows, &cols);
+    CHECK(rows == 0 && cols == 0);
+    CHECK(mazegen_solve(NULL, 0, 1, &len) == NULL && len == 0);
+    CHECK(mazegen_generate(0, 5, MAZEGEN_DIVISION, 1) == NULL);
+
+    return 0;
+}
@@ -0,0 +1,91 @@
+/*
+ * mazegen.h -- C API for the mazegen library.
+ *
+ * Build the library with the "ffi" feature, e.g.,
+ *
+ *     cargo build --release --features ffi
+ *
+ * and link against the dynamic library (libmazegen.so, libmazegen.dylib, or mazegen.dll)
+ * or the static library (libmazegen.a or mazegen.lib) in target/release.  See
+ * examples/ffi_smoke.c.  This header is maintained by hand to match src/ffi.rs.
+ *
+ * Ownership rules:
+ *
+ * - mazegen_generate returns a handle owned by the caller, who must release it with
+ *   mazegen_free.  Handles are IDs rather than addresses, and are never reused, so
+ *   freeing a NULL handle, or one that's already been freed, does nothing.
+ * - The buffers returned by mazegen_solve and mazegen_render_png are owned by the handle.
+ *   Each remains valid until the next call to the same function on the same handle, or
+ *   until the handle is freed; the caller must not free it.
+ * - Every function accepts a NULL or freed handle, and returns its failure value: NULL,
+ *   false, or zero dimensions.  No Rust panic unwinds into the caller; a function that
+ *   panics returns its failure value.
+ * - A handle mustn't be used by more than one thread at a time.
+ */
+
+#ifndef MAZEGEN_H
+#define MAZEGEN_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Algorithm IDs for mazegen_generate. */
+#define MAZEGEN_BACKTRACKER 0
+#define MAZEGEN_BINARY_TREE 1
+#define MAZEGEN_HUNT_AND_KILL 2
+#define MAZEGEN_SIDEWINDER 3
+#define MAZEGEN_DIVISION 4
+
+/* Directions for mazegen_is_linked. */
+#define MAZEGEN_NORTH 0
+#define MAZEGEN_SOUTH 1
+#define MAZEGEN_EAST 2
+#define MAZEGEN_WEST 3
+
+/* The largest number of cells mazegen_generate will create. */
+#define MAZEGEN_MAX_CELLS 1048576
+
+/* An opaque handle to a maze.  Cell IDs run from 0 to rows*cols - 1, row by row. */
+typedef struct MazeHandle MazeHandle;
+
+/*
+ * Generates a rows x cols maze with the given algorithm; the same seed always produces
+ * the same maze.  Returns NULL if either dimension is zero, there would be more than
+ * MAZEGEN_MAX_CELLS cells, or the algorithm ID is unknown.
+ */
+MazeHandle *mazegen_generate(uint32_t rows, uint32_t cols, uint32_t algorithm_id,
+                             uint64_t seed);
+
+/* Frees the handle and the buffers it owns; does nothing if it's NULL or already freed. */
+void mazegen_free(MazeHandle *handle);
+
+/* Stores the maze's dimensions in *rows and *cols; either pointer may be NULL. */
+void mazegen_dims(const MazeHandle *handle, uint32_t *rows, uint32_t *cols);
+
+/* Returns true if there's no wall between the cell and its neighbor in the direction. */
+bool mazegen_is_linked(const MazeHandle *handle, uint32_t cell, uint32_t dir);
+
+/*
+ * Returns the cells on the shortest path from start to goal, storing the number of cells
+ * in *out_len.  Returns NULL, with a length of zero, if a cell is invalid or there's no
+ * path.
+ */
+const uint32_t *mazegen_solve(MazeHandle *handle, uint32_t start, uint32_t goal,
+                              size_t *out_len);
+
+/*
+ * Returns the maze rendered with the default settings and encoded as a PNG, storing the
+ * number of bytes in *out_len.  Returns NULL, with a length of zero, on failure.
+ */
+const uint8_t *mazegen_render_png(MazeHandle *handle, size_t *out_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MAZEGEN_H */
@@ -0,0 +1,265 @@
+//! The maze generation algorithms, as values that can be named and selected at run time.
+
+use crate::grid::Grid;
+use rand::{thread_rng, Rng};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use std::time::Instant;
+
+/// A maze generation algorithm for `Grid`.  Each algorithm has a short name, used by the
+/// Molt `maze generate` command, and can be parsed from it.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let algo: Algorithm = "sidewinder".parse().unwrap();
+/// assert_eq!(algo, Algorithm::Sidewinder);
+///
+/// let mut grid = Grid::new(5, 5);
+/// algo.generate(&mut grid);
+/// assert!(grid.is_perfect_maze());
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Algorithm {
+    /// `recursive_backtracker`
+    Backtracker,
+
+    /// `binary_tree_maze`
+    BinaryTree,
+
+    /// `recursive_division`
+    Division,
+
+    /// `hunt_and_kill`
+    HuntAndKill,
+
+    /// `sidewinder_maze`
+    Sidewinder,
+}
+
+impl Algorithm {
+    /// All of the algorithms, in order by name.
+    pub const ALL: [Algorithm; 5] = [
+        Algorithm::Backtracker,
+        Algorithm::BinaryTree,
+        Algorithm::Division,
+        Algorithm::HuntAndKill,
+        Algorithm::Sidewinder,
+    ];
+
+    /// Gets the algorithm's short name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Backtracker => "backtracker",
+            Algorithm::BinaryTree => "bintree",
+            Algorithm::Division => "division",
+            Algorithm::HuntAndKill => "huntandkill",
+            Algorithm::Sidewinder => "sidewinder",
+        }
+    }
+
+    /// Carves a maze into the grid using this algorithm.  Any existing links are cleared
+    /// first.
+    pub fn generate(&self, grid: &mut Grid) {
+        self.generate_rng(grid, &mut thread_rng());
+    }
+
+    /// Carves a maze into the grid using this algorithm and the given random number
+    /// generator.  See `generate`.
+    pub fn generate_rng(&self, grid: &mut Grid, rng: &mut impl Rng) {
+        match self {
+            Algorithm::Backtracker => crate::recursive_backtracker_rng(grid, rng),
+            Algorithm::BinaryTree => crate::binary_tree_maze_rng(grid, rng),
+            Algorithm::Division => crate::recursive_division_rng(grid, rng),
+            Algorithm::HuntAndKill => crate::hunt_and_kill_rng(grid, rng),
+            Algorithm::Sidewinder => crate::sidewinder_maze_rng(grid, rng),
+        }
+    }
+}
+
+/// Carves a maze into the grid using the named algorithm and the given random number
+/// generator, and returns the wall-clock time it took.  Returns an error if there's no
+/// algorithm with that name; see `Algorithm`.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(20, 20);
+/// let elapsed = generate_timed(&mut grid, "huntandkill", &mut rand::thread_rng()).unwrap();
+/// println!("hunt-and-kill took {:?}", elapsed);
+/// assert!(grid.is_perfect_maze());
+///
+/// assert!(generate_timed(&mut grid, "nonesuch", &mut rand::thread_rng()).is_err());
+/// ```
+pub fn generate_timed(
+    grid: &mut Grid,
+    algorithm: &str,
+    rng: &mut impl Rng,
+) -> Result<Duration, String> {
+    let algo: Algorithm = algorithm.parse()?;
+
+    let start = Instant::now();
+    algo.generate_rng(grid, rng);
+    Ok(start.elapsed())
+}
+
+/// Carves a maze into the grid by calling the given algorithm, and returns the order in
+/// which the algorithm reached each cell: for each cell, the number of cells that were
+/// linked for the first time before it, or None if the cell was never linked.  Thus the
+/// first cell linked has order 0, and if every cell is linked the last has order
+/// `num_cells - 1`.  Shading a rendering by the order shows how the algorithm works.
+///
+/// The order comes from the links the algorithm makes; for Recursive Division, which
+/// links every cell before adding walls, it's simply the order of the cells.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(10, 10);
+/// let order = generate_with_order(&mut grid, recursive_backtracker);
+/// assert!(grid.is_perfect_maze());
+/// assert_eq!(order.iter().max(), Some(&Some(99)));
+///
+/// let image = ImageGridRenderer::new().render_with(&grid, |c| order[c].map(|o| o as i64));
+/// ```
+pub fn generate_with_order<F>(grid: &mut Grid, algo: F) -> Vec<Option<usize>>
+where
+    F: FnOnce(&mut Grid),
+{
+    grid.record_links();
+    algo(grid);
+
+    let mut order = vec![None; grid.num_cells()];
+    let mut next = 0;
+
+    for (cell1, cell2) in grid.take_link_log() {
+        for &cell in &[cell1, cell2] {
+            if order[cell].is_none() {
+                order[cell] = Some(next);
+                next += 1;
+            }
+        }
+    }
+
+    order
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if let Some(algo) = Algorithm::ALL.iter().find(|a| a.name() == name) {
+            return Ok(*algo);
+        }
+
+        let names: Vec<&str> = Algorithm::ALL.iter().map(|a| a.name()).collect();
+        Err(format!(
+            "expected algorithm ({}), got \"{}\"",
+            names.join(", "),
+            name
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_algorithm_names() {
+        for algo in &Algorithm::ALL {
+            assert_eq!(algo.name().parse::<Algorithm>(), Ok(*algo));
+            assert_eq!(algo.to_string(), algo.name());
+        }
+
+        let expected = "expected algorithm (backtracker, bintree, division, huntandkill, \
+                        sidewinder), got \"nonesuch\"";
+        assert_eq!("nonesuch".parse::<Algorithm>(), Err(expected.into()));
+    }
+
+    #[test]
+    fn test_algorithm_generate() {
+        for algo in &Algorithm::ALL {
+            let mut grid = Grid::new(6, 7);
+            grid.link(0, 1);
+            algo.generate_rng(&mut grid, &mut StdRng::seed_from_u64(2034));
+            assert!(grid.is_perfect_maze());
+
+            // It's the same as calling the algorithm directly.
+            let mut grid2 = Grid::new(6, 7);
+            let mut rng = StdRng::seed_from_u64(2034);
+            match algo {
+                Algorithm::Backtracker => crate::recursive_backtracker_rng(&mut grid2, &mut rng),
+                Algorithm::BinaryTree => crate::binary_tree_maze_rng(&mut grid2, &mut rng),
+                Algorithm::Division => crate::recursive_division_rng(&mut grid2, &mut rng),
+                Algorithm::HuntAndKill => crate::hunt_and_kill_rng(&mut grid2, &mut rng),
+                Algorithm::Sidewinder => crate::sidewinder_maze_rng(&mut grid2, &mut rng),
+            }
+            assert_eq!(grid, grid2);
+        }
+    }
+
+    #[test]
+    fn test_generate_timed() {
+        let mut grid = Grid::new(100, 100);
+        let mut rng = StdRng::seed_from_u64(2036);
+        let elapsed = generate_timed(&mut grid, "backtracker", &mut rng).unwrap();
+        assert!(elapsed > Duration::from_secs(0));
+        assert!(grid.is_perfect_maze());
+
+        let expected = "expected algorithm (backtracker, bintree, division, huntandkill, \
+                        sidewinder), got \"nonesuch\"";
+        assert_eq!(
+            generate_timed(&mut grid, "nonesuch", &mut rng),
+            Err(expected.into())
+        );
+    }
+
+    #[test]
+    fn test_generate_with_order() {
+        let grid = Grid::new(12, 16);
+        let mut images = Vec::new();
+
+        for algo in &[Algorithm::Backtracker, Algorithm::Sidewinder] {
+            let mut grid = grid.clone();
+            let mut rng = StdRng::seed_from_u64(2046);
+            let order = generate_with_order(&mut grid, |g| algo.generate_rng(g, &mut rng));
+
+            // Every cell has a distinct order, from 0 to cells-1.
+            let mut sorted: Vec<usize> = order.iter().map(|o| o.unwrap()).collect();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..grid.num_cells()).collect::<Vec<_>>());
+
+            // The first link made is from the cell with order 0.
+            let first = order.iter().position(|o| *o == Some(0)).unwrap();
+            let second = order.iter().position(|o| *o == Some(1)).unwrap();
+            assert!(grid.is_linked(first, second));
+
+            // The same seed gives the same maze as without the order.
+            let mut plain = Grid::new(12, 16);
+            algo.generate_rng(&mut plain, &mut StdRng::seed_from_u64(2046));
+            assert_eq!(grid, plain);
+
+            images.push(
+                crate::ImageGridRenderer::new().render_with(&grid, |c| order[c].map(|o| o as i64)),
+            );
+        }
+
+        assert_ne!(images[0], images[1]);
+
+        // Cells that are never linked have no order.
+        let mut grid = Grid::new(2, 2);
+        let order = generate_with_order(&mut grid, |g| g.link(0, 1));
+        assert_eq!(order, vec![Some(0), Some(1), None, None]);
+        assert!(grid.take_link_log().is_empty());
+    }
+}
@@ -0,0 +1,334 @@
+//! Maze banners: text rendered as mazes, one maze per character, for posters and other
+//! decorations.
+//!
+//! The characters are drawn in a built-in 5x7 bitmap font; each pixel of a glyph becomes a
+//! 3x3 block of maze cells, so that each character is a 15x21 grid masked to the shape of
+//! its glyph.
+
+use crate::sample_rng;
+use crate::Cell;
+use crate::Grid;
+use crate::ImageGridRenderer;
+use crate::Mask;
+use crate::MazeGrid;
+use image::imageops;
+use image::ImageBuffer;
+use image::RgbaImage;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// The number of rows of pixels in a glyph.
+const GLYPH_ROWS: usize = 7;
+
+/// The number of columns of pixels in a glyph.
+const GLYPH_COLS: usize = 5;
+
+/// The number of maze cells on a side of each glyph pixel.  The gap between characters is
+/// the width of one glyph pixel.
+const GLYPH_SCALE: usize = 3;
+
+/// The font: the glyph for each supported character, one string per row, with "#" for the
+/// pixels that are set.
+#[rustfmt::skip]
+const FONT: [(char, [&str; GLYPH_ROWS]); 43] = [
+    ('A', [".###.", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+    ('B', ["####.", "#...#", "#...#", "####.", "#...#", "#...#", "####."]),
+    ('C', [".###.", "#...#", "#....", "#....", "#....", "#...#", ".###."]),
+    ('D', ["####.", "#...#", "#...#", "#...#", "#...#", "#...#", "####."]),
+    ('E', ["#####", "#....", "#....", "####.", "#....", "#....", "#####"]),
+    ('F', ["#####", "#....", "#....", "####.", "#....", "#....", "#...."]),
+    ('G', [".###.", "#...#", "#....", "#.###", "#...#", "#...#", ".####"]),
+    ('H', ["#...#", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+    ('I', [".###.", "..#..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('J', ["..###", "...#.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('K', ["#...#", "#..#.", "#.#..", "##...", "#.#..", "#..#.", "#...#"]),
+    ('L', ["#....", "#....", "#....", "#....", "#....", "#....", "#####"]),
+    ('M', ["#...#", "##.##", "#.#.#", "#.#.#", "#...#", "#...#", "#...#"]),
+    ('N', ["#...#", "#...#", "##..#", "#.#.#", "#..##", "#...#", "#...#"]),
+    ('O', [".###.", "#...#", "#...#", "#...#", "#...#", "#...#", ".###."]),
+    ('P', ["####.", "#...#", "#...#", "####.", "#....", "#....", "#...."]),
+    ('Q', [".###.", "#...#", "#...#", "#...#", "#.#.#", "#..#.", ".##.#"]),
+    ('R', ["####.", "#...#", "#...#", "####.", "#.#..", "#..#.", "#...#"]),
+    ('S', [".####", "#....", "#....", ".###.", "....#", "....#", "####."]),
+    ('T', ["#####", "..#..", "..#..", "..#..", "..#..", "..#..", "..#.."]),
+    ('U', ["#...#", "#...#", "#...#", "#...#", "#...#", "#...#", ".###."]),
+    ('V', ["#...#", "#...#", "#...#", "#...#", "#...#", ".#.#.", "..#.."]),
+    ('W', ["#...#", "#...#", "#...#", "#.#.#", "#.#.#", "#.#.#", ".#.#."]),
+    ('X', ["#...#", "#...#", ".#.#.", "..#..", ".#.#.", "#...#", "#...#"]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#..", "..#.."]),
+    ('Z', ["#####", "....#", "...#.", "..#..", ".#...", "#....", "#####"]),
+    ('0', [".###.", "#...#", "#..##", "#.#.#", "##..#", "#...#", ".###."]),
+    ('1', ["..#..", ".##..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('2', [".###.", "#...#", "....#", "...#.", "..#..", ".#...", "#####"]),
+    ('3', ["####.", "....#", "....#", ".###.", "....#", "....#", "####."]),
+    ('4', ["...#.", "..##.", ".#.#.", "#..#.", "#####", "...#.", "...#."]),
+    ('5', ["#####", "#....", "####.", "....#", "....#", "#...#", ".###."]),
+    ('6', ["..##.", ".#...", "#....", "####.", "#...#", "#...#", ".###."]),
+    ('7', ["#####", "....#", "...#.", "..#..", ".#...", ".#...", ".#..."]),
+    ('8', [".###.", "#...#", "#...#", ".###.", "#...#", "#...#", ".###."]),
+    ('9', [".###.", "#...#", "#...#", ".####", "....#", "...#.", ".##.."]),
+    (' ', [".....", ".....", ".....", ".....", ".....", ".....", "....."]),
+    ('!', ["..#..", "..#..", "..#..", "..#..", "..#..", ".....", "..#.."]),
+    ('?', [".###.", "#...#", "....#", "...#.", "..#..", ".....", "..#.."]),
+    ('.', [".....", ".....", ".....", ".....", ".....", ".....", "..#.."]),
+    (',', [".....", ".....", ".....", ".....", ".....", "..#..", ".#..."]),
+    ('-', [".....", ".....", ".....", ".###.", ".....", ".....", "....."]),
+    (':', [".....", "..#..", ".....", ".....", ".....", "..#..", "....."]),
+];
+
+/// Renders the text as a banner in which each character is a maze in the shape of the
+/// character's glyph, drawn in channel mode (see `ImageGridRenderer::channel_mode`) with
+/// cells `cell_px` pixels on a side.  The characters are laid out side by side on a field
+/// of wall, with a gap between them.
+///
+/// The font has the letters, which are case-insensitive, the digits, space, and the
+/// punctuation `! ? . , - :`.  Each character is 15 cells wide and 21 cells high, and the
+/// gap is 3 cells wide, so a banner of n characters is `cell_px * (18 * n - 3)` pixels
+/// wide and `21 * cell_px` pixels high.  Each connected piece of a glyph is a perfect
+/// maze of its own; a glyph whose pixels touch only at their corners, like "X", is in
+/// several pieces.
+///
+/// Returns an error if the text is empty or has characters the font doesn't support,
+/// listing them, or if `cell_px` is zero.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let image = maze_text_banner("Hi!", 4, &mut rand::thread_rng()).unwrap();
+/// assert_eq!(image.dimensions(), (4 * (18 * 3 - 3), 4 * 21));
+///
+/// assert!(maze_text_banner("Hi~", 4, &mut rand::thread_rng()).is_err());
+/// ```
+pub fn maze_text_banner(
+    text: &str,
+    cell_px: usize,
+    rng: &mut impl Rng,
+) -> Result<RgbaImage, String> {
+    // FIRST, check the arguments, and look up the glyphs.
+    if cell_px == 0 {
+        return Err("expected a cell size of at least 1 pixel".to_string());
+    }
+
+    if text.is_empty() {
+        return Err("expected at least one character of banner text".to_string());
+    }
+
+    let mut glyphs = Vec::new();
+    let mut unsupported: Vec<char> = Vec::new();
+
+    for ch in text.chars() {
+        match glyph(ch) {
+            Some(rows) => glyphs.push((ch, rows)),
+            None if !unsupported.contains(&ch) => unsupported.push(ch),
+            None => (),
+        }
+    }
+
+    if !unsupported.is_empty() {
+        let list: Vec<String> = unsupported.iter().map(|ch| format!("{:?}", ch)).collect();
+        return Err(format!(
+            "unsupported characters in banner text: {}",
+            list.join(", ")
+        ));
+    }
+
+    // NEXT, size the banner, and fill it with wall.
+    let glyph_width = (GLYPH_COLS * GLYPH_SCALE * cell_px) as u32;
+    let gap = (GLYPH_SCALE * cell_px) as u32;
+    let num_glyphs = glyphs.len() as u32;
+    let width = num_glyphs * glyph_width + (num_glyphs - 1) * gap;
+    let height = (GLYPH_ROWS * GLYPH_SCALE * cell_px) as u32;
+
+    let wall = ImageGridRenderer::DEFAULT_WALL_COLOR;
+    let mut image: RgbaImage = ImageBuffer::from_pixel(width, height, wall);
+
+    // FINALLY, carve and render each glyph's maze, and put it in its place.  The cells
+    // outside the glyph are drawn as solid wall.
+    let mut renderer = ImageGridRenderer::new();
+    renderer.cell_size(cell_px).channel_mode(0.5);
+
+    for (k, (ch, rows)) in glyphs.iter().enumerate() {
+        let mut grid = Grid::from_mask(&glyph_mask(rows));
+        carve_pieces(&mut grid, rng);
+
+        if !is_perfect_by_piece(&grid) {
+            return Err(format!("failed to carve a maze in the shape of {:?}", ch));
+        }
+
+        let tile = renderer.render_colored(&grid, |cell| {
+            if grid.is_isolated(cell) {
+                Some(wall)
+            } else {
+                None
+            }
+        });
+        imageops::replace(&mut image, &tile, k as u32 * (glyph_width + gap), 0);
+    }
+
+    Ok(image)
+}
+
+/// Gets the glyph for the character, if the font has one.
+fn glyph(ch: char) -> Option<&'static [&'static str; GLYPH_ROWS]> {
+    let ch = ch.to_ascii_uppercase();
+    FONT.iter().find(|(c, _)| *c == ch).map(|(_, rows)| rows)
+}
+
+/// Makes the mask for a glyph, scaling each pixel to a block of cells.
+fn glyph_mask(rows: &[&str; GLYPH_ROWS]) -> Mask {
+    let mut mask = Mask::new(GLYPH_ROWS * GLYPH_SCALE, GLYPH_COLS * GLYPH_SCALE);
+
+    for (gi, row) in rows.iter().enumerate() {
+        for (gj, pixel) in row.chars().enumerate() {
+            for i in gi * GLYPH_SCALE..(gi + 1) * GLYPH_SCALE {
+                for j in gj * GLYPH_SCALE..(gj + 1) * GLYPH_SCALE {
+                    mask.set((i, j), pixel == '#');
+                }
+            }
+        }
+    }
+
+    mask
+}
+
+/// Carves a Recursive Backtracker maze in each connected piece of a masked grid, starting
+/// each from a random cell.
+fn carve_pieces(grid: &mut Grid, rng: &mut impl Rng) {
+    let mut starts: Vec<Cell> = (0..grid.num_cells())
+        .filter(|c| !grid.is_isolated(*c))
+        .collect();
+    starts.shuffle(rng);
+
+    for start in starts {
+        // A start that's been visited is in a piece that's already carved.
+        if grid.is_visited(start) {
+            continue;
+        }
+
+        let mut stack = vec![start];
+
+        while let Some(&current) = stack.last() {
+            let neighbors = grid.unvisited_neighbors(current);
+
+            if let Some(neighbor) = sample_rng(&neighbors, rng) {
+                grid.link(current, neighbor);
+                stack.push(neighbor);
+            } else {
+                stack.pop();
+            }
+        }
+    }
+}
+
+/// Returns true if each connected piece of a masked grid is a perfect maze: the links
+/// connect the piece's cells without loops.
+fn is_perfect_by_piece(grid: &Grid) -> bool {
+    // FIRST, count the pieces: the regions of live cells that neighbor each other.
+    let mut seen = vec![false; grid.num_cells()];
+    let mut pieces = 0;
+
+    for start in 0..grid.num_cells() {
+        if seen[start] || grid.is_isolated(start) {
+            continue;
+        }
+
+        pieces += 1;
+        seen[start] = true;
+        let mut stack = vec![start];
+
+        while let Some(cell) = stack.pop() {
+            for n in grid.neighbors(cell) {
+                if !seen[n] {
+                    seen[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+    }
+
+    // NEXT, each piece is a perfect maze if it's a single linked component with one
+    // fewer link than cells.
+    let live = (0..grid.num_cells())
+        .filter(|c| !grid.is_isolated(*c))
+        .count();
+    let components = grid
+        .connected_components()
+        .iter()
+        .filter(|component| !grid.is_isolated(component[0]))
+        .count();
+
+    components == pieces && grid.num_links() + pieces == live
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_banner_size() {
+        let mut rng = StdRng::seed_from_u64(2050);
+
+        for (text, cell_px) in &[("A", 2), ("MAZE", 3), ("Hello, world!", 1)] {
+            let n = text.chars().count() as u32;
+            let cell = *cell_px as u32;
+            let image = maze_text_banner(text, *cell_px, &mut rng).unwrap();
+
+            let width = n * GLYPH_COLS as u32 * GLYPH_SCALE as u32 * cell
+                + (n - 1) * GLYPH_SCALE as u32 * cell;
+            assert_eq!(image.dimensions(), (width, 21 * cell));
+        }
+    }
+
+    #[test]
+    fn test_banner_glyphs() {
+        // Every glyph's pieces are carved as perfect mazes.
+        let mut rng = StdRng::seed_from_u64(2050);
+
+        for (_, rows) in FONT.iter() {
+            let mut grid = Grid::from_mask(&glyph_mask(rows));
+            carve_pieces(&mut grid, &mut rng);
+            assert!(is_perfect_by_piece(&grid));
+        }
+
+        // "X" is in several pieces, the largest two pixels tall; " " is in none.
+        let grid = Grid::from_mask(&glyph_mask(glyph('x').unwrap()));
+        assert_eq!(grid.largest_component().len(), 2 * 9);
+        let grid = Grid::from_mask(&glyph_mask(glyph(' ').unwrap()));
+        assert!(is_perfect_by_piece(&grid));
+    }
+
+    #[test]
+    fn test_banner_halves_differ() {
+        let image = maze_text_banner("OO", 2, &mut StdRng::seed_from_u64(7)).unwrap();
+        let glyph_width = 30;
+        let offset = glyph_width + 6;
+
+        let left: Vec<_> = (0..glyph_width)
+            .flat_map(|x| (0..42).map(move |y| (x, y)))
+            .map(|(x, y)| *image.get_pixel(x, y))
+            .collect();
+        let right: Vec<_> = (0..glyph_width)
+            .flat_map(|x| (0..42).map(move |y| (x, y)))
+            .map(|(x, y)| *image.get_pixel(offset + x, y))
+            .collect();
+        assert_ne!(left, right);
+
+        // The gap is solid wall.
+        let wall = ImageGridRenderer::DEFAULT_WALL_COLOR;
+        assert!((0..42).all(|y| *image.get_pixel(glyph_width + 3, y) == wall));
+    }
+
+    #[test]
+    fn test_banner_errors() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        assert_eq!(
+            maze_text_banner("a~b#~", 4, &mut rng),
+            Err("unsupported characters in banner text: '~', '#'".to_string())
+        );
+        assert!(maze_text_banner("", 4, &mut rng).is_err());
+        assert!(maze_text_banner("A", 0, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,421 @@
+//! Maze bundles: a single file holding a grid, its metadata, and any number of named
+//! blobs, such as rendered images, so that a puzzle can be distributed as one artifact.
+//!
+//! A bundle is a sequence of length-prefixed entries.  All integers are little-endian.
+//!
+//! ```text
+//! magic      8 bytes      "MAZEBNDL"
+//! version    u32          1
+//! count      u32          the number of entries
+//! entries    count times:
+//!   name_len   u32
+//!   name       name_len bytes of UTF-8
+//!   data_len   u64
+//!   data       data_len bytes
+//! ```
+//!
+//! The grid is stored in the entry named "grid.json", as written by `Grid::to_json`, and
+//! the metadata in the entry named "meta.json", as a JSON object whose values are
+//! strings.  Every other entry is a blob.  Reading a bundle only reads its table of
+//! entries; each blob is read from the file when it's asked for.
+
+use crate::Grid;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// The magic number at the beginning of every bundle.
+const MAGIC: &[u8; 8] = b"MAZEBNDL";
+
+/// The version of the container format.
+const VERSION: u32 = 1;
+
+/// The name of the grid's entry.
+const GRID_ENTRY: &str = "grid.json";
+
+/// The name of the metadata's entry.
+const META_ENTRY: &str = "meta.json";
+
+/// The parts of a maze bundle: the grid, its metadata, and the named blobs, such as
+/// rendered images, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MazeBundle {
+    /// The maze.
+    pub grid: Grid,
+
+    /// The metadata, e.g., the algorithm, the seed, and the author.
+    pub meta: HashMap<String, String>,
+
+    /// The named blobs.  The names "grid.json" and "meta.json" are reserved.
+    pub blobs: Vec<(String, Vec<u8>)>,
+}
+
+/// Writes the bundle to the file at the given path, replacing it if it exists.  Returns
+/// an error if the file can't be written, or if a blob's name is reserved or repeated.
+///
+/// ```no_run
+/// use mazegen::*;
+/// use std::collections::HashMap;
+/// use std::path::Path;
+///
+/// let mut grid = Grid::new(10, 10);
+/// recursive_backtracker(&mut grid);
+///
+/// let mut png = Vec::new();
+/// image::DynamicImage::ImageRgba8(grid.to_image())
+///     .write_to(&mut png, image::ImageOutputFormat::Png)
+///     .unwrap();
+///
+/// let bundle = MazeBundle {
+///     grid,
+///     meta: HashMap::new(),
+///     blobs: vec![("maze.png".into(), png)],
+/// };
+/// write_bundle(Path::new("puzzle.mzb"), &bundle).unwrap();
+///
+/// let reader = read_bundle(Path::new("puzzle.mzb")).unwrap();
+/// assert_eq!(reader.grid().unwrap(), bundle.grid);
+/// assert_eq!(reader.blob("maze.png").unwrap(), bundle.blobs[0].1);
+/// ```
+pub fn write_bundle(path: &Path, bundle: &MazeBundle) -> io::Result<()> {
+    // FIRST, check the blob names.
+    let mut names = vec![GRID_ENTRY, META_ENTRY];
+
+    for (name, _) in &bundle.blobs {
+        if names.contains(&name.as_str()) {
+            return Err(invalid_input(format!(
+                "duplicate or reserved bundle entry name: \"{}\"",
+                name
+            )));
+        }
+        names.push(name);
+    }
+
+    // NEXT, serialize the grid and the metadata, the latter with its keys sorted.
+    let meta: BTreeMap<&String, &String> = bundle.meta.iter().collect();
+    let meta = serde_json::to_string(&meta).expect("serializable metadata");
+    let grid = bundle.grid.to_json();
+
+    // FINALLY, write the entries.
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+    out.write_all(&(names.len() as u32).to_le_bytes())?;
+
+    write_entry(&mut out, GRID_ENTRY, grid.as_bytes())?;
+    write_entry(&mut out, META_ENTRY, meta.as_bytes())?;
+
+    for (name, data) in &bundle.blobs {
+        write_entry(&mut out, name, data)?;
+    }
+
+    out.flush()
+}
+
+/// Opens the bundle at the given path, reading its table of entries.  Returns an error
+/// if the file can't be read, isn't a bundle, or is truncated.
+pub fn read_bundle(path: &Path) -> io::Result<BundleReader> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut input = BufReader::new(file);
+
+    // FIRST, check the header.
+    let mut magic = [0u8; 8];
+    read_exact(&mut input, &mut magic)?;
+
+    if &magic != MAGIC {
+        return Err(invalid_data("not a maze bundle".into()));
+    }
+
+    let version = read_u32(&mut input)?;
+
+    if version != VERSION {
+        return Err(invalid_data(format!(
+            "unsupported bundle version: {}",
+            version
+        )));
+    }
+
+    // NEXT, read the table of entries, skipping over the data.
+    let count = read_u32(&mut input)?;
+    let mut entries = Vec::new();
+    let mut offset = 16;
+
+    for _ in 0..count {
+        let name_len = read_u32(&mut input)? as u64;
+
+        if offset + 4 + name_len + 8 > file_len {
+            return Err(truncated());
+        }
+
+        let mut name = vec![0u8; name_len as usize];
+        read_exact(&mut input, &mut name)?;
+        let name = String::from_utf8(name)
+            .map_err(|_| invalid_data("bundle entry name isn't UTF-8".into()))?;
+
+        let len = read_u64(&mut input)?;
+        offset += 4 + name_len + 8;
+
+        if len > file_len - offset {
+            return Err(truncated());
+        }
+
+        input.seek(SeekFrom::Current(len as i64))?;
+        entries.push(BundleEntry { name, offset, len });
+        offset += len;
+    }
+
+    // FINALLY, make sure the grid and metadata are there.
+    let reader = BundleReader {
+        path: path.to_path_buf(),
+        entries,
+    };
+
+    for name in &[GRID_ENTRY, META_ENTRY] {
+        if reader.entry(name).is_none() {
+            return Err(invalid_data(format!("bundle has no \"{}\" entry", name)));
+        }
+    }
+
+    Ok(reader)
+}
+
+/// An open maze bundle, as returned by `read_bundle`.  The reader knows where each
+/// entry is in the file, and reads the entries only when they're asked for, so that
+/// large blobs needn't be loaded unless they're needed.
+#[derive(Debug, Clone)]
+pub struct BundleReader {
+    path: PathBuf,
+    entries: Vec<BundleEntry>,
+}
+
+/// The location of an entry in a bundle file.
+#[derive(Debug, Clone)]
+struct BundleEntry {
+    name: String,
+    offset: u64,
+    len: u64,
+}
+
+impl BundleReader {
+    /// Reads the grid.
+    pub fn grid(&self) -> io::Result<Grid> {
+        let json = self.read_text(GRID_ENTRY)?;
+        Grid::from_json(&json).map_err(invalid_data)
+    }
+
+    /// Reads the metadata.
+    pub fn meta(&self) -> io::Result<HashMap<String, String>> {
+        let json = self.read_text(META_ENTRY)?;
+        serde_json::from_str(&json).map_err(|e| invalid_data(e.to_string()))
+    }
+
+    /// Gets the names of the blobs, in order.
+    pub fn blob_names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|e| e.name.as_str())
+            .filter(|name| *name != GRID_ENTRY && *name != META_ENTRY)
+            .collect()
+    }
+
+    /// Gets the length of the named blob in bytes, if there is such a blob.
+    pub fn blob_len(&self, name: &str) -> Option<u64> {
+        self.blob_entry(name).map(|e| e.len)
+    }
+
+    /// Reads the named blob.  Returns an error of kind `NotFound` if there's no such blob.
+    pub fn blob(&self, name: &str) -> io::Result<Vec<u8>> {
+        match self.blob_entry(name) {
+            Some(entry) => self.read_entry(entry),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no such bundle entry: \"{}\"", name),
+            )),
+        }
+    }
+
+    /// Reads all of the parts of the bundle.
+    pub fn load(&self) -> io::Result<MazeBundle> {
+        let mut blobs = Vec::new();
+
+        for name in self.blob_names() {
+            blobs.push((name.to_string(), self.blob(name)?));
+        }
+
+        Ok(MazeBundle {
+            grid: self.grid()?,
+            meta: self.meta()?,
+            blobs,
+        })
+    }
+
+    /// Finds the named entry.
+    fn entry(&self, name: &str) -> Option<&BundleEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Finds the named entry, if it's a blob.
+    fn blob_entry(&self, name: &str) -> Option<&BundleEntry> {
+        if name == GRID_ENTRY || name == META_ENTRY {
+            None
+        } else {
+            self.entry(name)
+        }
+    }
+
+    /// Reads the named entry, which must exist, as UTF-8 text.
+    fn read_text(&self, name: &str) -> io::Result<String> {
+        let entry = self.entry(name).expect("required entry");
+        String::from_utf8(self.read_entry(entry)?)
+            .map_err(|_| invalid_data(format!("bundle entry \"{}\" isn't UTF-8", name)))
+    }
+
+    /// Reads the entry's data from the file.
+    fn read_entry(&self, entry: &BundleEntry) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+
+        let mut data = vec![0u8; entry.len as usize];
+        read_exact(&mut file, &mut data)?;
+        Ok(data)
+    }
+}
+
+/// Writes one entry: its name and data, each preceded by its length.
+fn write_entry(out: &mut impl Write, name: &str, data: &[u8]) -> io::Result<()> {
+    out.write_all(&(name.len() as u32).to_le_bytes())?;
+    out.write_all(name.as_bytes())?;
+    out.write_all(&(data.len() as u64).to_le_bytes())?;
+    out.write_all(data)
+}
+
+/// Reads a little-endian u32.
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    read_exact(input, &mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Reads a little-endian u64.
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    read_exact(input, &mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Fills the buffer, reporting running out of data as a truncated bundle.
+fn read_exact(input: &mut impl Read, buf: &mut [u8]) -> io::Result<()> {
+    input.read_exact(buf).map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            truncated()
+        } else {
+            e
+        }
+    })
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "truncated bundle")
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A bundle with a maze, some metadata, and two blobs, one empty.
+    fn test_bundle() -> MazeBundle {
+        let mut grid = Grid::new(5, 7);
+        crate::recursive_backtracker(&mut grid);
+
+        let mut meta = HashMap::new();
+        meta.insert("algorithm".to_string(), "backtracker".to_string());
+        meta.insert("title".to_string(), "Test \"Maze\"".to_string());
+
+        MazeBundle {
+            grid,
+            meta,
+            blobs: vec![
+                (
+                    "maze.png".to_string(),
+                    (0..=255).cycle().take(3000).collect(),
+                ),
+                ("empty".to_string(), Vec::new()),
+            ],
+        }
+    }
+
+    fn temp_path(tag: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mazegen-bundle-{}-{}.mzb", tag, std::process::id()))
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let path = temp_path("trip");
+        let bundle = test_bundle();
+        write_bundle(&path, &bundle).unwrap();
+
+        let reader = read_bundle(&path).unwrap();
+        assert_eq!(reader.blob_names(), vec!["maze.png", "empty"]);
+        assert_eq!(reader.blob_len("maze.png"), Some(3000));
+        assert_eq!(reader.blob("maze.png").unwrap(), bundle.blobs[0].1);
+        assert_eq!(reader.load().unwrap(), bundle);
+
+        let err = reader.blob("nonesuch").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(
+            reader.blob("grid.json").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bundle_truncated() {
+        let path = temp_path("trunc");
+        write_bundle(&path, &test_bundle()).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        for &len in &[0, 5, 12, 16, 20, 40, bytes.len() / 2, bytes.len() - 1] {
+            std::fs::write(&path, &bytes[..len]).unwrap();
+            let err = read_bundle(&path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "length {}", len);
+        }
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        std::fs::write(&path, &bad).unwrap();
+        assert_eq!(
+            read_bundle(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bundle_reserved_names() {
+        let path = temp_path("names");
+        let mut bundle = test_bundle();
+        bundle.blobs.push(("meta.json".to_string(), Vec::new()));
+        assert!(write_bundle(&path, &bundle).is_err());
+
+        bundle.blobs.pop();
+        bundle.blobs.push(("empty".to_string(), Vec::new()));
+        assert!(write_bundle(&path, &bundle).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,397 @@
+//! A path through a rectangular grid, as a sequence of cells.
+//!
+//! A `CellPath` knows the dimensions of the grid it was built for, so it can express
+//! itself as moves or waypoints without reference to the grid.  Paths are validated
+//! when they're constructed: each step must be to an adjacent cell, and, for paths
+//! built with `CellPath::new`, through a link.
+
+use crate::Cell;
+use crate::Grid;
+use crate::GridDirection;
+use crate::Topology;
+use molt::molt_err;
+use molt::types::*;
+use serde::{Deserialize, Serialize};
+
+/// A sequence of cells in a rectangular grid, each adjacent to the one before.  An
+/// empty path means there's no path at all.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CellPath {
+    cells: Vec<Cell>,
+    rows: usize,
+    cols: usize,
+    #[serde(default, skip_serializing_if = "Topology::is_planar")]
+    topology: Topology,
+}
+
+impl CellPath {
+    /// Creates a path through the grid from the given cells.  Each step must be
+    /// through a link, which may cross a wrapped edge of the grid.  On error, the
+    /// message includes the index of the offending cell.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(2, 2);
+    /// grid.link(0, 1);
+    /// assert!(CellPath::new(&grid, vec![0, 1]).is_ok());
+    /// assert!(CellPath::new(&grid, vec![0, 2]).is_err());
+    /// ```
+    pub fn new(grid: &Grid, cells: Vec<Cell>) -> Result<Self, String> {
+        let path = Self::checked(grid.num_rows(), grid.num_cols(), grid.topology(), cells)?;
+
+        for (i, step) in path.cells.windows(2).enumerate() {
+            if !grid.is_linked(step[0], step[1]) {
+                return Err(format!(
+                    "cells {} and {} are not linked, at index {}",
+                    step[0],
+                    step[1],
+                    i + 1
+                ));
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Creates a path through a grid with the given dimensions from the given cells.
+    /// Each step must be to an adjacent cell; links aren't checked.  On error, the
+    /// message includes the index of the offending cell.
+    pub fn adjacent(rows: usize, cols: usize, cells: Vec<Cell>) -> Result<Self, String> {
+        Self::checked(rows, cols, Topology::Planar, cells)
+    }
+
+    /// Creates a path through a grid with the given dimensions and topology, checking
+    /// that each step is to an adjacent cell.
+    fn checked(
+        rows: usize,
+        cols: usize,
+        topology: Topology,
+        cells: Vec<Cell>,
+    ) -> Result<Self, String> {
+        let path = Self {
+            cells,
+            rows,
+            cols,
+            topology,
+        };
+
+        for (i, &cell) in path.cells.iter().enumerate() {
+            if cell >= rows * cols {
+                return Err(format!("cell {} is out of range, at index {}", cell, i));
+            }
+
+            if i > 0 && path.direction(path.cells[i - 1], cell).is_none() {
+                return Err(format!(
+                    "cells {} and {} are not adjacent, at index {}",
+                    path.cells[i - 1],
+                    cell,
+                    i
+                ));
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Creates a path from cells already known to be valid, e.g., from a search.
+    pub(crate) fn from_valid(grid: &Grid, cells: Vec<Cell>) -> Self {
+        Self {
+            cells,
+            rows: grid.num_rows(),
+            cols: grid.num_cols(),
+            topology: grid.topology(),
+        }
+    }
+
+    /// Returns the number of cells in the path, which is one more than the number of
+    /// steps.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns true if the path is empty, i.e., there was no path.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns an iterator over the cells in the path, in order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Cell> {
+        self.cells.iter()
+    }
+
+    /// Returns the cells in the path as a slice.
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Returns the cells in the path as a vector, consuming the path.
+    pub fn into_cells(self) -> Vec<Cell> {
+        self.cells
+    }
+
+    /// Returns the first cell in the path, if any.
+    pub fn first(&self) -> Option<Cell> {
+        self.cells.first().copied()
+    }
+
+    /// Returns the last cell in the path, if any.
+    pub fn last(&self) -> Option<Cell> {
+        self.cells.last().copied()
+    }
+
+    /// Reverses the path in place.
+    pub fn reverse(&mut self) {
+        self.cells.reverse();
+    }
+
+    /// Returns true if the path includes the cell.
+    pub fn contains(&self, cell: Cell) -> bool {
+        self.cells.contains(&cell)
+    }
+
+    /// Returns the path as a sequence of moves, one per step.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let path = CellPath::adjacent(2, 2, vec![0, 1, 3]).unwrap();
+    /// assert_eq!(path.as_moves(), vec![GridDirection::East, GridDirection::South]);
+    /// ```
+    pub fn as_moves(&self) -> Vec<GridDirection> {
+        self.cells
+            .windows(2)
+            .map(|step| self.direction(step[0], step[1]).expect("adjacent cells"))
+            .collect()
+    }
+
+    /// Returns the cells at which the path starts, turns, and ends.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let path = CellPath::adjacent(3, 3, vec![0, 1, 2, 5, 8]).unwrap();
+    /// assert_eq!(path.as_waypoints(), vec![0, 2, 8]);
+    /// ```
+    pub fn as_waypoints(&self) -> Vec<Cell> {
+        let moves = self.as_moves();
+        let mut waypoints: Vec<Cell> = self.cells.iter().take(1).copied().collect();
+
+        for i in 1..moves.len() {
+            if moves[i] != moves[i - 1] {
+                waypoints.push(self.cells[i]);
+            }
+        }
+
+        if self.cells.len() > 1 {
+            waypoints.push(self.cells[self.cells.len() - 1]);
+        }
+
+        waypoints
+    }
+
+    /// Returns the path as a flat Molt list of cell IDs.
+    pub fn to_molt_flat(&self) -> Value {
+        let list: MoltList = self
+            .cells
+            .iter()
+            .map(|c| Value::from(*c as MoltInt))
+            .collect();
+        Value::from(list)
+    }
+
+    /// Returns the path as a Molt list of *i j* pairs.
+    pub fn to_molt_pairs(&self) -> Value {
+        let list: MoltList = self
+            .cells
+            .iter()
+            .map(|c| {
+                let pair = vec![
+                    Value::from((c / self.cols) as MoltInt),
+                    Value::from((c % self.cols) as MoltInt),
+                ];
+                Value::from(pair)
+            })
+            .collect();
+        Value::from(list)
+    }
+
+    /// Converts a Molt list of cell IDs or of *i j* pairs into a path through the
+    /// grid, validating it as for `new`.
+    pub fn from_molt(grid: &Grid, value: &Value) -> Result<Self, Exception> {
+        let mut cells = Vec::new();
+
+        for item in value.as_list()?.iter() {
+            let cell = if let Ok(id) = item.as_int() {
+                id
+            } else {
+                let pair = item.as_list()?;
+                if pair.len() != 2 {
+                    return molt_err!("expected cell ID or i j pair, got \"{}\"", item);
+                }
+                let (i, j) = (pair[0].as_int()?, pair[1].as_int()?);
+                if i < 0 || j < 0 || j >= grid.num_cols() as MoltInt {
+                    return molt_err!("expected cell ID or i j pair, got \"{}\"", item);
+                }
+                i * grid.num_cols() as MoltInt + j
+            };
+
+            if cell < 0 {
+                return molt_err!("expected cell ID or i j pair, got \"{}\"", item);
+            }
+            cells.push(cell as Cell);
+        }
+
+        Self::new(grid, cells).map_err(|msg| Exception::molt_err(Value::from(msg)))
+    }
+
+    /// Returns the direction from one cell to the other, if they are adjacent, possibly
+    /// across a wrapped edge.
+    fn direction(&self, from: Cell, to: Cell) -> Option<GridDirection> {
+        let (fi, fj) = (from / self.cols, from % self.cols);
+        let (ti, tj) = (to / self.cols, to % self.cols);
+        let wrap_cols = self.topology != Topology::Planar && self.cols > 2;
+        let wrap_rows = self.topology == Topology::Torus && self.rows > 2;
+        let (last_row, last_col) = (self.rows - 1, self.cols - 1);
+
+        if fj == tj && (ti + 1 == fi || (wrap_rows && fi == 0 && ti == last_row)) {
+            Some(GridDirection::North)
+        } else if fj == tj && (fi + 1 == ti || (wrap_rows && fi == last_row && ti == 0)) {
+            Some(GridDirection::South)
+        } else if fi == ti && (fj + 1 == tj || (wrap_cols && fj == last_col && tj == 0)) {
+            Some(GridDirection::East)
+        } else if fi == ti && (tj + 1 == fj || (wrap_cols && fj == 0 && tj == last_col)) {
+            Some(GridDirection::West)
+        } else {
+            None
+        }
+    }
+}
+
+impl IntoIterator for CellPath {
+    type Item = Cell;
+    type IntoIter = std::vec::IntoIter<Cell>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cells.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CellPath {
+    type Item = &'a Cell;
+    type IntoIter = std::slice::Iter<'a, Cell>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cells.iter()
+    }
+}
+
+impl std::ops::Index<usize> for CellPath {
+    type Output = Cell;
+
+    fn index(&self, index: usize) -> &Cell {
+        &self.cells[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_path_new_rejects_broken_paths() {
+        let mut grid = Grid::new(3, 3);
+        grid.link(0, 1);
+        grid.link(1, 2);
+        grid.link(2, 5);
+
+        assert!(CellPath::new(&grid, vec![0, 1, 2, 5]).is_ok());
+        assert!(CellPath::new(&grid, vec![]).is_ok());
+
+        let err = CellPath::new(&grid, vec![0, 1, 2, 3]).unwrap_err();
+        assert!(err.contains("not adjacent"), "{}", err);
+        assert!(err.ends_with("at index 3"), "{}", err);
+
+        let err = CellPath::new(&grid, vec![0, 1, 4]).unwrap_err();
+        assert!(err.contains("not linked"), "{}", err);
+        assert!(err.ends_with("at index 2"), "{}", err);
+
+        let err = CellPath::new(&grid, vec![0, 9]).unwrap_err();
+        assert!(err.contains("out of range"), "{}", err);
+        assert!(err.ends_with("at index 1"), "{}", err);
+
+        // Adjacency alone is enough for adjacent().
+        assert!(CellPath::adjacent(3, 3, vec![0, 1, 4]).is_ok());
+    }
+
+    #[test]
+    fn test_cell_path_moves_and_waypoints() {
+        let mut path = CellPath::adjacent(3, 3, vec![0, 3, 4, 5, 8]).unwrap();
+        assert_eq!(
+            path.as_moves(),
+            vec![
+                GridDirection::South,
+                GridDirection::East,
+                GridDirection::East,
+                GridDirection::South
+            ]
+        );
+        assert_eq!(path.as_waypoints(), vec![0, 3, 5, 8]);
+
+        path.reverse();
+        assert_eq!(path.cells(), &[8, 5, 4, 3, 0]);
+        assert_eq!(path.as_waypoints(), vec![8, 5, 3, 0]);
+        assert!(path.contains(4));
+        assert!(!path.contains(1));
+
+        let single = CellPath::adjacent(3, 3, vec![4]).unwrap();
+        assert_eq!(single.as_waypoints(), vec![4]);
+    }
+
+    #[test]
+    fn test_cell_path_round_trips() {
+        let mut grid = Grid::new(6, 7);
+        crate::recursive_backtracker(&mut grid);
+        let path = grid.longest_path();
+
+        let flat = path.to_molt_flat();
+        assert_eq!(CellPath::from_molt(&grid, &flat).unwrap(), path);
+
+        let pairs = path.to_molt_pairs();
+        assert_eq!(CellPath::from_molt(&grid, &pairs).unwrap(), path);
+
+        let json = serde_json::to_string(&path).unwrap();
+        let back: CellPath = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, path);
+    }
+
+    #[test]
+    fn test_cell_path_wraps() {
+        let mut grid = Grid::with_topology(3, 4, Topology::Cylinder);
+        grid.link(4, 7);
+        grid.link(7, 11);
+
+        let path = CellPath::new(&grid, vec![4, 7, 11]).unwrap();
+        assert_eq!(
+            path.as_moves(),
+            vec![GridDirection::West, GridDirection::South]
+        );
+
+        // Only the grid's topology wraps.
+        assert!(CellPath::adjacent(3, 4, vec![4, 7]).is_err());
+        assert!(CellPath::new(&grid, vec![11, 3]).is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_cell_path_wrappers_match() {
+        let mut grid = Grid::new(8, 8);
+        crate::recursive_backtracker(&mut grid);
+
+        assert_eq!(
+            grid.shortest_path_vec(0, 63),
+            grid.shortest_path(0, 63).into_cells()
+        );
+        assert_eq!(grid.longest_path_vec(), grid.longest_path().into_cells());
+    }
+}
@@ -0,0 +1,269 @@
+//! Constraints on the walls of a maze, for constraint-aware maze algorithms such as
+//! `recursive_backtracker_constrained`.
+
+use crate::grid::Grid;
+use crate::union_find::UnionFind;
+use crate::Cell;
+use crate::GridDirection;
+use std::collections::HashSet;
+
+/// A set of constraints on the links that a maze algorithm may carve: links that are
+/// forbidden, i.e., walls that must remain, and links that are required.  Each link is
+/// named by a cell and the direction of the neighbor it joins.  Uses the builder pattern.
+///
+/// ```
+/// use mazegen::*;
+///
+/// // Wall off the left column from the rest of the grid, except at the bottom.
+/// let mut constraints = Constraints::new();
+/// for i in 0..4 {
+///     constraints.forbid_link(i * 5, GridDirection::East);
+/// }
+/// constraints.require_link(20, GridDirection::East);
+///
+/// let mut grid = Grid::new(5, 5);
+/// recursive_backtracker_constrained(&mut grid, &constraints).unwrap();
+/// assert!(grid.is_perfect_maze());
+/// assert!(!grid.is_linked_east(0));
+/// assert!(grid.is_linked_east(20));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Constraints {
+    /// The links that mustn't be carved.
+    forbidden: Vec<(Cell, GridDirection)>,
+
+    /// The links that must be carved.
+    required: Vec<(Cell, GridDirection)>,
+}
+
+impl Constraints {
+    /// Creates an empty set of constraints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forbids the link from the cell to its neighbor in the given direction, so that the
+    /// wall between them remains.
+    pub fn forbid_link(&mut self, cell: Cell, dir: GridDirection) -> &mut Self {
+        self.forbidden.push((cell, dir));
+        self
+    }
+
+    /// Requires the link from the cell to its neighbor in the given direction.
+    pub fn require_link(&mut self, cell: Cell, dir: GridDirection) -> &mut Self {
+        self.required.push((cell, dir));
+        self
+    }
+
+    /// Checks that the constraints can be satisfied by a perfect maze on the grid: each
+    /// constraint names a link between two neighboring cells, no link is both required
+    /// and forbidden, the required links don't form a loop, and the links that aren't
+    /// forbidden connect all of the grid's cells (other than isolated cells).  Returns an
+    /// error describing the first problem found.
+    pub fn check(&self, grid: &Grid) -> Result<(), String> {
+        self.resolve(grid).map(|_| ())
+    }
+
+    /// Checks the constraints against the grid, then clears the grid and carves the
+    /// required links.  Returns the forbidden links, each as a pair of cells with the lower
+    /// ID first.  On error, the grid is unchanged.
+    pub(crate) fn apply(&self, grid: &mut Grid) -> Result<HashSet<(Cell, Cell)>, String> {
+        let (forbidden, required) = self.resolve(grid)?;
+
+        grid.clear();
+        for (a, b) in required {
+            grid.link(a, b);
+        }
+
+        Ok(forbidden)
+    }
+
+    /// Resolves the constraints into forbidden and required pairs of cells, with the
+    /// lower ID first, verifying that they can be satisfied.
+    #[allow(clippy::type_complexity)]
+    fn resolve(&self, grid: &Grid) -> Result<(HashSet<(Cell, Cell)>, Vec<(Cell, Cell)>), String> {
+        // FIRST, resolve each constraint to a pair of neighboring cells.
+        let pair = |&(cell, dir): &(Cell, GridDirection)| -> Result<(Cell, Cell), String> {
+            if !grid.contains(cell) {
+                return Err(format!("cell not in grid: {}", cell));
+            }
+
+            match grid.cell_to(cell, dir) {
+                Some(other) if grid.neighbors(cell).contains(&other) => {
+                    Ok((cell.min(other), cell.max(other)))
+                }
+                _ => Err(format!("cell {} has no neighbor to the {}", cell, dir)),
+            }
+        };
+
+        let forbidden = self
+            .forbidden
+            .iter()
+            .map(pair)
+            .collect::<Result<HashSet<_>, _>>()?;
+        let required = self
+            .required
+            .iter()
+            .map(pair)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // NEXT, the required links mustn't be forbidden, or form a loop.  The cells they
+        // join are tracked as a union-find forest.
+        let mut joined = UnionFind::new(grid.num_cells());
+
+        let mut seen = HashSet::new();
+
+        for &(a, b) in &required {
+            if forbidden.contains(&(a, b)) {
+                return Err(format!(
+                    "link from cell {} to cell {} is both required and forbidden",
+                    a, b
+                ));
+            }
+
+            if !seen.insert((a, b)) {
+                continue;
+            }
+
+            if !joined.join(a, b) {
+                return Err(format!("required links form a loop at cell {}", a));
+            }
+        }
+
+        // NEXT, the allowed links must connect all of the cells that have neighbors.
+        let live: Vec<Cell> = (0..grid.num_cells())
+            .filter(|c| !grid.neighbors(*c).is_empty())
+            .collect();
+
+        if let Some(&start) = live.first() {
+            let mut reached = vec![false; grid.num_cells()];
+            let mut queue = vec![start];
+            reached[start] = true;
+
+            while let Some(cell) = queue.pop() {
+                for other in grid.neighbors(cell) {
+                    let key = (cell.min(other), cell.max(other));
+                    if !reached[other] && !forbidden.contains(&key) {
+                        reached[other] = true;
+                        queue.push(other);
+                    }
+                }
+            }
+
+            if let Some(cell) = live.iter().find(|c| !reached[**c]) {
+                return Err(format!(
+                    "constraints make a connected maze impossible: cell {} can't be reached \
+                     from cell {}",
+                    cell, start
+                ));
+            }
+        }
+
+        Ok((forbidden, seen.into_iter().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // Forbids every link across the vertical line to the east of column j, except in the
+    // given rows.
+    fn fence(grid: &Grid, j: usize, gates: &[usize]) -> Constraints {
+        let mut constraints = Constraints::new();
+
+        for i in 0..grid.num_rows() {
+            if !gates.contains(&i) {
+                constraints.forbid_link(grid.cell(i, j), GridDirection::East);
+            }
+        }
+
+        constraints
+    }
+
+    #[test]
+    fn test_constraints_check() {
+        let grid = Grid::new(4, 6);
+        assert_eq!(Constraints::new().check(&grid), Ok(()));
+        assert_eq!(fence(&grid, 2, &[3]).check(&grid), Ok(()));
+
+        // A complete cut with no allowed crossing.
+        assert_eq!(
+            fence(&grid, 2, &[]).check(&grid),
+            Err(
+                "constraints make a connected maze impossible: cell 3 can't be reached from \
+                 cell 0"
+                    .into()
+            )
+        );
+
+        assert_eq!(
+            Constraints::new()
+                .forbid_link(5, GridDirection::East)
+                .check(&grid),
+            Err("cell 5 has no neighbor to the east".into())
+        );
+        assert_eq!(
+            Constraints::new()
+                .forbid_link(1, GridDirection::South)
+                .require_link(7, GridDirection::North)
+                .check(&grid),
+            Err("link from cell 1 to cell 7 is both required and forbidden".into())
+        );
+        assert_eq!(
+            Constraints::new()
+                .require_link(0, GridDirection::East)
+                .require_link(1, GridDirection::South)
+                .require_link(7, GridDirection::West)
+                .require_link(6, GridDirection::North)
+                .check(&grid),
+            Err("required links form a loop at cell 0".into())
+        );
+        assert!(Constraints::new()
+            .require_link(24, GridDirection::East)
+            .check(&grid)
+            .is_err());
+    }
+
+    #[test]
+    fn test_constrained_algorithms() {
+        let mut rng = StdRng::seed_from_u64(2034);
+        let grid = Grid::new(8, 10);
+        let mut constraints = fence(&grid, 4, &[6]);
+        constraints
+            .require_link(grid.cell(6, 4), GridDirection::East)
+            .require_link(0, GridDirection::South)
+            .require_link(10, GridDirection::South)
+            .forbid_link(grid.cell(7, 9), GridDirection::West);
+
+        type Algo = fn(&mut Grid, &Constraints, &mut StdRng) -> Result<(), String>;
+        let algos: [Algo; 2] = [
+            |g, c, r| crate::recursive_backtracker_constrained_rng(g, c, r),
+            |g, c, r| crate::hunt_and_kill_constrained_rng(g, c, r),
+        ];
+
+        for algo in &algos {
+            for _ in 0..10 {
+                let mut grid = Grid::new(8, 10);
+                algo(&mut grid, &constraints, &mut rng).unwrap();
+
+                assert!(grid.is_perfect_maze());
+                for &(cell, dir) in &constraints.forbidden {
+                    assert!(!grid.is_linked_to(cell, dir));
+                }
+                for &(cell, dir) in &constraints.required {
+                    assert!(grid.is_linked_to(cell, dir));
+                }
+            }
+
+            // An infeasible set of constraints fails up front, leaving the grid alone.
+            let mut grid = Grid::new(8, 10);
+            grid.link(0, 1);
+            let cut = fence(&grid, 4, &[]);
+            assert!(algo(&mut grid, &cut, &mut rng).is_err());
+            assert_eq!(grid.num_links(), 1);
+        }
+    }
+}
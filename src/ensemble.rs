@@ -0,0 +1,182 @@
+//! Ensemble statistics: properties of a maze algorithm averaged over many generated mazes,
+//! for studying the algorithm's biases.
+
+use crate::grid::Grid;
+use crate::Cell;
+use rand::Rng;
+
+/// Generates `iterations` mazes of the given size with the algorithm, and returns the
+/// probability that each wall is open, i.e., the fraction of the mazes in which its two
+/// cells are linked.  The result is indexed by the canonical wall index; see
+/// `Grid::wall_index` and `Grid::wall_at`.  The grid is cleared before each call to the
+/// algorithm.  Panics if `iterations` is zero.
+///
+/// ```
+/// use mazegen::*;
+/// use rand::thread_rng;
+///
+/// let probs = ensemble_link_probability(4, 6, 20, binary_tree_maze_rng, &mut thread_rng());
+/// let grid = Grid::new(4, 6);
+/// let wall = grid.wall_index(grid.cell(0, 2), GridDirection::East).unwrap();
+/// assert_eq!(probs[wall], 1.0);
+/// ```
+pub fn ensemble_link_probability<F, R>(
+    rows: usize,
+    cols: usize,
+    iterations: usize,
+    mut algo: F,
+    rng: &mut R,
+) -> Vec<f64>
+where
+    F: FnMut(&mut Grid, &mut R),
+    R: Rng,
+{
+    assert!(iterations > 0, "expected at least one iteration");
+    let mut grid = Grid::new(rows, cols);
+    let mut counts = vec![0usize; grid.num_walls()];
+
+    for _ in 0..iterations {
+        grid.clear();
+        algo(&mut grid, rng);
+
+        for (index, count) in counts.iter_mut().enumerate() {
+            let (cell, dir) = grid.wall_at(index).expect("valid wall index");
+            if grid.is_linked_to(cell, dir) {
+                *count += 1;
+            }
+        }
+    }
+
+    counts
+        .iter()
+        .map(|count| *count as f64 / iterations as f64)
+        .collect()
+}
+
+/// Generates `iterations` mazes of the given size with the algorithm, and returns each
+/// cell's mean distance from the `source` cell, indexed by cell ID.  Each cell's mean is
+/// taken over the mazes in which it can be reached from the source; a cell that is never
+/// reachable has a mean of NaN.  The grid is cleared before each call to the algorithm.
+/// Panics if `iterations` is zero.
+pub fn ensemble_mean_distances<F, R>(
+    rows: usize,
+    cols: usize,
+    iterations: usize,
+    mut algo: F,
+    rng: &mut R,
+    source: Cell,
+) -> Vec<f64>
+where
+    F: FnMut(&mut Grid, &mut R),
+    R: Rng,
+{
+    assert!(iterations > 0, "expected at least one iteration");
+    let mut grid = Grid::new(rows, cols);
+    assert!(grid.contains(source), "source cell not in grid: {}", source);
+
+    let mut sums = vec![0usize; grid.num_cells()];
+    let mut counts = vec![0usize; grid.num_cells()];
+
+    for _ in 0..iterations {
+        grid.clear();
+        algo(&mut grid, rng);
+
+        for (cell, dist) in grid.distances(source).iter().enumerate() {
+            if let Some(dist) = dist {
+                sums[cell] += dist;
+                counts[cell] += 1;
+            }
+        }
+    }
+
+    sums.iter()
+        .zip(counts.iter())
+        .map(|(sum, count)| {
+            if *count > 0 {
+                *sum as f64 / *count as f64
+            } else {
+                f64::NAN
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GridDirection;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_link_probability_binary_tree() {
+        let mut rng = StdRng::seed_from_u64(2033);
+        let probs = ensemble_link_probability(5, 7, 50, crate::binary_tree_maze_rng, &mut rng);
+        let grid = Grid::new(5, 7);
+        assert_eq!(probs.len(), grid.num_walls());
+        assert!(probs.iter().all(|p| (0.0..=1.0).contains(p)));
+
+        // The top row and the east column are always open; the interior walls aren't.
+        for j in 0..6 {
+            let wall = grid
+                .wall_index(grid.cell(0, j), GridDirection::East)
+                .unwrap();
+            assert_eq!(probs[wall], 1.0);
+        }
+        for i in 1..5 {
+            let wall = grid
+                .wall_index(grid.cell(i, 6), GridDirection::North)
+                .unwrap();
+            assert_eq!(probs[wall], 1.0);
+        }
+        let wall = grid
+            .wall_index(grid.cell(2, 3), GridDirection::East)
+            .unwrap();
+        assert!(probs[wall] > 0.0 && probs[wall] < 1.0);
+
+        // A perfect maze has cells - 1 links, so the probabilities sum to that.
+        let total: f64 = probs.iter().sum();
+        assert!((total - 34.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ensemble_reproducible() {
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let probs = ensemble_link_probability(4, 4, 10, crate::sidewinder_maze_rng, &mut rng);
+            let means = ensemble_mean_distances(4, 4, 10, crate::sidewinder_maze_rng, &mut rng, 0);
+            (probs, means)
+        };
+
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1).0, run(2).0);
+    }
+
+    #[test]
+    fn test_mean_distances() {
+        let mut rng = StdRng::seed_from_u64(2033);
+        let means = ensemble_mean_distances(
+            4,
+            5,
+            30,
+            |grid: &mut Grid, rng: &mut StdRng| crate::recursive_backtracker_rng(grid, rng),
+            &mut rng,
+            0,
+        );
+
+        assert_eq!(means.len(), 20);
+        assert_eq!(means[0], 0.0);
+        assert!(means.iter().skip(1).all(|m| *m >= 1.0));
+
+        // Cell (i,j) is at least i + j steps from the corner.
+        for (cell, mean) in means.iter().enumerate() {
+            assert!(*mean >= (cell / 5 + cell % 5) as f64);
+        }
+
+        // Never-reached cells have no mean.
+        let means =
+            ensemble_mean_distances(2, 2, 3, |_: &mut Grid, _: &mut StdRng| {}, &mut rng, 0);
+        assert_eq!(means[0], 0.0);
+        assert!(means[3].is_nan());
+    }
+}
@@ -0,0 +1,252 @@
+//! Fairness checking for two-player start positions.
+
+use crate::grid::Grid;
+use crate::Cell;
+use std::collections::HashSet;
+
+/// A struct for finding pairs of "fair" start cells for a two-player race to a common goal.
+/// Uses the builder pattern.
+///
+/// A pair of distinct cells is fair if:
+///
+/// * Both cells can reach the goal, and neither is the goal itself.
+/// * Their distances to the goal differ by at most the `tolerance`.
+/// * They are at least `min_separation` steps apart from each other.
+/// * If `max_overlap` is set, their shortest paths to the goal share no more than that
+///   fraction of cells.
+///
+/// The path overlap is the number of cells the two paths have in common (including the goal
+/// itself) divided by the length of the shorter path.  Paths are read from the distance
+/// field computed from the goal; where there is more than one shortest path, the path
+/// through the lowest-numbered cells is used.
+///
+/// Checking separation requires a distance computation per candidate cell, so the search is
+/// quadratic in the number of cells.
+#[derive(Debug, Copy, Clone)]
+pub struct FairStarts {
+    /// The maximum difference between the two cells' distances to the goal.
+    tolerance: usize,
+
+    /// The minimum distance between the two cells.
+    min_separation: usize,
+
+    /// The maximum fraction of shared path cells, if any.
+    max_overlap: Option<f64>,
+}
+
+impl Default for FairStarts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FairStarts {
+    /// Creates a new fairness checker with default settings: a tolerance of 0, a minimum
+    /// separation of 1 (i.e., the cells must merely be distinct), and no overlap limit.
+    pub fn new() -> Self {
+        Self {
+            tolerance: 0,
+            min_separation: 1,
+            max_overlap: None,
+        }
+    }
+
+    /// Sets the maximum difference between the two cells' distances to the goal.
+    pub fn tolerance(&mut self, tolerance: usize) -> &mut Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the minimum distance between the two cells.
+    pub fn min_separation(&mut self, min_separation: usize) -> &mut Self {
+        assert!(min_separation > 0);
+        self.min_separation = min_separation;
+        self
+    }
+
+    /// Sets the maximum fraction of cells the two paths to the goal may share.
+    pub fn max_overlap(&mut self, max_overlap: f64) -> &mut Self {
+        assert!((0.0..=1.0).contains(&max_overlap));
+        self.max_overlap = Some(max_overlap);
+        self
+    }
+
+    /// Finds the fair pairs of start cells for the given goal.  Each pair is returned
+    /// with the lower cell ID first.  The pairs are ranked by the shorter of the two
+    /// distances to the goal, descending; ties are broken by the difference in distance,
+    /// ascending, and then by cell ID.
+    pub fn find(&self, grid: &Grid, goal: Cell) -> Vec<(Cell, Cell)> {
+        assert!(grid.contains(goal));
+
+        // FIRST, get the distance of every cell to the goal.  Only cells that can
+        // reach the goal are candidates.
+        let to_goal = grid.distances(goal);
+        let candidates: Vec<Cell> = (0..grid.num_cells())
+            .filter(|c| *c != goal && to_goal[*c].is_some())
+            .collect();
+
+        // NEXT, get the path cells for each candidate, if we need them.
+        let paths: Vec<HashSet<Cell>> = if self.max_overlap.is_some() {
+            (0..grid.num_cells())
+                .map(|c| {
+                    if to_goal[c].is_some() {
+                        path_to_goal(grid, &to_goal, c).into_iter().collect()
+                    } else {
+                        HashSet::new()
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        // NEXT, check each pair.
+        let mut pairs: Vec<(usize, usize, Cell, Cell)> = Vec::new();
+
+        for (n, &a) in candidates.iter().enumerate() {
+            let da = to_goal[a].expect("reachable");
+
+            // Only compute the separation distances if they matter.
+            let from_a = if self.min_separation > 1 {
+                grid.distances(a)
+            } else {
+                Vec::new()
+            };
+
+            for &b in &candidates[n + 1..] {
+                let db = to_goal[b].expect("reachable");
+                let diff = da.max(db) - da.min(db);
+
+                if diff > self.tolerance {
+                    continue;
+                }
+
+                if self.min_separation > 1 {
+                    match from_a[b] {
+                        Some(sep) if sep >= self.min_separation => {}
+                        _ => continue,
+                    }
+                }
+
+                if let Some(max) = self.max_overlap {
+                    let shared = paths[a].intersection(&paths[b]).count();
+                    let shorter = std::cmp::min(paths[a].len(), paths[b].len());
+
+                    if shared as f64 / shorter as f64 > max {
+                        continue;
+                    }
+                }
+
+                pairs.push((std::cmp::min(da, db), diff, a, b));
+            }
+        }
+
+        // FINALLY, rank the pairs.
+        pairs.sort_by(|x, y| {
+            y.0.cmp(&x.0)
+                .then(x.1.cmp(&y.1))
+                .then(x.2.cmp(&y.2))
+                .then(x.3.cmp(&y.3))
+        });
+
+        pairs.into_iter().map(|(_, _, a, b)| (a, b)).collect()
+    }
+}
+
+/// Follows the distance field from the cell down to the goal, returning the cells on
+/// the path, including both ends.  Prefers the lowest-numbered cell at each step.
+fn path_to_goal(grid: &Grid, to_goal: &[Option<usize>], cell: Cell) -> Vec<Cell> {
+    let mut path = vec![cell];
+    let mut current = cell;
+
+    while let Some(dist) = to_goal[current] {
+        if dist == 0 {
+            break;
+        }
+
+        let next = grid
+            .links(current)
+            .into_iter()
+            .filter(|c| to_goal[*c] == Some(dist - 1))
+            .min()
+            .expect("a step toward the goal");
+
+        path.push(next);
+        current = next;
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Links every cell in a 1xN grid to its eastern neighbor.
+    fn corridor(len: usize) -> Grid {
+        let mut grid = Grid::new(1, len);
+        for c in 0..len - 1 {
+            grid.link(c, c + 1);
+        }
+        grid
+    }
+
+    #[test]
+    fn test_fair_starts_symmetric() {
+        let grid = corridor(5);
+
+        // The goal is in the middle, so the mirror pairs are fair.
+        assert_eq!(grid.fair_starts(2, 0), vec![(0, 4), (1, 3)]);
+    }
+
+    #[test]
+    fn test_fair_starts_impossible() {
+        let grid = corridor(3);
+
+        // The goal is at one end, so no two cells are equally distant.
+        assert!(grid.fair_starts(0, 0).is_empty());
+
+        // No two cells are this far apart.
+        assert!(FairStarts::new()
+            .tolerance(2)
+            .min_separation(3)
+            .find(&grid, 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_fair_starts_constraints() {
+        let mut grid = Grid::new(6, 6);
+        crate::recursive_backtracker(&mut grid);
+        let goal = grid.cell(3, 3);
+        let to_goal = grid.distances(goal);
+
+        let pairs = FairStarts::new()
+            .tolerance(1)
+            .min_separation(4)
+            .max_overlap(0.5)
+            .find(&grid, goal);
+
+        let mut last = usize::MAX;
+
+        for (a, b) in pairs {
+            assert!(a < b);
+            assert_ne!(a, goal);
+            assert_ne!(b, goal);
+
+            let da = to_goal[a].unwrap();
+            let db = to_goal[b].unwrap();
+            assert!(da.max(db) - da.min(db) <= 1);
+            assert!(grid.distances(a)[b].unwrap() >= 4);
+
+            let pa: HashSet<Cell> = grid.shortest_path(a, goal).into_iter().collect();
+            let pb: HashSet<Cell> = grid.shortest_path(b, goal).into_iter().collect();
+            let shared = pa.intersection(&pb).count();
+            assert!(shared as f64 <= 0.5 * pa.len().min(pb.len()) as f64);
+
+            // Ranked by distance, descending
+            assert!(da.min(db) <= last);
+            last = da.min(db);
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! A minimal C API, for generating, solving, and rendering mazes from C, Python, and
+//! other languages without the Molt layer.  Enabled by the "ffi" feature; the matching C
+//! header is `include/mazegen.h`.
+//!
+//! Ownership rules:
+//!
+//! * `mazegen_generate` returns a handle owned by the caller, who must release it with
+//!   `mazegen_free`.  Handles are IDs rather than addresses, and are never reused, so
+//!   freeing a null handle, or one that's already been freed, does nothing.
+//! * The buffers returned by `mazegen_solve` and `mazegen_render_png` are owned by the
+//!   handle.  Each remains valid until the next call to the same function on the same
+//!   handle, or until the handle is freed; the caller must not free it.
+//! * Every function accepts a null or freed handle, and returns its failure value: null,
+//!   false, or zero dimensions.  No panic unwinds into the caller; a function that
+//!   panics returns its failure value.
+//! * A handle mustn't be used by more than one thread at a time.
+
+use crate::Algorithm;
+use crate::Grid;
+use crate::GridDirection;
+use image::DynamicImage;
+use image::ImageOutputFormat;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+/// `algorithm_id` for `recursive_backtracker`.
+pub const MAZEGEN_BACKTRACKER: u32 = 0;
+
+/// `algorithm_id` for `binary_tree_maze`.
+pub const MAZEGEN_BINARY_TREE: u32 = 1;
+
+/// `algorithm_id` for `hunt_and_kill`.
+pub const MAZEGEN_HUNT_AND_KILL: u32 = 2;
+
+/// `algorithm_id` for `sidewinder_maze`.
+pub const MAZEGEN_SIDEWINDER: u32 = 3;
+
+/// `algorithm_id` for `recursive_division`.
+pub const MAZEGEN_DIVISION: u32 = 4;
+
+/// `dir` for `mazegen_is_linked`: north.
+pub const MAZEGEN_NORTH: u32 = 0;
+
+/// `dir` for `mazegen_is_linked`: south.
+pub const MAZEGEN_SOUTH: u32 = 1;
+
+/// `dir` for `mazegen_is_linked`: east.
+pub const MAZEGEN_EAST: u32 = 2;
+
+/// `dir` for `mazegen_is_linked`: west.
+pub const MAZEGEN_WEST: u32 = 3;
+
+/// The largest number of cells `mazegen_generate` will create, which keeps the maze and
+/// its rendered image to a reasonable size.
+pub const MAZEGEN_MAX_CELLS: u32 = 1 << 20;
+
+/// A maze, as seen from C: an opaque handle.  The handle's value is the ID of a maze in
+/// the table of live mazes, and is never dereferenced.
+pub struct MazeHandle {
+    _private: [u8; 0],
+}
+
+/// A maze and the buffers returned for it.
+struct Maze {
+    grid: Grid,
+    path: Vec<u32>,
+    png: Vec<u8>,
+}
+
+/// The live mazes, by handle ID.  IDs start at 1, so that no handle is null, and are
+/// never reused.  Each maze has its own lock, so that work on one handle doesn't hold up
+/// the others; the table is locked only to add, remove, or look up a maze.
+struct MazeTable {
+    next_id: usize,
+    mazes: BTreeMap<usize, Arc<Mutex<Maze>>>,
+}
+
+static MAZES: Mutex<MazeTable> = Mutex::new(MazeTable {
+    next_id: 1,
+    mazes: BTreeMap::new(),
+});
+
+/// Generates a maze with the given number of rows and columns using the given algorithm,
+/// seeding the random number generator with `seed` so that the same arguments always
+/// produce the same maze.  Returns a new handle, or null if either dimension is zero,
+/// there would be more than `MAZEGEN_MAX_CELLS` cells, or the algorithm ID is unknown.
+#[no_mangle]
+pub extern "C" fn mazegen_generate(
+    rows: u32,
+    cols: u32,
+    algorithm_id: u32,
+    seed: u64,
+) -> *mut MazeHandle {
+    catch(ptr::null_mut(), || {
+        let algo = match algorithm_id {
+            MAZEGEN_BACKTRACKER => Algorithm::Backtracker,
+            MAZEGEN_BINARY_TREE => Algorithm::BinaryTree,
+            MAZEGEN_HUNT_AND_KILL => Algorithm::HuntAndKill,
+            MAZEGEN_SIDEWINDER => Algorithm::Sidewinder,
+            MAZEGEN_DIVISION => Algorithm::Division,
+            _ => return ptr::null_mut(),
+        };
+
+        if rows == 0 || cols == 0 || rows as u64 * cols as u64 > MAZEGEN_MAX_CELLS as u64 {
+            return ptr::null_mut();
+        }
+
+        let mut grid = Grid::new(rows as usize, cols as usize);
+        algo.generate_rng(&mut grid, &mut StdRng::seed_from_u64(seed));
+
+        let maze = Arc::new(Mutex::new(Maze {
+            grid,
+            path: Vec::new(),
+            png: Vec::new(),
+        }));
+
+        // An ID that can't be incremented has been used, so it's never handed out.
+        let mut table = mazes();
+        let id = table.next_id;
+        table.next_id = match id.checked_add(1) {
+            Some(next_id) => next_id,
+            None => return ptr::null_mut(),
+        };
+        table.mazes.insert(id, maze);
+        id as *mut MazeHandle
+    })
+}
+
+/// Frees the handle and the buffers it owns.  Does nothing if the handle is null or has
+/// already been freed.
+#[no_mangle]
+pub extern "C" fn mazegen_free(handle: *mut MazeHandle) {
+    catch((), || {
+        mazes().mazes.remove(&(handle as usize));
+    })
+}
+
+/// Gets the maze's dimensions, storing them in `rows` and `cols`, either of which may be
+/// null.  The dimensions are zero if the handle is null or freed.
+///
+/// # Safety
+///
+/// `rows` and `cols` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn mazegen_dims(handle: *const MazeHandle, rows: *mut u32, cols: *mut u32) {
+    let (num_rows, num_cols) = catch((0, 0), || {
+        with_maze(handle, |maze| {
+            (maze.grid.num_rows() as u32, maze.grid.num_cols() as u32)
+        })
+        .unwrap_or((0, 0))
+    });
+
+    if !rows.is_null() {
+        *rows = num_rows;
+    }
+
+    if !cols.is_null() {
+        *cols = num_cols;
+    }
+}
+
+/// Returns true if the cell is linked to its neighbor in the given direction, i.e., if
+/// there's no wall between them.  Returns false if the handle, cell, or direction is
+/// invalid.
+#[no_mangle]
+pub extern "C" fn mazegen_is_linked(handle: *const MazeHandle, cell: u32, dir: u32) -> bool {
+    let dir = match dir {
+        MAZEGEN_NORTH => GridDirection::North,
+        MAZEGEN_SOUTH => GridDirection::South,
+        MAZEGEN_EAST => GridDirection::East,
+        MAZEGEN_WEST => GridDirection::West,
+        _ => return false,
+    };
+
+    catch(false, || {
+        with_maze(handle, |maze| {
+            maze.grid.contains(cell as usize) && maze.grid.is_linked_to(cell as usize, dir)
+        })
+        .unwrap_or(false)
+    })
+}
+
+/// Finds the shortest path from the start cell to the goal cell, returning the cells
+/// along it and storing its length in `out_len`.  Returns null, with a length of zero, if
+/// the handle or either cell is invalid, or if there's no path.
+///
+/// # Safety
+///
+/// `out_len` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn mazegen_solve(
+    handle: *mut MazeHandle,
+    start: u32,
+    goal: u32,
+    out_len: *mut usize,
+) -> *const u32 {
+    let path = catch(None, || {
+        with_maze(handle, |maze| {
+            let (start, goal) = (start as usize, goal as usize);
+
+            if !maze.grid.contains(start) || !maze.grid.contains(goal) {
+                return None;
+            }
+
+            let path = maze.grid.shortest_path(start, goal);
+
+            if path.is_empty() {
+                return None;
+            }
+
+            maze.path = path.cells().iter().map(|c| *c as u32).collect();
+            Some((maze.path.as_ptr(), maze.path.len()))
+        })
+        .flatten()
+    });
+
+    result(path, out_len)
+}
+
+/// Renders the maze with the default settings, returning the image encoded as a PNG and
+/// storing its length in `out_len`.  Returns null, with a length of zero, if the handle
+/// is invalid or the image can't be encoded.
+///
+/// # Safety
+///
+/// `out_len` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn mazegen_render_png(
+    handle: *mut MazeHandle,
+    out_len: *mut usize,
+) -> *const u8 {
+    let png = catch(None, || {
+        with_maze(handle, |maze| {
+            let mut png = Vec::new();
+
+            DynamicImage::ImageRgba8(maze.grid.to_image())
+                .write_to(&mut png, ImageOutputFormat::Png)
+                .ok()?;
+
+            maze.png = png;
+            Some((maze.png.as_ptr(), maze.png.len()))
+        })
+        .flatten()
+    });
+
+    result(png, out_len)
+}
+
+/// Calls the function, returning `failure` instead if it panics, so that no panic
+/// unwinds into C.
+fn catch<T>(failure: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(failure)
+}
+
+/// Locks the table of live mazes.  The table is only changed by inserting or removing a
+/// whole maze, and a maze's buffers only after they're complete, so a poisoned lock is
+/// used anyway.
+fn mazes() -> MutexGuard<'static, MazeTable> {
+    MAZES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Calls the function with the maze for a handle, if it's live, holding the maze's own
+/// lock meanwhile; the table is unlocked as soon as the maze is found.  Returns None if
+/// the handle isn't live.  As with the table, a poisoned lock is used anyway.
+fn with_maze<T>(handle: *const MazeHandle, f: impl FnOnce(&mut Maze) -> T) -> Option<T> {
+    let maze = mazes().mazes.get(&(handle as usize)).cloned()?;
+    let mut maze = maze.lock().unwrap_or_else(|e| e.into_inner());
+    Some(f(&mut maze))
+}
+
+/// Returns a buffer to C, storing its length, or null with a length of zero.
+unsafe fn result<T>(buf: Option<(*const T, usize)>, out_len: *mut usize) -> *const T {
+    let (ptr, len) = buf.unwrap_or((ptr::null(), 0));
+
+    if !out_len.is_null() {
+        *out_len = len;
+    }
+    ptr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+    use std::env;
+    use std::path::Path;
+    use std::process::Command;
+    use std::slice;
+
+    #[test]
+    fn test_ffi_generate_and_query() {
+        unsafe {
+            let handle = mazegen_generate(5, 7, MAZEGEN_BACKTRACKER, 42);
+            assert!(!handle.is_null());
+
+            let (mut rows, mut cols) = (0, 0);
+            mazegen_dims(handle, &mut rows, &mut cols);
+            assert_eq!((rows, cols), (5, 7));
+
+            // The same seed gives the same maze.
+            let mut grid = Grid::new(5, 7);
+            crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(42));
+            for cell in 0..35 {
+                assert_eq!(
+                    mazegen_is_linked(handle, cell, MAZEGEN_EAST),
+                    grid.is_linked_east(cell as usize)
+                );
+                assert_eq!(
+                    mazegen_is_linked(handle, cell, MAZEGEN_SOUTH),
+                    grid.is_linked_south(cell as usize)
+                );
+            }
+            assert!(!mazegen_is_linked(handle, 0, MAZEGEN_NORTH));
+            assert!(!mazegen_is_linked(handle, 35, MAZEGEN_EAST));
+            assert!(!mazegen_is_linked(handle, 0, 17));
+
+            // Solving
+            let mut len = 99;
+            let path = mazegen_solve(handle, 0, 34, &mut len);
+            let path = slice::from_raw_parts(path, len);
+            let expected: Vec<u32> = grid
+                .shortest_path(0, 34)
+                .cells()
+                .iter()
+                .map(|c| *c as u32)
+                .collect();
+            assert_eq!(path, &expected[..]);
+
+            assert!(mazegen_solve(handle, 0, 35, &mut len).is_null());
+            assert_eq!(len, 0);
+
+            // Rendering
+            let png = mazegen_render_png(handle, &mut len);
+            let png = slice::from_raw_parts(png, len);
+            let image = image::load_from_memory(png).unwrap();
+            let expected = grid.to_image();
+            assert_eq!(image.dimensions(), expected.dimensions());
+            for (x, y, pixel) in expected.enumerate_pixels() {
+                assert_eq!(image.get_pixel(x, y), *pixel);
+            }
+
+            mazegen_free(handle);
+        }
+    }
+
+    #[test]
+    fn test_ffi_invalid_handles() {
+        unsafe {
+            assert!(mazegen_generate(0, 5, MAZEGEN_BACKTRACKER, 1).is_null());
+            assert!(mazegen_generate(5, 5, 99, 1).is_null());
+
+            let handle = mazegen_generate(3, 3, MAZEGEN_DIVISION, 1);
+            mazegen_free(handle);
+
+            // Double frees and nulls are ignored; freed and null handles fail.  Handles
+            // aren't reused, so a stale double free can't free another maze.
+            let other = mazegen_generate(3, 3, MAZEGEN_DIVISION, 1);
+            assert_ne!(other, handle);
+            mazegen_free(handle);
+            mazegen_free(ptr::null_mut());
+            let (mut rows, mut cols) = (0, 0);
+            mazegen_dims(other, &mut rows, &mut cols);
+            assert_eq!((rows, cols), (3, 3));
+            mazegen_free(other);
+
+            for &h in &[handle, ptr::null_mut()] {
+                let (mut rows, mut cols) = (9, 9);
+                mazegen_dims(h, &mut rows, &mut cols);
+                assert_eq!((rows, cols), (0, 0));
+                assert!(!mazegen_is_linked(h, 0, MAZEGEN_EAST));
+
+                let mut len = 9;
+                assert!(mazegen_solve(h, 0, 1, &mut len).is_null());
+                assert_eq!(len, 0);
+                assert!(mazegen_render_png(h, ptr::null_mut()).is_null());
+            }
+        }
+    }
+
+    #[test]
+    fn test_ffi_huge_dimensions() {
+        // Grids too large to allocate or render are refused, not panicked over.
+        assert!(mazegen_generate(u32::MAX, u32::MAX, MAZEGEN_BACKTRACKER, 1).is_null());
+        assert!(mazegen_generate(u32::MAX, 1, MAZEGEN_BACKTRACKER, 1).is_null());
+        assert!(mazegen_generate(1024, 1025, MAZEGEN_SIDEWINDER, 1).is_null());
+
+        // A panic returns the failure value.
+        assert!(catch(ptr::null_mut::<MazeHandle>(), || panic!("no unwinding")).is_null());
+    }
+
+    #[test]
+    fn test_ffi_handles_lock_separately() {
+        let busy = mazegen_generate(3, 3, MAZEGEN_BACKTRACKER, 1);
+        let other = mazegen_generate(4, 2, MAZEGEN_BACKTRACKER, 1);
+
+        // While one maze is in use, the others can still be used, created, and freed.
+        let maze = mazes().mazes[&(busy as usize)].clone();
+        let _guard = maze.lock().unwrap();
+
+        let (mut rows, mut cols) = (0, 0);
+        unsafe { mazegen_dims(other, &mut rows, &mut cols) };
+        assert_eq!((rows, cols), (4, 2));
+        let third = mazegen_generate(2, 2, MAZEGEN_SIDEWINDER, 1);
+        assert!(!third.is_null());
+        mazegen_free(third);
+        mazegen_free(other);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_ffi_c_header() {
+        // Compiles examples/ffi_smoke.c against include/mazegen.h, links it with the
+        // cdylib that cargo builds alongside this test, and runs it.  Skipped if there's no
+        // C compiler or no cdylib, e.g., when only the lib tests were built.  Cargo puts
+        // target/debug on the library path, and the cdylib there may have been built
+        // without the "ffi" feature, so the library path is pointed at this build's.
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+        let lib = deps.join(format!(
+            "{}mazegen{}",
+            env::consts::DLL_PREFIX,
+            env::consts::DLL_SUFFIX
+        ));
+        if !lib.exists() {
+            eprintln!("skipped: no {}", lib.display());
+            return;
+        }
+
+        let exe = env::temp_dir().join(format!("mazegen_ffi_smoke_{}", std::process::id()));
+        let compiled = Command::new("cc")
+            .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-I"])
+            .arg(root.join("include"))
+            .arg(root.join("examples/ffi_smoke.c"))
+            .arg("-L")
+            .arg(&deps)
+            .args(["-lmazegen", "-o"])
+            .arg(&exe)
+            .status();
+
+        match compiled {
+            Ok(status) => assert!(status.success(), "ffi_smoke.c didn't compile and link"),
+            Err(_) => {
+                eprintln!("skipped: no C compiler");
+                return;
+            }
+        }
+
+        let output = Command::new(&exe)
+            .env("LD_LIBRARY_PATH", &deps)
+            .env("DYLD_LIBRARY_PATH", &deps)
+            .output()
+            .unwrap();
+        let _ = std::fs::remove_file(&exe);
+        assert!(
+            output.status.success(),
+            "ffi_smoke failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
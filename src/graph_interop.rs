@@ -0,0 +1,142 @@
+//! Conversions between grids and `petgraph` graphs, for analyzing mazes with petgraph's
+//! algorithms.  Enabled by the "petgraph" feature.
+//!
+//! A grid becomes an undirected graph with one node per cell, whose weight is the cell's
+//! ID, and one edge per link.
+
+use crate::Cell;
+use crate::Grid;
+use petgraph::graph::NodeIndex;
+use petgraph::graph::UnGraph;
+
+impl Grid {
+    /// Converts the grid to an undirected petgraph graph: node k has weight k, the ID of
+    /// cell k, and there's one edge for each link, in the order given by `link_pairs`.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(4, 5);
+    /// recursive_backtracker(&mut grid);
+    ///
+    /// let graph = grid.to_petgraph();
+    /// assert_eq!(graph.node_count(), 20);
+    /// assert_eq!(graph.edge_count(), 19);
+    /// ```
+    pub fn to_petgraph(&self) -> UnGraph<Cell, ()> {
+        let mut graph = UnGraph::with_capacity(self.num_cells(), self.num_links());
+
+        for cell in 0..self.num_cells() {
+            graph.add_node(cell);
+        }
+
+        for (a, b) in self.link_pairs() {
+            graph.add_edge(NodeIndex::new(a), NodeIndex::new(b), ());
+        }
+
+        graph
+    }
+
+    /// Creates a planar grid with the given number of rows and columns from an undirected
+    /// petgraph graph whose node weights are cell IDs, such as one returned by
+    /// `to_petgraph`, linking the cells joined by each edge.  Returns an error if either
+    /// dimension is zero, if a node's weight isn't a cell in the grid or is shared with
+    /// another node, or if an edge joins cells that aren't neighbors.  Nodes needn't be
+    /// given for cells that have no links.
+    pub fn from_petgraph(
+        num_rows: usize,
+        num_cols: usize,
+        graph: &UnGraph<Cell, ()>,
+    ) -> Result<Grid, String> {
+        if num_rows == 0 || num_cols == 0 {
+            return Err(format!(
+                "expected a grid of at least 1x1 cells, got {}x{}",
+                num_rows, num_cols
+            ));
+        }
+
+        // FIRST, check the nodes.
+        let mut grid = Grid::new(num_rows, num_cols);
+        let mut seen = vec![false; grid.num_cells()];
+
+        for node in graph.raw_nodes() {
+            let cell = node.weight;
+
+            if !grid.contains(cell) {
+                return Err(format!("node weight is not a cell in the grid: {}", cell));
+            }
+
+            if seen[cell] {
+                return Err(format!("more than one node has cell {}", cell));
+            }
+            seen[cell] = true;
+        }
+
+        // NEXT, link the cells.
+        for edge in graph.raw_edges() {
+            let a = graph[edge.source()];
+            let b = graph[edge.target()];
+
+            if !grid.neighbors(a).contains(&b) {
+                return Err(format!(
+                    "edge joins cells that aren't neighbors: {}, {}",
+                    a, b
+                ));
+            }
+
+            grid.link(a, b);
+        }
+
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_petgraph_round_trip() {
+        let mut grid = Grid::new(6, 8);
+        crate::hunt_and_kill_rng(&mut grid, &mut StdRng::seed_from_u64(2048));
+        grid.braid_rng(0.5, &mut StdRng::seed_from_u64(1));
+
+        let graph = grid.to_petgraph();
+        assert_eq!(graph.node_count(), grid.num_cells());
+        assert_eq!(graph.edge_count(), grid.num_links());
+
+        // Each node's degree is its cell's number of links.
+        let degrees = grid.degree_sequence();
+        for node in graph.node_indices() {
+            assert_eq!(graph.neighbors(node).count(), degrees[graph[node]]);
+        }
+
+        assert_eq!(Grid::from_petgraph(6, 8, &graph), Ok(grid));
+    }
+
+    #[test]
+    fn test_petgraph_import_errors() {
+        let mut graph = UnGraph::<Cell, ()>::new_undirected();
+        let a = graph.add_node(0);
+        let b = graph.add_node(2);
+
+        // A graph without edges is an unlinked grid.
+        assert_eq!(Grid::from_petgraph(2, 2, &graph), Ok(Grid::new(2, 2)));
+        assert!(Grid::from_petgraph(0, 2, &graph).is_err());
+
+        // Cells 0 and 2 are neighbors in a 2x2 grid, but not in a 1x3 grid.
+        graph.add_edge(a, b, ());
+        assert!(Grid::from_petgraph(2, 2, &graph).unwrap().is_linked(0, 2));
+        assert_eq!(
+            Grid::from_petgraph(1, 3, &graph),
+            Err("edge joins cells that aren't neighbors: 0, 2".to_string())
+        );
+
+        let c = graph.add_node(2);
+        assert!(Grid::from_petgraph(2, 2, &graph).is_err());
+        graph[c] = 4;
+        assert!(Grid::from_petgraph(2, 2, &graph).is_err());
+    }
+}
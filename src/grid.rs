@@ -1,12 +1,52 @@
 //! This module defines Grid, a rectilinear Grid for building mazes with.
 
+use crate::maze_grid::path_back;
+use crate::union_find::UnionFind;
 use crate::Cell;
+use crate::CellPath;
+use crate::FairStarts;
 use crate::GridDirection;
 use crate::ImageGridRenderer;
+use crate::Mask;
+use crate::MazeGrid;
+use crate::RenderabilityIssue;
 use crate::TextGridRenderer;
+use crate::Theme;
+use image::Rgba;
 use image::RgbaImage;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::Display;
+use std::sync::Mutex;
+
+/// The way a Grid's edges connect.  On a planar grid the cells on the edges have no
+/// neighbors beyond them.  On a cylinder the east and west edges wrap around, so that
+/// the cells in the first and last columns are neighbors; on a torus the north and south
+/// edges wrap around as well.  An edge only wraps if the grid is at least three cells
+/// across in that direction, so that no cell is its own neighbor or a neighbor twice.
+///
+/// Wrapped neighbors are ordinary neighbors to the maze algorithms and to path finding;
+/// walls across the wrap have no wall index, and the renderers draw the grid flat, with
+/// a passage across the wrap shown as a gap in the outer wall.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topology {
+    #[default]
+    Planar,
+    Cylinder,
+    Torus,
+}
+
+impl Topology {
+    pub(crate) fn is_planar(&self) -> bool {
+        *self == Topology::Planar
+    }
+}
 
 /// A rectangular grid of cells, which can be used to represent a maze.
 /// Each cell has its neighbors to the north, south, east, and west (as constrained by
@@ -19,16 +59,91 @@ use std::fmt::Display;
 ///
 /// A Grid is created with a particular number of rows and columns.  Initially no cell is
 /// linked to any other cell.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
 pub struct Grid {
     num_rows: usize,
     num_cols: usize,
     num_cells: usize,
     cells: Vec<CellData>,
+
+    /// How the grid's edges connect.
+    topology: Topology,
+
+    /// The openings in the outer walls, sorted.
+    openings: Vec<(Cell, GridDirection)>,
+
+    /// Incremented whenever the grid is modified.
+    revision: u64,
+
+    /// The diameter as of the given revision, if it's been computed.
+    diameter_cache: DiameterCache,
+
+    /// The links made, in order, while they're being recorded; see `generate_with_order`.
+    link_log: Option<Vec<(Cell, Cell)>>,
+
+    /// The cells that have been linked or have lost neighbors since the grid was created
+    /// or reset, and a flag for each cell saying whether it's in the list; see `reset`.
+    dirty_cells: Vec<Cell>,
+    is_dirty: Vec<bool>,
+}
+
+// Two grids are equal if they have the same topology, cells, links, and openings, whatever
+// their revisions.
+impl PartialEq for Grid {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_rows == other.num_rows
+            && self.num_cols == other.num_cols
+            && self.topology == other.topology
+            && self.cells == other.cells
+            && self.openings == other.openings
+    }
 }
 
+impl Eq for Grid {}
+
 impl Grid {
+    /// The largest number of cells in a grid read from JSON, which may come from an
+    /// untrusted source.
+    pub const MAX_JSON_CELLS: usize = 1 << 20;
+
+    /// Creates a new grid with the given number of rows and columns.  Initially no cell
+    /// is linked to any other cell.  Panics if either dimension is zero.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let grid = Grid::new(3, 4);
+    /// assert_eq!(grid.num_cells(), 12);
+    /// assert_eq!(grid.cell(1, 2), 6);
+    /// assert_eq!(grid.ij(6), (1, 2));
+    /// assert_eq!(grid.num_links(), 0);
+    /// ```
     pub fn new(num_rows: usize, num_cols: usize) -> Self {
+        Grid::with_topology(num_rows, num_cols, Topology::Planar)
+    }
+
+    /// Creates a new grid with the given number of rows and columns whose edges connect
+    /// according to the topology.  Initially no cell is linked to any other cell.  Panics
+    /// if either dimension is zero.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let grid = Grid::with_topology(3, 4, Topology::Cylinder);
+    /// assert_eq!(grid.west_of(grid.cell(0, 0)), Some(grid.cell(0, 3)));
+    /// assert_eq!(grid.north_of(grid.cell(0, 0)), None);
+    ///
+    /// let grid = Grid::with_topology(3, 4, Topology::Torus);
+    /// assert_eq!(grid.north_of(grid.cell(0, 0)), Some(grid.cell(2, 0)));
+    /// ```
+    pub fn with_topology(num_rows: usize, num_cols: usize, topology: Topology) -> Self {
+        assert!(
+            num_rows > 0 && num_cols > 0,
+            "a grid must have at least one row and one column, got {}x{}",
+            num_rows,
+            num_cols
+        );
+
         // FIRST, initialize the cells vector
         let num_cells = num_rows * num_cols;
         let cells = Vec::with_capacity(num_cells);
@@ -38,35 +153,18 @@ impl Grid {
             num_cols,
             num_cells,
             cells,
+            topology,
+            openings: Vec::new(),
+            revision: 0,
+            diameter_cache: DiameterCache::default(),
+            link_log: None,
+            dirty_cells: Vec::new(),
+            is_dirty: vec![false; num_cells],
         };
 
         for cell in 0..num_cells {
-            let i = grid.i(cell);
-            let j = grid.j(cell);
-
-            let north = if i > 0 {
-                Some(grid.cell(i - 1, j))
-            } else {
-                None
-            };
-
-            let south = if i < num_rows - 1 {
-                Some(grid.cell(i + 1, j))
-            } else {
-                None
-            };
-
-            let east = if j < num_cols - 1 {
-                Some(grid.cell(i, j + 1))
-            } else {
-                None
-            };
-
-            let west = if j > 0 {
-                Some(grid.cell(i, j - 1))
-            } else {
-                None
-            };
+            let [north, south, east, west] =
+                compute_neighbors(num_rows, num_cols, topology, cell);
 
             grid.cells.push(CellData {
                 cell,
@@ -81,6 +179,21 @@ impl Grid {
         grid
     }
 
+    /// Creates a new grid with the same dimensions as the mask, in which the mask's dead
+    /// cells are isolated: they have no neighbors, and so the maze algorithms will never
+    /// link to them.
+    pub fn from_mask(mask: &Mask) -> Self {
+        let mut grid = Grid::new(mask.num_rows(), mask.num_cols());
+
+        for cell in 0..grid.num_cells {
+            if !mask[grid.ij(cell)] {
+                grid.isolate(cell);
+            }
+        }
+
+        grid
+    }
+
     /// The number of rows in the grid.
     pub fn num_rows(&self) -> usize {
         self.num_rows
@@ -91,6 +204,11 @@ impl Grid {
         self.num_cols
     }
 
+    /// The grid's topology: how its edges connect.
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
     /// The number of cells in the grid.
     pub fn num_cells(&self) -> usize {
         self.num_cells
@@ -120,7 +238,91 @@ impl Grid {
         (cell / self.num_cols, cell % self.num_cols)
     }
 
-    // Links cell 1 to cell 2.
+    /// Gets the cell's name, spreadsheet style: the column as letters and the row as a
+    /// number.  Columns are lettered from the left, "A" to "Z" and then "AA", "AB", and so
+    /// on; rows are numbered from 1 at the top.  Thus cell (i,j) = (0,0) is "A1", and
+    /// (2,27) is "AB3".
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let grid = Grid::new(5, 30);
+    /// assert_eq!(grid.cell_name(grid.cell(0, 0)), "A1");
+    /// assert_eq!(grid.cell_name(grid.cell(2, 1)), "B3");
+    /// assert_eq!(grid.cell_name(grid.cell(4, 26)), "AA5");
+    /// assert_eq!(grid.cell_from_name("AA5"), Ok(grid.cell(4, 26)));
+    /// ```
+    pub fn cell_name(&self, cell: Cell) -> String {
+        let (i, j) = self.ij(cell);
+
+        // The column letters are a base-26 number with digits A to Z, and no zero.
+        let mut letters = Vec::new();
+        let mut n = j + 1;
+
+        while n > 0 {
+            n -= 1;
+            letters.push((b'A' + (n % 26) as u8) as char);
+            n /= 26;
+        }
+
+        letters.iter().rev().collect::<String>() + &(i + 1).to_string()
+    }
+
+    /// Gets the cell with the given name, e.g., "B3"; see `cell_name`.  The column letters
+    /// may be in either case.  Returns an error if the name is malformed or names a cell
+    /// outside the grid.
+    pub fn cell_from_name(&self, name: &str) -> Result<Cell, String> {
+        let malformed = || format!("expected a cell name like \"B3\", got \"{}\"", name);
+        let outside = || format!("cell \"{}\" is outside the grid", name);
+
+        // FIRST, split the name into letters and digits.
+        let split = name
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(name.len());
+        let (letters, digits) = name.split_at(split);
+
+        if letters.is_empty() || digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(malformed());
+        }
+
+        // NEXT, convert the letters to a column index.
+        let mut col: usize = 0;
+
+        for ch in letters.to_ascii_uppercase().bytes() {
+            col = col
+                .checked_mul(26)
+                .and_then(|c| c.checked_add((ch - b'A') as usize + 1))
+                .ok_or_else(outside)?;
+        }
+
+        // NEXT, convert the digits to a row index.
+        let row: usize = digits.parse().map_err(|_| outside())?;
+
+        if row == 0 {
+            return Err(malformed());
+        }
+
+        let (i, j) = (row - 1, col - 1);
+
+        if i < self.num_rows && j < self.num_cols {
+            Ok(self.cell(i, j))
+        } else {
+            Err(outside())
+        }
+    }
+
+    /// Links cell 1 to cell 2.  Links are bidirectional.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let mut grid = Grid::new(2, 2);
+    /// grid.link(0, 1);
+    /// assert!(grid.is_linked(0, 1));
+    /// assert!(grid.is_linked(1, 0));
+    /// assert!(grid.is_linked_east(0));
+    /// assert!(!grid.is_linked(0, 2));
+    /// ```
     // TODO: The linked cells should always be adjacent; but this implementation doesn't
     // require it.  Later in the book, the author talks about "braiding"; possibly,
     // braiding involves non-adjacent links.  If not, an assertion should be put in.
@@ -130,12 +332,52 @@ impl Grid {
 
         self.cells[cell1].link(cell2);
         self.cells[cell2].link(cell1);
+        self.mark_dirty(cell1);
+        self.mark_dirty(cell2);
+        self.revision += 1;
+
+        if let Some(log) = self.link_log.as_mut() {
+            log.push((cell1, cell2));
+        }
+    }
+
+    /// Links the cell to its neighbor in the given direction, if it has one, returning
+    /// true if it did.  Returns false, linking nothing, if there's no cell in that
+    /// direction, e.g., at the edge of the grid or next to an isolated cell.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(2, 2);
+    /// assert!(grid.link_direction(0, GridDirection::East));
+    /// assert!(grid.is_linked(0, 1));
+    /// assert!(!grid.link_direction(0, GridDirection::North));
+    /// ```
+    pub fn link_direction(&mut self, cell: Cell, dir: GridDirection) -> bool {
+        match self.cell_to(cell, dir) {
+            Some(other) => {
+                self.link(cell, other);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Starts recording the links made, in order, discarding any earlier record.
+    pub(crate) fn record_links(&mut self) {
+        self.link_log = Some(Vec::new());
+    }
+
+    /// Stops recording links, and returns the links made since recording started.
+    pub(crate) fn take_link_log(&mut self) -> Vec<(Cell, Cell)> {
+        self.link_log.take().unwrap_or_default()
     }
 
     // Unlinks cell 1 from cell 2
     pub fn unlink(&mut self, cell1: Cell, cell2: Cell) {
         self.cells[cell1].unlink(cell2);
         self.cells[cell2].unlink(cell1);
+        self.revision += 1;
     }
 
     // Gets the cells linked to this cell
@@ -168,6 +410,267 @@ impl Grid {
         self.cells[cell].neighbors()
     }
 
+    /// Gets the cells to the north, south, east, and west of this cell by position alone,
+    /// in that order, or None at the edges of the grid, given its topology.  Unlike
+    /// `neighbors`, this ignores isolation.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let grid = Grid::new(3, 4);
+    /// assert_eq!(grid.neighbors_of(0), [None, Some(4), Some(1), None]);
+    /// assert_eq!(grid.neighbors_of(5), [Some(1), Some(9), Some(6), Some(4)]);
+    /// ```
+    pub fn neighbors_of(&self, cell: Cell) -> [Option<Cell>; 4] {
+        assert!(self.contains(cell));
+        compute_neighbors(self.num_rows, self.num_cols, self.topology, cell)
+    }
+
+    /// Returns a random cell from the grid.
+    pub fn random_cell(&self) -> Cell {
+        self.random_cell_with(&mut thread_rng())
+    }
+
+    /// Returns a random cell from the grid, using the given random number generator.
+    /// Isolated cells are never chosen, unless every cell is isolated.
+    pub fn random_cell_with(&self, rng: &mut impl Rng) -> Cell {
+        let cell = rng.gen_range(0, self.num_cells);
+
+        if self.num_cells == 1 || !self.cells[cell].neighbors().is_empty() {
+            return cell;
+        }
+
+        // The cell has been isolated; choose from the cells that haven't been.
+        let candidates: Vec<Cell> = (0..self.num_cells)
+            .filter(|c| !self.cells[*c].neighbors().is_empty())
+            .collect();
+
+        if candidates.is_empty() {
+            cell
+        } else {
+            candidates[rng.gen_range(0, candidates.len())]
+        }
+    }
+
+    /// Returns `count` distinct random cells from the grid, using the given random number
+    /// generator, e.g., as the starting points of an algorithm that grows from several
+    /// origins.  Isolated cells are never chosen; if there are fewer than `count` other
+    /// cells, they are all returned, in random order.
+    pub fn seed_points(&self, count: usize, rng: &mut impl Rng) -> Vec<Cell> {
+        let candidates: Vec<Cell> = (0..self.num_cells)
+            .filter(|c| !self.is_isolated(*c))
+            .collect();
+        let count = count.min(candidates.len());
+
+        rand::seq::index::sample(rng, candidates.len(), count)
+            .into_iter()
+            .map(|i| candidates[i])
+            .collect()
+    }
+
+    /// Isolates the cell, unlinking it from its neighbors and removing it from their
+    /// neighbor lists, so that it's no longer part of the maze.  This is how dead cells
+    /// are excluded from a masked grid.
+    pub fn isolate(&mut self, cell: Cell) {
+        self.unlink_all(cell);
+
+        let data = &mut self.cells[cell];
+        let (north, south, east, west) = (data.north, data.south, data.east, data.west);
+        data.north = None;
+        data.south = None;
+        data.east = None;
+        data.west = None;
+
+        if let Some(c) = north {
+            self.cells[c].south = None;
+        }
+        if let Some(c) = south {
+            self.cells[c].north = None;
+        }
+        if let Some(c) = east {
+            self.cells[c].west = None;
+        }
+        if let Some(c) = west {
+            self.cells[c].east = None;
+        }
+
+        self.mark_dirty(cell);
+
+        for c in [north, south, east, west].iter().flatten() {
+            self.mark_dirty(*c);
+        }
+
+        self.revision += 1;
+    }
+
+    /// Adds the cell to the list of cells that `reset` must restore, if it isn't there.
+    fn mark_dirty(&mut self, cell: Cell) {
+        if !self.is_dirty[cell] {
+            self.is_dirty[cell] = true;
+            self.dirty_cells.push(cell);
+        }
+    }
+
+    /// Unlinks the cell from every cell to which it's linked.  Unlike `isolate`, the cell
+    /// remains part of the maze and can be linked again.
+    pub fn unlink_all(&mut self, cell: Cell) {
+        assert!(self.contains(cell));
+
+        for other in self.links(cell) {
+            self.unlink(cell, other);
+        }
+    }
+
+    /// Links every pair of neighboring cells, leaving the grid with no interior walls.
+    /// Cells that have been isolated aren't linked.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(3, 4);
+    /// grid.link_all();
+    ///
+    /// // 3 rows of 3 horizontal links, and 2 rows of 4 vertical links
+    /// assert_eq!(grid.num_links(), 17);
+    /// ```
+    pub fn link_all(&mut self) {
+        for cell in 0..self.num_cells() {
+            for other in self.neighbors(cell) {
+                if cell < other {
+                    self.link(cell, other);
+                }
+            }
+        }
+    }
+
+    /// Carves an open room: links every pair of adjacent cells in the rectangle whose
+    /// upper-left cell is at (top, left), and which is `height` rows by `width` columns.
+    /// Cells that have been isolated aren't linked.  Panics if the rectangle is empty or
+    /// doesn't fit in the grid.  Use `recursive_backtracker_preserve` to carve a maze
+    /// around the rooms.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(5, 5);
+    /// grid.carve_room(1, 1, 2, 3);
+    ///
+    /// // A 2x3 room has 7 links.
+    /// assert_eq!(grid.num_links(), 7);
+    /// assert!(grid.is_linked_south(grid.cell(1, 3)));
+    /// ```
+    pub fn carve_room(&mut self, top: usize, left: usize, height: usize, width: usize) {
+        assert!(
+            height > 0 && width > 0,
+            "a room must have at least one row and one column, got {}x{}",
+            height,
+            width
+        );
+        assert!(
+            top + height <= self.num_rows && left + width <= self.num_cols,
+            "a {}x{} room at ({}, {}) doesn't fit in a {}x{} grid",
+            height,
+            width,
+            top,
+            left,
+            self.num_rows,
+            self.num_cols
+        );
+
+        for i in top..top + height {
+            for j in left..left + width {
+                let cell = self.cell(i, j);
+
+                if i + 1 < top + height {
+                    if let Some(south) = self.south_of(cell) {
+                        self.link(cell, south);
+                    }
+                }
+                if j + 1 < left + width {
+                    if let Some(east) = self.east_of(cell) {
+                        self.link(cell, east);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if the cell has been isolated, i.e., has no neighbors.
+    pub fn is_isolated(&self, cell: Cell) -> bool {
+        assert!(self.contains(cell));
+        self.num_cells > 1 && self.cells[cell].neighbors().is_empty()
+    }
+
+    /// Isolates every cell that isn't in the given list, so that only the listed cells
+    /// remain part of the maze.  See `isolate`.
+    pub fn keep_only(&mut self, cells: &[Cell]) {
+        let mut keep = vec![false; self.num_cells];
+
+        for &cell in cells {
+            assert!(self.contains(cell));
+            keep[cell] = true;
+        }
+
+        for (cell, keep) in keep.into_iter().enumerate() {
+            if !keep {
+                self.isolate(cell);
+            }
+        }
+    }
+
+    /// Returns the cells of the largest connected region of the grid, in order of cell ID.
+    /// Two cells are in the same region if there's a chain of neighbors from one to the
+    /// other, whether or not they are linked; so after masking, this is the largest area
+    /// in which a maze can be carved.  Ties go to the region containing the lowest cell ID.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// // Two regions, separated by a dead column.
+    /// let mask = Mask::from_text("ooXo\nooXo").unwrap();
+    /// let mut grid = Grid::from_mask(&mask);
+    ///
+    /// let region = grid.largest_component();
+    /// assert_eq!(region, vec![0, 1, 4, 5]);
+    ///
+    /// // Discard the rest, and carve a maze in what's left.
+    /// grid.keep_only(&region);
+    /// recursive_backtracker(&mut grid);
+    /// assert_eq!(grid.num_links(), 3);
+    /// ```
+    pub fn largest_component(&self) -> Vec<Cell> {
+        let mut seen = vec![false; self.num_cells];
+        let mut largest: Vec<Cell> = Vec::new();
+
+        for start in 0..self.num_cells {
+            if seen[start] {
+                continue;
+            }
+
+            // Collect the region containing this cell.
+            seen[start] = true;
+            let mut region = vec![start];
+            let mut next = 0;
+
+            while next < region.len() {
+                for n in self.neighbors(region[next]) {
+                    if !seen[n] {
+                        seen[n] = true;
+                        region.push(n);
+                    }
+                }
+                next += 1;
+            }
+
+            if region.len() > largest.len() {
+                largest = region;
+            }
+        }
+
+        largest.sort_unstable();
+        largest
+    }
+
     /// Does the grid contain the location?
     pub fn contains(&self, cell: Cell) -> bool {
         // NOTE: No need to check against zero, since we're using an unsigned type.
@@ -252,142 +755,2069 @@ impl Grid {
         }
     }
 
-    /// Returns the grid to its initial state:  no cell is linked to any other cell.
+    /// Gets the number of interior walls in the grid: the walls between horizontally or
+    /// vertically adjacent cells.  See `wall_index`.
+    pub fn num_walls(&self) -> usize {
+        self.num_rows * (self.num_cols - 1) + (self.num_rows - 1) * self.num_cols
+    }
+
+    /// Gets the canonical index of the wall on the given side of the cell, or None if that
+    /// side is on the grid's boundary.  Each interior wall is named by the cell to its west
+    /// or north, and the walls are numbered from 0 by visiting the cells in row-major
+    /// order, taking each cell's east wall and then its south wall.  Thus the north wall
+    /// of a cell has the same index as the south wall of the cell above it.  The index
+    /// depends only on the grid's dimensions, not on its links or on isolated cells.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let grid = Grid::new(2, 3);
+    /// assert_eq!(grid.num_walls(), 7);
+    /// assert_eq!(grid.wall_index(0, GridDirection::East), Some(0));
+    /// assert_eq!(grid.wall_index(0, GridDirection::South), Some(1));
+    /// assert_eq!(grid.wall_index(3, GridDirection::North), Some(1));
+    /// assert_eq!(grid.wall_index(2, GridDirection::South), Some(4));
+    /// assert_eq!(grid.wall_index(4, GridDirection::East), Some(6));
+    /// assert_eq!(grid.wall_index(0, GridDirection::West), None);
+    /// ```
+    pub fn wall_index(&self, cell: Cell, dir: GridDirection) -> Option<usize> {
+        assert!(self.contains(cell));
+        let (i, j) = self.ij(cell);
+
+        // FIRST, name the wall by the cell to its west or north.
+        let (i, j, dir) = match dir {
+            GridDirection::North if i > 0 => (i - 1, j, GridDirection::South),
+            GridDirection::West if j > 0 => (i, j - 1, GridDirection::East),
+            GridDirection::South if i + 1 < self.num_rows => (i, j, dir),
+            GridDirection::East if j + 1 < self.num_cols => (i, j, dir),
+            _ => return None,
+        };
+
+        // NEXT, every row but the last has (cols - 1) east walls and cols south walls,
+        // and every cell in such a row but the last has two walls.
+        let base = i * (2 * self.num_cols - 1);
+
+        if i + 1 == self.num_rows {
+            Some(base + j)
+        } else if dir == GridDirection::East || j + 1 == self.num_cols {
+            Some(base + 2 * j)
+        } else {
+            Some(base + 2 * j + 1)
+        }
+    }
+
+    /// Gets the wall with the given canonical index as a cell and direction, East or South;
+    /// the inverse of `wall_index`.  Returns None if there's no such wall.
+    pub fn wall_at(&self, index: usize) -> Option<(Cell, GridDirection)> {
+        if index >= self.num_walls() {
+            return None;
+        }
+
+        let per_row = 2 * self.num_cols - 1;
+        let (i, k) = (index / per_row, index % per_row);
+
+        let (j, dir) = if i + 1 == self.num_rows {
+            (k, GridDirection::East)
+        } else if k + 1 == per_row {
+            (k / 2, GridDirection::South)
+        } else if k & 1 == 0 {
+            (k / 2, GridDirection::East)
+        } else {
+            (k / 2, GridDirection::South)
+        };
+
+        Some((self.cell(i, j), dir))
+    }
+
+    /// Returns the grid to its initial state:  no cell is linked to any other cell.  The
+    /// openings in the outer walls are retained.
     pub fn clear(&mut self) {
         for c in 0..self.num_cells {
             self.cells[c].links.clear();
         }
+        self.revision += 1;
     }
 
-    /// Computes the shortest distance from the cell to each other cell.
-    /// Returns the distances as a vector of length `num_cells`.
-    pub fn distances(&self, cell: Cell) -> Vec<Option<usize>> {
-        // FIRST, create a working vector.  Initially, no distances are computed.
-        let mut dists = Vec::<Option<usize>>::with_capacity(self.num_cells());
+    /// Returns a copy of the grid with no links: the same dimensions, topology, isolated
+    /// cells, and openings, as for `clone` followed by `clear`, but without copying the
+    /// links only to discard them.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(6, 8);
+    /// recursive_backtracker(&mut grid);
+    ///
+    /// let mut blank = grid.clone();
+    /// blank.clear();
+    /// assert_eq!(grid.clone_without_links(), blank);
+    /// ```
+    pub fn clone_without_links(&self) -> Grid {
+        Grid {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            num_cells: self.num_cells,
+            cells: self
+                .cells
+                .iter()
+                .map(|data| CellData {
+                    links: HashSet::new(),
+                    ..*data
+                })
+                .collect(),
+            topology: self.topology,
+            openings: self.openings.clone(),
+            revision: 0,
+            diameter_cache: DiameterCache::default(),
+            link_log: None,
+            dirty_cells: self.dirty_cells.clone(),
+            is_dirty: self.is_dirty.clone(),
+        }
+    }
 
-        for _ in 0..self.num_cells() {
-            dists.push(None);
+    /// Returns a new grid containing the `rows` x `cols` region of this grid whose top-left
+    /// cell is at (`top`, `left`).  The links between the region's cells are preserved;
+    /// links that cross the region's boundary are dropped.  The new grid is planar, so
+    /// links that wrap around a cylinder or torus are dropped as well, and it has no
+    /// openings; cells that are isolated in this grid are isolated in it.  Returns an
+    /// error if the region is empty or extends past the grid's edges.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(4, 5);
+    /// grid.link_all();
+    ///
+    /// let part = grid.crop(1, 2, 2, 3).unwrap();
+    /// assert_eq!((part.num_rows(), part.num_cols()), (2, 3));
+    /// assert_eq!(part.num_links(), 7);
+    /// assert!(grid.crop(3, 0, 2, 5).is_err());
+    /// ```
+    pub fn crop(&self, top: usize, left: usize, rows: usize, cols: usize) -> Result<Grid, String> {
+        if rows == 0 || cols == 0 {
+            return Err(format!(
+                "expected a region of at least 1x1 cells, got {}x{}",
+                rows, cols
+            ));
         }
 
-        // NEXT, use a (simplified) Dijkstra's algorithm to compute the distances.
-        // See "Mazes for Programmers" Ch. 3.
-        dists[cell] = Some(0);
-        let mut frontier = HashSet::new();
-        frontier.insert(cell);
+        if top + rows > self.num_rows || left + cols > self.num_cols {
+            return Err(format!(
+                "region of {}x{} cells at ({}, {}) extends past the edge of a {}x{} grid",
+                rows, cols, top, left, self.num_rows, self.num_cols
+            ));
+        }
 
-        while !frontier.is_empty() {
-            let mut new_frontier = HashSet::new();
+        let mut grid = Grid::new(rows, cols);
+        self.copy_into(&mut grid, (top, left, rows, cols), (0, 0));
+        Ok(grid)
+    }
 
-            for c in frontier {
-                for d in self.links(c) {
-                    if dists[d].is_none() {
-                        dists[d] = Some(dists[c].expect("valid distance") + 1);
-                        new_frontier.insert(d);
-                    }
-                }
-            }
-            frontier = new_frontier;
+    /// Returns a new grid with the given number of rows and columns, containing this
+    /// grid's maze in its top-left corner; the rest of the cells are unlinked, ready for
+    /// another maze to be carved and stitched to this one.  As for `crop`, the new grid is
+    /// planar and has no openings.  Returns an error if either dimension is smaller than
+    /// this grid's.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(3, 3);
+    /// recursive_backtracker(&mut grid);
+    ///
+    /// let bigger = grid.expanded(3, 6).unwrap();
+    /// assert_eq!(bigger.num_links(), grid.num_links());
+    /// assert_eq!(bigger.crop(0, 0, 3, 3), Ok(grid.clone()));
+    /// assert!(grid.expanded(2, 6).is_err());
+    /// ```
+    pub fn expanded(&self, new_rows: usize, new_cols: usize) -> Result<Grid, String> {
+        if new_rows < self.num_rows || new_cols < self.num_cols {
+            return Err(format!(
+                "can't expand a {}x{} grid to {}x{}",
+                self.num_rows, self.num_cols, new_rows, new_cols
+            ));
         }
 
-        // NEXT, return the distances.
-        dists
+        let mut grid = Grid::new(new_rows, new_cols);
+        self.copy_into(&mut grid, (0, 0, self.num_rows, self.num_cols), (0, 0));
+        Ok(grid)
     }
 
-    /// Computes the shortest path from the first cell to the second, returning the path
-    /// as a vector of cells.  If there is no path, the vector will be empty.
-    pub fn shortest_path(&self, start: Cell, goal: Cell) -> Vec<Cell> {
-        // FIRST, compute distances from the starting cell.
-        let dists = self.distances(start);
+    /// Returns a new grid with this grid's maze on the left and the other grid's on the
+    /// right, linked by `seam_links` passages through the wall between them, placed at
+    /// random.  One seam link joins two perfect mazes into a perfect maze; with none, the
+    /// two mazes remain separate components.  As for `crop`, the new grid is planar and
+    /// has no openings.  Returns an error if the grids have different numbers of rows, or
+    /// if there are more seam links than rows in which to place them.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut left = Grid::new(4, 3);
+    /// recursive_backtracker(&mut left);
+    /// let mut right = Grid::new(4, 5);
+    /// sidewinder_maze(&mut right);
+    ///
+    /// let both = left.append_east(&right, 1).unwrap();
+    /// assert_eq!((both.num_rows(), both.num_cols()), (4, 8));
+    /// assert!(both.is_perfect_maze());
+    /// assert!(left.append_east(&Grid::new(3, 3), 1).is_err());
+    /// ```
+    pub fn append_east(&self, other: &Grid, seam_links: usize) -> Result<Grid, String> {
+        self.append_east_rng(other, seam_links, &mut thread_rng())
+    }
 
-        // NEXT, compute a path from the goal back to start.
-        let mut path = Vec::new();
+    /// Appends the other grid east of this one using the given random number generator.
+    /// See `append_east`.
+    pub fn append_east_rng(
+        &self,
+        other: &Grid,
+        seam_links: usize,
+        rng: &mut impl Rng,
+    ) -> Result<Grid, String> {
+        if self.num_rows != other.num_rows {
+            return Err(format!(
+                "can't append a {}x{} grid east of a {}x{} grid: the numbers of rows differ",
+                other.num_rows, other.num_cols, self.num_rows, self.num_cols
+            ));
+        }
 
-        let mut current = goal;
-        path.push(current);
+        let (rows, cols) = (self.num_rows, self.num_cols);
+        let mut grid = Grid::new(rows, cols + other.num_cols);
+        self.copy_into(&mut grid, (0, 0, rows, cols), (0, 0));
+        other.copy_into(&mut grid, (0, 0, rows, other.num_cols), (0, cols));
 
-        while current != start {
-            let old_len = path.len();
+        let seam: Vec<(Cell, Cell)> = (0..rows)
+            .map(|i| (grid.cell(i, cols - 1), grid.cell(i, cols)))
+            .collect();
+        grid.link_seam(&seam, seam_links, rng)?;
 
-            // FIRST, get the next step in the path.
-            let cdist = dists[current].expect("valid distance");
-            for neighbor in self.links(current) {
-                let ndist = dists[neighbor].expect("valid distance");
+        Ok(grid)
+    }
 
-                if ndist < cdist {
-                    path.push(neighbor);
-                    current = neighbor;
-                    break;
-                }
-            }
+    /// Returns a new grid with this grid's maze on the top and the other grid's on the
+    /// bottom, linked by `seam_links` passages placed at random, as for `append_east`.
+    /// Returns an error if the grids have different numbers of columns, or if there are
+    /// more seam links than columns in which to place them.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut top = Grid::new(2, 6);
+    /// recursive_backtracker(&mut top);
+    ///
+    /// let both = top.append_south(&top, 0).unwrap();
+    /// assert_eq!((both.num_rows(), both.num_cols()), (4, 6));
+    /// assert_eq!(both.num_components(), 2);
+    /// ```
+    pub fn append_south(&self, other: &Grid, seam_links: usize) -> Result<Grid, String> {
+        self.append_south_rng(other, seam_links, &mut thread_rng())
+    }
 
-            // NEXT, if we didn't add a new step to the path then there is no path to start.
-            if path.len() == old_len {
-                path.clear();
-                break;
-            }
+    /// Appends the other grid south of this one using the given random number generator.
+    /// See `append_south`.
+    pub fn append_south_rng(
+        &self,
+        other: &Grid,
+        seam_links: usize,
+        rng: &mut impl Rng,
+    ) -> Result<Grid, String> {
+        if self.num_cols != other.num_cols {
+            return Err(format!(
+                "can't append a {}x{} grid south of a {}x{} grid: the numbers of columns differ",
+                other.num_rows, other.num_cols, self.num_rows, self.num_cols
+            ));
         }
 
-        // FINALLY, return the computed path.
-        path.reverse();
-        path
+        let (rows, cols) = (self.num_rows, self.num_cols);
+        let mut grid = Grid::new(rows + other.num_rows, cols);
+        self.copy_into(&mut grid, (0, 0, rows, cols), (0, 0));
+        other.copy_into(&mut grid, (0, 0, other.num_rows, cols), (rows, 0));
+
+        let seam: Vec<(Cell, Cell)> = (0..cols)
+            .map(|j| (grid.cell(rows - 1, j), grid.cell(rows, j)))
+            .collect();
+        grid.link_seam(&seam, seam_links, rng)?;
+
+        Ok(grid)
     }
 
-    /// Return the farthest cell from the given cell.
-    pub fn farthest(&self, start: Cell) -> Cell {
-        // Get distances from upper left corner
-        let dists = self.distances(start);
+    /// Links `count` of the pairs of cells across a seam, chosen at random from those
+    /// that are neighbors, i.e., in which neither cell is isolated.
+    fn link_seam(
+        &mut self,
+        seam: &[(Cell, Cell)],
+        count: usize,
+        rng: &mut impl Rng,
+    ) -> Result<(), String> {
+        let open: Vec<&(Cell, Cell)> = seam
+            .iter()
+            .filter(|(a, b)| self.neighbors(*a).contains(b))
+            .collect();
 
-        let mut max = 0;
-        let mut argmax = 0;
+        if count > open.len() {
+            return Err(format!(
+                "can't place {} seam links in a seam with room for {}",
+                count,
+                open.len()
+            ));
+        }
 
-        for c in 0..self.num_cells {
-            if let Some(dist) = dists[c] {
-                if dist > max {
-                    max = dist;
-                    argmax = c;
-                }
-            }
+        for &&(a, b) in open.choose_multiple(rng, count) {
+            self.link(a, b);
         }
 
-        argmax
+        Ok(())
     }
 
-    /// Get a list of the dead-end cells in the grid: those cells that link to
-    /// only one other cell
-    pub fn dead_ends(&self) -> Vec<Cell> {
-        (0..self.num_cells)
-            .filter(|c| self.links(*c).len() == 1)
-            .collect()
-    }
+    /// Copies the links and isolated cells of a region of this grid, given as (top row,
+    /// left column, rows, columns), into `grid` with its top-left cell at the given (row,
+    /// column), remapping the cell IDs; the region must fit.  Links that leave the region,
+    /// or that join cells that aren't neighbors in `grid`, are dropped.
+    fn copy_into(
+        &self,
+        grid: &mut Grid,
+        region: (usize, usize, usize, usize),
+        (at_i, at_j): (usize, usize),
+    ) {
+        let (top, left, num_rows, num_cols) = region;
+        let rows = top..top + num_rows;
+        let cols = left..left + num_cols;
 
-    /// Returns the longest path through the maze.
-    ///
-    /// TODO: This could be more efficient, since we end up computing the distances more often
-    /// than is really necessary.
-    pub fn longest_path(&self) -> Vec<Cell> {
-        let end = self.farthest(0);
-        let start = self.farthest(end);
-        self.shortest_path(start, end)
-    }
+        for i in rows.clone() {
+            for j in cols.clone() {
+                let cell = self.cell(i, j);
+                let a = grid.cell(at_i + i - top, at_j + j - left);
 
-    /// Renders the maze to an image::RgbaImage, which can then be modified further
-    /// or written to disk.
-    pub fn to_image(&self) -> RgbaImage {
-        ImageGridRenderer::new()
-            .cell_size(10)
-            .border_width(2)
-            .render(self)
-    }
-}
+                if self.is_isolated(cell) {
+                    grid.isolate(a);
+                    continue;
+                }
 
-// Output the maze dimensions and the maze itself using simply ASCII graphics.
-impl Display for Grid {
+                for link in self.links(cell) {
+                    let (li, lj) = self.ij(link);
+                    if !rows.contains(&li) || !cols.contains(&lj) {
+                        continue;
+                    }
+
+                    let b = grid.cell(at_i + li - top, at_j + lj - left);
+                    if a < b && grid.neighbors(a).contains(&b) {
+                        grid.link(a, b);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replaces this grid's links with those of the other grid, e.g., to restore a
+    /// snapshot.  Returns an error, leaving the grid unchanged, if the grids' dimensions
+    /// differ or if the other grid links cells that aren't neighbors in this one.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut maze = Grid::new(6, 8);
+    /// recursive_backtracker(&mut maze);
+    /// let snapshot = maze.clone();
+    ///
+    /// maze.braid(1.0);
+    /// maze.copy_links_from(&snapshot).unwrap();
+    /// assert_eq!(maze, snapshot);
+    ///
+    /// assert!(maze.copy_links_from(&Grid::new(8, 6)).is_err());
+    /// ```
+    pub fn copy_links_from(&mut self, other: &Grid) -> Result<(), String> {
+        if self.num_rows != other.num_rows || self.num_cols != other.num_cols {
+            return Err(format!(
+                "expected a {}x{} grid, got {}x{}",
+                self.num_rows, self.num_cols, other.num_rows, other.num_cols
+            ));
+        }
+
+        let links = other.link_pairs();
+
+        for &(cell1, cell2) in &links {
+            if !self.neighbors(cell1).contains(&cell2) {
+                return Err(format!("cells not adjacent: {}-{}", cell1, cell2));
+            }
+        }
+
+        self.clear();
+
+        for (cell1, cell2) in links {
+            self.link(cell1, cell2);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the links present in one of the grids but not the other, each link listed
+    /// once with the lower cell ID first, sorted.  Panics if the grids' dimensions differ.
+    /// See `link_diff` to tell the added links from the removed ones.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut before = Grid::new(2, 2);
+    /// before.link(0, 1);
+    /// before.link(1, 3);
+    ///
+    /// let mut after = before.clone();
+    /// after.unlink(1, 3);
+    /// after.link(0, 2);
+    ///
+    /// assert_eq!(before.diff_links(&after), vec![(0, 2), (1, 3)]);
+    /// ```
+    pub fn diff_links(&self, other: &Grid) -> Vec<(Cell, Cell)> {
+        let diff = self.link_diff(other);
+        let mut links = diff.added;
+        links.extend(diff.removed);
+        links.sort_unstable();
+        links
+    }
+
+    /// Generates a maze confined to a region of the grid, using the named algorithm (see
+    /// `Algorithm`), so that different parts of a maze can be given different textures.
+    /// Only links between two cells in the region are changed: existing ones are removed,
+    /// and new ones carved.  Cells outside the region, and any links between the region
+    /// and the rest of the grid, are untouched.
+    ///
+    /// The algorithm runs over a copy of the grid in which every cell outside the region is
+    /// isolated; then, where that leaves the region in several pieces, as it may for an
+    /// irregular region, the pieces are joined by random links between neighbors.  Thus a
+    /// region whose cells are all reachable from one another is a perfect maze.
+    ///
+    /// Returns an error, leaving the grid unchanged, if there's no such algorithm or if a
+    /// cell isn't in the grid.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(10, 20);
+    /// let (left, right): (Vec<Cell>, Vec<Cell>) =
+    ///     (0..grid.num_cells()).partition(|c| grid.j(*c) < 10);
+    ///
+    /// let mut rng = rand::thread_rng();
+    /// grid.generate_region(&left, "backtracker", &mut rng).unwrap();
+    /// grid.generate_region(&right, "sidewinder", &mut rng).unwrap();
+    /// grid.link(grid.cell(0, 9), grid.cell(0, 10));
+    /// assert!(grid.is_perfect_maze());
+    /// ```
+    pub fn generate_region(
+        &mut self,
+        region: &[Cell],
+        algorithm: &str,
+        rng: &mut impl Rng,
+    ) -> Result<(), String> {
+        let algo: crate::Algorithm = algorithm.parse()?;
+
+        if let Some(cell) = region.iter().find(|c| !self.contains(**c)) {
+            return Err(format!("cell not in grid: {}", cell));
+        }
+
+        let mut in_region = vec![false; self.num_cells()];
+        for &cell in region {
+            in_region[cell] = true;
+        }
+
+        // FIRST, generate a maze over the region alone.
+        let mut maze = self.clone_without_links();
+
+        for cell in (0..self.num_cells()).filter(|c| !in_region[*c]) {
+            maze.isolate(cell);
+        }
+
+        algo.generate_rng(&mut maze, rng);
+
+        // NEXT, replace the region's internal links with the maze's, tracking the pieces
+        // of the region they join.
+        let mut pieces = UnionFind::new(self.num_cells());
+
+        for &cell in region {
+            for other in self.links(cell) {
+                if in_region[other] {
+                    self.unlink(cell, other);
+                }
+            }
+        }
+
+        for (cell1, cell2) in maze.link_pairs() {
+            self.link(cell1, cell2);
+            pieces.join(cell1, cell2);
+        }
+
+        // FINALLY, join the pieces with random links between neighbors.
+        let mut walls: Vec<(Cell, Cell)> = Vec::new();
+
+        for &cell in region {
+            for other in self.neighbors(cell) {
+                if cell < other && in_region[other] {
+                    walls.push((cell, other));
+                }
+            }
+        }
+        walls.shuffle(rng);
+
+        for (cell1, cell2) in walls {
+            if pieces.join(cell1, cell2) {
+                self.link(cell1, cell2);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves every link by `di` rows and `dj` columns, e.g., to center a small maze
+    /// generated in the corner of a larger grid.  Links that would fall off the edge of the
+    /// grid, or that would join cells that aren't neighbors, such as isolated cells, are
+    /// dropped; the cells the maze moves away from are left unlinked.  The openings in the
+    /// outer walls don't move.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let mut grid = Grid::new(3, 3);
+    /// grid.link(0, 1);
+    /// grid.link(1, 2);
+    ///
+    /// // Shift the row down one; the link off the east edge is dropped.
+    /// grid.shift(1, 1);
+    /// assert_eq!(grid.link_pairs(), vec![(4, 5)]);
+    /// ```
+    pub fn shift(&mut self, di: isize, dj: isize) {
+        let (rows, cols) = (self.num_rows as isize, self.num_cols as isize);
+        let moved = |cell: Cell| {
+            let i = self.i(cell) as isize + di;
+            let j = self.j(cell) as isize + dj;
+
+            if 0 <= i && i < rows && 0 <= j && j < cols {
+                Some(self.cell(i as usize, j as usize))
+            } else {
+                None
+            }
+        };
+
+        let pairs: Vec<(Cell, Cell)> = self
+            .link_pairs()
+            .into_iter()
+            .filter_map(|(a, b)| Some((moved(a)?, moved(b)?)))
+            .collect();
+
+        self.clear();
+
+        for (a, b) in pairs {
+            if self.neighbors(a).contains(&b) {
+                self.link(a, b);
+            }
+        }
+    }
+
+    /// Returns the grid to the state of a new grid of the same size, as created by
+    /// `Grid::new`: the grid is planar, no cell is linked to any other cell, no cell is
+    /// isolated, there are no openings, and no links are being recorded.  Unless the grid
+    /// wasn't planar, only the cells that have been linked or isolated since the grid was
+    /// created or last reset are restored, and nothing is allocated, so this is cheaper
+    /// than creating a new grid.
+    pub(crate) fn reset(&mut self) {
+        if self.topology != Topology::Planar {
+            self.topology = Topology::Planar;
+            self.dirty_cells = (0..self.num_cells).collect();
+        }
+
+        let (num_rows, num_cols, topology) = (self.num_rows, self.num_cols, self.topology);
+
+        for cell in self.dirty_cells.drain(..) {
+            let data = &mut self.cells[cell];
+            data.links.clear();
+
+            let [north, south, east, west] =
+                compute_neighbors(num_rows, num_cols, topology, cell);
+            data.north = north;
+            data.south = south;
+            data.east = east;
+            data.west = west;
+            self.is_dirty[cell] = false;
+        }
+
+        self.openings.clear();
+        self.link_log = None;
+        self.revision += 1;
+    }
+
+    /// Computes the shortest distance from the cell to each other cell.
+    /// Returns the distances as a vector of length `num_cells`; cells that can't be
+    /// reached have no distance.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// // A corridor from cell 0 to cell 2; cell 3 is walled off.
+    /// let mut grid = Grid::new(1, 4);
+    /// grid.link(0, 1);
+    /// grid.link(1, 2);
+    ///
+    /// assert_eq!(grid.distances(0), vec![Some(0), Some(1), Some(2), None]);
+    /// ```
+    pub fn distances(&self, cell: Cell) -> Vec<Option<usize>> {
+        self.multi_source_distances(&[cell])
+    }
+
+    /// Computes the shortest distance from the nearest of the source cells to each other
+    /// cell.  Returns the distances as a vector of length `num_cells`; each of the sources
+    /// has distance 0.
+    pub fn multi_source_distances(&self, sources: &[Cell]) -> Vec<Option<usize>> {
+        MazeGrid::multi_source_distances(self, sources)
+    }
+
+    /// Returns the cells that lie on any shortest path from `a` to `b`, plus the cells within
+    /// `slack` steps of such a path, in order of cell ID.  This is useful for highlighting a
+    /// band around the solution.  If there is no path, the vector will be empty.
+    pub fn cells_between(&self, a: Cell, b: Cell, slack: usize) -> Vec<Cell> {
+        // FIRST, find the cells on the shortest paths: those whose distances from the two
+        // ends sum to the length of the path.
+        let from_a = self.distances(a);
+        let from_b = self.distances(b);
+
+        let length = match from_a[b] {
+            Some(length) => length,
+            None => return Vec::new(),
+        };
+
+        let on_path: Vec<Cell> = (0..self.num_cells)
+            .filter(|c| match (from_a[*c], from_b[*c]) {
+                (Some(da), Some(db)) => da + db == length,
+                _ => false,
+            })
+            .collect();
+
+        if slack == 0 {
+            return on_path;
+        }
+
+        // NEXT, add the cells within the slack of the path.
+        self.multi_source_distances(&on_path)
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| matches!(d, Some(d) if *d <= slack))
+            .map(|(c, _)| c)
+            .collect()
+    }
+
+    /// Returns the cells that lie on every shortest path from `start` to `goal`, in order
+    /// from the start, including both ends.  In a perfect maze this is the whole shortest
+    /// path; in a braided maze it's the unavoidable chokepoints.  If there is no path, the
+    /// vector will be empty.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// // A loop around cell 4, with corridors leading in from cell 0 and out to cell 8.
+    /// let mut grid = Grid::new(3, 3);
+    /// for &(a, b) in &[(0, 1), (1, 2), (2, 5), (1, 4), (3, 4), (4, 5), (5, 8)] {
+    ///     grid.link(a, b);
+    /// }
+    ///
+    /// // From 1 to 5 there are two shortest routes, via 2 or via 4.
+    /// assert_eq!(grid.essential_cells(0, 8), vec![0, 1, 5, 8]);
+    /// ```
+    pub fn essential_cells(&self, start: Cell, goal: Cell) -> Vec<Cell> {
+        let from_start = self.distances(start);
+        let from_goal = self.distances(goal);
+
+        let length = match from_start[goal] {
+            Some(length) => length,
+            None => return Vec::new(),
+        };
+
+        // A cell is on every shortest path if it's the only cell on any shortest path at
+        // its distance from the start.
+        let mut layers: Vec<Vec<Cell>> = vec![Vec::new(); length + 1];
+
+        for cell in 0..self.num_cells {
+            if let (Some(ds), Some(dg)) = (from_start[cell], from_goal[cell]) {
+                if ds + dg == length {
+                    layers[ds].push(cell);
+                }
+            }
+        }
+
+        layers
+            .into_iter()
+            .filter(|layer| layer.len() == 1)
+            .map(|layer| layer[0])
+            .collect()
+    }
+
+    /// Computes the shortest path from the first cell to the second.  If there is no
+    /// path, the path will be empty.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(8, 8);
+    /// recursive_backtracker(&mut grid);
+    ///
+    /// // The path includes both ends, so it's one cell longer than the distance.
+    /// let goal = grid.cell(7, 7);
+    /// let path = grid.shortest_path(0, goal);
+    /// assert_eq!(path.first(), Some(0));
+    /// assert_eq!(path.last(), Some(goal));
+    /// assert_eq!(path.len(), grid.distances(0)[goal].unwrap() + 1);
+    ///
+    /// // Each step is through a link.
+    /// for step in path.cells().windows(2) {
+    ///     assert!(grid.is_linked(step[0], step[1]));
+    /// }
+    /// ```
+    pub fn shortest_path(&self, start: Cell, goal: Cell) -> CellPath {
+        CellPath::from_valid(self, MazeGrid::shortest_path(self, start, goal))
+    }
+
+    /// Computes the shortest path from the start cell to the goal cell using A* search,
+    /// which explores toward the goal rather than in every direction, and so can be much
+    /// faster than `shortest_path` for a single query on a large maze.  The heuristic
+    /// estimates the number of steps from a cell to the goal; if it never overestimates,
+    /// the path is a shortest path.  The Manhattan distance is a natural choice, except on
+    /// a wrapped grid.  The path is empty if there's no path.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(20, 30);
+    /// recursive_backtracker(&mut grid);
+    ///
+    /// let goal = grid.num_cells() - 1;
+    /// let (gi, gj) = grid.ij(goal);
+    /// let path = grid.astar(0, goal, |c| {
+    ///     let (i, j) = grid.ij(c);
+    ///     (gi - i) + (gj - j)
+    /// });
+    /// assert_eq!(path, grid.shortest_path(0, goal));
+    /// ```
+    pub fn astar<H>(&self, start: Cell, goal: Cell, heuristic: H) -> CellPath
+    where
+        H: Fn(Cell) -> usize,
+    {
+        assert!(self.contains(start));
+        assert!(self.contains(goal));
+
+        // FIRST, search from the start, always expanding the cell with the smallest
+        // estimated total path length.  A cell is pushed again whenever a shorter way
+        // to it is found; stale entries are skipped.
+        let mut steps: Vec<Option<usize>> = vec![None; self.num_cells];
+        let mut came_from: Vec<Option<Cell>> = vec![None; self.num_cells];
+        let mut frontier = BinaryHeap::new();
+
+        steps[start] = Some(0);
+        frontier.push(Reverse((heuristic(start), start)));
+
+        while let Some(Reverse((estimate, cell))) = frontier.pop() {
+            let g = steps[cell].expect("reached cell");
+
+            if cell == goal {
+                break;
+            }
+            if estimate > g + heuristic(cell) {
+                continue;
+            }
+
+            for other in self.links(cell) {
+                if !matches!(steps[other], Some(s) if s <= g + 1) {
+                    steps[other] = Some(g + 1);
+                    came_from[other] = Some(cell);
+                    frontier.push(Reverse((g + 1 + heuristic(other), other)));
+                }
+            }
+        }
+
+        // NEXT, follow the trail back from the goal.
+        let mut cells = Vec::new();
+
+        if steps[goal].is_some() {
+            let mut cell = goal;
+            cells.push(cell);
+
+            while let Some(prev) = came_from[cell] {
+                cells.push(prev);
+                cell = prev;
+            }
+            cells.reverse();
+        }
+
+        CellPath::from_valid(self, cells)
+    }
+
+    /// Computes the shortest path from the first cell to the second as a vector of
+    /// cells.
+    #[deprecated(note = "use shortest_path, which returns a CellPath")]
+    pub fn shortest_path_vec(&self, start: Cell, goal: Cell) -> Vec<Cell> {
+        self.shortest_path(start, goal).into_cells()
+    }
+
+    /// Return the farthest cell from the given cell.  See `farthest_with_distance`.
+    pub fn farthest(&self, start: Cell) -> Cell {
+        self.farthest_with_distance(start).0
+    }
+
+    /// Returns the farthest cell reachable from the given cell, along with its distance.
+    /// If several cells tie for farthest, returns the one with the lowest cell ID; if no
+    /// other cell is reachable, returns the start cell itself at distance 0.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(1, 5);
+    /// grid.carve_room(0, 0, 1, 5);
+    ///
+    /// assert_eq!(grid.farthest_with_distance(1), (4, 3));
+    /// assert_eq!(grid.farthest_with_distance(2), (0, 2));
+    /// ```
+    pub fn farthest_with_distance(&self, start: Cell) -> (Cell, usize) {
+        max_distance(&self.distances(start))
+    }
+
+    /// Returns the two ends of the longest path through the maze, along with the length
+    /// of the path in steps, i.e., the maze's diameter.  Only the cells reachable from
+    /// cell 0 are considered.
+    pub fn farthest_pair(&self) -> (Cell, Cell, usize) {
+        let end = self.farthest(0);
+        let (start, diameter) = max_distance(&self.distances(end));
+
+        (start, end, diameter)
+    }
+
+    /// Returns the length of the longest path through the maze, in steps.  Only the cells
+    /// reachable from cell 0 are considered.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(8, 8);
+    /// sidewinder_maze(&mut grid);
+    ///
+    /// // The maze is at least as long as the distance between opposite corners.
+    /// assert!(grid.diameter() >= 14);
+    /// assert_eq!(grid.longest_path().len(), grid.diameter() + 1);
+    /// ```
+    pub fn diameter(&self) -> usize {
+        // FIRST, use the cached value if the grid hasn't changed since it was computed.
+        if let Some((revision, diameter)) = self.diameter_cache.get() {
+            if revision == self.revision {
+                return diameter;
+            }
+        }
+
+        // NEXT, compute and cache it.
+        let diameter = self.farthest_pair().2;
+        self.diameter_cache.set(Some((self.revision, diameter)));
+        diameter
+    }
+
+    /// Returns the grid's revision number, which starts at zero and increases whenever the
+    /// grid is modified: when cells are linked or unlinked, isolated, or cleared, or when
+    /// an opening is added.  Values computed from the grid can be cached until the
+    /// revision changes.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let mut grid = Grid::new(2, 2);
+    /// let rev = grid.revision();
+    /// grid.link(0, 1);
+    /// assert!(grid.revision() > rev);
+    /// ```
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Get a list of the dead-end cells in the grid: those cells that link to
+    /// only one other cell.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let mut grid = Grid::new(2, 2);
+    /// grid.link(0, 1);
+    /// grid.link(0, 2);
+    /// grid.link(2, 3);
+    /// assert_eq!(grid.dead_ends(), vec![1, 3]);
+    /// ```
+    pub fn dead_ends(&self) -> Vec<Cell> {
+        (0..self.num_cells)
+            .filter(|c| self.links(*c).len() == 1)
+            .collect()
+    }
+
+    /// Returns true if the cell is a dead end, i.e., is linked to exactly one other cell.
+    pub fn is_dead_end(&self, cell: Cell) -> bool {
+        assert!(self.contains(cell));
+        self.cells[cell].links.len() == 1
+    }
+
+    /// Returns true if the cell is a junction, i.e., is linked to three or more other
+    /// cells.
+    pub fn is_junction(&self, cell: Cell) -> bool {
+        assert!(self.contains(cell));
+        self.cells[cell].links.len() >= 3
+    }
+
+    /// Counts the cells for which the predicate returns true, without collecting them.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(2, 2);
+    /// grid.link(0, 1);
+    /// grid.link(0, 2);
+    /// grid.link(2, 3);
+    /// assert_eq!(grid.count_cells(Grid::is_dead_end), 2);
+    /// assert_eq!(grid.count_cells(|g, c| g.i(c) == 0 && !g.is_junction(c)), 2);
+    /// ```
+    pub fn count_cells(&self, pred: impl Fn(&Grid, Cell) -> bool) -> usize {
+        (0..self.num_cells).filter(|c| pred(self, *c)).count()
+    }
+
+    /// Gets the number of dead-end cells in the grid, without collecting them.
+    pub fn dead_end_count(&self) -> usize {
+        self.cells.iter().filter(|c| c.links.len() == 1).count()
+    }
+
+    /// Braids the maze, replacing dead ends with loops: each dead end is linked to one of
+    /// its unlinked neighbors with probability `p`.  Neighbors that are themselves dead
+    /// ends are preferred, so that two dead ends are removed at once.  A dead end with no
+    /// unlinked neighbors, e.g., at the end of a 1xN grid, remains.  Panics if `p` isn't
+    /// between 0.0 and 1.0.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(8, 8);
+    /// recursive_backtracker(&mut grid);
+    /// grid.braid(1.0);
+    /// assert_eq!(grid.dead_end_count(), 0);
+    /// assert!(grid.is_connected());
+    /// ```
+    pub fn braid(&mut self, p: f64) {
+        self.braid_rng(p, &mut thread_rng());
+    }
+
+    /// Braids the maze using the given random number generator.  See `braid`.
+    pub fn braid_rng(&mut self, p: f64, rng: &mut impl Rng) {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "braid probability out of range: {}",
+            p
+        );
+
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(rng);
+
+        for cell in dead_ends {
+            // An earlier link may already have removed this dead end.
+            if self.cells[cell].links.len() != 1 || !rng.gen_bool(p) {
+                continue;
+            }
+
+            let unlinked: Vec<Cell> = self
+                .neighbors(cell)
+                .into_iter()
+                .filter(|n| !self.is_linked(cell, *n))
+                .collect();
+
+            if unlinked.is_empty() {
+                continue;
+            }
+
+            let dead: Vec<Cell> = unlinked
+                .iter()
+                .copied()
+                .filter(|n| self.cells[*n].links.len() == 1)
+                .collect();
+
+            let other = if dead.is_empty() {
+                crate::sample_rng(&unlinked, rng)
+            } else {
+                crate::sample_rng(&dead, rng)
+            };
+
+            self.link(cell, other.expect("unlinked neighbor"));
+        }
+    }
+
+    /// Dilates the passages, treating the cells with at least one link as a binary
+    /// structure: on each of the given number of iterations, every linked cell is linked
+    /// to each of its neighbors that is also linked, as of the start of the iteration.
+    /// This thickens the passages into open areas without reaching into solid rock; it
+    /// never removes a link.  The result is deterministic.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// // Two parallel corridors merge into an open area.
+    /// let mut grid = Grid::new(2, 3);
+    /// grid.link(0, 1);
+    /// grid.link(1, 2);
+    /// grid.link(3, 4);
+    /// grid.dilate_passages(1);
+    /// assert_eq!(grid.num_links(), 5);
+    /// assert!(grid.is_linked(0, 3) && grid.is_linked(1, 4));
+    /// ```
+    pub fn dilate_passages(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            let linked = self.linked_cells();
+
+            for (cell, _) in linked.iter().enumerate().filter(|(_, l)| **l) {
+                for n in self.neighbors(cell) {
+                    if n > cell && linked[n] {
+                        self.link(cell, n);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Erodes the passages: on each of the given number of iterations, every cell with at
+    /// least one link but fewer than `threshold` links, as of the start of the iteration,
+    /// is unlinked from all of its neighbors.  With a threshold of 2 this peels the dead
+    /// ends off the passages, shortening each tendril by one cell per iteration.  It never
+    /// adds a link.  The result is deterministic.
+    ///
+    /// Erosion doesn't preserve connectivity: it can cut a region in two, or leave
+    /// fragments behind.  Use `keep_largest_component` to discard the fragments.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// // A loop with a tendril; erosion removes the tendril and keeps the loop.
+    /// let mut grid = Grid::new(2, 4);
+    /// grid.carve_room(0, 0, 2, 2);
+    /// grid.link(1, 2);
+    /// grid.link(2, 3);
+    /// grid.erode_passages(2, 2);
+    /// assert_eq!(grid.num_links(), 4);
+    /// assert!(grid.links(3).is_empty());
+    /// ```
+    pub fn erode_passages(&mut self, threshold: usize, iterations: usize) {
+        for _ in 0..iterations {
+            let doomed: Vec<Cell> = (0..self.num_cells)
+                .filter(|c| {
+                    let count = self.cells[*c].links.len();
+                    count > 0 && count < threshold
+                })
+                .collect();
+
+            if doomed.is_empty() {
+                break;
+            }
+
+            for cell in doomed {
+                self.unlink_all(cell);
+            }
+        }
+    }
+
+    /// Opens the passages: erodes them and then dilates them, each for the given number
+    /// of iterations.  This removes tendrils and thin spurs while leaving the larger open
+    /// areas roughly as they were.  See `erode_passages` and `dilate_passages`.
+    pub fn open_passages(&mut self, threshold: usize, iterations: usize) {
+        self.erode_passages(threshold, iterations);
+        self.dilate_passages(iterations);
+    }
+
+    /// Closes the passages: dilates them and then erodes them, each for the given number
+    /// of iterations.  This fills in the walls between nearby passages while trimming
+    /// the new growth at the edges.  See `dilate_passages` and `erode_passages`.
+    pub fn close_passages(&mut self, threshold: usize, iterations: usize) {
+        self.dilate_passages(iterations);
+        self.erode_passages(threshold, iterations);
+    }
+
+    /// Unlinks every cell that isn't in the largest connected component, i.e., the
+    /// largest set of cells that can reach each other through links, so that only the
+    /// main cave remains after erosion.  Ties go to the component containing the lowest
+    /// cell ID.  Unlike `keep_only`, the other cells aren't isolated.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(1, 5);
+    /// grid.link(0, 1);
+    /// grid.link(3, 4);
+    /// grid.link(2, 3);
+    /// grid.keep_largest_component();
+    /// assert_eq!(grid.num_links(), 2);
+    /// assert!(grid.links(0).is_empty());
+    /// ```
+    pub fn keep_largest_component(&mut self) {
+        let components = self.connected_components();
+
+        // The first of the largest components wins.
+        let mut keep = 0;
+        for (k, component) in components.iter().enumerate() {
+            if component.len() > components[keep].len() {
+                keep = k;
+            }
+        }
+
+        for (k, component) in components.iter().enumerate() {
+            if k != keep {
+                for &cell in component {
+                    self.unlink_all(cell);
+                }
+            }
+        }
+    }
+
+    /// Flags the cells that have at least one link.
+    fn linked_cells(&self) -> Vec<bool> {
+        self.cells.iter().map(|c| !c.links.is_empty()).collect()
+    }
+
+    /// Gets the number of links in the grid, counting each link between two cells once.
+    pub fn num_links(&self) -> usize {
+        self.cells.iter().map(|c| c.links.len()).sum::<usize>() / 2
+    }
+
+    /// Returns the number of links of each cell, indexed by cell ID.  Dead ends have
+    /// degree 1.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let mut grid = Grid::new(1, 3);
+    /// grid.link(0, 1);
+    /// assert_eq!(grid.degree_sequence(), vec![1, 1, 0]);
+    /// ```
+    pub fn degree_sequence(&self) -> Vec<usize> {
+        self.cells.iter().map(|c| c.links.len()).collect()
+    }
+
+    /// Returns the grid's links as an adjacency matrix: a row-major bitmap of
+    /// `num_cells * num_cells` flags in which the flag at `a * num_cells + b` is true if
+    /// cells a and b are linked.  The matrix is symmetric.
+    ///
+    /// Beware: the matrix grows with the square of the number of cells, and nearly all of
+    /// it is false; a 100x100 grid's matrix has 100 million entries.  Prefer `link_pairs`
+    /// for all but small grids.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let mut grid = Grid::new(1, 2);
+    /// grid.link(0, 1);
+    /// assert_eq!(grid.adjacency_matrix(), vec![false, true, true, false]);
+    /// ```
+    pub fn adjacency_matrix(&self) -> Vec<bool> {
+        let n = self.num_cells;
+        let mut matrix = vec![false; n * n];
+
+        for (a, data) in self.cells.iter().enumerate() {
+            for &b in &data.links {
+                matrix[a * n + b] = true;
+            }
+        }
+
+        matrix
+    }
+
+    /// Returns the number of connected components: sets of cells that can reach each
+    /// other through links.  An unlinked cell is a component by itself, and isolated cells
+    /// are counted like any other.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let mut grid = Grid::new(2, 2);
+    /// assert_eq!(grid.num_components(), 4);
+    /// grid.link(0, 1);
+    /// grid.link(2, 3);
+    /// assert_eq!(grid.num_components(), 2);
+    /// ```
+    pub fn num_components(&self) -> usize {
+        self.connected_components().len()
+    }
+
+    /// Returns the connected components: the sets of cells that can reach each other
+    /// through links.  Each component's cells are in increasing order, and the components
+    /// are in order by their first cells.  An unlinked cell is a component by itself, and
+    /// isolated cells are included like any other.  A connected grid has one component.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let mut grid = Grid::new(2, 3);
+    /// grid.link(0, 1);
+    /// grid.link(1, 4);
+    /// grid.link(2, 5);
+    /// assert_eq!(grid.connected_components(), vec![vec![0, 1, 4], vec![2, 5], vec![3]]);
+    /// ```
+    pub fn connected_components(&self) -> Vec<Vec<Cell>> {
+        let mut seen = vec![false; self.num_cells];
+        let mut components = Vec::new();
+
+        for start in 0..self.num_cells {
+            if seen[start] {
+                continue;
+            }
+
+            // Walk the component with an explicit stack, so that huge grids can't
+            // overflow the call stack.
+            seen[start] = true;
+            let mut component = vec![start];
+            let mut stack = vec![start];
+
+            while let Some(cell) = stack.pop() {
+                for other in self.links(cell) {
+                    if !seen[other] {
+                        seen[other] = true;
+                        component.push(other);
+                        stack.push(other);
+                    }
+                }
+            }
+
+            component.sort_unstable();
+            components.push(component);
+        }
+
+        components
+    }
+
+    /// Returns true if every cell can be reached from every other cell.
+    pub fn is_connected(&self) -> bool {
+        self.num_cells == 0 || self.distances(0).iter().all(|d| d.is_some())
+    }
+
+    /// Returns true if the grid is a perfect maze: one in which there is exactly one
+    /// path between any two cells.  All of the maze algorithms produce perfect mazes.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(5, 5);
+    /// hunt_and_kill(&mut grid);
+    /// assert!(grid.is_connected());
+    /// assert!(grid.is_perfect_maze());
+    ///
+    /// // Removing a link disconnects the maze; adding one creates a loop.
+    /// let cell = grid.links(0)[0];
+    /// grid.unlink(0, cell);
+    /// assert!(!grid.is_connected());
+    /// assert!(!grid.is_perfect_maze());
+    ///
+    /// grid.link(0, cell);
+    /// let (a, b) = (0..grid.num_cells())
+    ///     .flat_map(|c| grid.neighbors(c).into_iter().map(move |n| (c, n)))
+    ///     .find(|&(c, n)| !grid.is_linked(c, n))
+    ///     .unwrap();
+    /// grid.link(a, b);
+    /// assert!(grid.is_connected());
+    /// assert!(!grid.is_perfect_maze());
+    /// ```
+    pub fn is_perfect_maze(&self) -> bool {
+        // A connected graph is a tree if it has one fewer edge than it has nodes.
+        self.is_connected() && self.num_links() + 1 == self.num_cells
+    }
+
+    /// Computes summary statistics about the structure of the maze.  See `GridStats`.
+    pub fn stats(&self) -> GridStats {
+        let mut stats = GridStats::default();
+
+        for data in &self.cells {
+            match data.links.len() {
+                1 => stats.dead_ends += 1,
+                2 => stats.corridors += 1,
+                3 => stats.junctions3 += 1,
+                4 => stats.junctions4 += 1,
+                _ => (),
+            }
+
+            if let Some(east) = data.east {
+                if data.links.contains(&east) {
+                    stats.horiz_links += 1;
+                }
+            }
+
+            if let Some(south) = data.south {
+                if data.links.contains(&south) {
+                    stats.vert_links += 1;
+                }
+            }
+        }
+
+        stats.longest = self.diameter();
+
+        stats
+    }
+
+    /// Returns the longest path through the maze.  Only the cells reachable from cell 0
+    /// are considered.
+    pub fn longest_path(&self) -> CellPath {
+        // FIRST, find one end of the path and the distances from it.
+        let end = self.farthest(0);
+        let dists = self.distances(end);
+
+        // NEXT, find the other end of the path, and follow the distances back.  We reuse
+        // the distances rather than calling shortest_path, which would compute them again.
+        let (start, _) = max_distance(&dists);
+        CellPath::from_valid(self, path_back(self, &dists, start))
+    }
+
+    /// Returns the longest path through the maze as a vector of cells.
+    #[deprecated(note = "use longest_path, which returns a CellPath")]
+    pub fn longest_path_vec(&self) -> Vec<Cell> {
+        self.longest_path().into_cells()
+    }
+
+    /// Opens a gap in the outer wall on the given side of the cell, e.g., for an entrance
+    /// or exit.  The renderers leave the wall out.  Panics if the cell has a neighbor in
+    /// that direction.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(2, 2);
+    /// grid.open_boundary(0, GridDirection::West);
+    /// assert!(grid.is_open_boundary(0, GridDirection::West));
+    /// assert_eq!(TextGridRenderer::new().render(&grid).lines().nth(1), Some("    |   |"));
+    /// ```
+    pub fn open_boundary(&mut self, cell: Cell, dir: GridDirection) {
+        assert!(
+            self.cell_to(cell, dir).is_none(),
+            "cell {} has a neighbor to the {}, so there's no outer wall to open",
+            cell,
+            dir
+        );
+
+        if !self.is_open_boundary(cell, dir) {
+            self.openings.push((cell, dir));
+            self.openings.sort_by_key(|&(c, d)| (c, d as usize));
+            self.revision += 1;
+        }
+    }
+
+    /// Returns true if the outer wall on the given side of the cell has been opened.
+    pub fn is_open_boundary(&self, cell: Cell, dir: GridDirection) -> bool {
+        self.openings.contains(&(cell, dir))
+    }
+
+    /// Gets the openings in the outer walls, as (cell, direction) pairs.
+    pub fn openings(&self) -> &[(Cell, GridDirection)] {
+        &self.openings
+    }
+
+    /// Opens the outer wall nearest each end of the longest path, so that the maze has an
+    /// entrance and an exit.  Nearness is measured along the maze's passages; ties go to
+    /// the lowest cell ID, and to the first of north, south, east, and west.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(8, 8);
+    /// recursive_backtracker(&mut grid);
+    /// grid.open_longest_path_ends();
+    /// assert_eq!(grid.openings().len(), 2);
+    /// ```
+    pub fn open_longest_path_ends(&mut self) {
+        let path = self.longest_path();
+        let ends = [path[0], path[path.len() - 1]];
+
+        for &end in &ends {
+            let dists = self.distances(end);
+            let mut best: Option<(usize, Cell, GridDirection)> = None;
+
+            for (cell, dist) in dists.iter().enumerate() {
+                let dist = match dist {
+                    Some(dist) => *dist,
+                    None => continue,
+                };
+
+                if matches!(best, Some((d, _, _)) if d <= dist) {
+                    continue;
+                }
+
+                let dir = [
+                    GridDirection::North,
+                    GridDirection::South,
+                    GridDirection::East,
+                    GridDirection::West,
+                ]
+                .iter()
+                .copied()
+                .find(|d| self.cell_to(cell, *d).is_none() && !self.is_open_boundary(cell, *d));
+
+                if let Some(dir) = dir {
+                    best = Some((dist, cell, dir));
+                }
+            }
+
+            if let Some((_, cell, dir)) = best {
+                self.open_boundary(cell, dir);
+            }
+        }
+    }
+
+    /// Returns true if the wall on the given side of the cell's position is an opening,
+    /// whether it was opened from this cell or from the cell on the other side.
+    pub(crate) fn has_opening(&self, cell: Cell, dir: GridDirection) -> bool {
+        if self.openings.is_empty() {
+            return false;
+        }
+
+        if self.is_open_boundary(cell, dir) {
+            return true;
+        }
+
+        let (i, j) = self.ij(cell);
+        let (other, opposite) = match dir {
+            GridDirection::North if i > 0 => (cell - self.num_cols, GridDirection::South),
+            GridDirection::South if i + 1 < self.num_rows => {
+                (cell + self.num_cols, GridDirection::North)
+            }
+            GridDirection::East if j + 1 < self.num_cols => (cell + 1, GridDirection::West),
+            GridDirection::West if j > 0 => (cell - 1, GridDirection::East),
+            _ => return false,
+        };
+
+        self.is_open_boundary(other, opposite)
+    }
+
+    /// Produces an answer key for a path through the maze, e.g., the solution.  The key has
+    /// two lines: the names of the cells along the path (see `cell_name`), and the same
+    /// path as turn-by-turn directions.  Returns the empty string if the path is empty.
+    /// Panics if a step in the path is not between neighboring cells.
+    ///
+    /// ```
+    /// use mazegen::Grid;
+    ///
+    /// let grid = Grid::new(3, 3);
+    /// let path = vec![0, 1, 2, 5, 8, 7];
+    /// assert_eq!(
+    ///     grid.solution_key(&path),
+    ///     "A1, B1, C1, C2, C3, B3\nStart at A1, east 2, south 2, west 1, finish at B3\n"
+    /// );
+    /// ```
+    pub fn solution_key(&self, path: &[Cell]) -> String {
+        if path.is_empty() {
+            return String::new();
+        }
+
+        // FIRST, list the cells.
+        let names: Vec<String> = path.iter().map(|c| self.cell_name(*c)).collect();
+        let mut key = names.join(", ");
+        key.push('\n');
+
+        // NEXT, compress the steps into runs in the same direction.
+        let mut runs: Vec<(GridDirection, usize)> = Vec::new();
+
+        for step in path.windows(2) {
+            let dir = self
+                .direction_to(step[0], step[1])
+                .expect("path steps between neighboring cells");
+
+            match runs.last_mut() {
+                Some((last, count)) if *last == dir => *count += 1,
+                _ => runs.push((dir, 1)),
+            }
+        }
+
+        // FINALLY, describe the runs.
+        key.push_str(&format!("Start at {}", names[0]));
+        for (dir, count) in runs {
+            key.push_str(&format!(", {} {}", dir, count));
+        }
+        key.push_str(&format!(", finish at {}\n", names[names.len() - 1]));
+
+        key
+    }
+
+    /// Gets the direction from one cell to its neighbor, or None if they aren't neighbors.
+    fn direction_to(&self, from: Cell, to: Cell) -> Option<GridDirection> {
+        [
+            GridDirection::North,
+            GridDirection::South,
+            GridDirection::East,
+            GridDirection::West,
+        ]
+        .iter()
+        .copied()
+        .find(|dir| self.cell_to(from, *dir) == Some(to))
+    }
+
+    /// Serializes the grid as a JSON string, capturing its dimensions and its links.
+    /// Each link is listed once, as a pair of cell IDs with the lower ID first.  The cells
+    /// isolated by a mask, if any, are listed under the `isolated` key.
+    ///
+    /// ```json
+    /// {"rows":2,"cols":2,"links":[[0,1],[0,2],[2,3]]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.to_grid_json()).expect("serializable grid")
+    }
+
+    /// Serializes the grid as a JSON string, as for `to_json`, adding the given metadata
+    /// (e.g., the algorithm name, the seed, and a timestamp) as an object under the `meta`
+    /// key, with its keys in sorted order.  If `meta` is empty the result is the same as
+    /// `to_json`.
+    ///
+    /// ```json
+    /// {"rows":1,"cols":2,"links":[[0,1]],"meta":{"algorithm":"backtracker","seed":"7"}}
+    /// ```
+    pub fn to_json_with_meta(&self, meta: HashMap<String, String>) -> String {
+        let mut data = self.to_grid_json();
+        data.meta = meta
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::Value::String(value)))
+            .collect();
+        serde_json::to_string(&data).expect("serializable grid")
+    }
+
+    /// Creates a grid from a JSON string produced by `to_json` or `to_json_with_meta`.
+    /// The metadata, if any, is ignored.  Returns an error if the JSON is malformed or
+    /// describes an invalid grid, including one of more than `Grid::MAX_JSON_CELLS` cells.
+    pub fn from_json(json: &str) -> Result<Grid, String> {
+        let data: GridJson = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Grid::from_grid_json(&data)
+    }
+
+    /// Reads the metadata from a JSON string produced by `to_json_with_meta`.  Entries
+    /// whose values aren't strings are skipped; a grid with no metadata yields an empty
+    /// map.  Returns an error if the JSON is malformed.
+    pub fn meta_from_json(json: &str) -> Result<HashMap<String, String>, String> {
+        let data: GridJson = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+        Ok(data
+            .meta
+            .into_iter()
+            .filter_map(|(key, value)| match value {
+                serde_json::Value::String(value) => Some((key, value)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Creates a grid from the ASCII form produced by `TextGridRenderer`, inferring its
+    /// dimensions, links, and openings.  Returns an error if the text isn't a well-formed
+    /// grid.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let grid = Grid::from_ascii("+---+---+\n|       |\n+---+---+\n").unwrap();
+    /// assert_eq!(grid.num_cols(), 2);
+    /// assert!(grid.is_linked(0, 1));
+    /// ```
+    pub fn from_ascii(text: &str) -> Result<Grid, String> {
+        crate::text_grid_renderer::parse_ascii(text)
+    }
+
+    /// Exports the maze as a Graphviz DOT document: an undirected graph with one node per
+    /// cell, labeled with its (i,j) coordinates, and one edge per link.
+    ///
+    /// ```text
+    /// graph maze {
+    ///     0 [label="(0,0)"];
+    ///     1 [label="(0,1)"];
+    ///     0 -- 1;
+    /// }
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph maze {\n");
+
+        for cell in 0..self.num_cells {
+            let (i, j) = self.ij(cell);
+            dot.push_str(&format!("    {} [label=\"({},{})\"];\n", cell, i, j));
+        }
+
+        for (cell1, cell2) in self.link_pairs() {
+            dot.push_str(&format!("    {} -- {};\n", cell1, cell2));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the maze as a TMX document, for use with the Tiled map editor.  The map is
+    /// a single layer of `2 * cols + 1` by `2 * rows + 1` tiles, each `tile_size` pixels
+    /// square: each cell is a tile, as is each wall between cells and each corner.  Cells,
+    /// the walls between linked cells, and openings are floor tiles, with GID 1; all other
+    /// tiles, including isolated cells, are wall tiles, with GID 2.
+    ///
+    /// The map uses an embedded tileset named "maze" whose image, "maze-tiles.png", is
+    /// expected to be two tiles wide and one tile high, with the floor tile on the left
+    /// and the wall tile on the right.  The layer data is CSV-encoded.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(1, 2);
+    /// grid.link(0, 1);
+    ///
+    /// let tmx = grid.to_tmx(16);
+    /// assert!(tmx.contains("width=\"5\" height=\"3\" tilewidth=\"16\""));
+    /// assert!(tmx.contains("2,2,2,2,2,\n2,1,1,1,2,\n2,2,2,2,2\n"));
+    /// ```
+    pub fn to_tmx(&self, tile_size: usize) -> String {
+        // FIRST, lay out the tiles: true for floor, false for wall.
+        let width = 2 * self.num_cols + 1;
+        let height = 2 * self.num_rows + 1;
+        let mut floor = vec![vec![false; width]; height];
+
+        for cell in 0..self.num_cells {
+            if self.is_isolated(cell) {
+                continue;
+            }
+
+            let (i, j) = self.ij(cell);
+            let (y, x) = (2 * i + 1, 2 * j + 1);
+            floor[y][x] = true;
+
+            if self.is_linked_east(cell) || self.has_opening(cell, GridDirection::East) {
+                floor[y][x + 1] = true;
+            }
+            if self.is_linked_south(cell) || self.has_opening(cell, GridDirection::South) {
+                floor[y + 1][x] = true;
+            }
+            if self.has_opening(cell, GridDirection::North) {
+                floor[y - 1][x] = true;
+            }
+            if self.has_opening(cell, GridDirection::West) {
+                floor[y][x - 1] = true;
+            }
+        }
+
+        // NEXT, write the map.
+        let mut tmx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        tmx.push_str(&format!(
+            "<map version=\"1.2\" orientation=\"orthogonal\" renderorder=\"right-down\" \
+             width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" \
+             nextlayerid=\"2\" nextobjectid=\"1\">\n",
+            width, height, tile_size, tile_size
+        ));
+        tmx.push_str(&format!(
+            " <tileset firstgid=\"1\" name=\"maze\" tilewidth=\"{}\" tileheight=\"{}\" \
+             tilecount=\"2\" columns=\"2\">\n",
+            tile_size, tile_size
+        ));
+        tmx.push_str(&format!(
+            "  <image source=\"maze-tiles.png\" width=\"{}\" height=\"{}\"/>\n",
+            2 * tile_size,
+            tile_size
+        ));
+        tmx.push_str(" </tileset>\n");
+        tmx.push_str(&format!(
+            " <layer id=\"1\" name=\"maze\" width=\"{}\" height=\"{}\">\n",
+            width, height
+        ));
+        tmx.push_str("  <data encoding=\"csv\">\n");
+
+        let rows: Vec<String> = floor
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|f| if *f { "1" } else { "2" })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+        tmx.push_str(&rows.join(",\n"));
+
+        tmx.push_str("\n</data>\n </layer>\n</map>\n");
+        tmx
+    }
+
+    /// Gets the serializable form of the grid.
+    fn to_grid_json(&self) -> GridJson {
+        GridJson {
+            rows: self.num_rows,
+            cols: self.num_cols,
+            topology: self.topology,
+            links: self.link_pairs(),
+            isolated: (0..self.num_cells)
+                .filter(|c| self.is_isolated(*c))
+                .collect(),
+            openings: self
+                .openings
+                .iter()
+                .map(|(cell, dir)| (*cell, dir.to_string()))
+                .collect(),
+            meta: serde_json::Map::new(),
+        }
+    }
+
+    /// Creates a grid from its serializable form, validating the content.
+    fn from_grid_json(data: &GridJson) -> Result<Grid, String> {
+        if data.rows == 0 || data.cols == 0 {
+            return Err(format!(
+                "expected a grid of size at least 1x1, got {}x{}",
+                data.rows, data.cols
+            ));
+        }
+
+        match data.rows.checked_mul(data.cols) {
+            Some(cells) if cells <= Grid::MAX_JSON_CELLS => (),
+            _ => {
+                return Err(format!(
+                    "expected a grid of at most {} cells, got {}x{}",
+                    Grid::MAX_JSON_CELLS,
+                    data.rows,
+                    data.cols
+                ))
+            }
+        }
+
+        let mut grid = Grid::with_topology(data.rows, data.cols, data.topology);
+
+        for &cell in &data.isolated {
+            if !grid.contains(cell) {
+                return Err(format!("invalid isolated cell: {}", cell));
+            }
+
+            grid.isolate(cell);
+        }
+
+        for &(cell1, cell2) in &data.links {
+            if !grid.contains(cell1) || !grid.contains(cell2) {
+                return Err(format!("invalid link, no such cell: {}-{}", cell1, cell2));
+            }
+
+            if !grid.neighbors(cell1).contains(&cell2) {
+                return Err(format!("invalid link, cells not adjacent: {}-{}", cell1, cell2));
+            }
+
+            grid.link(cell1, cell2);
+        }
+
+        for (cell, dir) in &data.openings {
+            let dir: GridDirection = dir.parse()?;
+
+            if !grid.contains(*cell) || grid.cell_to(*cell, dir).is_some() {
+                return Err(format!("invalid opening: {} {}", cell, dir));
+            }
+
+            grid.open_boundary(*cell, dir);
+        }
+
+        Ok(grid)
+    }
+
+    /// Gets all of the links in the grid as pairs of cell IDs.  Each link is listed once,
+    /// with the lower ID first, and the pairs are sorted.
+    pub fn link_pairs(&self) -> Vec<(Cell, Cell)> {
+        let mut links = Vec::new();
+
+        for cell in 0..self.num_cells {
+            let mut cell_links = self.links(cell);
+            cell_links.sort();
+
+            for other in cell_links {
+                if cell < other {
+                    links.push((cell, other));
+                }
+            }
+        }
+
+        links
+    }
+
+    /// Compares this grid's links with those of another grid of the same dimensions,
+    /// returning the links the other grid adds and the links it removes.
+    pub fn link_diff(&self, other: &Grid) -> LinkDiff {
+        assert!(self.num_rows == other.num_rows && self.num_cols == other.num_cols);
+
+        let before: HashSet<(Cell, Cell)> = self.link_pairs().into_iter().collect();
+        let after: HashSet<(Cell, Cell)> = other.link_pairs().into_iter().collect();
+
+        let mut added: Vec<(Cell, Cell)> = after.difference(&before).copied().collect();
+        let mut removed: Vec<(Cell, Cell)> = before.difference(&after).copied().collect();
+        added.sort();
+        removed.sort();
+
+        LinkDiff { added, removed }
+    }
+
+    /// Returns the pairs of distinct cells whose distances to the goal differ by at most
+    /// `tolerance`, ranked by distance from the goal, descending.  See `FairStarts` for
+    /// the full set of fairness constraints.
+    pub fn fair_starts(&self, goal: Cell, tolerance: usize) -> Vec<(Cell, Cell)> {
+        FairStarts::new().tolerance(tolerance).find(self, goal)
+    }
+
+    /// Checks that the grid can be rendered: every link must join neighboring cells, no
+    /// cell may be linked to itself, every link must be recorded on both sides, and no two
+    /// passages may cross.  Returns all of the issues found.
+    pub fn validate_renderable(&self) -> Result<(), Vec<RenderabilityIssue>> {
+        let issues = crate::validation::renderability_issues(self);
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Renders the maze to an image::RgbaImage, which can then be modified further
+    /// or written to disk.  Cells are 10 pixels square, with 2-pixel borders.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(4, 6);
+    /// binary_tree_maze(&mut grid);
+    ///
+    /// let image = grid.to_image();
+    /// assert_eq!(image.width(), 6 * 10 + 7 * 2);
+    /// assert_eq!(image.height(), 4 * 10 + 5 * 2);
+    /// ```
+    pub fn to_image(&self) -> RgbaImage {
+        ImageGridRenderer::new()
+            .cell_size(10)
+            .border_width(2)
+            .render(self)
+    }
+
+    /// Renders the grid as an image like `to_image`, but draws the boundaries between
+    /// linked cells in the `gridline` color, like the lines on graph paper, and the walls
+    /// in the `wall` color.  This shows how the maze maps onto the grid's cells.
+    ///
+    /// ```
+    /// use mazegen::*;
+    /// use image::Rgba;
+    ///
+    /// let mut grid = Grid::new(1, 2);
+    /// grid.link(0, 1);
+    ///
+    /// let faint = Rgba([192, 192, 255, 255]);
+    /// let strong = Rgba([0, 0, 128, 255]);
+    /// let image = grid.to_image_with_gridlines(faint, strong);
+    ///
+    /// // The boundary between the cells is a gridline; the outer walls are walls.
+    /// assert_eq!(*image.get_pixel(12, 6), faint);
+    /// assert_eq!(*image.get_pixel(6, 0), strong);
+    /// ```
+    pub fn to_image_with_gridlines(&self, gridline: Rgba<u8>, wall: Rgba<u8>) -> RgbaImage {
+        ImageGridRenderer::new()
+            .cell_size(10)
+            .border_width(2)
+            .gridline_color(gridline)
+            .wall_color(wall)
+            .render(self)
+    }
+
+    /// Renders the grid as an image in the given theme.  See `Theme`.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(4, 6);
+    /// binary_tree_maze(&mut grid);
+    ///
+    /// let image = grid.render_themed(&Theme::blueprint());
+    /// assert_eq!(image.width(), 6 * 12 + 7);
+    /// ```
+    pub fn render_themed(&self, theme: &Theme) -> RgbaImage {
+        theme.render(self)
+    }
+
+    /// Renders a map of the distances from the start cell, shading the cells with the
+    /// theme's distance palette and adding a gradient legend below the maze.  See
+    /// `Theme::render_distance_map`.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(6, 8);
+    /// recursive_backtracker(&mut grid);
+    ///
+    /// let image = grid.render_distance_map(0, &Theme::dark());
+    /// assert_eq!(image.height(), 6 * 12 + 2 + 10 + 4);
+    /// ```
+    pub fn render_distance_map(&self, start: Cell, theme: &Theme) -> RgbaImage {
+        theme.render_distance_map(self, start)
+    }
+}
+
+/// Finds the cell with the maximum distance in a vector of distances, returning the cell and
+/// its distance.  Ties go to the lowest cell ID.  Returns (0, 0) if no cell has a distance.
+fn max_distance(dists: &[Option<usize>]) -> (Cell, usize) {
+    let mut best: Option<(Cell, usize)> = None;
+
+    for (c, dist) in dists.iter().enumerate() {
+        // Only a strictly greater distance replaces the best, so that the first of any
+        // ties is kept.
+        match (best, *dist) {
+            (_, None) => (),
+            (Some((_, max)), Some(dist)) if dist <= max => (),
+            (_, Some(dist)) => best = Some((c, dist)),
+        }
+    }
+
+    best.unwrap_or((0, 0))
+}
+
+impl MazeGrid for Grid {
+    fn num_cells(&self) -> usize {
+        Grid::num_cells(self)
+    }
+
+    fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        Grid::neighbors(self, cell)
+    }
+
+    fn link(&mut self, cell1: Cell, cell2: Cell) {
+        Grid::link(self, cell1, cell2)
+    }
+
+    fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        Grid::unlink(self, cell1, cell2)
+    }
+
+    fn links(&self, cell: Cell) -> Vec<Cell> {
+        Grid::links(self, cell)
+    }
+
+    fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        Grid::is_linked(self, cell1, cell2)
+    }
+
+    fn random_cell_with(&self, rng: &mut impl Rng) -> Cell {
+        Grid::random_cell_with(self, rng)
+    }
+
+    fn clear(&mut self) {
+        Grid::clear(self)
+    }
+}
+
+// Output the maze dimensions and the maze itself using simply ASCII graphics.
+impl Display for Grid {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         writeln!(f, "Grid({}x{})", self.num_rows, self.num_cols)?;
         writeln!(f, "{}", TextGridRenderer::new().render(self))
     }
 }
 
+/// The serialized form of a Grid: its dimensions, its links, and its isolated cells,
+/// openings, and metadata, if any.  The grid itself doesn't use the metadata, so any JSON object is
+/// accepted there.
+#[derive(Debug, Serialize, Deserialize)]
+struct GridJson {
+    rows: usize,
+    cols: usize,
+    #[serde(default, skip_serializing_if = "Topology::is_planar")]
+    topology: Topology,
+    links: Vec<(Cell, Cell)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    isolated: Vec<Cell>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    openings: Vec<(Cell, String)>,
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    meta: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The differences between the links of two grids, as computed by `Grid::link_diff`.
+/// Each link is a pair of cell IDs with the lower ID first, and the pairs are sorted.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct LinkDiff {
+    /// The links present only in the second grid.
+    pub added: Vec<(Cell, Cell)>,
+
+    /// The links present only in the first grid.
+    pub removed: Vec<(Cell, Cell)>,
+}
+
+/// Summary statistics about the structure of a maze, as computed by `Grid::stats`.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct GridStats {
+    /// The number of cells with exactly one link.
+    pub dead_ends: usize,
+
+    /// The number of cells with exactly two links.
+    pub corridors: usize,
+
+    /// The number of cells with exactly three links.
+    pub junctions3: usize,
+
+    /// The number of cells with exactly four links.
+    pub junctions4: usize,
+
+    /// The number of east/west links.
+    pub horiz_links: usize,
+
+    /// The number of north/south links.
+    pub vert_links: usize,
+
+    /// The length of the longest path through the maze, in steps.
+    pub longest: usize,
+}
+
+/// Computes the cells to the north, south, east, and west of the cell in a grid with the
+/// given dimensions and topology, in that order, or None at the edges of the grid.
+fn compute_neighbors(
+    num_rows: usize,
+    num_cols: usize,
+    topology: Topology,
+    cell: Cell,
+) -> [Option<Cell>; 4] {
+    let (i, j) = (cell / num_cols, cell % num_cols);
+    let wrap_cols = topology != Topology::Planar && num_cols > 2;
+    let wrap_rows = topology == Topology::Torus && num_rows > 2;
+    let last_row = num_rows - 1;
+    let last_col = num_cols - 1;
+
+    [
+        if i > 0 {
+            Some(cell - num_cols)
+        } else if wrap_rows {
+            Some(cell + last_row * num_cols)
+        } else {
+            None
+        },
+        if i < last_row {
+            Some(cell + num_cols)
+        } else if wrap_rows {
+            Some(cell - last_row * num_cols)
+        } else {
+            None
+        },
+        if j < last_col {
+            Some(cell + 1)
+        } else if wrap_cols {
+            Some(cell - last_col)
+        } else {
+            None
+        },
+        if j > 0 {
+            Some(cell - 1)
+        } else if wrap_cols {
+            Some(cell + last_col)
+        } else {
+            None
+        },
+    ]
+}
+
+/// A grid's cached diameter and the revision it was computed for, which `Grid::diameter`
+/// sets through a shared reference.  It's kept behind a mutex, rather than in a
+/// `std::cell::Cell`, so that Grid remains `Sync`.
+#[derive(Debug, Default)]
+struct DiameterCache(Mutex<Option<(u64, usize)>>);
+
+impl DiameterCache {
+    fn get(&self) -> Option<(u64, usize)> {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set(&self, value: Option<(u64, usize)>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = value;
+    }
+}
+
+impl Clone for DiameterCache {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.get()))
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct CellData {
@@ -433,17 +2863,181 @@ impl CellData {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_grid_new() {
+        let grid = Grid::new(5, 6);
+
+        assert_eq!(grid.num_rows(), 5);
+        assert_eq!(grid.num_cols(), 6);
+        assert_eq!(grid.num_cells(), 30);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one row and one column")]
+    fn test_grid_new_empty() {
+        let _ = Grid::new(0, 5);
+    }
+
+    #[test]
+    fn test_grid_link_diff() {
+        let mut before = Grid::new(2, 2);
+        before.link(0, 1);
+        before.link(1, 3);
+
+        let mut after = before.clone();
+        after.unlink(1, 3);
+        after.link(2, 3);
+        after.link(0, 2);
+
+        assert_eq!(before.link_pairs(), vec![(0, 1), (1, 3)]);
+
+        let diff = before.link_diff(&after);
+        assert_eq!(diff.added, vec![(0, 2), (2, 3)]);
+        assert_eq!(diff.removed, vec![(1, 3)]);
+        assert_eq!(after.link_diff(&after), LinkDiff::default());
+    }
+
+    #[test]
+    fn test_grid_to_dot() {
+        let mut grid = Grid::new(1, 2);
+        grid.link(0, 1);
+        assert_eq!(
+            grid.to_dot(),
+            "graph maze {\n    0 [label=\"(0,0)\"];\n    1 [label=\"(0,1)\"];\n    0 -- 1;\n}\n"
+        );
+
+        let mut grid = Grid::new(6, 7);
+        crate::hunt_and_kill(&mut grid);
+        let dot = grid.to_dot();
+
+        assert_eq!(dot.matches(" -- ").count(), grid.num_links());
+        assert_eq!(dot.matches("[label=").count(), grid.num_cells());
+    }
+
+    #[test]
+    fn test_grid_to_tmx() {
+        let mut grid = Grid::new(4, 6);
+        crate::recursive_backtracker(&mut grid);
+        grid.open_boundary(0, GridDirection::West);
+        let tmx = grid.to_tmx(32);
+
+        assert!(tmx.starts_with("<?xml"));
+        assert!(tmx.contains(" width=\"13\" height=\"9\" tilewidth=\"32\" tileheight=\"32\""));
+
+        // The data layer has a GID for each tile.
+        let start = tmx.find("<data encoding=\"csv\">").unwrap();
+        let end = tmx.find("</data>").unwrap();
+        let gids: Vec<&str> = tmx[start..end]
+            .split('>')
+            .nth(1)
+            .unwrap()
+            .split(',')
+            .map(|g| g.trim())
+            .collect();
+        assert_eq!(gids.len(), 13 * 9);
+
+        // Floors are cells plus links plus the opening.
+        let floors = gids.iter().filter(|g| **g == "1").count();
+        assert_eq!(floors, grid.num_cells() + grid.num_links() + 1);
+        assert!(gids.iter().all(|g| *g == "1" || *g == "2"));
+        assert_eq!(gids[13], "1");
+    }
+
+    #[test]
+    fn test_grid_from_mask() {
+        // A ring of cells around a dead center.
+        let mask = Mask::from_text("ooo\noXo\nooo").unwrap();
+        let mut grid = Grid::from_mask(&mask);
+
+        assert_eq!(grid.num_rows(), 3);
+        assert_eq!(grid.num_cols(), 3);
+        assert!(grid.is_isolated(4));
+        assert!(!grid.is_isolated(1));
+        assert!(grid.neighbors(4).is_empty());
+        assert_eq!(grid.south_of(1), None);
+
+        for _ in 0..20 {
+            assert_ne!(grid.random_cell(), 4);
+        }
+
+        crate::recursive_backtracker(&mut grid);
+        assert!(grid.links(4).is_empty());
+        assert_eq!(grid.num_links(), 7);
+        assert!(grid.distances(0).iter().filter(|d| d.is_some()).count() == 8);
+    }
+
+    #[test]
+    fn test_grid_largest_component() {
+        // A big region on the left, a small one on the right, and a lone cell.
+        let mask = Mask::from_text("ooooX.o\nooooX.X\nXXXXXoo").unwrap();
+        let mut grid = Grid::from_mask(&mask);
+
+        let region = grid.largest_component();
+        assert_eq!(region, vec![0, 1, 2, 3, 7, 8, 9, 10]);
+
+        grid.keep_only(&region);
+        assert!(grid.is_isolated(6));
+        assert!(grid.is_isolated(19));
+        assert!(!grid.is_isolated(3));
+
+        crate::recursive_backtracker(&mut grid);
+        assert_eq!(grid.num_links(), 7);
+        assert_eq!(grid.largest_component(), region);
+
+        // With a single region, it's the whole grid.
+        let grid = Grid::new(3, 4);
+        assert_eq!(grid.largest_component(), (0..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_grid_cell_names() {
+        let grid = Grid::new(12, 800);
+
+        // Names round-trip, including past column Z.
+        for &j in &[0, 1, 25, 26, 27, 51, 52, 701, 702, 799] {
+            for &i in &[0, 9, 11] {
+                let cell = grid.cell(i, j);
+                assert_eq!(grid.cell_from_name(&grid.cell_name(cell)), Ok(cell));
+            }
+        }
+
+        // The letters are the column and the number is the row.
+        assert_eq!(grid.cell_name(grid.cell(0, 25)), "Z1");
+        assert_eq!(grid.cell_name(grid.cell(0, 26)), "AA1");
+        assert_eq!(grid.cell_name(grid.cell(11, 51)), "AZ12");
+        assert_eq!(grid.cell_name(grid.cell(0, 52)), "BA1");
+        assert_eq!(grid.cell_name(grid.cell(0, 701)), "ZZ1");
+        assert_eq!(grid.cell_name(grid.cell(0, 702)), "AAA1");
+        assert_eq!(grid.ij(grid.cell_from_name("c2").unwrap()), (1, 2));
+
+        // Errors
+        assert!(grid.cell_from_name("").is_err());
+        assert!(grid.cell_from_name("A").is_err());
+        assert!(grid.cell_from_name("12").is_err());
+        assert!(grid.cell_from_name("A0").is_err());
+        assert!(grid.cell_from_name("A1B").is_err());
+        assert!(grid.cell_from_name("A13").is_err());
+        assert!(grid.cell_from_name("ZZZ1").is_err());
+        assert!(grid.cell_from_name("ZZZZZZZZZZZZZZZZZZZZ1").is_err());
+    }
 
     #[test]
-    fn test_grid_new() {
-        let grid = Grid::new(5, 6);
+    fn test_grid_solution_key() {
+        let grid = Grid::new(4, 4);
 
-        assert_eq!(grid.num_rows(), 5);
-        assert_eq!(grid.num_cols(), 6);
-        assert_eq!(grid.num_cells(), 30);
+        assert_eq!(grid.solution_key(&[]), "");
+        assert_eq!(grid.solution_key(&[5]), "B2\nStart at B2, finish at B2\n");
+        assert_eq!(
+            grid.solution_key(&[0, 4, 5, 6, 2, 3, 7, 11, 15]),
+            "A1, A2, B2, C2, C1, D1, D2, D3, D4\n\
+             Start at A1, south 1, east 2, north 1, east 1, south 3, finish at D4\n"
+        );
     }
 
     #[test]
@@ -489,6 +3083,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_grid_random_cell() {
+        for (rows, cols) in &[(1, 1), (1, 10), (10, 1), (5, 6), (20, 30)] {
+            let grid = Grid::new(*rows, *cols);
+
+            for _ in 0..100 {
+                assert!(grid.contains(grid.random_cell()));
+            }
+        }
+    }
+
     #[test]
     fn test_grid_neighbors() {
         let grid = Grid::new(5, 6);
@@ -550,6 +3155,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_grid_link_direction() {
+        let mut grid = Grid::new(3, 4);
+
+        // Linking east from the east edge links nothing.
+        let edge = grid.cell(1, 3);
+        assert!(!grid.link_direction(edge, GridDirection::East));
+        assert_eq!(grid.num_links(), 0);
+        assert!(grid.links(edge).is_empty());
+
+        // Linking in a direction with a neighbor links to it.
+        assert!(grid.link_direction(edge, GridDirection::West));
+        assert!(grid.is_linked(edge, grid.cell(1, 2)));
+        assert!(grid.link_direction(edge, GridDirection::North));
+        assert!(grid.is_linked_north(edge));
+        assert_eq!(grid.num_links(), 2);
+
+        // Isolated cells aren't neighbors.
+        grid.isolate(grid.cell(0, 0));
+        assert!(!grid.link_direction(grid.cell(0, 1), GridDirection::West));
+        assert_eq!(grid.num_links(), 2);
+    }
+
     #[test]
     fn test_grid_is_linked_direction() {
         let mut grid = Grid::new(5, 6);
@@ -580,6 +3208,928 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_grid_stats() {
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 1);
+        grid.link(0, 2);
+        grid.link(2, 3);
+
+        let stats = grid.stats();
+        assert_eq!(stats.dead_ends, 2);
+        assert_eq!(stats.corridors, 2);
+        assert_eq!(stats.junctions3, 0);
+        assert_eq!(stats.junctions4, 0);
+        assert_eq!(stats.horiz_links, 2);
+        assert_eq!(stats.vert_links, 1);
+        assert_eq!(stats.longest, 3);
+        assert_eq!(grid.dead_end_count(), grid.dead_ends().len());
+    }
+
+    #[test]
+    fn test_grid_cells_between() {
+        // A loop around the center cell, with a spur to the east of the top-right corner.
+        //
+        // +---+---+---+---+
+        // | 0   1   2   3 |
+        // +   +---+   +---+
+        // | 4 | 5 | 6 | 7 |
+        // +   +---+   +---+
+        // | 8   9  10 |11 |
+        // +---+---+---+---+
+        let mut grid = Grid::new(3, 4);
+        grid.link(0, 1);
+        grid.link(1, 2);
+        grid.link(2, 3);
+        grid.link(0, 4);
+        grid.link(4, 8);
+        grid.link(8, 9);
+        grid.link(9, 10);
+        grid.link(2, 6);
+        grid.link(6, 10);
+
+        // Two shortest paths from 0 to 10, around either side of the loop.
+        assert_eq!(grid.cells_between(0, 10, 0), vec![0, 1, 2, 4, 6, 8, 9, 10]);
+
+        // Only one shortest path from 1 to 6; the spur is within one step of it.
+        assert_eq!(grid.cells_between(1, 6, 0), vec![1, 2, 6]);
+        assert_eq!(grid.cells_between(1, 6, 1), vec![0, 1, 2, 3, 6, 10]);
+
+        // Unreachable
+        assert!(grid.cells_between(0, 5, 0).is_empty());
+    }
+
+    #[test]
+    fn test_grid_cells_between_shortest_path() {
+        let mut grid = Grid::new(8, 8);
+        crate::recursive_backtracker(&mut grid);
+
+        let mut path = grid.shortest_path(0, 63).into_cells();
+        path.sort();
+
+        assert_eq!(grid.cells_between(0, 63, 0), path);
+    }
+
+    #[test]
+    fn test_grid_compute_neighbors() {
+        for &(rows, cols) in &[(1, 1), (1, 5), (4, 1), (6, 7)] {
+            let grid = Grid::new(rows, cols);
+
+            for cell in 0..grid.num_cells() {
+                let data = &grid.cells[cell];
+                let stored = [data.north, data.south, data.east, data.west];
+                assert_eq!(
+                    compute_neighbors(rows, cols, Topology::Planar, cell),
+                    stored
+                );
+                assert_eq!(grid.neighbors_of(cell), stored);
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_cylinder() {
+        let cols = 5;
+        let grid = Grid::with_topology(4, cols, Topology::Cylinder);
+        assert_eq!(grid.topology(), Topology::Cylinder);
+        assert_eq!(grid.west_of(grid.cell(0, 0)), Some(grid.cell(0, cols - 1)));
+        assert_eq!(grid.east_of(grid.cell(2, cols - 1)), Some(grid.cell(2, 0)));
+        assert_eq!(grid.north_of(grid.cell(0, 0)), None);
+        assert_eq!(grid.south_of(grid.cell(3, 0)), None);
+
+        // Every cell has an east and a west neighbor.
+        for cell in 0..grid.num_cells() {
+            let (i, _) = grid.ij(cell);
+            let edge = i == 0 || i == 3;
+            assert_eq!(grid.neighbors(cell).len(), if edge { 3 } else { 4 });
+        }
+    }
+
+    #[test]
+    fn test_grid_torus() {
+        let mut grid = Grid::with_topology(4, 5, Topology::Torus);
+        assert_eq!(grid.north_of(grid.cell(0, 2)), Some(grid.cell(3, 2)));
+        assert_eq!(grid.south_of(grid.cell(3, 2)), Some(grid.cell(0, 2)));
+        assert_eq!(grid.west_of(grid.cell(1, 0)), Some(grid.cell(1, 4)));
+        assert!((0..grid.num_cells()).all(|c| grid.neighbors(c).len() == 4));
+
+        for cell in 0..grid.num_cells() {
+            let data = &grid.cells[cell];
+            let stored = [data.north, data.south, data.east, data.west];
+            assert_eq!(grid.neighbors_of(cell), stored);
+        }
+
+        crate::recursive_backtracker(&mut grid);
+        assert!(grid.is_perfect_maze());
+
+        // The topology survives JSON, and wrapped links are accepted.
+        let copy = Grid::from_json(&grid.to_json()).unwrap();
+        assert_eq!(copy, grid);
+        assert!(!Grid::new(4, 5).to_json().contains("topology"));
+        assert_ne!(Grid::new(4, 5), Grid::with_topology(4, 5, Topology::Torus));
+    }
+
+    #[test]
+    fn test_grid_narrow_wrap() {
+        // Edges less than three cells across don't wrap.
+        let grid = Grid::with_topology(2, 2, Topology::Torus);
+        assert_eq!(grid.neighbors_of(0), Grid::new(2, 2).neighbors_of(0));
+
+        let grid = Grid::with_topology(1, 1, Topology::Cylinder);
+        assert!(grid.neighbors(0).is_empty());
+    }
+
+    #[test]
+    fn test_grid_passage_morphology() {
+        let mut base = Grid::new(12, 16);
+        crate::recursive_backtracker_rng(&mut base, &mut StdRng::seed_from_u64(3));
+        base.braid_rng(0.5, &mut StdRng::seed_from_u64(4));
+
+        // Dilation never removes links, and erosion never adds them.
+        let mut dilated = base.clone();
+        dilated.dilate_passages(2);
+        assert!(dilated.num_links() >= base.num_links());
+        assert!(base
+            .link_pairs()
+            .iter()
+            .all(|(a, b)| dilated.is_linked(*a, *b)));
+
+        let mut eroded = base.clone();
+        eroded.erode_passages(2, 3);
+        assert!(eroded.num_links() <= base.num_links());
+        assert!(eroded
+            .link_pairs()
+            .iter()
+            .all(|(a, b)| base.is_linked(*a, *b)));
+
+        // Open and close compose the primitives.
+        let mut opened = base.clone();
+        opened.open_passages(2, 2);
+        let mut expected = base.clone();
+        expected.erode_passages(2, 2);
+        expected.dilate_passages(2);
+        assert_eq!(opened, expected);
+
+        let mut closed = base.clone();
+        closed.close_passages(3, 1);
+        let mut expected = base.clone();
+        expected.dilate_passages(1);
+        expected.erode_passages(3, 1);
+        assert_eq!(closed, expected);
+
+        // The results are deterministic.
+        let mut again = base.clone();
+        again.open_passages(2, 2);
+        assert_eq!(again, opened);
+    }
+
+    #[test]
+    fn test_grid_connected_components() {
+        // No links: every cell is a component.
+        let mut grid = Grid::new(3, 4);
+        assert_eq!(grid.connected_components().len(), 12);
+        assert!(!grid.is_connected());
+        assert!(!grid.is_perfect_maze());
+
+        // Two components, built by hand.
+        for &(a, b) in &[
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (4, 5),
+            (5, 6),
+            (6, 7),
+            (8, 9),
+            (9, 10),
+        ] {
+            grid.link(a, b);
+        }
+        for &(a, b) in &[(10, 11), (0, 4), (3, 7)] {
+            grid.link(a, b);
+        }
+        assert_eq!(
+            grid.connected_components(),
+            vec![vec![0, 1, 2, 3, 4, 5, 6, 7], vec![8, 9, 10, 11]]
+        );
+        assert_eq!(grid.num_components(), 2);
+        assert!(!grid.is_connected());
+
+        // Each generator gives a perfect maze; braiding it keeps it connected.
+        let mut rng = StdRng::seed_from_u64(23);
+        for algo in &crate::Algorithm::ALL {
+            let mut grid = Grid::new(15, 20);
+            algo.generate_rng(&mut grid, &mut rng);
+            assert!(grid.is_perfect_maze(), "{}", algo);
+            assert_eq!(grid.connected_components().len(), 1);
+            assert_eq!(grid.num_links(), grid.num_cells() - 1);
+
+            grid.braid_rng(1.0, &mut rng);
+            assert!(grid.is_connected());
+            assert!(!grid.is_perfect_maze(), "{}", algo);
+            assert_eq!(grid.connected_components().len(), 1);
+        }
+
+        // A grid too big for a recursive walk.
+        let mut grid = Grid::new(1, 200_000);
+        grid.link_all();
+        assert_eq!(grid.connected_components().len(), 1);
+    }
+
+    #[test]
+    fn test_grid_shortest_path_unreachable() {
+        // Two components: the left two columns, and the right two.
+        let mut grid = Grid::new(3, 4);
+        for i in 0..3 {
+            grid.link(grid.cell(i, 0), grid.cell(i, 1));
+            grid.link(grid.cell(i, 2), grid.cell(i, 3));
+        }
+        grid.link(0, 4);
+        grid.link(4, 8);
+        grid.link(3, 7);
+        grid.link(7, 11);
+        assert_eq!(grid.num_components(), 2);
+
+        assert_eq!(MazeGrid::shortest_path(&grid, 0, 3), Vec::<Cell>::new());
+        assert!(grid.shortest_path(0, 3).is_empty());
+        assert!(grid.shortest_path(11, 9).is_empty());
+        assert_eq!(grid.shortest_path(0, 9).cells(), &[0, 4, 8, 9]);
+
+        // An isolated cell can't be reached either.
+        grid.isolate(10);
+        assert!(grid.shortest_path(0, 10).is_empty());
+        assert!(grid.shortest_path(10, 0).is_empty());
+    }
+
+    #[test]
+    fn test_grid_astar() {
+        let mut rng = StdRng::seed_from_u64(24);
+        let mut grid = Grid::new(25, 30);
+        crate::recursive_backtracker_rng(&mut grid, &mut rng);
+        grid.braid_rng(0.5, &mut rng);
+
+        for _ in 0..20 {
+            let start = grid.random_cell_with(&mut rng);
+            let goal = grid.random_cell_with(&mut rng);
+            let (gi, gj) = grid.ij(goal);
+            let manhattan = |c: Cell| {
+                let (i, j) = grid.ij(c);
+                i.max(gi) - i.min(gi) + j.max(gj) - j.min(gj)
+            };
+
+            // With no heuristic, it's a breadth-first search.
+            let bfs = grid.shortest_path(start, goal);
+            let path = grid.astar(start, goal, |_| 0);
+            assert_eq!(path.len(), bfs.len());
+            assert_eq!(path.first(), Some(start));
+            assert_eq!(path.last(), Some(goal));
+
+            // With the Manhattan distance, the path is still a shortest one.
+            let path = grid.astar(start, goal, manhattan);
+            assert_eq!(path.len(), bfs.len());
+            assert!(CellPath::new(&grid, path.into_cells()).is_ok());
+        }
+
+        // No path
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 1);
+        assert!(grid.astar(0, 3, |_| 0).is_empty());
+        assert_eq!(grid.astar(1, 1, |_| 0).into_cells(), vec![1]);
+    }
+
+    #[test]
+    fn test_grid_keep_largest_component() {
+        let mut grid = Grid::new(3, 3);
+        grid.link(0, 1);
+        grid.link(6, 7);
+        grid.link(7, 8);
+        grid.link(8, 5);
+        grid.keep_largest_component();
+        assert_eq!(grid.link_pairs(), vec![(5, 8), (6, 7), (7, 8)]);
+
+        // Ties go to the component with the lowest cell.
+        let mut grid = Grid::new(1, 4);
+        grid.link(2, 3);
+        grid.link(0, 1);
+        grid.keep_largest_component();
+        assert_eq!(grid.link_pairs(), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_grid_diff_links_after_braid() {
+        let mut maze = Grid::new(10, 12);
+        crate::recursive_backtracker_rng(&mut maze, &mut StdRng::seed_from_u64(17));
+
+        let mut braided = maze.clone_without_links();
+        assert_eq!(braided.num_links(), 0);
+        braided.copy_links_from(&maze).unwrap();
+        assert_eq!(braided, maze);
+        assert!(maze.diff_links(&braided).is_empty());
+
+        braided.braid_rng(0.7, &mut StdRng::seed_from_u64(18));
+        let added: Vec<(Cell, Cell)> = braided
+            .link_pairs()
+            .into_iter()
+            .filter(|(a, b)| !maze.is_linked(*a, *b))
+            .collect();
+
+        assert!(!added.is_empty());
+        assert_eq!(maze.diff_links(&braided), added);
+        assert_eq!(braided.diff_links(&maze), added);
+    }
+
+    #[test]
+    fn test_grid_copy_links_from_errors() {
+        let mut grid = Grid::new(3, 3);
+        grid.link(0, 1);
+
+        assert!(grid.copy_links_from(&Grid::new(3, 4)).is_err());
+
+        // A link into a cell that's isolated here is rejected.
+        let mut other = Grid::new(3, 3);
+        other.link(4, 5);
+        grid.isolate(5);
+        assert!(grid.copy_links_from(&other).is_err());
+        assert!(grid.is_linked(0, 1));
+    }
+
+    #[test]
+    fn test_grid_append() {
+        let mut rng = StdRng::seed_from_u64(2049);
+        let mut left = Grid::new(5, 4);
+        crate::recursive_backtracker_rng(&mut left, &mut rng);
+        let mut right = Grid::new(5, 6);
+        crate::hunt_and_kill_rng(&mut right, &mut rng);
+
+        // One seam link joins two perfect mazes into one.
+        let both = left.append_east_rng(&right, 1, &mut rng).unwrap();
+        assert_eq!((both.num_rows(), both.num_cols()), (5, 10));
+        assert_eq!(both.connected_components().len(), 1);
+        assert_eq!(both.num_links(), both.num_cells() - 1);
+        assert_eq!(both.crop(0, 0, 5, 4), Ok(left.clone()));
+        assert_eq!(both.crop(0, 4, 5, 6), Ok(right.clone()));
+
+        // With no seam links, the mazes stay apart; with more, there are loops.
+        let apart = left.append_east_rng(&right, 0, &mut rng).unwrap();
+        assert_eq!(apart.connected_components().len(), 2);
+        let loopy = left.append_east_rng(&right, 5, &mut rng).unwrap();
+        assert_eq!(loopy.num_links(), left.num_links() + right.num_links() + 5);
+
+        // South
+        let mut bottom = Grid::new(3, 4);
+        crate::sidewinder_maze_rng(&mut bottom, &mut rng);
+        let both = left.append_south_rng(&bottom, 1, &mut rng).unwrap();
+        assert_eq!((both.num_rows(), both.num_cols()), (8, 4));
+        assert!(both.is_perfect_maze());
+        assert_eq!(both.crop(5, 0, 3, 4), Ok(bottom.clone()));
+
+        // Mismatches and overfull seams are errors.
+        assert!(left.append_east(&bottom, 1).is_err());
+        assert!(left.append_south(&right, 1).is_err());
+        assert!(left.append_east(&right, 6).is_err());
+
+        // Isolated cells aren't linked through the seam.
+        let mut masked = left.clone();
+        for i in 0..5 {
+            masked.isolate(masked.cell(i, 3));
+        }
+        assert!(masked.append_east(&right, 1).is_err());
+    }
+
+    #[test]
+    fn test_grid_adjacency_matrix() {
+        let mut grid = Grid::new(3, 4);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(2048));
+        let n = grid.num_cells();
+        let matrix = grid.adjacency_matrix();
+        assert_eq!(matrix.len(), n * n);
+
+        for a in 0..n {
+            for b in 0..n {
+                assert_eq!(matrix[a * n + b], grid.is_linked(a, b));
+            }
+        }
+
+        let degrees = grid.degree_sequence();
+        assert_eq!(degrees.iter().sum::<usize>(), 2 * grid.num_links());
+        assert_eq!(
+            degrees.iter().filter(|d| **d == 1).count(),
+            grid.dead_end_count()
+        );
+    }
+
+    #[test]
+    fn test_grid_crop_and_expand() {
+        // A 3x4 maze, drawn as text so that its links are known.
+        let grid = Grid::from_ascii(
+            "\
++---+---+---+---+
+|           |   |
++   +---+   +   +
+|   |       |   |
++   +   +---+   +
+|       |       |
++---+---+---+---+
+",
+        )
+        .unwrap();
+
+        // Crop the bottom-right 2x3 region.
+        let part = grid.crop(1, 1, 2, 3).unwrap();
+        assert_eq!((part.num_rows(), part.num_cols()), (2, 3));
+        assert!(part.is_linked_east(part.cell(0, 0)));
+        assert!(!part.is_linked_east(part.cell(0, 1)));
+        assert!(part.is_linked_south(part.cell(0, 2)));
+        assert!(part.is_linked_east(part.cell(1, 1)));
+        assert!(part.is_linked_south(part.cell(0, 0)));
+        assert!(!part.is_linked_south(part.cell(0, 1)));
+        assert_eq!(part.num_links(), 4);
+
+        // Cropping and expanding back loses just the links across the crop's boundary.
+        let back = grid.crop(0, 0, 2, 3).unwrap().expanded(3, 4).unwrap();
+        let diff = grid.link_diff(&back);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed.len(), 6);
+        for &(a, b) in &diff.removed {
+            let (ai, aj) = grid.ij(a);
+            let (bi, bj) = grid.ij(b);
+            assert!(ai.max(bi) == 2 || aj.max(bj) == 3);
+        }
+
+        // Isolated cells stay isolated.
+        let mut masked = grid.clone();
+        masked.isolate(masked.cell(2, 3));
+        let part = masked.crop(1, 2, 2, 2).unwrap();
+        assert!(part.is_isolated(part.cell(1, 1)));
+        assert!(!part.is_isolated(part.cell(0, 1)));
+
+        // The whole grid crops to itself.
+        assert_eq!(grid.crop(0, 0, 3, 4), Ok(grid.clone()));
+        assert_eq!(grid.expanded(3, 4), Ok(grid.clone()));
+
+        // Bad regions and sizes
+        assert!(grid.crop(0, 0, 0, 4).is_err());
+        assert!(grid.crop(1, 0, 3, 4).is_err());
+        assert!(grid.crop(0, 2, 3, 3).is_err());
+        assert!(grid.expanded(3, 3).is_err());
+    }
+
+    #[test]
+    fn test_grid_generate_region() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut maze = Grid::new(12, 12);
+        crate::recursive_backtracker_rng(&mut maze, &mut rng);
+
+        // A ring of cells, which the row-by-row algorithms would leave in pieces.
+        let ring: Vec<Cell> = (0..maze.num_cells())
+            .filter(|&c| {
+                let (i, j) = maze.ij(c);
+                (3..9).contains(&i)
+                    && (3..9).contains(&j)
+                    && !((5..7).contains(&i) && (5..7).contains(&j))
+            })
+            .collect();
+        let in_ring = |c: Cell| ring.contains(&c);
+
+        for algo in &["backtracker", "bintree", "division", "sidewinder"] {
+            let mut grid = maze.clone();
+            grid.generate_region(&ring, algo, &mut rng).unwrap();
+
+            // Links that aren't within the ring are untouched.
+            for (a, b) in grid.diff_links(&maze) {
+                assert!(in_ring(a) && in_ring(b), "{}: {}-{}", algo, a, b);
+            }
+
+            // The ring is a tree: connected, with one fewer link than it has cells.
+            let inside: Vec<(Cell, Cell)> = grid
+                .link_pairs()
+                .into_iter()
+                .filter(|&(a, b)| in_ring(a) && in_ring(b))
+                .collect();
+            assert_eq!(inside.len(), ring.len() - 1, "{}", algo);
+
+            let mut reached = vec![ring[0]];
+            let mut next = 0;
+            while next < reached.len() {
+                for other in grid.links(reached[next]) {
+                    if in_ring(other) && !reached.contains(&other) {
+                        reached.push(other);
+                    }
+                }
+                next += 1;
+            }
+            assert_eq!(reached.len(), ring.len(), "{}", algo);
+        }
+
+        // Errors leave the grid unchanged.
+        let mut grid = maze.clone();
+        assert!(grid.generate_region(&ring, "nonesuch", &mut rng).is_err());
+        assert!(grid
+            .generate_region(&[0, 1, 500], "backtracker", &mut rng)
+            .is_err());
+        assert_eq!(grid, maze);
+    }
+
+    #[test]
+    fn test_grid_essential_cells() {
+        // In a perfect maze, every cell on the path is essential.
+        let mut grid = Grid::new(9, 11);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(12));
+        let goal = grid.num_cells() - 1;
+        assert_eq!(
+            grid.essential_cells(0, goal),
+            grid.shortest_path(0, goal).into_cells()
+        );
+        assert_eq!(grid.essential_cells(7, 7), vec![7]);
+
+        // A loop in the middle of the corridor gives two equally short routes, so the
+        // cells on either side of the loop are bypassed.
+        let mut grid = Grid::new(2, 5);
+        for &(a, b) in &[(0, 1), (1, 2), (2, 7), (1, 6), (6, 7), (7, 8), (8, 9)] {
+            grid.link(a, b);
+        }
+        assert_eq!(grid.essential_cells(0, 9), vec![0, 1, 7, 8, 9]);
+        assert_eq!(grid.shortest_path(0, 9).len(), 6);
+
+        // No path at all
+        assert!(grid.essential_cells(0, 4).is_empty());
+    }
+
+    #[test]
+    fn test_grid_farthest_ties() {
+        // In an open 3x3 room, the four corners tie for farthest from the center.
+        let mut grid = Grid::new(3, 3);
+        grid.carve_room(0, 0, 3, 3);
+        assert_eq!(grid.farthest_with_distance(4), (0, 2));
+        assert_eq!(grid.farthest(4), 0);
+
+        // From a corner, only the opposite corner is at distance 4.
+        assert_eq!(grid.farthest_with_distance(0), (8, 4));
+        assert_eq!(grid.farthest_with_distance(8), (0, 4));
+
+        // From the middle of an edge, the two far corners tie.
+        assert_eq!(grid.farthest_with_distance(1), (6, 3));
+
+        // A cell that reaches no other is its own farthest cell.
+        let grid = Grid::new(2, 2);
+        assert_eq!(grid.farthest_with_distance(3), (3, 0));
+    }
+
+    #[test]
+    fn test_grid_farthest_pair() {
+        for _ in 0..5 {
+            let mut grid = Grid::new(10, 15);
+            crate::recursive_backtracker(&mut grid);
+
+            let (start, end, diameter) = grid.farthest_pair();
+            let path = grid.longest_path();
+
+            assert_eq!(path.len(), diameter + 1);
+            assert_eq!(grid.diameter(), diameter);
+            assert_eq!(path[0], start);
+            assert_eq!(path[diameter], end);
+            assert_eq!(grid.distances(start)[end], Some(diameter));
+        }
+    }
+
+    #[test]
+    fn test_grid_validate_one_sided() {
+        let mut grid = Grid::new(2, 2);
+        grid.cells[0].link(1);
+
+        assert_eq!(
+            grid.validate_renderable(),
+            Err(vec![RenderabilityIssue::OneSided {
+                cell1: 0,
+                cell2: 1,
+                ij1: (0, 0),
+                ij2: (0, 1),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_grid_json() {
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 1);
+        grid.link(0, 2);
+        grid.link(2, 3);
+
+        let json = grid.to_json();
+        assert_eq!(json, r#"{"rows":2,"cols":2,"links":[[0,1],[0,2],[2,3]]}"#);
+        assert_eq!(Grid::from_json(&json), Ok(grid));
+    }
+
+    #[test]
+    fn test_grid_json_round_trip() {
+        let mut grid = Grid::new(10, 15);
+        crate::recursive_backtracker(&mut grid);
+
+        let grid2 = Grid::from_json(&grid.to_json()).unwrap();
+        assert_eq!(grid2, grid);
+
+        // A masked grid keeps its mask.
+        let mask = Mask::from_text("XooX\nooo.\nXooo").unwrap();
+        let mut grid = Grid::from_mask(&mask);
+        crate::recursive_backtracker(&mut grid);
+
+        let json = grid.to_json();
+        assert!(json.contains(r#""isolated":[0,3,7,8]"#));
+        let grid2 = Grid::from_json(&json).unwrap();
+        assert_eq!(grid2, grid);
+        assert!(grid2.is_isolated(7));
+        assert!(Grid::from_json(r#"{"rows":1,"cols":2,"links":[],"isolated":[2]}"#).is_err());
+    }
+
+    #[test]
+    fn test_grid_reset() {
+        let mut grid = Grid::with_topology(4, 5, Topology::Cylinder);
+        grid.link(0, 1);
+        grid.link(1, 6);
+        grid.unlink(1, 6);
+        grid.isolate(12);
+        grid.open_boundary(0, GridDirection::North);
+        grid.link_log = Some(vec![(0, 1)]);
+
+        // Only the linked cells and the isolated cell and its neighbors need restoring.
+        assert_eq!(grid.dirty_cells, vec![0, 1, 6, 12, 7, 17, 13, 11]);
+
+        grid.reset();
+        assert_eq!(grid, Grid::new(4, 5));
+        assert_eq!(grid.topology(), Topology::Planar);
+        assert!(grid.openings().is_empty());
+        assert!(grid.dirty_cells.is_empty());
+        assert!(grid.is_dirty.iter().all(|dirty| !dirty));
+        assert!(grid.link_log.is_none());
+
+        // A planar grid restores only its dirty cells.
+        grid.link(2, 3);
+        assert_eq!(grid.dirty_cells, vec![2, 3]);
+        grid.reset();
+        assert_eq!(grid, Grid::new(4, 5));
+    }
+
+    #[test]
+    fn test_grid_openings() {
+        let mut grid = Grid::new(3, 4);
+        grid.open_boundary(0, GridDirection::North);
+        grid.open_boundary(0, GridDirection::West);
+        grid.open_boundary(0, GridDirection::North);
+        assert_eq!(
+            grid.openings(),
+            &[(0, GridDirection::North), (0, GridDirection::West)]
+        );
+
+        // Openings survive clearing and JSON.
+        grid.link(0, 1);
+        grid.clear();
+        assert_eq!(grid.openings().len(), 2);
+        assert_eq!(Grid::from_json(&grid.to_json()), Ok(grid.clone()));
+        assert!(
+            Grid::from_json(r#"{"rows":2,"cols":2,"links":[],"openings":[[0,"east"]]}"#).is_err()
+        );
+
+        // An opening next to an isolated cell is seen from both sides.
+        let mut grid = Grid::new(3, 3);
+        grid.isolate(4);
+        grid.open_boundary(1, GridDirection::South);
+        assert!(grid.has_opening(4, GridDirection::North));
+        assert!(!grid.has_opening(4, GridDirection::South));
+
+        // The ends of the longest path get openings on the outer walls.
+        let mut grid = Grid::new(6, 7);
+        crate::recursive_backtracker(&mut grid);
+        grid.open_longest_path_ends();
+
+        let openings = grid.openings().to_vec();
+        assert_eq!(openings.len(), 2);
+        for (cell, dir) in openings {
+            assert_eq!(grid.cell_to(cell, dir), None);
+        }
+
+        // Even in a single cell.
+        let mut grid = Grid::new(1, 1);
+        grid.open_longest_path_ends();
+        assert_eq!(
+            grid.openings(),
+            &[(0, GridDirection::North), (0, GridDirection::South)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_grid_open_interior() {
+        let mut grid = Grid::new(3, 3);
+        grid.open_boundary(4, GridDirection::North);
+    }
+
+    #[test]
+    fn test_grid_shift() {
+        let mut grid = Grid::new(4, 5);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(2038));
+        let before = grid.clone();
+
+        // Shift right by one column: each link moves, except those in the last column.
+        grid.shift(0, 1);
+
+        for (a, b) in before.link_pairs() {
+            let (ia, ja) = before.ij(a);
+            let (ib, jb) = before.ij(b);
+
+            if ja + 1 < 5 && jb + 1 < 5 {
+                assert!(grid.is_linked(grid.cell(ia, ja + 1), grid.cell(ib, jb + 1)));
+            }
+        }
+        for i in 0..4 {
+            assert!(grid.links(grid.cell(i, 0)).is_empty());
+        }
+
+        let kept = before
+            .link_pairs()
+            .iter()
+            .filter(|(a, b)| before.j(*a) < 4 && before.j(*b) < 4)
+            .count();
+        assert_eq!(grid.num_links(), kept);
+
+        // Shifting back restores the links that weren't dropped.
+        grid.shift(0, -1);
+        assert!(grid
+            .link_pairs()
+            .iter()
+            .all(|(a, b)| before.is_linked(*a, *b)));
+        assert_eq!(grid.num_links(), kept);
+
+        // Links never land on isolated cells.
+        let mut grid = Grid::new(1, 3);
+        grid.link(0, 1);
+        grid.isolate(2);
+        grid.shift(0, 1);
+        assert_eq!(grid.num_links(), 0);
+    }
+
+    #[test]
+    fn test_grid_revision() {
+        let mut grid = Grid::new(1, 3);
+        grid.link(0, 1);
+        assert_eq!(grid.diameter(), 1);
+
+        // The cached diameter is discarded when the grid changes.
+        let rev = grid.revision();
+        grid.link(1, 2);
+        assert!(grid.revision() > rev);
+        assert_eq!(grid.diameter(), 2);
+
+        let rev = grid.revision();
+        grid.clear();
+        assert!(grid.revision() > rev);
+        assert_eq!(grid.diameter(), 0);
+
+        // Equality ignores the revision.
+        assert_eq!(grid, Grid::new(1, 3));
+
+        // The cache doesn't keep grids from being shared between threads.
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        assert_send_sync(&grid);
+        assert_eq!(grid.num_components(), 3);
+    }
+
+    #[test]
+    fn test_grid_count_cells() {
+        let mut grid = Grid::new(7, 9);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(2035));
+        grid.braid_rng(0.3, &mut StdRng::seed_from_u64(2035));
+
+        assert_eq!(grid.count_cells(Grid::is_dead_end), grid.dead_end_count());
+        assert_eq!(grid.count_cells(Grid::is_junction), {
+            let stats = grid.stats();
+            stats.junctions3 + stats.junctions4
+        });
+        assert_eq!(grid.count_cells(|_, _| true), grid.num_cells());
+        assert_eq!(
+            grid.count_cells(|g, c| g.is_linked_east(c)),
+            grid.stats().horiz_links
+        );
+    }
+
+    #[test]
+    fn test_grid_braid() {
+        let mut rng = StdRng::seed_from_u64(2033);
+        let mut grid = Grid::new(10, 10);
+        crate::recursive_backtracker_rng(&mut grid, &mut rng);
+        let before = grid.clone();
+
+        grid.braid_rng(0.0, &mut rng);
+        assert_eq!(grid, before);
+
+        grid.braid_rng(0.5, &mut rng);
+        assert!(grid.dead_end_count() < before.dead_end_count());
+        assert!(grid.num_links() > before.num_links());
+
+        grid.braid_rng(1.0, &mut rng);
+        assert_eq!(grid.dead_end_count(), 0);
+        assert!(grid.is_connected());
+        assert!(before.link_diff(&grid).removed.is_empty());
+
+        // The ends of a corridor can't be braided.
+        let mut grid = Grid::new(1, 4);
+        crate::recursive_backtracker_rng(&mut grid, &mut rng);
+        grid.braid_rng(1.0, &mut rng);
+        assert_eq!(grid.dead_end_count(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_grid_braid_range() {
+        Grid::new(3, 3).braid(1.5);
+    }
+
+    #[test]
+    fn test_grid_wall_index() {
+        let opposite = |dir: GridDirection| match dir {
+            GridDirection::North => GridDirection::South,
+            GridDirection::South => GridDirection::North,
+            GridDirection::East => GridDirection::West,
+            GridDirection::West => GridDirection::East,
+        };
+
+        for &(rows, cols) in &[(1, 1), (1, 4), (4, 1), (3, 5), (6, 6)] {
+            let grid = Grid::new(rows, cols);
+            let mut seen = vec![false; grid.num_walls()];
+
+            for cell in 0..grid.num_cells() {
+                for &dir in &[
+                    GridDirection::North,
+                    GridDirection::South,
+                    GridDirection::East,
+                    GridDirection::West,
+                ] {
+                    let index = grid.wall_index(cell, dir);
+                    assert_eq!(index.is_some(), grid.cell_to(cell, dir).is_some());
+
+                    if let Some(index) = index {
+                        let other = grid.cell_to(cell, dir).unwrap();
+                        assert_eq!(grid.wall_index(other, opposite(dir)), Some(index));
+
+                        let (wcell, wdir) = grid.wall_at(index).unwrap();
+                        assert_eq!(grid.wall_index(wcell, wdir), Some(index));
+                        assert!(wdir == GridDirection::East || wdir == GridDirection::South);
+                        assert!(wcell == cell || wcell == other);
+                        seen[index] = true;
+                    }
+                }
+            }
+
+            assert!(seen.iter().all(|s| *s));
+            assert_eq!(grid.wall_at(grid.num_walls()), None);
+        }
+    }
+
+    #[test]
+    fn test_grid_json_meta() {
+        let mut grid = Grid::new(4, 5);
+        crate::recursive_backtracker(&mut grid);
+
+        let mut meta = HashMap::new();
+        meta.insert("seed".to_string(), "2033".to_string());
+        meta.insert("algorithm".to_string(), "backtracker".to_string());
+
+        let json = grid.to_json_with_meta(meta.clone());
+        assert!(json.ends_with(r#""meta":{"algorithm":"backtracker","seed":"2033"}}"#));
+
+        let grid2 = Grid::from_json(&json).unwrap();
+        assert_eq!(grid2.link_pairs(), grid.link_pairs());
+        assert_eq!(Grid::meta_from_json(&json).unwrap(), meta);
+        assert_eq!(Grid::meta_from_json(&json).unwrap()["seed"], "2033");
+
+        // No metadata, no meta key; and unexpected metadata is ignored.
+        assert_eq!(grid.to_json_with_meta(HashMap::new()), grid.to_json());
+        assert!(Grid::meta_from_json(&grid.to_json()).unwrap().is_empty());
+        let odd = r#"{"rows":1,"cols":2,"links":[[0,1]],"meta":{"n":[1,2],"who":"me"}}"#;
+        assert!(Grid::from_json(odd).unwrap().is_linked(0, 1));
+        assert_eq!(Grid::meta_from_json(odd).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_grid_json_errors() {
+        assert!(Grid::from_json("{").is_err());
+        assert!(Grid::from_json(r#"{"rows":0,"cols":2,"links":[]}"#).is_err());
+        assert_eq!(
+            Grid::from_json(r#"{"rows":2,"cols":2,"links":[[0,4]]}"#),
+            Err("invalid link, no such cell: 0-4".into())
+        );
+        assert_eq!(
+            Grid::from_json(r#"{"rows":2,"cols":2,"links":[[0,3]]}"#),
+            Err("invalid link, cells not adjacent: 0-3".into())
+        );
+
+        assert_eq!(
+            Grid::from_json(r#"{"rows":2000,"cols":2000,"links":[]}"#),
+            Err("expected a grid of at most 1048576 cells, got 2000x2000".into())
+        );
+
+        let huge = format!(r#"{{"rows":{},"cols":2,"links":[]}}"#, usize::MAX);
+        assert!(Grid::from_json(&huge).is_err());
+    }
+
     #[test]
     fn test_grid_directions() {
         let mut grid = Grid::new(5, 6);
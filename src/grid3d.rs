@@ -0,0 +1,360 @@
+//! This module defines Grid3D, a stack of rectangular grids for building multi-level
+//! mazes, in which passages may lead up and down between levels.
+
+use crate::Cell;
+use crate::Grid;
+use crate::MazeGrid;
+use std::collections::HashSet;
+
+/// The directions between cells in a Grid3D: the four compass directions within a level,
+/// and up and down to the cell in the same row and column on the adjacent levels.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Direction3D {
+    North,
+    South,
+    East,
+    West,
+    Up,
+    Down,
+}
+
+impl Direction3D {
+    /// All six directions: the compass directions within a level, then up and down.
+    pub const ALL: [Direction3D; 6] = [
+        Direction3D::North,
+        Direction3D::South,
+        Direction3D::East,
+        Direction3D::West,
+        Direction3D::Up,
+        Direction3D::Down,
+    ];
+}
+
+/// A multi-level grid of rectangular cells, which can be used to represent a maze with
+/// several floors.
+///
+/// The grid is a stack of levels, each with the same number of rows and columns.  Level
+/// 0 is the bottom level, and "up" leads to the next higher level.  Each cell is
+/// identified by a unique integer cell ID and also by a (level, row, column) triple; the
+/// cells are numbered level by level, and within a level row by row, as in `Grid`.  Each
+/// cell's neighbors are its neighbors within its level plus the cells directly above and
+/// below it, and as with `Grid`, two neighboring cells may be linked.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid3D::new(3, 4, 5);
+/// recursive_backtracker_rng(&mut grid, &mut rand::thread_rng());
+/// assert!(grid.is_perfect_maze());
+///
+/// let cell = grid.cell(1, 2, 3);
+/// assert_eq!(grid.lij(cell), (1, 2, 3));
+/// assert_eq!(grid.cell_to(cell, Direction3D::Up), Some(grid.cell(2, 2, 3)));
+/// assert_eq!(grid.neighbors(cell).len(), 6);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Grid3D {
+    num_levels: usize,
+    num_rows: usize,
+    num_cols: usize,
+    num_cells: usize,
+
+    /// The links for each cell.
+    links: Vec<HashSet<Cell>>,
+}
+
+impl Grid3D {
+    /// Creates a new grid with the given number of levels, rows, and columns.  Initially
+    /// no cell is linked to any other cell.  Panics if any dimension is zero.
+    pub fn new(num_levels: usize, num_rows: usize, num_cols: usize) -> Self {
+        assert!(
+            num_levels > 0 && num_rows > 0 && num_cols > 0,
+            "a 3D grid must have at least one level, row, and column, got {}x{}x{}",
+            num_levels,
+            num_rows,
+            num_cols
+        );
+
+        let num_cells = num_levels * num_rows * num_cols;
+
+        Self {
+            num_levels,
+            num_rows,
+            num_cols,
+            num_cells,
+            links: vec![HashSet::new(); num_cells],
+        }
+    }
+
+    /// The number of levels in the grid.
+    pub fn num_levels(&self) -> usize {
+        self.num_levels
+    }
+
+    /// The number of rows in each level.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// The number of columns in each level.
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    /// The number of cells in the grid, over all levels.
+    pub fn num_cells(&self) -> usize {
+        self.num_cells
+    }
+
+    /// Computes the cell from the level, row, and column.
+    pub fn cell(&self, level: usize, i: usize, j: usize) -> Cell {
+        assert!(level < self.num_levels && i < self.num_rows && j < self.num_cols);
+        (level * self.num_rows + i) * self.num_cols + j
+    }
+
+    /// Computes the level, row, and column from the cell ID.
+    pub fn lij(&self, cell: Cell) -> (usize, usize, usize) {
+        assert!(self.contains(cell));
+        let per_level = self.num_rows * self.num_cols;
+        let rest = cell % per_level;
+        (cell / per_level, rest / self.num_cols, rest % self.num_cols)
+    }
+
+    /// Returns true if the cell ID is valid for this grid.
+    pub fn contains(&self, cell: Cell) -> bool {
+        cell < self.num_cells
+    }
+
+    /// Links cell 1 to cell 2.  Links are bidirectional.
+    pub fn link(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].insert(cell2);
+        self.links[cell2].insert(cell1);
+    }
+
+    /// Unlinks cell 1 from cell 2.
+    pub fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].remove(&cell2);
+        self.links[cell2].remove(&cell1);
+    }
+
+    /// Gets the cells to which the cell is linked.
+    pub fn links(&self, cell: Cell) -> Vec<Cell> {
+        assert!(self.contains(cell));
+        self.links[cell].iter().cloned().collect()
+    }
+
+    /// Indicates whether or not the cells are linked.
+    pub fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].contains(&cell2)
+    }
+
+    /// Indicates whether the cell is linked to the cell in the given direction.
+    /// Returns false if there is no cell in that direction.
+    pub fn is_linked_to(&self, cell: Cell, dir: Direction3D) -> bool {
+        match self.cell_to(cell, dir) {
+            Some(other) => self.is_linked(cell, other),
+            None => false,
+        }
+    }
+
+    /// Returns the grid to its initial state:  no cell is linked to any other cell.
+    pub fn clear(&mut self) {
+        for links in &mut self.links {
+            links.clear();
+        }
+    }
+
+    /// Gets the cell in the given direction, if any.
+    pub fn cell_to(&self, cell: Cell, dir: Direction3D) -> Option<Cell> {
+        let (level, i, j) = self.lij(cell);
+
+        match dir {
+            Direction3D::North if i > 0 => Some(self.cell(level, i - 1, j)),
+            Direction3D::South if i + 1 < self.num_rows => Some(self.cell(level, i + 1, j)),
+            Direction3D::East if j + 1 < self.num_cols => Some(self.cell(level, i, j + 1)),
+            Direction3D::West if j > 0 => Some(self.cell(level, i, j - 1)),
+            Direction3D::Up if level + 1 < self.num_levels => Some(self.cell(level + 1, i, j)),
+            Direction3D::Down if level > 0 => Some(self.cell(level - 1, i, j)),
+            _ => None,
+        }
+    }
+
+    /// Gets the cell's neighbors, in the order of `Direction3D::ALL`.
+    pub fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        Direction3D::ALL
+            .iter()
+            .filter_map(|dir| self.cell_to(cell, *dir))
+            .collect()
+    }
+
+    /// Computes the shortest distance from the cell to each other cell, on any level.
+    /// Returns the distances as a vector of length `num_cells`; cells that can't be
+    /// reached have no distance.
+    pub fn distances(&self, cell: Cell) -> Vec<Option<usize>> {
+        MazeGrid::distances(self, cell)
+    }
+
+    /// Computes the shortest path from the first cell to the second, which may lead up and
+    /// down between levels.  If there is no path, the vector will be empty.
+    pub fn shortest_path(&self, start: Cell, goal: Cell) -> Vec<Cell> {
+        MazeGrid::shortest_path(self, start, goal)
+    }
+
+    /// Gets the number of links in the grid, counting each link between two cells once.
+    pub fn num_links(&self) -> usize {
+        self.links.iter().map(|l| l.len()).sum::<usize>() / 2
+    }
+
+    /// Returns true if the grid is a perfect maze: one in which there is exactly one
+    /// path between any two cells, over all levels.
+    pub fn is_perfect_maze(&self) -> bool {
+        self.distances(0).iter().all(|d| d.is_some()) && self.num_links() + 1 == self.num_cells
+    }
+
+    /// Returns one level of the grid as a `Grid` with the level's rows and columns and the
+    /// links within the level; links up and down are omitted.  Panics if there's no such
+    /// level.
+    pub fn level(&self, level: usize) -> Grid {
+        assert!(level < self.num_levels, "no such level: {}", level);
+        let mut grid = Grid::new(self.num_rows, self.num_cols);
+
+        for i in 0..self.num_rows {
+            for j in 0..self.num_cols {
+                let cell = self.cell(level, i, j);
+                for dir in &[Direction3D::South, Direction3D::East] {
+                    if self.is_linked_to(cell, *dir) {
+                        let (_, i1, j1) = self.lij(self.cell_to(cell, *dir).unwrap());
+                        grid.link(grid.cell(i, j), grid.cell(i1, j1));
+                    }
+                }
+            }
+        }
+
+        grid
+    }
+}
+
+impl MazeGrid for Grid3D {
+    fn num_cells(&self) -> usize {
+        Grid3D::num_cells(self)
+    }
+
+    fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        Grid3D::neighbors(self, cell)
+    }
+
+    fn link(&mut self, cell1: Cell, cell2: Cell) {
+        Grid3D::link(self, cell1, cell2)
+    }
+
+    fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        Grid3D::unlink(self, cell1, cell2)
+    }
+
+    fn links(&self, cell: Cell) -> Vec<Cell> {
+        Grid3D::links(self, cell)
+    }
+
+    fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        Grid3D::is_linked(self, cell1, cell2)
+    }
+
+    fn clear(&mut self) {
+        Grid3D::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_grid3d_neighbors() {
+        let grid = Grid3D::new(3, 2, 4);
+        assert_eq!(grid.num_cells(), 24);
+
+        // A corner cell on the bottom level
+        assert_eq!(
+            grid.neighbors(0),
+            vec![grid.cell(0, 1, 0), grid.cell(0, 0, 1), grid.cell(1, 0, 0)]
+        );
+
+        // A cell on the middle level has neighbors above and below.
+        let cell = grid.cell(1, 1, 2);
+        assert_eq!(grid.lij(cell), (1, 1, 2));
+        assert_eq!(
+            grid.neighbors(cell),
+            vec![
+                grid.cell(1, 0, 2),
+                grid.cell(1, 1, 3),
+                grid.cell(1, 1, 1),
+                grid.cell(2, 1, 2),
+                grid.cell(0, 1, 2),
+            ]
+        );
+
+        // Neighbors are symmetric.
+        for c in 0..grid.num_cells() {
+            for n in grid.neighbors(c) {
+                assert!(grid.neighbors(n).contains(&c));
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid3d_maze() {
+        let mut grid = Grid3D::new(3, 5, 6);
+        crate::hunt_and_kill_rng(&mut grid, &mut StdRng::seed_from_u64(2050));
+        assert!(grid.is_perfect_maze());
+
+        let mut grid = Grid3D::new(3, 5, 6);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(2050));
+        assert!(grid.is_perfect_maze());
+        assert_eq!(grid.num_links(), 3 * 5 * 6 - 1);
+
+        // Each level is reached from the others only by links up and down.
+        let vertical = (0..grid.num_cells())
+            .flat_map(|c| grid.links(c).into_iter().map(move |l| (c, l)))
+            .filter(|&(c, l)| c < l && grid.lij(c).0 != grid.lij(l).0)
+            .count();
+        assert!(vertical >= 2);
+        let flat: usize = (0..3).map(|level| grid.level(level).num_links()).sum();
+        assert_eq!(flat + vertical, grid.num_links());
+    }
+
+    #[test]
+    fn test_grid3d_shortest_path() {
+        // Two levels joined only at one end: the path from one corner of the bottom level
+        // to the same corner of the top level must climb at the far end.
+        let mut grid = Grid3D::new(2, 1, 4);
+        for level in 0..2 {
+            for j in 0..3 {
+                grid.link(grid.cell(level, 0, j), grid.cell(level, 0, j + 1));
+            }
+        }
+        grid.link(grid.cell(0, 0, 3), grid.cell(1, 0, 3));
+        assert!(grid.is_perfect_maze());
+        assert!(grid.is_linked_to(grid.cell(0, 0, 3), Direction3D::Up));
+
+        let start = grid.cell(0, 0, 0);
+        let goal = grid.cell(1, 0, 0);
+        let path = grid.shortest_path(start, goal);
+        assert_eq!(path.len(), 8);
+        assert_eq!(grid.distances(start)[goal], Some(7));
+        assert_eq!(&path[3..5], &[grid.cell(0, 0, 3), grid.cell(1, 0, 3)]);
+
+        grid.unlink(grid.cell(0, 0, 3), grid.cell(1, 0, 3));
+        assert!(grid.shortest_path(start, goal).is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::grid3d::Direction3D;
+use crate::grid3d::Grid3D;
+use crate::image_grid_renderer::draw_segment;
+use crate::ImageGridRenderer;
+use image::imageops;
+use image::ImageBuffer;
+use image::Rgba;
+use image::RgbaImage;
+
+/// A struct for rendering a Grid3D as images.  Uses the builder pattern.
+///
+/// Each level is drawn as `ImageGridRenderer` would draw it, and each cell with a
+/// passage up or down is marked with a dot: the dot for a passage up is in the upper half
+/// of the cell, and the dot for a passage down is in the lower half.  `render` places the
+/// levels side by side, from level 0 on the left, separated by `level_gap` pixels of
+/// white.
+#[derive(Debug, Copy, Clone)]
+pub struct Grid3DRenderer {
+    /// The width and height of the rendered cell in pixels, not including the borders.
+    cell_size: usize,
+
+    /// The border width, in pixels.
+    border_width: usize,
+
+    /// The gap between levels, in pixels.
+    level_gap: usize,
+
+    /// The color of the dot marking a passage up.
+    up_color: Rgba<u8>,
+
+    /// The color of the dot marking a passage down.
+    down_color: Rgba<u8>,
+}
+
+impl Default for Grid3DRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Grid3DRenderer {
+    /// The default color for passages up.
+    pub const DEFAULT_UP_COLOR: Rgba<u8> = Rgba([0, 160, 0, 255]);
+
+    /// The default color for passages down.
+    pub const DEFAULT_DOWN_COLOR: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    /// Creates a new renderer for the Grid3D with default settings
+    pub fn new() -> Self {
+        Self {
+            cell_size: 20,
+            border_width: 1,
+            level_gap: 10,
+            up_color: Self::DEFAULT_UP_COLOR,
+            down_color: Self::DEFAULT_DOWN_COLOR,
+        }
+    }
+
+    /// Adds the desired cell size.
+    pub fn cell_size(&mut self, cell_size: usize) -> &mut Self {
+        assert!(cell_size > 0);
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Adds the desired border width.
+    pub fn border_width(&mut self, border_width: usize) -> &mut Self {
+        assert!(border_width > 0);
+        self.border_width = border_width;
+        self
+    }
+
+    /// Adds the desired gap between levels, in pixels; it may be zero.
+    pub fn level_gap(&mut self, level_gap: usize) -> &mut Self {
+        self.level_gap = level_gap;
+        self
+    }
+
+    /// Sets the color of the dots marking passages up.
+    pub fn up_color(&mut self, color: Rgba<u8>) -> &mut Self {
+        self.up_color = color;
+        self
+    }
+
+    /// Sets the color of the dots marking passages down.
+    pub fn down_color(&mut self, color: Rgba<u8>) -> &mut Self {
+        self.down_color = color;
+        self
+    }
+
+    /// Renders each level of the grid as a separate image, from level 0 up.  Each image is
+    /// the size `ImageGridRenderer` would make it for a grid of the same rows and columns.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid3D::new(3, 4, 5);
+    /// recursive_backtracker_rng(&mut grid, &mut rand::thread_rng());
+    ///
+    /// let images = Grid3DRenderer::new().cell_size(10).border_width(2).render_levels(&grid);
+    /// assert_eq!(images.len(), 3);
+    /// assert_eq!(images[0].dimensions(), (2 * 6 + 10 * 5, 2 * 5 + 10 * 4));
+    /// ```
+    pub fn render_levels(&self, grid: &Grid3D) -> Vec<RgbaImage> {
+        let mut renderer = ImageGridRenderer::new();
+        renderer
+            .cell_size(self.cell_size)
+            .border_width(self.border_width);
+
+        let offset = self.cell_size as f64 / 4.0;
+        let radius = (self.cell_size as f64 / 8.0).max(1.0);
+
+        (0..grid.num_levels())
+            .map(|level| {
+                let mut image = renderer.render(&grid.level(level));
+
+                for i in 0..grid.num_rows() {
+                    for j in 0..grid.num_cols() {
+                        let cell = grid.cell(level, i, j);
+                        let (x, y) = renderer.cell_center((i, j));
+
+                        if grid.is_linked_to(cell, Direction3D::Up) {
+                            let p = (x, y - offset);
+                            draw_segment(&mut image, p, p, radius, self.up_color);
+                        }
+
+                        if grid.is_linked_to(cell, Direction3D::Down) {
+                            let p = (x, y + offset);
+                            draw_segment(&mut image, p, p, radius, self.down_color);
+                        }
+                    }
+                }
+
+                image
+            })
+            .collect()
+    }
+
+    /// Renders the grid as a single image, with the levels side by side.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid3D::new(3, 4, 5);
+    /// recursive_backtracker_rng(&mut grid, &mut rand::thread_rng());
+    ///
+    /// let image = Grid3DRenderer::new().cell_size(10).level_gap(8).render(&grid);
+    /// assert_eq!(image.dimensions(), (3 * 56 + 2 * 8, 45));
+    /// ```
+    pub fn render(&self, grid: &Grid3D) -> RgbaImage {
+        let levels = self.render_levels(grid);
+        let (level_width, height) = levels[0].dimensions();
+        let step = level_width + self.level_gap as u32;
+        let width = step * levels.len() as u32 - self.level_gap as u32;
+
+        let white = Rgba([255, 255, 255, 255]);
+        let mut image: RgbaImage = ImageBuffer::from_pixel(width, height, white);
+
+        for (k, level) in levels.iter().enumerate() {
+            imageops::replace(&mut image, level, step * k as u32, 0);
+        }
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid3d_render() {
+        // Two 1x2 levels, joined at the right-hand cells.
+        let mut grid = Grid3D::new(2, 1, 2);
+        grid.link(grid.cell(0, 0, 0), grid.cell(0, 0, 1));
+        grid.link(grid.cell(1, 0, 0), grid.cell(1, 0, 1));
+        grid.link(grid.cell(0, 0, 1), grid.cell(1, 0, 1));
+
+        let mut renderer = Grid3DRenderer::new();
+        renderer.cell_size(16).border_width(2).level_gap(5);
+
+        let levels = renderer.render_levels(&grid);
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].dimensions(), (2 * 3 + 16 * 2, 2 * 2 + 16));
+
+        let image = renderer.render(&grid);
+        assert_eq!(image.dimensions(), (38 * 2 + 5, 20));
+
+        // The cell with the passage up is marked on level 0, and the cell with the passage
+        // down on level 1; the other cells aren't marked.
+        let white = Rgba([255, 255, 255, 255]);
+        let up = Grid3DRenderer::DEFAULT_UP_COLOR;
+        let down = Grid3DRenderer::DEFAULT_DOWN_COLOR;
+        assert_eq!(*image.get_pixel(28, 6), up);
+        assert_eq!(*image.get_pixel(28, 14), white);
+        assert_eq!(*image.get_pixel(10, 6), white);
+        assert_eq!(*image.get_pixel(43 + 28, 14), down);
+        assert_eq!(*image.get_pixel(43 + 28, 6), white);
+
+        // The gap is white.
+        assert_eq!(*image.get_pixel(40, 10), white);
+    }
+}
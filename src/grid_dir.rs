@@ -3,7 +3,7 @@ use std::str::FromStr;
 
 /// The directions between cells in this grid.
 /// TODO: Should be an associated type?
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum GridDirection {
     North,
     South,
@@ -11,6 +11,18 @@ pub enum GridDirection {
     West,
 }
 
+impl GridDirection {
+    /// Returns true if the direction is East or West.
+    pub fn is_horizontal(&self) -> bool {
+        *self == GridDirection::East || *self == GridDirection::West
+    }
+
+    /// Returns true if the two directions are at right angles to each other.
+    pub fn is_perpendicular(&self, other: GridDirection) -> bool {
+        self.is_horizontal() != other.is_horizontal()
+    }
+}
+
 impl fmt::Display for GridDirection {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -0,0 +1,317 @@
+//! This module defines GridPool, a thread-safe pool of same-sized grids for programs that
+//! generate many mazes of the same size, e.g., one per web request.
+
+use crate::Grid;
+use crate::Topology;
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// A thread-safe pool of grids of a fixed size.  Acquiring a grid from the pool avoids
+/// the cost of allocating and initializing a new grid.
+///
+/// Grids are returned to the pool when their `PooledGrid` guard is dropped, and are reset
+/// lazily, when next acquired: only the cells that were linked are cleared.  An acquired
+/// grid is always equal to a new grid of the same size; a grid whose size or topology was
+/// changed while it was acquired, e.g., by replacing it, isn't returned to the pool.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let pool = GridPool::new(10, 10, 4);
+///
+/// for _ in 0..3 {
+///     let mut grid = pool.acquire();
+///     assert_eq!(grid.num_links(), 0);
+///     recursive_backtracker(&mut grid);
+///     assert!(grid.is_perfect_maze());
+/// }
+///
+/// let stats = pool.stats();
+/// assert_eq!((stats.hits, stats.misses, stats.outstanding), (3, 0, 0));
+/// ```
+#[derive(Debug)]
+pub struct GridPool {
+    num_rows: usize,
+    num_cols: usize,
+
+    /// The maximum number of idle grids to keep.
+    capacity: usize,
+
+    /// The idle grids and the usage statistics.
+    inner: Mutex<PoolInner>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    /// The idle grids, each with a flag indicating whether it needs to be reset.
+    free: Vec<(Grid, bool)>,
+
+    stats: GridPoolStats,
+}
+
+/// Usage statistics for a `GridPool`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct GridPoolStats {
+    /// The number of acquisitions that reused an idle grid.
+    pub hits: usize,
+
+    /// The number of acquisitions that had to create a new grid.
+    pub misses: usize,
+
+    /// The number of grids currently acquired.
+    pub outstanding: usize,
+}
+
+impl GridPool {
+    /// Creates a pool of grids with the given number of rows and columns, pre-allocating
+    /// `capacity` grids.  The pool keeps at most `capacity` idle grids; grids released
+    /// when the pool is full are dropped.  Panics if either dimension is zero.
+    pub fn new(num_rows: usize, num_cols: usize, capacity: usize) -> Self {
+        let free = (0..capacity)
+            .map(|_| (Grid::new(num_rows, num_cols), false))
+            .collect();
+
+        Self {
+            num_rows,
+            num_cols,
+            capacity,
+            inner: Mutex::new(PoolInner {
+                free,
+                stats: GridPoolStats::default(),
+            }),
+        }
+    }
+
+    /// The number of rows in the pool's grids.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// The number of columns in the pool's grids.
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    /// Acquires a grid from the pool, creating a new one if there are no idle grids.
+    /// The grid has no links, and is returned to the pool when the guard is dropped.
+    pub fn acquire(&self) -> PooledGrid<'_> {
+        // FIRST, get an idle grid, if any.  Don't hold the lock any longer than needed.
+        let idle = {
+            let mut inner = self.lock();
+            let idle = inner.free.pop();
+
+            if idle.is_some() {
+                inner.stats.hits += 1;
+            } else {
+                inner.stats.misses += 1;
+            }
+            inner.stats.outstanding += 1;
+
+            idle
+        };
+
+        // NEXT, reset it if it's been used, or create a new one.
+        let grid = match idle {
+            Some((mut grid, dirty)) => {
+                if dirty {
+                    grid.reset();
+                }
+                grid
+            }
+            None => Grid::new(self.num_rows, self.num_cols),
+        };
+
+        PooledGrid {
+            pool: self,
+            grid: Some(grid),
+        }
+    }
+
+    /// Gets the pool's usage statistics.
+    pub fn stats(&self) -> GridPoolStats {
+        self.lock().stats
+    }
+
+    /// Returns a grid to the pool.  A grid that's no longer the pool's size, or that
+    /// isn't planar, e.g., because the caller replaced it, is dropped instead.
+    fn release(&self, grid: Grid) {
+        let fits = grid.num_rows() == self.num_rows
+            && grid.num_cols() == self.num_cols
+            && grid.topology() == Topology::Planar;
+
+        let mut inner = self.lock();
+        inner.stats.outstanding -= 1;
+
+        if fits && inner.free.len() < self.capacity {
+            inner.free.push((grid, true));
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PoolInner> {
+        // The lock is never held while user code runs, so it can't be poisoned.
+        self.inner.lock().expect("grid pool lock")
+    }
+}
+
+/// A grid acquired from a `GridPool`.  It dereferences to the `Grid`, and returns it to
+/// the pool when dropped.
+#[derive(Debug)]
+pub struct PooledGrid<'a> {
+    pool: &'a GridPool,
+
+    /// The grid; None only while it's being returned to the pool.
+    grid: Option<Grid>,
+}
+
+impl Deref for PooledGrid<'_> {
+    type Target = Grid;
+
+    fn deref(&self) -> &Grid {
+        self.grid.as_ref().expect("pooled grid")
+    }
+}
+
+impl DerefMut for PooledGrid<'_> {
+    fn deref_mut(&mut self) -> &mut Grid {
+        self.grid.as_mut().expect("pooled grid")
+    }
+}
+
+impl Drop for PooledGrid<'_> {
+    fn drop(&mut self) {
+        if let Some(grid) = self.grid.take() {
+            self.pool.release(grid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GridDirection;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_acquired_grids_are_clear() {
+        let pool = GridPool::new(6, 7, 2);
+        let fresh = Grid::new(6, 7);
+        let mut rng = StdRng::seed_from_u64(21);
+
+        for _ in 0..50 {
+            let mut grid = pool.acquire();
+            assert_eq!(*grid, fresh);
+
+            // Use it heavily: carve a maze, and isolate some cells.
+            crate::recursive_backtracker_rng(&mut *grid, &mut rng);
+            for _ in 0..rng.gen_range(0, 5) {
+                let cell = rng.gen_range(0, grid.num_cells());
+                grid.isolate(cell);
+            }
+        }
+
+        let stats = pool.stats();
+        assert_eq!(stats.hits, 50);
+        assert_eq!(stats.misses, 0);
+        assert_eq!(stats.outstanding, 0);
+    }
+
+    #[test]
+    fn test_pool_reuse() {
+        let pool = GridPool::new(3, 3, 1);
+
+        let a = pool.acquire();
+        let b = pool.acquire();
+        assert_eq!(
+            pool.stats(),
+            GridPoolStats {
+                hits: 1,
+                misses: 1,
+                outstanding: 2
+            }
+        );
+
+        // Only one idle grid is kept.
+        drop(a);
+        drop(b);
+        let _c = pool.acquire();
+        let _d = pool.acquire();
+        assert_eq!(
+            pool.stats(),
+            GridPoolStats {
+                hits: 2,
+                misses: 2,
+                outstanding: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_replaced_grids_are_dropped() {
+        let pool = GridPool::new(3, 3, 2);
+
+        {
+            let mut grid = pool.acquire();
+            *grid = Grid::new(2, 2);
+        }
+        {
+            let mut grid = pool.acquire();
+            *grid = Grid::with_topology(3, 3, Topology::Torus);
+        }
+        {
+            let mut grid = pool.acquire();
+            *grid = Grid::new(3, 3);
+            grid.link(0, 1);
+            grid.open_boundary(0, GridDirection::North);
+        }
+
+        // Only the last grid was returned, and it comes back as new.
+        let grid = pool.acquire();
+        assert_eq!(*grid, Grid::new(3, 3));
+        assert_eq!(
+            pool.stats(),
+            GridPoolStats {
+                hits: 3,
+                misses: 1,
+                outstanding: 1
+            }
+        );
+        let _other = pool.acquire();
+        assert_eq!(pool.stats().misses, 2);
+    }
+
+    #[test]
+    fn test_concurrent_use() {
+        let pool = Arc::new(GridPool::new(8, 8, 4));
+        let fresh = Grid::new(8, 8);
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let pool = Arc::clone(&pool);
+                let fresh = fresh.clone();
+
+                thread::spawn(move || {
+                    let mut rng = StdRng::seed_from_u64(t);
+
+                    for _ in 0..25 {
+                        let mut grid = pool.acquire();
+                        assert_eq!(*grid, fresh);
+                        crate::hunt_and_kill_rng(&mut *grid, &mut rng);
+                        assert!(grid.is_perfect_maze());
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let stats = pool.stats();
+        assert_eq!(stats.hits + stats.misses, 8 * 25);
+        assert_eq!(stats.outstanding, 0);
+        assert!(stats.hits > 0);
+    }
+}
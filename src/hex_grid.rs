@@ -0,0 +1,315 @@
+//! This module defines HexGrid, a grid of hexagonal cells for building hex ("sigma")
+//! mazes.
+
+use crate::Cell;
+use crate::MazeGrid;
+use std::collections::HashSet;
+
+/// The directions between cells in a HexGrid.  The cells are flat-topped hexagons, so
+/// each cell has neighbors to the north and south and on each of its four diagonals.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum HexDirection {
+    North,
+    NorthEast,
+    SouthEast,
+    South,
+    SouthWest,
+    NorthWest,
+}
+
+impl HexDirection {
+    /// All six directions, clockwise from north.
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::North,
+        HexDirection::NorthEast,
+        HexDirection::SouthEast,
+        HexDirection::South,
+        HexDirection::SouthWest,
+        HexDirection::NorthWest,
+    ];
+}
+
+/// A grid of flat-topped hexagonal cells, which can be used to represent a maze.
+///
+/// The cells are arranged in rows and columns, and each cell is identified by a unique
+/// integer cell ID and also by an (i,j) row/column pair, just as in `Grid`.  Odd columns
+/// are shifted down by half a cell, so that a cell's diagonal neighbors depend on whether
+/// its column is even or odd.  As with `Grid`, two neighboring cells may be linked.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let grid = HexGrid::new(4, 4);
+///
+/// // An even column's diagonal neighbors are in its own row and the row above; an odd
+/// // column's are in its own row and the row below.
+/// assert_eq!(grid.cell_to(grid.cell(1, 0), HexDirection::NorthEast), Some(grid.cell(0, 1)));
+/// assert_eq!(grid.cell_to(grid.cell(1, 1), HexDirection::NorthEast), Some(grid.cell(1, 2)));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HexGrid {
+    num_rows: usize,
+    num_cols: usize,
+    num_cells: usize,
+
+    /// The links for each cell.
+    links: Vec<HashSet<Cell>>,
+}
+
+impl HexGrid {
+    /// Creates a new hex grid with the given number of rows and columns.  Initially no
+    /// cell is linked to any other cell.  Panics if either dimension is zero.
+    pub fn new(num_rows: usize, num_cols: usize) -> Self {
+        assert!(
+            num_rows > 0 && num_cols > 0,
+            "a hex grid must have at least one row and one column, got {}x{}",
+            num_rows,
+            num_cols
+        );
+
+        let num_cells = num_rows * num_cols;
+
+        Self {
+            num_rows,
+            num_cols,
+            num_cells,
+            links: vec![HashSet::new(); num_cells],
+        }
+    }
+
+    /// The number of rows in the grid.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// The number of columns in the grid.
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    /// The number of cells in the grid.
+    pub fn num_cells(&self) -> usize {
+        self.num_cells
+    }
+
+    /// Computes the cell from the row and column.
+    pub fn cell(&self, i: usize, j: usize) -> Cell {
+        assert!(i < self.num_rows && j < self.num_cols);
+        i * self.num_cols + j
+    }
+
+    /// Computes the row and column from the cell ID.
+    pub fn ij(&self, cell: Cell) -> (usize, usize) {
+        assert!(self.contains(cell));
+        (cell / self.num_cols, cell % self.num_cols)
+    }
+
+    /// Returns true if the cell ID is valid for this grid.
+    pub fn contains(&self, cell: Cell) -> bool {
+        cell < self.num_cells
+    }
+
+    /// Links cell 1 to cell 2.  Links are bidirectional.
+    pub fn link(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].insert(cell2);
+        self.links[cell2].insert(cell1);
+    }
+
+    /// Unlinks cell 1 from cell 2.
+    pub fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].remove(&cell2);
+        self.links[cell2].remove(&cell1);
+    }
+
+    /// Gets the cells to which the cell is linked.
+    pub fn links(&self, cell: Cell) -> Vec<Cell> {
+        assert!(self.contains(cell));
+        self.links[cell].iter().cloned().collect()
+    }
+
+    /// Indicates whether or not the cells are linked.
+    pub fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].contains(&cell2)
+    }
+
+    /// Indicates whether the cell is linked to the cell in the given direction.
+    /// Returns false if there is no cell in that direction.
+    pub fn is_linked_to(&self, cell: Cell, dir: HexDirection) -> bool {
+        match self.cell_to(cell, dir) {
+            Some(other) => self.is_linked(cell, other),
+            None => false,
+        }
+    }
+
+    /// Returns the grid to its initial state:  no cell is linked to any other cell.
+    pub fn clear(&mut self) {
+        for links in &mut self.links {
+            links.clear();
+        }
+    }
+
+    /// Gets the cell in the given direction, if any.
+    pub fn cell_to(&self, cell: Cell, dir: HexDirection) -> Option<Cell> {
+        let (i, j) = self.ij(cell);
+        let (i, j) = (i as isize, j as isize);
+
+        // The row of the diagonal neighbors to the north; those to the south are one row
+        // further down.
+        let upper = if j % 2 == 1 { i } else { i - 1 };
+
+        let (i1, j1) = match dir {
+            HexDirection::North => (i - 1, j),
+            HexDirection::NorthEast => (upper, j + 1),
+            HexDirection::SouthEast => (upper + 1, j + 1),
+            HexDirection::South => (i + 1, j),
+            HexDirection::SouthWest => (upper + 1, j - 1),
+            HexDirection::NorthWest => (upper, j - 1),
+        };
+
+        if i1 < 0 || j1 < 0 || i1 >= self.num_rows as isize || j1 >= self.num_cols as isize {
+            None
+        } else {
+            Some(self.cell(i1 as usize, j1 as usize))
+        }
+    }
+
+    /// Gets the cell's neighbors, clockwise from north.
+    pub fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        HexDirection::ALL
+            .iter()
+            .filter_map(|dir| self.cell_to(cell, *dir))
+            .collect()
+    }
+
+    /// Computes the shortest distance from the cell to each other cell.
+    /// Returns the distances as a vector of length `num_cells`; cells that can't be
+    /// reached have no distance.
+    pub fn distances(&self, cell: Cell) -> Vec<Option<usize>> {
+        MazeGrid::distances(self, cell)
+    }
+
+    /// Gets the number of links in the grid, counting each link between two cells once.
+    pub fn num_links(&self) -> usize {
+        self.links.iter().map(|l| l.len()).sum::<usize>() / 2
+    }
+
+    /// Returns true if the grid is a perfect maze: one in which there is exactly one
+    /// path between any two cells.
+    pub fn is_perfect_maze(&self) -> bool {
+        self.distances(0).iter().all(|d| d.is_some()) && self.num_links() + 1 == self.num_cells
+    }
+}
+
+impl MazeGrid for HexGrid {
+    fn num_cells(&self) -> usize {
+        HexGrid::num_cells(self)
+    }
+
+    fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        HexGrid::neighbors(self, cell)
+    }
+
+    fn link(&mut self, cell1: Cell, cell2: Cell) {
+        HexGrid::link(self, cell1, cell2)
+    }
+
+    fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        HexGrid::unlink(self, cell1, cell2)
+    }
+
+    fn links(&self, cell: Cell) -> Vec<Cell> {
+        HexGrid::links(self, cell)
+    }
+
+    fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        HexGrid::is_linked(self, cell1, cell2)
+    }
+
+    fn clear(&mut self) {
+        HexGrid::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_hex_grid_neighbors() {
+        let grid = HexGrid::new(3, 4);
+
+        // A corner cell
+        assert_eq!(grid.neighbors(0), vec![grid.cell(0, 1), grid.cell(1, 0)]);
+
+        // Interior cells in even and odd columns have six neighbors.
+        let even = grid.cell(1, 2);
+        assert_eq!(
+            grid.neighbors(even),
+            vec![
+                grid.cell(0, 2),
+                grid.cell(0, 3),
+                grid.cell(1, 3),
+                grid.cell(2, 2),
+                grid.cell(1, 1),
+                grid.cell(0, 1),
+            ]
+        );
+
+        let odd = grid.cell(1, 1);
+        assert_eq!(
+            grid.neighbors(odd),
+            vec![
+                grid.cell(0, 1),
+                grid.cell(1, 2),
+                grid.cell(2, 2),
+                grid.cell(2, 1),
+                grid.cell(2, 0),
+                grid.cell(1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hex_grid_symmetry() {
+        let grid = HexGrid::new(5, 6);
+
+        let opposite = |dir: HexDirection| HexDirection::ALL[(dir as usize + 3) % 6];
+
+        for c in 0..grid.num_cells() {
+            for dir in &HexDirection::ALL {
+                if let Some(n) = grid.cell_to(c, *dir) {
+                    assert_eq!(grid.cell_to(n, opposite(*dir)), Some(c));
+                    assert!(grid.neighbors(n).contains(&c));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hex_grid_maze() {
+        let mut grid = HexGrid::new(7, 9);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(19));
+        assert!(grid.is_perfect_maze());
+
+        let mut grid = HexGrid::new(7, 9);
+        crate::hunt_and_kill_rng(&mut grid, &mut StdRng::seed_from_u64(19));
+        assert!(grid.is_perfect_maze());
+
+        for c in 0..grid.num_cells() {
+            for l in grid.links(c) {
+                assert!(grid.neighbors(c).contains(&l));
+            }
+        }
+    }
+}
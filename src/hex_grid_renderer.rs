@@ -0,0 +1,144 @@
+use crate::hex_grid::HexDirection;
+use crate::hex_grid::HexGrid;
+use crate::image_grid_renderer::draw_segment;
+use image::ImageBuffer;
+use image::Rgba;
+use image::RgbaImage;
+
+/// A struct for rendering a HexGrid as an Image.  Uses the builder pattern.
+///
+/// The cells are drawn as flat-topped hexagons.  Each hexagon's corners are `cell_size`
+/// pixels from its center, so that it's `2 * cell_size` pixels wide and `sqrt(3) *
+/// cell_size` pixels high; odd columns are shifted down by half a cell.  The image is
+/// `border_width + cell_size * (1.5 * cols + 0.5)` pixels wide and `border_width +
+/// sqrt(3) * cell_size * (rows + 0.5)` pixels high (or just `rows` if there's only one
+/// column), rounded up.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct HexGridRenderer {
+    /// The distance from the center of each cell to its corners, in pixels.
+    cell_size: usize,
+
+    /// The border width, in pixels.
+    border_width: usize,
+}
+
+impl HexGridRenderer {
+    /// Creates a new renderer for the HexGrid with default settings
+    pub fn new() -> Self {
+        Self {
+            cell_size: 10,
+            border_width: 1,
+        }
+    }
+
+    /// Adds the desired distance from the center of each cell to its corners.
+    pub fn cell_size(&mut self, cell_size: usize) -> &mut Self {
+        assert!(cell_size > 0);
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Adds the desired border width.
+    pub fn border_width(&mut self, border_width: usize) -> &mut Self {
+        assert!(border_width > 0);
+        self.border_width = border_width;
+        self
+    }
+
+    /// Render the grid using the current parameters.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = HexGrid::new(4, 5);
+    /// recursive_backtracker_rng(&mut grid, &mut rand::thread_rng());
+    ///
+    /// let image = HexGridRenderer::new().cell_size(10).border_width(2).render(&grid);
+    /// assert_eq!(image.dimensions(), (82, 80));
+    /// ```
+    pub fn render(&self, grid: &HexGrid) -> RgbaImage {
+        // FIRST, size and create the image, and clear it to white.  The half border on
+        // each side keeps the outer walls from being clipped.
+        let s = self.cell_size as f64;
+        let h = s * 3.0f64.sqrt() / 2.0;
+        let margin = self.border_width as f64 / 2.0;
+        let nr = grid.num_rows() as f64;
+        let nc = grid.num_cols() as f64;
+        let stagger = if grid.num_cols() > 1 { h } else { 0.0 };
+
+        let width = (2.0 * margin + s * (1.5 * nc + 0.5)).ceil() as u32;
+        let height = (2.0 * margin + 2.0 * h * nr + stagger).ceil() as u32;
+
+        let black = Rgba([0, 0, 0, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+        let mut image: RgbaImage = ImageBuffer::from_pixel(width, height, white);
+
+        // NEXT, draw the walls.  Each cell draws the walls between it and the neighbors
+        // with higher cell IDs, and its walls on the edge of the grid.
+        for cell in 0..grid.num_cells() {
+            let (i, j) = grid.ij(cell);
+            let cx = margin + s + 1.5 * s * j as f64;
+            let cy = margin + h + 2.0 * h * i as f64 + if j % 2 == 1 { h } else { 0.0 };
+
+            // The corners, clockwise from the east.
+            let e = (cx + s, cy);
+            let se = (cx + s / 2.0, cy + h);
+            let sw = (cx - s / 2.0, cy + h);
+            let w = (cx - s, cy);
+            let nw = (cx - s / 2.0, cy - h);
+            let ne = (cx + s / 2.0, cy - h);
+
+            for dir in &HexDirection::ALL {
+                let open = match grid.cell_to(cell, *dir) {
+                    Some(other) => other < cell || grid.is_linked(cell, other),
+                    None => false,
+                };
+
+                if !open {
+                    let (a, b) = match dir {
+                        HexDirection::North => (nw, ne),
+                        HexDirection::NorthEast => (ne, e),
+                        HexDirection::SouthEast => (e, se),
+                        HexDirection::South => (se, sw),
+                        HexDirection::SouthWest => (sw, w),
+                        HexDirection::NorthWest => (w, nw),
+                    };
+                    draw_segment(&mut image, a, b, margin, black);
+                }
+            }
+        }
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_render() {
+        let mut grid = HexGrid::new(4, 5);
+        crate::recursive_backtracker_rng(&mut grid, &mut rand::thread_rng());
+
+        let image = HexGridRenderer::new()
+            .cell_size(10)
+            .border_width(2)
+            .render(&grid);
+
+        // 2 + 10 * (1.5 * 5 + 0.5) by 2 + 10 * sqrt(3) * 4.5, rounded up
+        assert_eq!(image.dimensions(), (82, 80));
+
+        // The center of a cell is open, and the outer walls are closed.
+        let white = Rgba([255, 255, 255, 255]);
+        let black = Rgba([0, 0, 0, 255]);
+        assert_eq!(*image.get_pixel(11, 9), white);
+        assert_eq!(*image.get_pixel(11, 1), black);
+        assert_eq!(*image.get_pixel(1, 9), black);
+
+        // A single column isn't staggered.
+        let grid = HexGrid::new(3, 1);
+        let image = HexGridRenderer::new().render(&grid);
+        assert_eq!(image.dimensions(), (21, 53));
+    }
+}
@@ -1,11 +1,36 @@
 use crate::grid::Grid;
 use crate::Cell;
+use crate::GridDirection;
 use image::ImageBuffer;
+use image::Rgba;
 use image::RgbaImage;
+use std::ops::Range;
+
+/// The width in pixels of the margin around each page rendered by `render_paged`; the
+/// registration marks are drawn in it.
+pub const PAGE_MARGIN: u32 = 8;
 
 /// A struct for rendering a grid as an Image, optionally colored with some data.  Uses the
 /// builder pattern.
-#[derive(Debug, Default, Copy, Clone)]
+///
+/// # Pixel Layout
+///
+/// The outer border is `border_width` pixels thick, and the interior walls are
+/// `wall_width` pixels thick, which defaults to the border width.  The image is
+/// `2 * border_width + cell_width * cols + wall_width * (cols - 1)` pixels wide, and
+/// similarly for its height.  Column j's cells start at x = `border_width + j *
+/// (cell_width + wall_width)`, and the wall to its east occupies the `wall_width` pixels
+/// that follow the cell, or the `border_width` pixels for the last column; rows are laid
+/// out in the same way.  The top-left pixel is always wall.
+///
+/// # Determinism
+///
+/// The rendered image depends only on the grid's dimensions and links, the data (if any),
+/// and the renderer's options; it doesn't depend on the platform, on the order in which
+/// links were made, or on anything else.  All color arithmetic is done in integers with
+/// documented rounding (see `render_with` and `antialias`), and the wall geometry for
+/// antialiasing uses only IEEE-754 operations that are exactly specified.
+#[derive(Debug, Copy, Clone)]
 pub struct ImageGridRenderer {
     /// The width of the rendered cell in pixels, not including the borders.
     cell_width: usize,
@@ -15,15 +40,85 @@ pub struct ImageGridRenderer {
 
     /// The border width, in pixels.
     border_width: usize,
+
+    /// The interior wall width, in pixels, if it differs from the border width.
+    wall_width: Option<usize>,
+
+    /// The supersampling factor for antialiased walls; 1 means no antialiasing.
+    supersample: usize,
+
+    /// The color of the walls.
+    wall_color: Rgba<u8>,
+
+    /// The color of the cells' floors, where no other color is given.
+    floor_color: Rgba<u8>,
+
+    /// The color of the cell boundaries where there are no walls, if they're drawn.
+    gridline_color: Option<Rgba<u8>>,
+
+    /// The inset of each cell's floor from its bounds, in pixels.
+    inset: usize,
+
+    /// The passage fraction, in channel mode.
+    channel: Option<f32>,
+
+    /// The color of the path drawn by `render_path`.
+    path_color: Rgba<u8>,
+}
+
+/// One page of a maze rendered by `ImageGridRenderer::render_paged`.
+#[derive(Debug, Clone)]
+pub struct PagedTile {
+    /// The page's image: a piece of the full render surrounded by a margin of
+    /// `PAGE_MARGIN` pixels.
+    pub image: RgbaImage,
+
+    /// The page's (row, column) position in the grid of pages.
+    pub page: (usize, usize),
+
+    /// The rows of maze cells that appear on the page.
+    pub rows: Range<usize>,
+
+    /// The columns of maze cells that appear on the page.
+    pub cols: Range<usize>,
+}
+
+impl Default for ImageGridRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ImageGridRenderer {
+    /// The default cell width and height, in pixels.
+    pub const DEFAULT_CELL_SIZE: usize = 10;
+
+    /// The default border width, in pixels.
+    pub const DEFAULT_BORDER_WIDTH: usize = 1;
+
+    /// The default wall color.
+    pub const DEFAULT_WALL_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    /// The default floor color.
+    pub const DEFAULT_FLOOR_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    /// The default path color.
+    pub const DEFAULT_PATH_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
     /// Creates a new renderer for the Grid with default settings
     pub fn new() -> Self {
         Self {
-            cell_width: 10,
-            cell_height: 10,
-            border_width: 1,
+            cell_width: Self::DEFAULT_CELL_SIZE,
+            cell_height: Self::DEFAULT_CELL_SIZE,
+            border_width: Self::DEFAULT_BORDER_WIDTH,
+            wall_width: None,
+            supersample: 1,
+            wall_color: Self::DEFAULT_WALL_COLOR,
+            floor_color: Self::DEFAULT_FLOOR_COLOR,
+            gridline_color: None,
+            inset: 0,
+            channel: None,
+            path_color: Self::DEFAULT_PATH_COLOR,
         }
     }
 
@@ -49,88 +144,301 @@ impl ImageGridRenderer {
         self
     }
 
-    /// Adds the desired cell_width and height.
+    /// Sets the width of the outer border, in pixels.  Unless `wall_width` is given, the
+    /// interior walls are the same width.
     pub fn border_width(&mut self, border_width: usize) -> &mut Self {
         assert!(border_width > 0);
         self.border_width = border_width;
         self
     }
 
+    /// Sets the width of the interior walls, in pixels, independently of the outer
+    /// border.  The rendered image is then `2 * border_width + cell_width * cols +
+    /// wall_width * (cols - 1)` pixels wide, and `2 * border_width + cell_height * rows +
+    /// wall_width * (rows - 1)` pixels high.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let grid = Grid::new(3, 5);
+    /// let image = ImageGridRenderer::new()
+    ///     .cell_size(10)
+    ///     .border_width(6)
+    ///     .wall_width(1)
+    ///     .render(&grid);
+    /// assert_eq!(image.dimensions(), (2 * 6 + 5 * 10 + 4, 2 * 6 + 3 * 10 + 2));
+    /// ```
+    pub fn wall_width(&mut self, wall_width: usize) -> &mut Self {
+        assert!(wall_width > 0);
+        self.wall_width = Some(wall_width);
+        self
+    }
+
+    /// Enables antialiasing of the walls.  The walls are drawn with rounded ends, and each
+    /// pixel is sampled on a `factor` x `factor` grid; the pixel's color is blended from
+    /// the floor and wall colors according to the number of samples, k of n, that lie in
+    /// a wall.  Each channel is `(floor * (n - k) + wall * k + n / 2) / n`, in integer
+    /// arithmetic.  The size of the image is unchanged.  A factor of 1 disables
+    /// antialiasing.
+    pub fn antialias(&mut self, factor: usize) -> &mut Self {
+        assert!(factor > 0);
+        self.supersample = factor;
+        self
+    }
+
+    /// Sets the color of the walls.  Defaults to black.
+    pub fn wall_color(&mut self, color: Rgba<u8>) -> &mut Self {
+        self.wall_color = color;
+        self
+    }
+
+    /// Sets the color of the cells' floors, where no other color is given.  Defaults to
+    /// white.
+    pub fn floor_color(&mut self, color: Rgba<u8>) -> &mut Self {
+        self.floor_color = color;
+        self
+    }
+
+    /// Sets the color of the path drawn by `render_path`.  Defaults to red.
+    pub fn path_color(&mut self, color: Rgba<u8>) -> &mut Self {
+        self.path_color = color;
+        self
+    }
+
+    /// Draws the cell boundaries where there are no walls in the given color, like the
+    /// lines on graph paper, so that the grid's cell structure can be seen along with the
+    /// maze.  Use a faint color, and a strong wall color.  Openings in the outer walls are
+    /// left open.  The gridlines aren't drawn when antialiasing, with an inset, or in
+    /// channel mode.
+    pub fn gridline_color(&mut self, color: Rgba<u8>) -> &mut Self {
+        self.gridline_color = Some(color);
+        self
+    }
+
+    /// Sets the inset, in pixels.  When the inset is greater than zero, each cell's floor is
+    /// drawn as a rectangle `inset` pixels smaller than the cell on each side, and a
+    /// narrower corridor, about half as wide as the floor and centered on it, bridges the
+    /// gap to each cell to which it's linked, and through each opening; everything else is
+    /// wall.  The image has the same size and layout as usual.  Antialiasing is ignored
+    /// when there's an inset, and the inset is ignored in channel mode.  An inset that
+    /// would leave no floor is reduced at render time to leave at least one pixel.  The
+    /// inset defaults to 0, which draws the walls as usual.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(1, 2);
+    /// grid.link(0, 1);
+    ///
+    /// let image = ImageGridRenderer::new().inset(2).render(&grid);
+    /// assert_eq!(image.dimensions(), (23, 12));
+    ///
+    /// // The floors run from y = 3 to 8; the corridor between them, from 4 to 7.
+    /// assert_eq!(image.get_pixel(11, 3).0, [0, 0, 0, 255]);
+    /// assert_eq!(image.get_pixel(11, 4).0, [255, 255, 255, 255]);
+    /// assert_eq!(image.get_pixel(11, 7).0, [255, 255, 255, 255]);
+    /// assert_eq!(image.get_pixel(11, 8).0, [0, 0, 0, 255]);
+    /// ```
+    pub fn inset(&mut self, inset: usize) -> &mut Self {
+        self.inset = inset;
+        self
+    }
+
+    /// Enables channel mode, which draws the maze as a corn maze looks from the air: cut
+    /// passages through a solid field.  Each cell's passage is a square in the middle of the
+    /// cell, `passage_fraction` of the cell's width and height, extended to the cell's edge
+    /// in each direction in which the cell is linked or open; everything else is filled
+    /// with the wall color.  The cells abut, so the image is `cell_width * cols` pixels wide
+    /// and `cell_height * rows` pixels high; the border width and antialiasing are ignored.
+    /// The fraction must be greater than 0.0 and no greater than 1.0.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(1, 2);
+    /// grid.link(0, 1);
+    ///
+    /// let image = ImageGridRenderer::new().channel_mode(0.4).render(&grid);
+    /// assert_eq!(image.dimensions(), (20, 10));
+    ///
+    /// // The passage runs through the middle four pixels of each row of cells.
+    /// assert_eq!(image.get_pixel(10, 2).0, [0, 0, 0, 255]);
+    /// assert_eq!(image.get_pixel(10, 3).0, [255, 255, 255, 255]);
+    /// assert_eq!(image.get_pixel(10, 6).0, [255, 255, 255, 255]);
+    /// assert_eq!(image.get_pixel(10, 7).0, [0, 0, 0, 255]);
+    /// ```
+    pub fn channel_mode(&mut self, passage_fraction: f32) -> &mut Self {
+        assert!(
+            passage_fraction > 0.0 && passage_fraction <= 1.0,
+            "passage fraction must be in (0.0, 1.0], got {}",
+            passage_fraction
+        );
+        self.channel = Some(passage_fraction);
+        self
+    }
+
+    // The width of the interior walls.
+    fn interior_width(&self) -> usize {
+        self.wall_width.unwrap_or(self.border_width)
+    }
+
+    // The size in pixels of n cells of the given size, with their walls and borders.
+    fn extent(&self, n: usize, cell: usize) -> u32 {
+        (2 * self.border_width + n * cell + (n - 1) * self.interior_width()) as u32
+    }
+
+    // The pixels occupied by grid line k of n cells of the given size, counting from the
+    // top or left.  Lines 0 and n are the outer border; the rest are interior walls.
+    fn line(&self, k: usize, n: usize, cell: usize) -> Range<u32> {
+        let bw = self.border_width;
+        let ww = self.interior_width();
+
+        if k == 0 {
+            return 0..bw as u32;
+        }
+
+        let start = bw + k * cell + (k - 1) * ww;
+        let width = if k == n { bw } else { ww };
+        start as u32..(start + width) as u32
+    }
+
     fn iy(&self, i: usize) -> u32 {
-        (self.border_width + i * (self.cell_height + self.border_width)) as u32
+        (self.border_width + i * (self.cell_height + self.interior_width())) as u32
     }
 
     fn jx(&self, j: usize) -> u32 {
-        (self.border_width + j * (self.cell_width + self.border_width)) as u32
+        (self.border_width + j * (self.cell_width + self.interior_width())) as u32
     }
 
     /// Render the grid using the current parameters.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(3, 5);
+    /// recursive_backtracker(&mut grid);
+    ///
+    /// let image = ImageGridRenderer::new()
+    ///     .cell_width(20)
+    ///     .cell_height(10)
+    ///     .border_width(2)
+    ///     .render(&grid);
+    /// assert_eq!(image.dimensions(), (5 * 20 + 6 * 2, 3 * 10 + 4 * 2));
+    ///
+    /// // The top-left corner is wall, and the center of each cell is floor.
+    /// assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    /// assert_eq!(image.get_pixel(2 + 10, 2 + 5).0, [255, 255, 255, 255]);
+    /// ```
     pub fn render(&self, grid: &Grid) -> RgbaImage {
         self.render_with(grid, |_| None)
     }
 
     /// Render the grid using the current parameters.  Fill the cells by scaling the data in
     /// the data set from min to max.
-    #[allow(clippy::cognitive_complexity)]
+    ///
+    /// Each cell with data is filled with the color `(255 - shade, 255 - shade, 255)`,
+    /// where `shade = 255 * (value - min) / (max - min)`, computed in integer arithmetic and
+    /// rounded down.  If all of the values are the same, the shade is 0.  Cells without
+    /// data have the floor color.
     pub fn render_with<F>(&self, grid: &Grid, f: F) -> RgbaImage
     where
         F: Fn(Cell) -> Option<i64>,
     {
-        // FIRST, size and create the image
-        let nr = grid.num_rows() as u32;
-        let nc = grid.num_cols() as u32;
-        let bw = self.border_width as u32;
-        let cellw = self.cell_width as u32;
-        let cellh = self.cell_height as u32;
-        let bcellw = (self.border_width + self.cell_width) as u32;
-        let bcellh = (self.border_width + self.cell_height) as u32;
-        let width = bw * (nc + 1) + cellw * nc;
-        let height = bw * (nr + 1) + cellh * nr;
-
-        let mut image: RgbaImage = ImageBuffer::new(width, height);
-        let black = image::Rgba([0, 0, 0, 255]);
-        let white = image::Rgba([255, 255, 255, 255]);
-
-        // NEXT, are we rendering data?
-        let mut data_min = std::i64::MAX;
-        let mut data_max = std::i64::MIN;
-        let mut range: f64 = 0.0;
+        // FIRST, find the range of the data.
+        let mut data_min = i64::MAX;
+        let mut data_max = i64::MIN;
 
         for c in 0..grid.num_cells() {
             if let Some(val) = f(c) {
                 data_min = std::cmp::min(val, data_min);
-                data_max = std::cmp::max(val, data_min);
+                data_max = std::cmp::max(val, data_max);
             }
         }
 
-        if data_min < data_max {
-            // We have a range of data; we can plot colors.
-            range = (data_max - data_min) as f64;
+        // NEXT, render the data as shades of blue.
+        self.render_colored(grid, |cell| {
+            f(cell).map(|value| {
+                let shade = data_shade(value, data_min, data_max);
+                Rgba([255 - shade, 255 - shade, 255, 255])
+            })
+        })
+    }
+
+    /// Render the grid using the current parameters, filling each cell's interior with the
+    /// color returned for it by the closure.  Cells for which it returns `None` have the
+    /// floor color.
+    ///
+    /// ```
+    /// use mazegen::*;
+    /// use image::Rgba;
+    ///
+    /// let grid = Grid::new(2, 2);
+    /// let red = Rgba([255, 0, 0, 255]);
+    ///
+    /// let image = ImageGridRenderer::new().render_colored(&grid, |c| {
+    ///     if c == 3 { Some(red) } else { None }
+    /// });
+    /// assert_eq!(*image.get_pixel(1 + 11 + 5, 1 + 11 + 5), red);
+    /// assert_eq!(image.get_pixel(6, 6).0, [255, 255, 255, 255]);
+    /// ```
+    #[allow(clippy::cognitive_complexity)]
+    pub fn render_colored<F>(&self, grid: &Grid, floor_of: F) -> RgbaImage
+    where
+        F: Fn(Cell) -> Option<Rgba<u8>>,
+    {
+        crate::validation::debug_check_renderable(grid);
+
+        // FIRST, size the image
+        let nr = grid.num_rows();
+        let nc = grid.num_cols();
+        let cellw = self.cell_width as u32;
+        let cellh = self.cell_height as u32;
+        let width = self.extent(nc, self.cell_width);
+        let height = self.extent(nr, self.cell_height);
+        let col_line = |k: usize| self.line(k, nc, self.cell_width);
+        let row_line = |k: usize| self.line(k, nr, self.cell_height);
+
+        let wall = self.wall_color;
+        let background = self.floor_color;
+
+        if let Some(fraction) = self.channel {
+            return self
+                .render_channels(grid, fraction, |cell| floor_of(cell).unwrap_or(background));
+        }
+
+        if self.inset > 0 {
+            return self.render_inset(grid, |cell| floor_of(cell).unwrap_or(background));
+        }
+
+        if self.supersample > 1 {
+            return self.render_antialiased(grid, |cell| floor_of(cell).unwrap_or(background));
         }
 
-        // NEXT, clear the image to white.
+        let mut image: RgbaImage = ImageBuffer::new(width, height);
+
+        // NEXT, clear the image to the floor color.
         for y in 0..height {
             for x in 0..width {
-                image.put_pixel(x, y, white);
+                image.put_pixel(x, y, background);
             }
         }
 
         // NEXT, draw the top and left lines, and the intersection points
         for x in 0..width {
-            for y in 0..bw {
-                image.put_pixel(x, y, black);
+            for y in row_line(0) {
+                image.put_pixel(x, y, wall);
             }
         }
         for y in 0..height {
-            for x in 0..bw {
-                image.put_pixel(x, y, black);
+            for x in col_line(0) {
+                image.put_pixel(x, y, wall);
             }
         }
-        for y in (bcellh..height).step_by(bcellh as usize) {
-            for x in (bcellw..width).step_by(bcellw as usize) {
-                for x1 in x..(x + bw) {
-                    for y1 in y..(y + bw) {
-                        image.put_pixel(x1, y1, black);
+        for l in 1..=nr {
+            for k in 1..=nc {
+                for y1 in row_line(l) {
+                    for x1 in col_line(k) {
+                        image.put_pixel(x1, y1, wall);
                     }
                 }
             }
@@ -145,52 +453,60 @@ impl ImageGridRenderer {
                 let x = self.jx(j);
 
                 // Fill the cell with the data color.
-                let mut floor = white;
-
-                if let Some(value) = f(cell) {
-                    let val = 255.0 * (value as f64) / range;
+                let mut floor = background;
 
-                    let scaled: u8;
+                if let Some(color) = floor_of(cell) {
+                    floor = color;
 
-                    if val < 0.0 {
-                        scaled = 0;
-                    } else if val > 255.0 {
-                        scaled = 255;
-                    } else {
-                        scaled = val as u8;
+                    for y1 in y..(y + cellh) {
+                        for x1 in x..(x + cellw) {
+                            image.put_pixel(x1, y1, floor);
+                        }
                     }
+                }
 
-                    floor = image::Rgba([255 - scaled, 255 - scaled, 255, 255]);
-
-                    for y1 in y..(y + cellh) {
+                // Open the top and left borders at the grid's openings.
+                if i == 0 && grid.has_opening(cell, GridDirection::North) {
+                    for y1 in row_line(0) {
                         for x1 in x..(x + cellw) {
                             image.put_pixel(x1, y1, floor);
                         }
                     }
                 }
+                if j == 0 && grid.has_opening(cell, GridDirection::West) {
+                    for y1 in y..(y + cellh) {
+                        for x1 in col_line(0) {
+                            image.put_pixel(x1, y1, floor);
+                        }
+                    }
+                }
 
                 // Draw east border
-                let pixel = if grid.is_linked_east(cell) {
+                let pixel = if grid.has_opening(cell, GridDirection::East) {
                     floor
+                } else if grid.is_linked_east(cell) {
+                    self.gridline_color.unwrap_or(floor)
                 } else {
-                    black
+                    wall
                 };
 
                 for y1 in y..(y + cellh) {
-                    for x1 in (x + cellw)..(x + bcellw) {
+                    for x1 in col_line(j + 1) {
                         image.put_pixel(x1, y1, pixel);
                     }
                 }
 
                 // Draw south border
-                let pixel = if grid.is_linked_south(cell) {
+                let pixel = if grid.has_opening(cell, GridDirection::South) {
                     floor
+                } else if grid.is_linked_south(cell) {
+                    self.gridline_color.unwrap_or(floor)
                 } else {
-                    black
+                    wall
                 };
 
                 for x1 in x..(x + cellw) {
-                    for y1 in (y + cellh)..(y + bcellh) {
+                    for y1 in row_line(i + 1) {
                         image.put_pixel(x1, y1, pixel);
                     }
                 }
@@ -199,4 +515,1194 @@ impl ImageGridRenderer {
 
         image
     }
+
+    /// Renders the grid using the current parameters, and then draws the path, e.g., one
+    /// found by `Grid::shortest_path` or `Grid::longest_path`, as a line in the path color
+    /// through the centers of its cells.  The line is drawn straight from each cell's center
+    /// to the next cell's, whether or not the cells are linked; it's `2 * (size / 8) + 1`
+    /// pixels wide, where `size` is the larger of the cell width and height.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(1, 3);
+    /// grid.link(0, 1);
+    /// grid.link(1, 2);
+    ///
+    /// let path = grid.shortest_path(0, 2);
+    /// let image = ImageGridRenderer::new().render_path(&grid, path.cells());
+    ///
+    /// // The path runs through the wall between the first two cells.
+    /// assert_eq!(image.get_pixel(11, 6).0, [255, 0, 0, 255]);
+    /// assert_eq!(image.get_pixel(11, 2).0, [255, 255, 255, 255]);
+    /// ```
+    pub fn render_path(&self, grid: &Grid, path: &[Cell]) -> RgbaImage {
+        let mut image = self.render(grid);
+        let half = (self.cell_width.max(self.cell_height) / 8) as f64;
+        let (width, height) = image.dimensions();
+
+        let centers: Vec<(f64, f64)> = path
+            .iter()
+            .map(|&cell| self.cell_center(grid.ij(cell)))
+            .collect();
+
+        // Draw a segment for each step, or a dot for a path of a single cell.
+        let mut segments: Vec<((f64, f64), (f64, f64))> =
+            centers.windows(2).map(|w| (w[0], w[1])).collect();
+        if centers.len() == 1 {
+            segments.push((centers[0], centers[0]));
+        }
+
+        for (a, b) in segments {
+            // Only the pixels in the segment's bounding box can be close enough to it.
+            let x0 = (a.0.min(b.0) - half).max(0.0) as u32;
+            let y0 = (a.1.min(b.1) - half).max(0.0) as u32;
+            let x1 = ((a.0.max(b.0) + half) as u32).min(width - 1);
+            let y1 = ((a.1.max(b.1) + half) as u32).min(height - 1);
+
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    let p = (x as f64 + 0.5, y as f64 + 0.5);
+                    if distance_to_segment(p, a, b) <= half {
+                        image.put_pixel(x, y, self.path_color);
+                    }
+                }
+            }
+        }
+
+        image
+    }
+
+    // The center of the middle pixel of the cell at (row, col); in channel mode the cells
+    // abut.
+    pub(crate) fn cell_center(&self, (i, j): (usize, usize)) -> (f64, f64) {
+        let (x, y) = if self.channel.is_some() {
+            ((j * self.cell_width) as u32, (i * self.cell_height) as u32)
+        } else {
+            (self.jx(j), self.iy(i))
+        };
+
+        (
+            (x + self.cell_width as u32 / 2) as f64 + 0.5,
+            (y + self.cell_height as u32 / 2) as f64 + 0.5,
+        )
+    }
+
+    /// Renders a field of per-cell values, such as the mean distances computed by
+    /// `ensemble_mean_distances`, as a heatmap on an open `rows` x `cols` grid: the cells
+    /// are shaded as by `render_with`, and there are no interior walls.  NaN values are
+    /// left white.  Panics if `values` doesn't have one value per cell.
+    pub fn render_cell_field(&self, rows: usize, cols: usize, values: &[f64]) -> RgbaImage {
+        assert_eq!(values.len(), rows * cols, "expected one value per cell");
+        let mut grid = Grid::new(rows, cols);
+        grid.carve_room(0, 0, rows, cols);
+
+        // The shading is relative to the range of the data, so scaling preserves it.
+        self.render_with(&grid, |cell| {
+            let value = values[cell];
+            if value.is_nan() {
+                None
+            } else {
+                Some((value * 1000.0).round() as i64)
+            }
+        })
+    }
+
+    /// Renders a field of per-wall probabilities, such as those computed by
+    /// `ensemble_link_probability`, on a `rows` x `cols` grid.  Each interior wall is drawn
+    /// in gray, from black for a probability of 0 (always closed) to white for a
+    /// probability of 1 (always open); `probs` is indexed by the canonical wall index (see
+    /// `Grid::wall_index`).  Panics if `probs` doesn't have one value per wall.
+    pub fn render_wall_field(&self, rows: usize, cols: usize, probs: &[f64]) -> RgbaImage {
+        let mut grid = Grid::new(rows, cols);
+        assert_eq!(probs.len(), grid.num_walls(), "expected one value per wall");
+        grid.carve_room(0, 0, rows, cols);
+
+        let mut image = self.render(&grid);
+        let cellw = self.cell_width as u32;
+        let cellh = self.cell_height as u32;
+
+        for (index, prob) in probs.iter().enumerate() {
+            let (cell, dir) = grid.wall_at(index).expect("valid wall index");
+            let (i, j) = grid.ij(cell);
+            let level = (255.0 * prob.clamp(0.0, 1.0)).round() as u8;
+            let gray = Rgba([level, level, level, 255]);
+
+            let (xs, ys) = if dir == GridDirection::East {
+                (
+                    self.line(j + 1, cols, self.cell_width),
+                    self.iy(i)..self.iy(i) + cellh,
+                )
+            } else {
+                (
+                    self.jx(j)..self.jx(j) + cellw,
+                    self.line(i + 1, rows, self.cell_height),
+                )
+            };
+
+            for y1 in ys {
+                for x1 in xs.clone() {
+                    image.put_pixel(x1, y1, gray);
+                }
+            }
+        }
+
+        image
+    }
+
+    /// Renders the grid as a set of pages no larger than `page_px` (width, height) pixels,
+    /// for printing a maze too large for a single page.  The pages are returned in row
+    /// major order.
+    ///
+    /// Each page shows a block of whole cells, together with the walls that surround
+    /// them, cropped from the full render; so a wall that crosses a page boundary looks
+    /// the same on both pages, and cropping the duplicated content and the margins from
+    /// the pages and laying them side by side reproduces `render` exactly.  Adjacent pages
+    /// share `overlap_cells` rows or columns of cells.  Each page has a white margin of
+    /// `PAGE_MARGIN` pixels; on every edge shared with another page, two registration
+    /// crosses are drawn in the margin at a quarter and three quarters of the way along
+    /// the edge, so that the neighbor has its crosses at the mirrored positions.
+    ///
+    /// The pages always have thin walls; channel mode is ignored.
+    ///
+    /// Panics if a page can't hold more than `overlap_cells` cells in each direction.
+    pub fn render_paged(
+        &self,
+        grid: &Grid,
+        page_px: (u32, u32),
+        overlap_cells: usize,
+    ) -> Vec<PagedTile> {
+        // FIRST, determine how many cells fit on a page.
+        let (per_row, per_col) = self.cells_per_page(page_px);
+        assert!(
+            per_row > overlap_cells && per_col > overlap_cells,
+            "page of {}x{} pixels is too small for an overlap of {} cells",
+            page_px.0,
+            page_px.1,
+            overlap_cells
+        );
+
+        // NEXT, render the whole maze, and crop the pages from it.
+        let mut walled = *self;
+        walled.channel = None;
+        let full = walled.render(grid);
+        let (nr, nc) = (grid.num_rows(), grid.num_cols());
+        let white = Rgba([255, 255, 255, 255]);
+        let black = Rgba([0, 0, 0, 255]);
+
+        let row_ranges = page_ranges(grid.num_rows(), per_col, overlap_cells);
+        let col_ranges = page_ranges(grid.num_cols(), per_row, overlap_cells);
+        let mut tiles = Vec::new();
+
+        for (pi, rows) in row_ranges.iter().enumerate() {
+            for (pj, cols) in col_ranges.iter().enumerate() {
+                let x0 = self.line(cols.start, nc, self.cell_width).start;
+                let y0 = self.line(rows.start, nr, self.cell_height).start;
+                let content_w = self.line(cols.end, nc, self.cell_width).end - x0;
+                let content_h = self.line(rows.end, nr, self.cell_height).end - y0;
+                let width = content_w + 2 * PAGE_MARGIN;
+                let height = content_h + 2 * PAGE_MARGIN;
+
+                let mut image: RgbaImage = ImageBuffer::from_pixel(width, height, white);
+
+                for y in 0..content_h {
+                    for x in 0..content_w {
+                        let pixel = *full.get_pixel(x0 + x, y0 + y);
+                        image.put_pixel(PAGE_MARGIN + x, PAGE_MARGIN + y, pixel);
+                    }
+                }
+
+                // Draw the registration marks on the shared edges.
+                let near = PAGE_MARGIN / 2;
+                let ys = [PAGE_MARGIN + content_h / 4, PAGE_MARGIN + 3 * content_h / 4];
+                let xs = [PAGE_MARGIN + content_w / 4, PAGE_MARGIN + 3 * content_w / 4];
+
+                for &y in &ys {
+                    if pj > 0 {
+                        draw_cross(&mut image, (near, y), black);
+                    }
+                    if pj + 1 < col_ranges.len() {
+                        draw_cross(&mut image, (width - 1 - near, y), black);
+                    }
+                }
+                for &x in &xs {
+                    if pi > 0 {
+                        draw_cross(&mut image, (x, near), black);
+                    }
+                    if pi + 1 < row_ranges.len() {
+                        draw_cross(&mut image, (x, height - 1 - near), black);
+                    }
+                }
+
+                tiles.push(PagedTile {
+                    image,
+                    page: (pi, pj),
+                    rows: rows.clone(),
+                    cols: cols.clone(),
+                });
+            }
+        }
+
+        tiles
+    }
+
+    /// Returns the number of whole cells, (across, down), that fit on a page of the
+    /// given size inside the page margins.
+    pub(crate) fn cells_per_page(&self, page_px: (u32, u32)) -> (usize, usize) {
+        // A page's cells are bounded by lines that may be either border or wall.
+        let ww = self.interior_width();
+        let edge = self.border_width.max(ww);
+
+        let fit = |page: u32, cell: usize| {
+            let room = page as usize + ww;
+            let used = 2 * PAGE_MARGIN as usize + 2 * edge;
+            if room > used {
+                (room - used) / (cell + ww)
+            } else {
+                0
+            }
+        };
+
+        (
+            fit(page_px.0, self.cell_width),
+            fit(page_px.1, self.cell_height),
+        )
+    }
+
+    /// Renders the grid with inset cells, given the floor color of each cell.  See
+    /// `inset`.  Each cell draws its floor and its half of each of its corridors, up to the
+    /// middle of the wall between it and its neighbor, or to the edge of the image at an
+    /// opening.
+    fn render_inset<F>(&self, grid: &Grid, floor_of: F) -> RgbaImage
+    where
+        F: Fn(Cell) -> Rgba<u8>,
+    {
+        // FIRST, reduce the inset, if need be, to leave at least one pixel of floor.
+        let inset = self
+            .inset
+            .min((self.cell_width.min(self.cell_height) - 1) / 2);
+
+        // NEXT, size the image and fill it with wall.
+        let nr = grid.num_rows();
+        let nc = grid.num_cols();
+        let width = self.extent(nc, self.cell_width);
+        let height = self.extent(nr, self.cell_height);
+        let inset = inset as u32;
+        let cellw = self.cell_width as u32;
+        let cellh = self.cell_height as u32;
+        let middle = |line: Range<u32>| (line.start + line.end) / 2;
+        let narrow = |span: Range<u32>| {
+            let trim = (span.end - span.start) / 4;
+            span.start + trim..span.end - trim
+        };
+
+        let mut image: RgbaImage = ImageBuffer::from_pixel(width, height, self.wall_color);
+
+        // NEXT, draw each cell's floor and corridors.
+        for cell in 0..grid.num_cells() {
+            let (i, j) = grid.ij(cell);
+            let (x, y) = (self.jx(j), self.iy(i));
+            let floor = floor_of(cell);
+            let open = |dir| grid.is_linked_to(cell, dir) || grid.has_opening(cell, dir);
+
+            // The floor's bounds, the corridors' bounds across them, and how far the
+            // corridors reach in each direction.
+            let (left, right) = (x + inset, x + cellw - inset);
+            let (top, bottom) = (y + inset, y + cellh - inset);
+            let (across_x, across_y) = (narrow(left..right), narrow(top..bottom));
+            let west = if j == 0 {
+                0
+            } else {
+                middle(self.line(j, nc, self.cell_width))
+            };
+            let east = if j + 1 == nc {
+                width
+            } else {
+                middle(self.line(j + 1, nc, self.cell_width))
+            };
+            let north = if i == 0 {
+                0
+            } else {
+                middle(self.line(i, nr, self.cell_height))
+            };
+            let south = if i + 1 == nr {
+                height
+            } else {
+                middle(self.line(i + 1, nr, self.cell_height))
+            };
+
+            let mut fill = |xs: Range<u32>, ys: Range<u32>| {
+                for py in ys {
+                    for px in xs.clone() {
+                        image.put_pixel(px, py, floor);
+                    }
+                }
+            };
+
+            fill(left..right, top..bottom);
+
+            if open(GridDirection::West) {
+                fill(west..left, across_y.clone());
+            }
+            if open(GridDirection::East) {
+                fill(right..east, across_y);
+            }
+            if open(GridDirection::North) {
+                fill(across_x.clone(), north..top);
+            }
+            if open(GridDirection::South) {
+                fill(across_x, bottom..south);
+            }
+        }
+
+        image
+    }
+
+    /// Renders the grid in channel mode, given the passage fraction and the floor color of
+    /// each cell.  See `channel_mode`.
+    fn render_channels<F>(&self, grid: &Grid, fraction: f32, floor_of: F) -> RgbaImage
+    where
+        F: Fn(Cell) -> Rgba<u8>,
+    {
+        // FIRST, size the image and the passages, and fill the image with wall.
+        let cellw = self.cell_width;
+        let cellh = self.cell_height;
+        let width = (cellw * grid.num_cols()) as u32;
+        let height = (cellh * grid.num_rows()) as u32;
+
+        let passage = |size: usize| ((size as f32 * fraction).round() as usize).clamp(1, size);
+        let (pw, ph) = (passage(cellw), passage(cellh));
+        let (ox, oy) = ((cellw - pw) / 2, (cellh - ph) / 2);
+
+        let mut image: RgbaImage = ImageBuffer::from_pixel(width, height, self.wall_color);
+
+        // NEXT, cut each cell's passage: the middle square, extended to the edge of the
+        // cell in each open direction.
+        for cell in 0..grid.num_cells() {
+            let (i, j) = grid.ij(cell);
+            let (x, y) = (j * cellw, i * cellh);
+            let open = |dir| grid.is_linked_to(cell, dir) || grid.has_opening(cell, dir);
+
+            let left = if open(GridDirection::West) { x } else { x + ox };
+            let right = if open(GridDirection::East) {
+                x + cellw
+            } else {
+                x + ox + pw
+            };
+            let top = if open(GridDirection::North) {
+                y
+            } else {
+                y + oy
+            };
+            let bottom = if open(GridDirection::South) {
+                y + cellh
+            } else {
+                y + oy + ph
+            };
+
+            let floor = floor_of(cell);
+            let mut fill = |x0: usize, x1: usize, y0: usize, y1: usize| {
+                for py in y0..y1 {
+                    for px in x0..x1 {
+                        image.put_pixel(px as u32, py as u32, floor);
+                    }
+                }
+            };
+
+            fill(left, right, y + oy, y + oy + ph);
+            fill(x + ox, x + ox + pw, top, bottom);
+        }
+
+        image
+    }
+
+    /// Renders the grid with antialiased walls, given the floor color of each cell.
+    /// Each wall is a segment along a grid line, drawn border_width thick on the outside
+    /// and wall_width thick inside, with rounded ends; the walls are rasterized at the
+    /// supersampled resolution and then box-filtered down to the image size.
+    fn render_antialiased<F>(&self, grid: &Grid, floor_of: F) -> RgbaImage
+    where
+        F: Fn(Cell) -> Rgba<u8>,
+    {
+        // FIRST, size and create the image
+        let nr = grid.num_rows();
+        let nc = grid.num_cols();
+        let bw = self.border_width;
+        let ww = self.interior_width();
+        let bcellw = ww + self.cell_width;
+        let bcellh = ww + self.cell_height;
+        let width = self.extent(nc, self.cell_width) as usize;
+        let height = self.extent(nr, self.cell_height) as usize;
+        let ss = self.supersample;
+
+        let mut image: RgbaImage = ImageBuffer::new(width as u32, height as u32);
+        let wall = self.wall_color;
+
+        // NEXT, get the wall segments, in pixel coordinates, with their radii.  Grid line k
+        // runs through the middle of the k-th border or wall.
+        let middle = |line: Range<u32>| (line.start + line.end) as f64 / 2.0;
+        let lx = |k: usize| middle(self.line(k, nc, self.cell_width));
+        let ly = |k: usize| middle(self.line(k, nr, self.cell_height));
+        let outer = bw as f64 / 2.0;
+        let inner = ww as f64 / 2.0;
+        let mut walls: Vec<WallSegment> = Vec::new();
+
+        for j in 0..nc {
+            if !grid.has_opening(grid.cell(0, j), GridDirection::North) {
+                walls.push(((lx(j), ly(0)), (lx(j + 1), ly(0)), outer));
+            }
+        }
+        for i in 0..nr {
+            if !grid.has_opening(grid.cell(i, 0), GridDirection::West) {
+                walls.push(((lx(0), ly(i)), (lx(0), ly(i + 1)), outer));
+            }
+        }
+
+        for i in 0..nr {
+            for j in 0..nc {
+                let cell = grid.cell(i, j);
+
+                if !grid.is_linked_east(cell) && !grid.has_opening(cell, GridDirection::East) {
+                    let radius = if j + 1 == nc { outer } else { inner };
+                    walls.push(((lx(j + 1), ly(i)), (lx(j + 1), ly(i + 1)), radius));
+                }
+                if !grid.is_linked_south(cell) && !grid.has_opening(cell, GridDirection::South) {
+                    let radius = if i + 1 == nr { outer } else { inner };
+                    walls.push(((lx(j), ly(i + 1)), (lx(j + 1), ly(i + 1)), radius));
+                }
+            }
+        }
+
+        // NEXT, mark the samples that fall within a wall.  Sample (sx,sy) is at the center
+        // of its sub-pixel.
+        let sw = width * ss;
+        let sh = height * ss;
+        let mut covered = vec![false; sw * sh];
+
+        for &((x0, y0), (x1, y1), radius) in &walls {
+            let sx0 = ((x0.min(x1) - radius) * ss as f64).floor().max(0.0) as usize;
+            let sx1 = (((x0.max(x1) + radius) * ss as f64).ceil() as usize).min(sw);
+            let sy0 = ((y0.min(y1) - radius) * ss as f64).floor().max(0.0) as usize;
+            let sy1 = (((y0.max(y1) + radius) * ss as f64).ceil() as usize).min(sh);
+
+            for sy in sy0..sy1 {
+                for sx in sx0..sx1 {
+                    let px = (sx as f64 + 0.5) / ss as f64;
+                    let py = (sy as f64 + 0.5) / ss as f64;
+
+                    if distance_to_segment((px, py), (x0, y0), (x1, y1)) <= radius {
+                        covered[sy * sw + sx] = true;
+                    }
+                }
+            }
+        }
+
+        // FINALLY, blend each pixel's floor color with the wall color by its coverage.
+        for y in 0..height {
+            let i = (y.saturating_sub(bw) / bcellh).min(nr - 1);
+
+            for x in 0..width {
+                let j = (x.saturating_sub(bw) / bcellw).min(nc - 1);
+                let floor = floor_of(grid.cell(i, j));
+
+                let mut count = 0;
+                for sy in y * ss..(y + 1) * ss {
+                    for sx in x * ss..(x + 1) * ss {
+                        if covered[sy * sw + sx] {
+                            count += 1;
+                        }
+                    }
+                }
+
+                image.put_pixel(x as u32, y as u32, blend(floor, wall, count, ss * ss));
+            }
+        }
+
+        image
+    }
+}
+
+/// A wall to be antialiased: a segment from one point to another, and its radius.
+type WallSegment = ((f64, f64), (f64, f64), f64);
+
+/// Draws a line segment from a to b in the given color: a pixel is drawn if its center is
+/// within `radius` of the segment.
+pub(crate) fn draw_segment(
+    image: &mut RgbaImage,
+    a: (f64, f64),
+    b: (f64, f64),
+    radius: f64,
+    color: Rgba<u8>,
+) {
+    let (width, height) = image.dimensions();
+    let x0 = (a.0.min(b.0) - radius).floor().max(0.0) as u32;
+    let x1 = ((a.0.max(b.0) + radius).ceil() as u32).min(width);
+    let y0 = (a.1.min(b.1) - radius).floor().max(0.0) as u32;
+    let y1 = ((a.1.max(b.1) + radius).ceil() as u32).min(height);
+
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let p = (x as f64 + 0.5, y as f64 + 0.5);
+            if distance_to_segment(p, a, b) <= radius {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+/// Returns the distance from point p to the segment from a to b.
+pub(crate) fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+    };
+
+    let (cx, cy) = (a.0 + t * dx, a.1 + t * dy);
+    let (ex, ey) = (p.0 - cx, p.1 - cy);
+    (ex * ex + ey * ey).sqrt()
+}
+
+/// Splits `n` cells into ranges of at most `per_page` cells, where each range after the
+/// first begins `overlap` cells before the end of the previous one.
+fn page_ranges(n: usize, per_page: usize, overlap: usize) -> Vec<Range<usize>> {
+    let step = per_page - overlap;
+    let mut ranges = Vec::new();
+    ranges.push(0..n.min(per_page));
+
+    while ranges[ranges.len() - 1].end < n {
+        let start = ranges[ranges.len() - 1].start + step;
+        ranges.push(start..n.min(start + per_page));
+    }
+
+    ranges
+}
+
+/// Draws a small registration cross centered on the given point.
+fn draw_cross(image: &mut RgbaImage, (cx, cy): (u32, u32), color: Rgba<u8>) {
+    let arm = PAGE_MARGIN / 2 - 1;
+
+    for d in 0..=(2 * arm) {
+        image.put_pixel(cx - arm + d, cy, color);
+        image.put_pixel(cx, cy - arm + d, color);
+    }
+}
+
+/// Computes the shade for a data value: `255 * (value - min) / (max - min)`, rounded
+/// down, or 0 if `min == max`.
+pub(crate) fn data_shade(value: i64, min: i64, max: i64) -> u8 {
+    if min >= max {
+        return 0;
+    }
+
+    // Use i128 so that the arithmetic can't overflow.
+    let shade = 255 * (value as i128 - min as i128) / (max as i128 - min as i128);
+    shade.clamp(0, 255) as u8
+}
+
+/// Blends two colors, weighting c2 by `part` out of `whole`, with rounding to the nearest
+/// integer: `(c1 * (whole - part) + c2 * part + whole / 2) / whole` for each channel.
+pub(crate) fn blend(c1: Rgba<u8>, c2: Rgba<u8>, part: usize, whole: usize) -> Rgba<u8> {
+    let mut out = [0u8; 4];
+
+    for (k, channel) in out.iter_mut().enumerate() {
+        let v = c1[k] as usize * (whole - part) + c2[k] as usize * part + whole / 2;
+        *channel = (v / whole) as u8;
+    }
+
+    Rgba(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // The total "ink" in the image: the sum of each pixel's darkness, from 0.0 to 1.0.
+    fn ink(image: &RgbaImage) -> f64 {
+        image
+            .pixels()
+            .map(|p| 1.0 - (p[0] as f64 + p[1] as f64 + p[2] as f64) / (3.0 * 255.0))
+            .sum()
+    }
+
+    // Computes the 64-bit FNV-1a hash of the image's raw RGBA buffer.  (The standard
+    // library's hashers aren't guaranteed to be stable across releases.)
+    fn fnv1a(image: &RgbaImage) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+        for byte in image.as_raw() {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        hash
+    }
+
+    // A seeded maze, for the golden tests.
+    fn golden_grid() -> Grid {
+        let mut grid = Grid::new(8, 12);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(2028));
+        grid
+    }
+
+    #[test]
+    fn test_render_colored_checkerboard() {
+        let mut grid = Grid::new(4, 5);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(2048));
+        let red = Rgba([255, 0, 0, 255]);
+        let white = ImageGridRenderer::DEFAULT_FLOOR_COLOR;
+
+        let image = ImageGridRenderer::new().render_colored(&grid, |c| {
+            let (i, j) = grid.ij(c);
+            if (i + j) % 2 == 0 {
+                Some(red)
+            } else {
+                None
+            }
+        });
+
+        // Every pixel of each cell's interior has the cell's color; the cells alternate.
+        for i in 0..4 {
+            for j in 0..5 {
+                let color = if (i + j) % 2 == 0 { red } else { white };
+                for y in 0..10 {
+                    for x in 0..10 {
+                        assert_eq!(*image.get_pixel(1 + 11 * j + x, 1 + 11 * i + y), color);
+                    }
+                }
+            }
+        }
+
+        // The border is still wall.
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_render_path() {
+        let mut grid = Grid::new(6, 8);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(2047));
+        let path = grid.longest_path();
+        let blue = Rgba([0, 0, 255, 255]);
+
+        for channel in &[false, true] {
+            let mut renderer = ImageGridRenderer::new();
+            renderer.cell_size(12).path_color(blue);
+            if *channel {
+                renderer.channel_mode(0.5);
+            }
+
+            let plain = renderer.render(&grid);
+            let image = renderer.render_path(&grid, path.cells());
+            assert_eq!(image.dimensions(), plain.dimensions());
+
+            // The midpoints between consecutive cells, which are in the walls between
+            // them, are on the path.
+            for w in path.cells().windows(2) {
+                let a = renderer.cell_center(grid.ij(w[0]));
+                let b = renderer.cell_center(grid.ij(w[1]));
+                let (x, y) = (((a.0 + b.0) / 2.0) as u32, ((a.1 + b.1) / 2.0) as u32);
+                assert_eq!(*image.get_pixel(x, y), blue);
+            }
+
+            // Only the path's pixels differ.
+            for (x, y, pixel) in image.enumerate_pixels() {
+                assert!(*pixel == blue || pixel == plain.get_pixel(x, y));
+            }
+        }
+
+        // An empty path draws nothing.
+        let image = ImageGridRenderer::new().render_path(&grid, &[]);
+        assert_eq!(image, ImageGridRenderer::new().render(&grid));
+    }
+
+    #[test]
+    fn test_render_colors() {
+        let red = crate::MoltPixel::rgb(255, 0, 0).ipixel();
+        let gray = crate::MoltPixel::rgb(40, 40, 40).ipixel();
+        let grid = golden_grid();
+
+        let image = ImageGridRenderer::new()
+            .floor_color(red)
+            .wall_color(gray)
+            .render(&grid);
+
+        // Every cell's interior is the floor color, and the corner is the wall color.
+        for cell in 0..grid.num_cells() {
+            let (i, j) = grid.ij(cell);
+            let (x, y) = (1 + 11 * j as u32 + 5, 1 + 11 * i as u32 + 5);
+            assert_eq!(*image.get_pixel(x, y), red);
+        }
+        assert_eq!(*image.get_pixel(0, 0), gray);
+
+        // The data shading only replaces the floor where there's data.
+        let image = ImageGridRenderer::new()
+            .floor_color(red)
+            .render_with(&grid, |c| if c == 0 { Some(1) } else { None });
+        assert_ne!(*image.get_pixel(6, 6), red);
+        assert_eq!(*image.get_pixel(17, 6), red);
+    }
+
+    #[test]
+    fn test_data_shade() {
+        assert_eq!(data_shade(0, 0, 10), 0);
+        assert_eq!(data_shade(5, 0, 10), 127);
+        assert_eq!(data_shade(10, 0, 10), 255);
+        assert_eq!(data_shade(-5, -10, 0), 127);
+        assert_eq!(data_shade(7, 7, 7), 0);
+        assert_eq!(data_shade(i64::MAX, i64::MIN, i64::MAX), 255);
+    }
+
+    #[test]
+    fn test_blend() {
+        let black = Rgba([0, 0, 0, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+
+        assert_eq!(blend(white, black, 0, 16), white);
+        assert_eq!(blend(white, black, 16, 16), black);
+        assert_eq!(blend(white, black, 8, 16), Rgba([128, 128, 128, 255]));
+        assert_eq!(blend(white, black, 1, 3), Rgba([170, 170, 170, 255]));
+    }
+
+    #[test]
+    fn test_render_depends_only_on_structure() {
+        let grid = golden_grid();
+
+        // The same links, made in a different order
+        let mut copy = Grid::new(grid.num_rows(), grid.num_cols());
+        for (a, b) in grid.link_pairs().into_iter().rev() {
+            copy.link(b, a);
+        }
+
+        let dists = grid.distances(0);
+        let renderer = *ImageGridRenderer::new().cell_size(7).border_width(2);
+
+        assert_eq!(renderer.render(&grid), renderer.render(&copy));
+        assert_eq!(
+            renderer.render_with(&grid, |c| dists[c].map(|d| d as i64)),
+            renderer.render_with(&copy, |c| dists[c].map(|d| d as i64))
+        );
+    }
+
+    #[test]
+    fn test_render_golden_hashes() {
+        // If rendering changes intentionally, these hashes must be updated.
+        let grid = golden_grid();
+        let dists = grid.distances(grid.cell(4, 6));
+        let data = |c: Cell| dists[c].map(|d| d as i64);
+
+        let plain = ImageGridRenderer::new().render(&grid);
+        assert_eq!(fnv1a(&plain), 0x1fd70070c0963120);
+
+        let heatmap = ImageGridRenderer::new()
+            .cell_size(9)
+            .border_width(3)
+            .render_with(&grid, data);
+        assert_eq!(fnv1a(&heatmap), 0x7bcec0d5b110486f);
+
+        let antialiased = ImageGridRenderer::new()
+            .cell_size(9)
+            .border_width(3)
+            .antialias(4)
+            .render_with(&grid, data);
+        assert_eq!(fnv1a(&antialiased), 0xf84134e33ec88345);
+    }
+
+    #[test]
+    fn test_antialias_dimensions() {
+        let mut grid = Grid::new(4, 5);
+        crate::recursive_backtracker(&mut grid);
+
+        let plain = ImageGridRenderer::new().cell_size(8).border_width(3).render(&grid);
+        let aa = ImageGridRenderer::new()
+            .cell_size(8)
+            .border_width(3)
+            .antialias(4)
+            .render(&grid);
+
+        assert_eq!(aa.dimensions(), plain.dimensions());
+    }
+
+    #[test]
+    fn test_render_openings() {
+        // A 2x3 grid with an entrance on the west of (0,0) and an exit on the south
+        // of (1,2).
+        let mut grid = Grid::new(2, 3);
+        crate::recursive_backtracker(&mut grid);
+        grid.open_boundary(0, GridDirection::West);
+        grid.open_boundary(5, GridDirection::South);
+
+        let white = Rgba([255, 255, 255, 255]);
+        let black = Rgba([0, 0, 0, 255]);
+
+        // Cells are 10 pixels, with borders of 2: cell (i,j) starts at 2 + 12j, 2 + 12i.
+        for image in &[
+            ImageGridRenderer::new()
+                .cell_size(10)
+                .border_width(2)
+                .render(&grid),
+            ImageGridRenderer::new()
+                .cell_size(10)
+                .border_width(2)
+                .antialias(4)
+                .render(&grid),
+        ] {
+            assert_eq!(*image.get_pixel(0, 7), white);
+            assert_eq!(*image.get_pixel(1, 7), white);
+            assert_eq!(*image.get_pixel(0, 19), black);
+            assert_eq!(*image.get_pixel(31, 25), white);
+            assert_eq!(*image.get_pixel(31, 24), white);
+            assert_eq!(*image.get_pixel(19, 25), black);
+        }
+    }
+
+    #[test]
+    fn test_antialias_coverage() {
+        let mut grid = Grid::new(6, 6);
+        crate::recursive_backtracker(&mut grid);
+
+        let plain = ImageGridRenderer::new().cell_size(10).border_width(4).render(&grid);
+        let aa = ImageGridRenderer::new()
+            .cell_size(10)
+            .border_width(4)
+            .antialias(4)
+            .render(&grid);
+
+        // The rounded wall ends cover a little less than the square corner posts.
+        let plain_ink = ink(&plain);
+        let aa_ink = ink(&aa);
+        assert!(aa_ink <= plain_ink);
+        assert!(aa_ink > 0.9 * plain_ink);
+
+        // Some pixels are partially covered.
+        assert!(aa.pixels().any(|p| p[0] > 0 && p[0] < 255));
+    }
+
+    #[test]
+    fn test_render_paged_tiling() {
+        let grid = golden_grid();
+        let mut renderer = ImageGridRenderer::new();
+        renderer.cell_size(10).border_width(1);
+
+        // Room for 5x3 cells per page; with an overlap of 1, the 12 columns need
+        // 1 + ceil((12 - 5) / 4) = 3 pages, and the 8 rows need 1 + ceil((8 - 3) / 2) = 4.
+        let page = (2 * PAGE_MARGIN + 1 + 5 * 11, 2 * PAGE_MARGIN + 1 + 3 * 11);
+        let tiles = renderer.render_paged(&grid, page, 1);
+        assert_eq!(tiles.len(), 12);
+        assert_eq!(tiles[0].cols, 0..5);
+        assert_eq!(tiles[1].cols, 4..9);
+        assert_eq!(tiles[2].cols, 8..12);
+        assert_eq!(tiles[11].page, (3, 2));
+        assert_eq!(tiles[11].rows, 6..8);
+
+        for tile in &tiles {
+            assert!(tile.image.width() <= page.0 && tile.image.height() <= page.1);
+        }
+
+        // Without overlap, the page count is the plain ceiling.
+        assert_eq!(renderer.render_paged(&grid, page, 0).len(), 3 * 3);
+    }
+
+    #[test]
+    fn test_render_paged_reassembles() {
+        let grid = golden_grid();
+        let mut renderer = ImageGridRenderer::new();
+        renderer.cell_size(10).border_width(2);
+        let full = renderer.render(&grid);
+
+        let tiles = renderer.render_paged(&grid, (70, 60), 2);
+        assert!(tiles.len() > 4);
+
+        // Paste each page's content, cropped of its margins, into place; the duplicated
+        // content must agree with what's already there.
+        let mut canvas: RgbaImage = ImageBuffer::new(full.width(), full.height());
+        let mut written = vec![false; (full.width() * full.height()) as usize];
+
+        for tile in &tiles {
+            let x0 = tile.cols.start as u32 * 12;
+            let y0 = tile.rows.start as u32 * 12;
+
+            for y in PAGE_MARGIN..(tile.image.height() - PAGE_MARGIN) {
+                for x in PAGE_MARGIN..(tile.image.width() - PAGE_MARGIN) {
+                    let (fx, fy) = (x0 + x - PAGE_MARGIN, y0 + y - PAGE_MARGIN);
+                    let pixel = *tile.image.get_pixel(x, y);
+                    let index = (fy * full.width() + fx) as usize;
+
+                    if written[index] {
+                        assert_eq!(*canvas.get_pixel(fx, fy), pixel);
+                    }
+                    canvas.put_pixel(fx, fy, pixel);
+                    written[index] = true;
+                }
+            }
+        }
+
+        assert!(written.iter().all(|w| *w));
+        assert_eq!(canvas, full);
+    }
+
+    #[test]
+    fn test_render_paged_registration_marks() {
+        let grid = golden_grid();
+        let tiles = ImageGridRenderer::new().render_paged(&grid, (72, 300), 1);
+        assert_eq!(tiles.len(), 3);
+
+        let black = Rgba([0, 0, 0, 255]);
+        let near = PAGE_MARGIN / 2;
+        let (left, right) = (&tiles[0].image, &tiles[1].image);
+        assert_eq!(left.height(), right.height());
+
+        for y in 0..left.height() {
+            let on_left = *left.get_pixel(left.width() - 1 - near, y) == black;
+            let on_right = *right.get_pixel(near, y) == black;
+            assert_eq!(on_left, on_right);
+        }
+
+        let y = PAGE_MARGIN + (left.height() - 2 * PAGE_MARGIN) / 4;
+        assert_eq!(*right.get_pixel(near, y), black);
+        assert_eq!(*right.get_pixel(near - 1, y), black);
+
+        // No marks on the outer edges.
+        assert_eq!(*left.get_pixel(near, y), Rgba([255, 255, 255, 255]));
+        assert_eq!(
+            *tiles[0].image.get_pixel(left.width() / 2, near),
+            Rgba([255, 255, 255, 255])
+        );
+    }
+
+    #[test]
+    fn test_render_wall_width() {
+        // Cell 0 is linked east to cell 1, and cell 1 south to cell 3.
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 1);
+        grid.link(1, 3);
+
+        // Columns: border 0..3, cell 3..7, wall 7, cell 8..12, border 12..15; and the
+        // same for rows.
+        let mut renderer = ImageGridRenderer::new();
+        renderer.cell_size(4).border_width(3).wall_width(1);
+        let image = renderer.render(&grid);
+        assert_eq!(image.dimensions(), (15, 15));
+
+        let wall = [0, 0, 0, 255];
+        let floor = [255, 255, 255, 255];
+        let at = |x, y| image.get_pixel(x, y).0;
+
+        for k in 0..15 {
+            for b in &[0, 2, 12, 14] {
+                assert_eq!(at(k, *b), wall);
+                assert_eq!(at(*b, k), wall);
+            }
+        }
+        for k in 3..7 {
+            assert_eq!(at(7, k), floor); // Between cells 0 and 1
+            assert_eq!(at(k, 7), wall); // Between cells 0 and 2
+            assert_eq!(at(k + 5, 7), floor); // Between cells 1 and 3
+            assert_eq!(at(7, k + 5), wall); // Between cells 2 and 3
+            assert_eq!(at(k, k), floor);
+            assert_eq!(at(k + 5, k + 5), floor);
+        }
+
+        // The lines meet without gaps, at the T-junctions and in the middle.
+        for (x, y) in &[(7, 7), (7, 2), (7, 12), (2, 7), (12, 7)] {
+            assert_eq!(at(*x, *y), wall);
+        }
+
+        // The same layout holds when antialiasing.
+        let mut smooth = renderer;
+        smooth.antialias(4);
+        assert_eq!(smooth.render(&grid).dimensions(), (15, 15));
+
+        // Pages crop cleanly at the interior walls.
+        let grid = golden_grid();
+        let full = renderer.render(&grid);
+        for tile in renderer.render_paged(&grid, (40, 40), 1) {
+            let x0 = renderer.line(tile.cols.start, grid.num_cols(), 4).start;
+            let y0 = renderer.line(tile.rows.start, grid.num_rows(), 4).start;
+
+            for y in PAGE_MARGIN..(tile.image.height() - PAGE_MARGIN) {
+                for x in PAGE_MARGIN..(tile.image.width() - PAGE_MARGIN) {
+                    let pixel = *full.get_pixel(x0 + x - PAGE_MARGIN, y0 + y - PAGE_MARGIN);
+                    assert_eq!(*tile.image.get_pixel(x, y), pixel);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_gridlines() {
+        // Cell 0 is linked east to cell 1 and south to cell 2; cell 3 is walled off.
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 1);
+        grid.link(0, 2);
+
+        let faint = Rgba([200, 200, 255, 255]);
+        let strong = Rgba([0, 0, 128, 255]);
+        let image = ImageGridRenderer::new()
+            .wall_color(strong)
+            .gridline_color(faint)
+            .render(&grid);
+
+        // Where cells are linked, the boundary is a gridline; elsewhere it's wall.
+        for k in 1..11 {
+            assert_eq!(*image.get_pixel(11, k), faint);
+            assert_eq!(*image.get_pixel(k, 11), faint);
+            assert_eq!(*image.get_pixel(11, 11 + k), strong);
+            assert_eq!(*image.get_pixel(11 + k, 11), strong);
+            assert_eq!(*image.get_pixel(0, k), strong);
+        }
+
+        // The cells' interiors are still floor.
+        assert_eq!(image.get_pixel(5, 5).0, [255, 255, 255, 255]);
+
+        // Without gridlines, the boundary is floor.
+        let image = ImageGridRenderer::new().render(&grid);
+        assert_eq!(image.get_pixel(11, 5).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_render_inset() {
+        // Cell 0 is linked east to cell 1 and south to cell 2; cell 3 is walled off.
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 1);
+        grid.link(0, 2);
+
+        let wall = [0, 0, 0, 255];
+        let floor = [255, 255, 255, 255];
+        let image = ImageGridRenderer::new().inset(3).render(&grid);
+        assert_eq!(image.dimensions(), (23, 23));
+        let at = |x, y| image.get_pixel(x, y).0;
+
+        // Each cell's floor is inset 3 pixels: cells 1..11 and 12..22 have floors 4..8 and
+        // 15..19.
+        for (x, y) in &[(4, 4), (7, 7), (15, 4), (4, 15), (15, 15)] {
+            assert_eq!(at(*x, *y), floor);
+        }
+        for (x, y) in &[(3, 5), (5, 3), (14, 15), (15, 14), (19, 15), (15, 19)] {
+            assert_eq!(at(*x, *y), wall);
+        }
+
+        // There are corridors between linked cells, and none between the others.
+        let corridor = |xs: Range<u32>, ys: Range<u32>| {
+            ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+                .all(|(x, y)| image.get_pixel(x, y).0 == floor)
+        };
+        let solid = |xs: Range<u32>, ys: Range<u32>| {
+            ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+                .all(|(x, y)| image.get_pixel(x, y).0 == wall)
+        };
+
+        assert!(corridor(8..15, 5..7)); // 0 to 1
+        assert!(corridor(5..7, 8..15)); // 0 to 2
+        assert!(solid(19..23, 15..19)); // 3 to the east border
+        assert!(solid(8..15, 15..19)); // 2 to 3
+        assert!(solid(15..19, 8..15)); // 1 to 3
+
+        // The corridors are narrower than the floors: the middle 2 of their 4 pixels.
+        assert!(solid(8..15, 0..5));
+        assert!(solid(8..15, 7..11));
+        assert!(solid(0..5, 8..15));
+        assert!(solid(7..11, 8..15));
+
+        // An inset too large for the cells is reduced to leave some floor: here an inset
+        // of 4, leaving floors and corridors 2 pixels wide, rather than a panic.
+        let image = ImageGridRenderer::new().inset(100).render(&grid);
+        assert_eq!(image.dimensions(), (23, 23));
+        assert_eq!(image.get_pixel(4, 5).0, wall);
+        assert_eq!(image.get_pixel(5, 5).0, floor);
+        assert_eq!(image.get_pixel(6, 6).0, floor);
+        assert_eq!(image.get_pixel(11, 4).0, wall);
+        assert_eq!(image.get_pixel(11, 5).0, floor);
+        assert_eq!(image.get_pixel(11, 7).0, wall);
+
+        // With no inset, the rendering is unchanged.
+        let mut renderer = ImageGridRenderer::new();
+        let plain = renderer.render(&grid);
+        assert_eq!(renderer.inset(0).render(&grid), plain);
+    }
+
+    #[test]
+    fn test_render_channels() {
+        // A bend: cell 0 opens east to cell 1 and south to cell 2; cell 3 is walled off.
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 1);
+        grid.link(0, 2);
+
+        let wall = [0, 0, 0, 255];
+        let floor = [255, 255, 255, 255];
+        let image = ImageGridRenderer::new().channel_mode(0.4).render(&grid);
+        assert_eq!(image.dimensions(), (20, 20));
+
+        // The passages are 4 pixels wide, 3 pixels in from the cell's edges.
+        let row = |y| -> Vec<u32> {
+            (0..20)
+                .filter(|&x| image.get_pixel(x, y).0 == floor)
+                .collect()
+        };
+        let col = |x| -> Vec<u32> {
+            (0..20)
+                .filter(|&y| image.get_pixel(x, y).0 == floor)
+                .collect()
+        };
+
+        assert_eq!(row(5), (3..17).collect::<Vec<_>>());
+        assert_eq!(col(5), (3..17).collect::<Vec<_>>());
+        assert_eq!(row(7), (3..7).collect::<Vec<_>>());
+        assert_eq!(col(7), (3..7).collect::<Vec<_>>());
+        assert!(row(2).is_empty());
+
+        // The corner of the bend is open, and the inside of the bend is solid.
+        for (x, y) in &[(3, 3), (6, 6), (9, 3), (3, 9)] {
+            assert_eq!(image.get_pixel(*x, *y).0, floor);
+        }
+        assert_eq!(image.get_pixel(7, 7).0, wall);
+
+        // Unlinked directions are solid all the way to the cell's edge.
+        for k in 0..10 {
+            assert_eq!(image.get_pixel(k, 2).0, wall);
+            assert_eq!(image.get_pixel(2, k).0, wall);
+            assert_eq!(image.get_pixel(11, 10 + k).0, wall);
+        }
+        assert_eq!(image.get_pixel(15, 8).0, wall);
+        assert_eq!(image.get_pixel(15, 12).0, wall);
+
+        // Cell 3 has only its middle square.
+        assert_eq!(image.get_pixel(15, 15).0, floor);
+        assert_eq!(image.get_pixel(12, 15).0, wall);
+
+        // With a full fraction, every pixel is passage.
+        let image = ImageGridRenderer::new().channel_mode(1.0).render(&grid);
+        assert!(image.pixels().all(|p| p.0 == floor));
+    }
+
+    #[test]
+    fn test_render_fields() {
+        let grid = Grid::new(2, 3);
+        let renderer = ImageGridRenderer::new();
+
+        // The probabilities of the walls, in canonical order.
+        let probs = [0.0, 1.0, 0.0, 0.5, 0.0, 1.0, 1.0];
+        let image = renderer.render_wall_field(2, 3, &probs);
+        assert_eq!(image.dimensions(), (34, 23));
+
+        // Wall 0 is east of cell 0, wall 1 south of it, and wall 3 south of cell 1.
+        assert_eq!(image.get_pixel(11, 5).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(5, 11).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(16, 11).0, [128, 128, 128, 255]);
+        assert_eq!(grid.wall_at(3), Some((1, GridDirection::South)));
+
+        // Cells with the highest value are the darkest; NaN cells are white.  There are no
+        // interior walls.
+        let values = [0.0, 1.5, 3.0, f64::NAN, 1.0, 2.0];
+        let image = renderer.render_cell_field(2, 3, &values);
+        assert_eq!(image.get_pixel(5, 5).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(27, 5).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(5, 16).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(16, 11).0, image.get_pixel(16, 5).0);
+        assert_ne!(image.get_pixel(16, 5).0, image.get_pixel(16, 16).0);
+    }
 }
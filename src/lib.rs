@@ -1,22 +1,119 @@
 //! A library for generating and rendering and working with mazes.  The code is inspired
 //! by _Mazes for Programmers_ by Jamis Buck, but isn't a straightforward translation.
+//!
+//! # Example
+//!
+//! Generate a maze, solve it, and render it as text and as an image:
+//!
+//! ```
+//! use mazegen::*;
+//!
+//! // Generate a maze.  Every algorithm produces a perfect maze: there is exactly one
+//! // path between any two cells.
+//! let mut grid = Grid::new(10, 15);
+//! recursive_backtracker(&mut grid);
+//! assert!(grid.is_perfect_maze());
+//!
+//! // Solve it: find the path from the top-left corner to the bottom-right corner.
+//! let start = grid.cell(0, 0);
+//! let goal = grid.cell(9, 14);
+//! let path = grid.shortest_path(start, goal);
+//! assert_eq!(path.first(), Some(start));
+//! assert_eq!(path.last(), Some(goal));
+//! assert_eq!(path.len(), grid.distances(start)[goal].unwrap() + 1);
+//!
+//! // Render it as text, marking the path.  Each row of cells is a line of text, with a
+//! // line of borders above and below.
+//! let text = TextGridRenderer::new()
+//!     .render_with(&grid, |c| if path.contains(c) { Some("*") } else { None });
+//! assert_eq!(text.lines().count(), 2 * 10 + 1);
+//! assert_eq!(text.matches('*').count(), path.len());
+//!
+//! // Render it as an image, shading each cell by its distance from the start.
+//! let dists = grid.distances(start);
+//! let image = ImageGridRenderer::new()
+//!     .cell_size(10)
+//!     .border_width(2)
+//!     .render_with(&grid, |c| dists[c].map(|d| d as i64));
+//! assert_eq!(image.dimensions(), (2 * 16 + 10 * 15, 2 * 11 + 10 * 10));
+//! ```
+pub use crate::algorithm::*;
+pub use crate::banner::*;
+pub use crate::bundle::*;
+pub use crate::cell_path::*;
+pub use crate::constraints::*;
+pub use crate::ensemble::*;
+pub use crate::fair_starts::*;
 pub use crate::grid::*;
+pub use crate::grid3d::*;
+pub use crate::grid3d_renderer::*;
 pub use crate::grid_dir::*;
+pub use crate::grid_pool::*;
+pub use crate::hex_grid::*;
+pub use crate::hex_grid_renderer::*;
 pub use crate::image_grid_renderer::*;
 pub use crate::mask::*;
+pub use crate::maze_generator::*;
+pub use crate::maze_grid::*;
 pub use crate::pixel::*;
+pub use crate::polar_grid::*;
+pub use crate::polar_grid_renderer::*;
+pub use crate::text_chart::*;
 pub use crate::text_grid_renderer::*;
+pub use crate::theme::*;
+pub use crate::tri_grid::*;
+pub use crate::tri_grid_renderer::*;
+pub use crate::validation::*;
+pub use crate::weave_grid::*;
+pub use crate::weave_grid_renderer::*;
+pub use crate::wide_corridor::*;
+use crate::union_find::UnionFind;
+use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng};
+use std::collections::BTreeSet;
 
+mod algorithm;
+mod banner;
+mod bundle;
+mod cell_path;
+mod constraints;
+mod ensemble;
+mod fair_starts;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod grid;
+mod grid3d;
+mod grid3d_renderer;
 mod grid_dir;
+#[cfg(feature = "petgraph")]
+mod graph_interop;
+mod grid_pool;
+mod hex_grid;
+mod hex_grid_renderer;
 mod mask;
+mod maze_generator;
+mod maze_grid;
+mod molt_ensemble;
 mod image_grid_renderer;
+pub mod molt_generator;
 pub mod molt_grid;
 pub mod molt_image;
+mod molt_install;
+pub mod molt_maze;
 pub mod molt_rand;
 mod pixel;
+mod polar_grid;
+mod polar_grid_renderer;
+mod text_chart;
 mod text_grid_renderer;
+mod theme;
+mod tri_grid;
+mod tri_grid_renderer;
+mod union_find;
+mod validation;
+mod weave_grid;
+mod weave_grid_renderer;
+mod wide_corridor;
 
 /// A Cell ID.
 ///
@@ -25,103 +122,425 @@ mod text_grid_renderer;
 /// `Grid` provides a conversion between Cells and (i,j) row/column pairs.
 pub type Cell = usize;
 
-/// Algorithm to produce a Grid containing a binary-tree maze
+/// Algorithm to produce a Grid containing a binary-tree maze.  Any existing links are
+/// cleared first.
+///
+/// Each cell is linked to its northern or eastern neighbor, chosen at random, so the top
+/// row and the rightmost column are always unbroken corridors.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(8, 10);
+/// binary_tree_maze(&mut grid);
+/// assert!(grid.is_perfect_maze());
+///
+/// for j in 0..grid.num_cols() - 1 {
+///     assert!(grid.is_linked_east(grid.cell(0, j)));
+/// }
+/// for i in 1..grid.num_rows() {
+///     assert!(grid.is_linked_north(grid.cell(i, grid.num_cols() - 1)));
+/// }
+/// ```
 pub fn binary_tree_maze(grid: &mut Grid) {
-    grid.clear();
+    binary_tree_maze_rng(grid, &mut thread_rng());
+}
 
-    for cell in 0..grid.num_cells() {
-        let mut neighbors = Vec::new();
+/// Binary-tree maze algorithm, using the given random number generator.  See
+/// `binary_tree_maze`.
+pub fn binary_tree_maze_rng(grid: &mut Grid, rng: &mut impl Rng) {
+    binary_tree_maze_with_rng(grid, GridDirection::North, GridDirection::East, rng)
+        .expect("perpendicular directions");
+}
 
-        if let Some(ncell) = grid.north_of(cell) {
-            neighbors.push(ncell);
-        }
+/// Binary-tree maze algorithm, linking each cell in one of the two given directions
+/// rather than to the north or east, so that the maze's texture can flow toward any
+/// corner.  The boundary row or column in each direction is an unbroken corridor.  Any
+/// existing links are cleared first.  Returns an error, leaving the grid unchanged, if
+/// the directions aren't perpendicular.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(8, 10);
+/// binary_tree_maze_with(&mut grid, GridDirection::South, GridDirection::West).unwrap();
+/// assert!(grid.is_perfect_maze());
+///
+/// // The bottom row and the leftmost column are unbroken corridors.
+/// for j in 1..grid.num_cols() {
+///     assert!(grid.is_linked_west(grid.cell(7, j)));
+/// }
+/// for i in 0..grid.num_rows() - 1 {
+///     assert!(grid.is_linked_south(grid.cell(i, 0)));
+/// }
+///
+/// assert!(binary_tree_maze_with(&mut grid, GridDirection::North, GridDirection::South).is_err());
+/// ```
+pub fn binary_tree_maze_with(
+    grid: &mut Grid,
+    vertical: GridDirection,
+    horizontal: GridDirection,
+) -> Result<(), String> {
+    binary_tree_maze_with_rng(grid, vertical, horizontal, &mut thread_rng())
+}
 
-        if let Some(ecell) = grid.east_of(cell) {
-            neighbors.push(ecell);
-        }
+/// Binary-tree maze algorithm with the given directions, using the given random number
+/// generator.  See `binary_tree_maze_with`.
+pub fn binary_tree_maze_with_rng(
+    grid: &mut Grid,
+    vertical: GridDirection,
+    horizontal: GridDirection,
+    rng: &mut impl Rng,
+) -> Result<(), String> {
+    check_perpendicular(vertical, horizontal)?;
+    grid.clear();
 
-        if !neighbors.is_empty() {
-            grid.link(cell, sample(&neighbors));
+    for cell in 0..grid.num_cells() {
+        let dirs: Vec<GridDirection> = [vertical, horizontal]
+            .iter()
+            .copied()
+            .filter(|dir| grid.cell_to(cell, *dir).is_some())
+            .collect();
+
+        if let Some(dir) = sample_rng(&dirs, rng) {
+            grid.link_direction(cell, dir);
         }
     }
+
+    Ok(())
 }
 
-/// Algorithm to produce a Grid containing a sidewinder maze
+/// Algorithm to produce a Grid containing a sidewinder maze.  Any existing links are
+/// cleared first.
+///
+/// Each row is divided into runs of linked cells, and one cell in each run is linked to the
+/// north.  The top row is always a single unbroken corridor.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(8, 10);
+/// sidewinder_maze(&mut grid);
+/// assert!(grid.is_perfect_maze());
+///
+/// for j in 0..grid.num_cols() - 1 {
+///     assert!(grid.is_linked_east(grid.cell(0, j)));
+/// }
+/// ```
 pub fn sidewinder_maze(grid: &mut Grid) {
+    sidewinder_maze_rng(grid, &mut thread_rng());
+}
+
+/// Sidewinder maze algorithm, using the given random number generator.  See
+/// `sidewinder_maze`.
+pub fn sidewinder_maze_rng(grid: &mut Grid, rng: &mut impl Rng) {
+    sidewinder_maze_with_rng(grid, GridDirection::East, GridDirection::North, rng)
+        .expect("perpendicular directions");
+}
+
+/// Sidewinder maze algorithm, with the runs going in the `run_dir` direction and one
+/// cell in each run linked in the `carve_dir` direction, rather than east and north.  The
+/// boundary row or column in the `carve_dir` direction is a single unbroken corridor.
+/// Any existing links are cleared first.  Returns an error, leaving the grid unchanged, if
+/// the directions aren't perpendicular.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(8, 10);
+/// sidewinder_maze_with(&mut grid, GridDirection::South, GridDirection::East).unwrap();
+/// assert!(grid.is_perfect_maze());
+///
+/// // The rightmost column is an unbroken corridor.
+/// for i in 0..grid.num_rows() - 1 {
+///     assert!(grid.is_linked_south(grid.cell(i, 9)));
+/// }
+///
+/// assert!(sidewinder_maze_with(&mut grid, GridDirection::East, GridDirection::West).is_err());
+/// ```
+pub fn sidewinder_maze_with(
+    grid: &mut Grid,
+    run_dir: GridDirection,
+    carve_dir: GridDirection,
+) -> Result<(), String> {
+    sidewinder_maze_with_rng(grid, run_dir, carve_dir, &mut thread_rng())
+}
+
+/// Sidewinder maze algorithm with the given directions, using the given random number
+/// generator.  See `sidewinder_maze_with`.
+pub fn sidewinder_maze_with_rng(
+    grid: &mut Grid,
+    run_dir: GridDirection,
+    carve_dir: GridDirection,
+    rng: &mut impl Rng,
+) -> Result<(), String> {
+    check_perpendicular(run_dir, carve_dir)?;
     grid.clear();
 
-    for i in 0..grid.num_rows() {
+    // FIRST, get the lines of cells along which the runs go, each in run order.
+    let (rows, cols) = (grid.num_rows(), grid.num_cols());
+    let lines: Vec<Vec<Cell>> = if run_dir.is_horizontal() {
+        (0..rows)
+            .map(|i| (0..cols).map(|j| grid.cell(i, j)).collect())
+            .collect()
+    } else {
+        (0..cols)
+            .map(|j| (0..rows).map(|i| grid.cell(i, j)).collect())
+            .collect()
+    };
+
+    // NEXT, carve each line.
+    for mut line in lines {
+        if run_dir == GridDirection::West || run_dir == GridDirection::North {
+            line.reverse();
+        }
+
         let mut run = Vec::new();
 
-        for j in 0..grid.num_cols() {
-            let cell = grid.cell(i, j);
+        for cell in line {
             run.push(cell);
 
-            let at_eastern_boundary = grid.east_of(cell).is_none();
-            let at_northern_boundary = grid.north_of(cell).is_none();
-            let should_close_out = at_eastern_boundary || (!at_northern_boundary && !flip());
+            let at_run_boundary = grid.cell_to(cell, run_dir).is_none();
+            let at_carve_boundary = grid.cell_to(cell, carve_dir).is_none();
+            let should_close_out = at_run_boundary || (!at_carve_boundary && !flip_rng(rng));
 
             if should_close_out {
-                let member = sample(&run);
-                if let Some(ncell) = grid.north_of(member) {
-                    grid.link(member, ncell);
-                }
+                let member = sample_rng(&run, rng).expect("non-empty run");
+                grid.link_direction(member, carve_dir);
                 run.clear();
             } else {
-                grid.link(cell, grid.east_of(cell).expect("a cell"));
+                grid.link_direction(cell, run_dir);
             }
         }
     }
+
+    Ok(())
 }
 
-/// Hunt-and-Kill maze algorithm
+/// Returns an error unless the two directions are perpendicular.
+fn check_perpendicular(dir1: GridDirection, dir2: GridDirection) -> Result<(), String> {
+    if dir1.is_perpendicular(dir2) {
+        Ok(())
+    } else {
+        Err(format!(
+            "expected perpendicular directions, got {} and {}",
+            dir1, dir2
+        ))
+    }
+}
+
+/// Hunt-and-Kill maze algorithm.  Any existing links are cleared first.
+///
+/// Performs a random walk through unvisited cells; when the walk reaches a dead end, it
+/// "hunts" for an unvisited cell next to a visited one, and continues from there.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(6, 6);
+///
+/// // Existing links are discarded.
+/// grid.link(0, 1);
+/// grid.link(1, 7);
+/// grid.link(7, 6);
+/// grid.link(6, 0);
+/// assert!(!grid.is_perfect_maze());
+///
+/// hunt_and_kill(&mut grid);
+/// assert!(grid.is_perfect_maze());
+/// ```
 pub fn hunt_and_kill(grid: &mut Grid) {
+    hunt_and_kill_rng(grid, &mut thread_rng());
+}
+
+/// Hunt-and-Kill maze algorithm, using the given random number generator.  See
+/// `hunt_and_kill`.  Works with any kind of grid.
+pub fn hunt_and_kill_rng<G: MazeGrid>(grid: &mut G, rng: &mut impl Rng) {
     grid.clear();
 
+    // The hunt looks for the first unvisited cell, in order of cell ID, that has a
+    // visited neighbor.  Rather than rescanning the grid for it, which makes the
+    // algorithm quadratic, keep the set of such cells up to date as cells are visited.
+    let mut visited = vec![false; grid.num_cells()];
+    let mut frontier: BTreeSet<Cell> = BTreeSet::new();
+
     // FIRST, Pick a random starting point.
-    let mut current: Cell = thread_rng().gen_range(0, grid.num_cells());
+    let mut current: Cell = grid.random_cell_with(rng);
 
-    while current != grid.num_cells() {
+    loop {
         let unvisited_neighbors: Vec<Cell> = grid
             .neighbors(current)
             .into_iter()
-            .filter(|c| grid.links(*c).is_empty())
+            .filter(|n| !visited[*n])
             .collect();
 
-        if !unvisited_neighbors.is_empty() {
+        if let Some(neighbor) = sample_rng(&unvisited_neighbors, rng) {
             // Pick an unvisited neighbor as a random walk.
-            let neighbor = sample(&unvisited_neighbors);
             grid.link(current, neighbor);
+            visit(grid, current, &mut visited, &mut frontier);
+            visit(grid, neighbor, &mut visited, &mut frontier);
             current = neighbor;
+        } else if let Some(cell) = frontier.iter().next().copied() {
+            // Hunter Block
+            let visited_neighbors: Vec<Cell> = grid
+                .neighbors(cell)
+                .into_iter()
+                .filter(|n| visited[*n])
+                .collect();
+            let neighbor = sample_rng(&visited_neighbors, rng).expect("visited neighbor");
+
+            grid.link(cell, neighbor);
+            visit(grid, cell, &mut visited, &mut frontier);
+            current = cell;
         } else {
-            // Sentinal value: use this to indicate nothing more to do.
-            current = grid.num_cells();
+            break;
+        }
+    }
+}
+
+/// Marks the cell visited for `hunt_and_kill_rng`, moving its unvisited neighbors into
+/// the hunt's frontier.
+fn visit<G: MazeGrid>(
+    grid: &G,
+    cell: Cell,
+    visited: &mut [bool],
+    frontier: &mut BTreeSet<Cell>,
+) {
+    if visited[cell] {
+        return;
+    }
+
+    visited[cell] = true;
+    frontier.remove(&cell);
+
+    for other in grid.neighbors(cell) {
+        if !visited[other] {
+            frontier.insert(other);
+        }
+    }
+}
+
+/// Hunt-and-Kill maze algorithm subject to constraints on the walls.  Any existing links
+/// are cleared first.
+///
+/// The required links are carved first, and neither the walk nor the hunt ever carves a
+/// forbidden link; the result is a perfect maze among the allowed links.  Returns an
+/// error, leaving the grid unchanged, if the constraints can't be satisfied; see
+/// `Constraints::check`.
+pub fn hunt_and_kill_constrained(grid: &mut Grid, constraints: &Constraints) -> Result<(), String> {
+    hunt_and_kill_constrained_rng(grid, constraints, &mut thread_rng())
+}
+
+/// Hunt-and-Kill maze algorithm subject to constraints on the walls, using the given random
+/// number generator.  See `hunt_and_kill_constrained`.
+pub fn hunt_and_kill_constrained_rng(
+    grid: &mut Grid,
+    constraints: &Constraints,
+    rng: &mut impl Rng,
+) -> Result<(), String> {
+    let forbidden = constraints.apply(grid)?;
+    let allowed = |a: Cell, b: Cell| !forbidden.contains(&(a.min(b), a.max(b)));
+
+    // FIRST, the required links join cells into regions; entering a cell visits its
+    // whole region.
+    let (region, members) = linked_regions(grid);
+    let mut visited = vec![false; grid.num_cells()];
+
+    let enter = |cell: Cell, visited: &mut Vec<bool>| {
+        for &other in &members[region[cell]] {
+            visited[other] = true;
+        }
+    };
+
+    // NEXT, pick a random starting point.
+    let start = grid.random_cell_with(rng);
+    enter(start, &mut visited);
+    let mut current = Some(start);
+
+    while let Some(cell) = current {
+        let unvisited_neighbors: Vec<Cell> = grid
+            .neighbors(cell)
+            .into_iter()
+            .filter(|n| !visited[*n] && allowed(cell, *n))
+            .collect();
+
+        if let Some(neighbor) = sample_rng(&unvisited_neighbors, rng) {
+            // Walk to an unvisited neighbor.
+            grid.link(cell, neighbor);
+            enter(neighbor, &mut visited);
+            current = Some(neighbor);
+        } else {
+            // Hunt for an unvisited cell next to a visited one.
+            current = None;
 
-            // Hunter Block
             for cell in 0..grid.num_cells() {
+                if visited[cell] {
+                    continue;
+                }
+
                 let visited_neighbors: Vec<Cell> = grid
                     .neighbors(cell)
                     .into_iter()
-                    .filter(|c| !grid.links(*c).is_empty())
+                    .filter(|n| visited[*n] && allowed(cell, *n))
                     .collect();
 
-                if grid.links(cell).is_empty() && !visited_neighbors.is_empty() {
-                    current = cell;
-                    let neighbor = sample(&visited_neighbors);
-                    grid.link(current, neighbor);
+                if let Some(neighbor) = sample_rng(&visited_neighbors, rng) {
+                    grid.link(cell, neighbor);
+                    enter(cell, &mut visited);
+                    current = Some(cell);
                     break;
                 }
             }
         }
     }
+
+    Ok(())
 }
 
-/// Recursive Backtracker maze algorithm
+/// Recursive Backtracker maze algorithm.  Any existing links are cleared first.
+///
+/// Performs a random walk through unvisited cells, backtracking when it reaches a dead end.
+/// The resulting mazes have long, twisty corridors and relatively few dead ends.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(10, 10);
+/// recursive_backtracker(&mut grid);
+/// assert!(grid.is_perfect_maze());
+/// assert_eq!(grid.num_links(), grid.num_cells() - 1);
+///
+/// // Every cell is reachable, and the longest path visits at least one row's worth of
+/// // cells.
+/// assert!(grid.distances(0).iter().all(|d| d.is_some()));
+/// assert!(grid.longest_path().len() >= grid.num_cols());
+/// ```
 pub fn recursive_backtracker(grid: &mut Grid) {
+    recursive_backtracker_rng(grid, &mut thread_rng());
+}
+
+/// Recursive Backtracker maze algorithm, using the given random number generator.  See
+/// `recursive_backtracker`.  Works with any kind of grid.
+///
+/// ```
+/// use mazegen::*;
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+///
+/// // The same seed always produces the same maze.
+/// let mut grid1 = Grid::new(10, 10);
+/// recursive_backtracker_rng(&mut grid1, &mut StdRng::seed_from_u64(42));
+///
+/// let mut grid2 = Grid::new(10, 10);
+/// recursive_backtracker_rng(&mut grid2, &mut StdRng::seed_from_u64(42));
+///
+/// assert_eq!(grid1, grid2);
+/// ```
+pub fn recursive_backtracker_rng<G: MazeGrid>(grid: &mut G, rng: &mut impl Rng) {
     grid.clear();
 
     // FIRST, pick a random starting point.
-    let mut current: Cell = thread_rng().gen_range(0, grid.num_cells());
+    let mut current: Cell = grid.random_cell_with(rng);
 
     // NEXT, create the stack to control execution.
     let mut stack: Vec<Cell> = Vec::new();
@@ -133,38 +552,974 @@ pub fn recursive_backtracker(grid: &mut Grid) {
         current = stack.last().copied().expect("non-empty stack");
 
         // Get all neighbors of the current cell that have no links.
+        let neighbors = grid.unvisited_neighbors(current);
+
+        // Carve a link to one of them, and continue from there.  If there are none, we're
+        // done here.
+        if let Some(neighbor) = sample_rng(&neighbors, rng) {
+            grid.link(current, neighbor);
+            stack.push(neighbor);
+        } else {
+            stack.pop();
+        }
+    }
+}
+
+/// Recursive Backtracker maze algorithm that preserves existing links, e.g., rooms carved
+/// by `Grid::carve_room`.
+///
+/// Cells that are already linked together are treated as a single region: when the walk
+/// reaches any cell of a region, it links to it and continues from all of the region's
+/// cells.  The result is connected, but isn't a perfect maze if the existing links contain
+/// loops.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(10, 10);
+/// grid.carve_room(2, 2, 3, 4);
+/// recursive_backtracker_preserve(&mut grid);
+///
+/// assert!(grid.is_connected());
+/// assert!(grid.is_linked_east(grid.cell(2, 2)));
+/// ```
+pub fn recursive_backtracker_preserve(grid: &mut Grid) {
+    recursive_backtracker_preserve_rng(grid, &mut thread_rng());
+}
+
+/// Recursive Backtracker maze algorithm that preserves existing links, using the given
+/// random number generator.  See `recursive_backtracker_preserve`.  Works with any kind of
+/// grid.
+pub fn recursive_backtracker_preserve_rng<G: MazeGrid>(grid: &mut G, rng: &mut impl Rng) {
+    backtrack_regions(grid, rng, |_, _| true);
+}
+
+/// Recursive Backtracker maze algorithm subject to constraints on the walls.  Any existing
+/// links are cleared first.
+///
+/// The required links are carved first, and the walk never carves a forbidden link; the
+/// result is a perfect maze among the allowed links.  Returns an error, leaving the grid
+/// unchanged, if the constraints can't be satisfied; see `Constraints::check`.
+pub fn recursive_backtracker_constrained(
+    grid: &mut Grid,
+    constraints: &Constraints,
+) -> Result<(), String> {
+    recursive_backtracker_constrained_rng(grid, constraints, &mut thread_rng())
+}
+
+/// Recursive Backtracker maze algorithm subject to constraints on the walls, using the
+/// given random number generator.  See `recursive_backtracker_constrained`.
+pub fn recursive_backtracker_constrained_rng(
+    grid: &mut Grid,
+    constraints: &Constraints,
+    rng: &mut impl Rng,
+) -> Result<(), String> {
+    let forbidden = constraints.apply(grid)?;
+    backtrack_regions(grid, rng, |a, b| !forbidden.contains(&(a.min(b), a.max(b))));
+    Ok(())
+}
+
+/// Finds the regions of cells that are already linked together.  Returns the region ID of
+/// each cell, and the cells in each region.
+fn linked_regions<G: MazeGrid>(grid: &G) -> (Vec<usize>, Vec<Vec<Cell>>) {
+    let num_cells = grid.num_cells();
+    let mut region: Vec<Option<usize>> = vec![None; num_cells];
+    let mut members: Vec<Vec<Cell>> = Vec::new();
+
+    for start in 0..num_cells {
+        if region[start].is_some() {
+            continue;
+        }
+
+        let id = members.len();
+        let mut cells = vec![start];
+        region[start] = Some(id);
+        let mut next = 0;
+
+        while next < cells.len() {
+            for other in grid.links(cells[next]) {
+                if region[other].is_none() {
+                    region[other] = Some(id);
+                    cells.push(other);
+                }
+            }
+            next += 1;
+        }
+
+        members.push(cells);
+    }
+
+    let region = region.into_iter().map(|r| r.expect("region")).collect();
+    (region, members)
+}
+
+/// Runs the recursive backtracker over the regions of already-linked cells, carving only
+/// links between neighbors for which `allowed` returns true.
+fn backtrack_regions<G, F>(grid: &mut G, rng: &mut impl Rng, allowed: F)
+where
+    G: MazeGrid,
+    F: Fn(Cell, Cell) -> bool,
+{
+    // FIRST, find the regions of cells that are already linked together.
+    let (region, members) = linked_regions(grid);
+
+    // NEXT, entering a cell visits its whole region; the region's other cells go on the
+    // stack beneath it, so that the walk can continue from them later.
+    let mut visited = vec![false; grid.num_cells()];
+    let mut stack: Vec<Cell> = Vec::new();
+
+    let enter = |cell: Cell, visited: &mut Vec<bool>, stack: &mut Vec<Cell>| {
+        for &other in &members[region[cell]] {
+            visited[other] = true;
+            if other != cell {
+                stack.push(other);
+            }
+        }
+        stack.push(cell);
+    };
+
+    // NEXT, pick a random starting point.
+    enter(grid.random_cell_with(rng), &mut visited, &mut stack);
+
+    // NEXT, carve into unvisited neighbors, backtracking until there are none.
+    while let Some(&current) = stack.last() {
         let neighbors: Vec<Cell> = grid
             .neighbors(current)
             .into_iter()
-            .filter(|n| grid.links(*n).is_empty())
+            .filter(|n| !visited[*n] && allowed(current, *n))
             .collect();
 
-        // If there are none, we're done here.  Otherwise, carve a link to one of them, and
-        // continue from there.
-        if neighbors.is_empty() {
+        if let Some(neighbor) = sample_rng(&neighbors, rng) {
+            grid.link(current, neighbor);
+            enter(neighbor, &mut visited, &mut stack);
+        } else {
             stack.pop();
+        }
+    }
+}
+
+/// Multi-origin Recursive Backtracker maze algorithm.  Any existing links are cleared
+/// first.
+///
+/// Grows a backtracker region from each of `seeds` random starting cells, taking turns,
+/// until every cell belongs to some region; then stitches the regions together with one
+/// link between each pair of regions that need joining.  The result is a perfect maze
+/// with recognizable territories.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(20, 20);
+/// multi_origin_backtracker(&mut grid, 5);
+/// assert!(grid.is_perfect_maze());
+/// ```
+pub fn multi_origin_backtracker(grid: &mut Grid, seeds: usize) {
+    multi_origin_backtracker_rng(grid, seeds, &mut thread_rng());
+}
+
+/// Multi-origin Recursive Backtracker maze algorithm, using the given random number
+/// generator.  See `multi_origin_backtracker`.
+pub fn multi_origin_backtracker_rng(grid: &mut Grid, seeds: usize, rng: &mut impl Rng) {
+    grid.clear();
+
+    // FIRST, start a region at each seed.
+    let mut region: Vec<Option<usize>> = vec![None; grid.num_cells()];
+    let mut stacks: Vec<Vec<Cell>> = Vec::new();
+
+    for (id, seed) in grid.seed_points(seeds.max(1), rng).into_iter().enumerate() {
+        region[seed] = Some(id);
+        stacks.push(vec![seed]);
+    }
+
+    // NEXT, grow the regions in turn, one backtracker step each, until none can grow.
+    while stacks.iter().any(|s| !s.is_empty()) {
+        for (id, stack) in stacks.iter_mut().enumerate() {
+            let current = match stack.last() {
+                Some(&cell) => cell,
+                None => continue,
+            };
+
+            let neighbors: Vec<Cell> = grid
+                .neighbors(current)
+                .into_iter()
+                .filter(|n| region[*n].is_none())
+                .collect();
+
+            if let Some(neighbor) = sample_rng(&neighbors, rng) {
+                grid.link(current, neighbor);
+                region[neighbor] = Some(id);
+                stack.push(neighbor);
+            } else {
+                stack.pop();
+            }
+        }
+    }
+
+    // NEXT, find the walls between regions, in random order.
+    let mut walls: Vec<(Cell, Cell)> = Vec::new();
+
+    for cell in 0..grid.num_cells() {
+        for other in grid.neighbors(cell) {
+            if cell < other && region[cell] != region[other] {
+                walls.push((cell, other));
+            }
+        }
+    }
+
+    walls.shuffle(rng);
+
+    // FINALLY, knock down walls between regions that aren't yet joined.  The joined
+    // regions are tracked as a union-find forest.
+    let mut joined = UnionFind::new(stacks.len());
+
+    for (a, b) in walls {
+        if joined.join(region[a].expect("region"), region[b].expect("region")) {
+            grid.link(a, b);
+        }
+    }
+}
+
+/// Symmetric maze algorithm: carves a maze into the left half of the grid with the
+/// Recursive Backtracker and mirrors it onto the right half, producing a maze that is
+/// symmetric about the vertical center line.  The halves are joined by a single passage
+/// across the center, at a random row; in a grid with an odd number of columns, the
+/// center column is a single north-south corridor joined to both halves.  Either way the
+/// result is a perfect maze.  Any existing links are cleared first.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(8, 10);
+/// symmetric_maze(&mut grid);
+/// assert!(grid.is_perfect_maze());
+///
+/// // The first cell's passages mirror the last cell in the row's.
+/// let (left, right) = (grid.cell(0, 0), grid.cell(0, 9));
+/// assert_eq!(grid.is_linked_east(left), grid.is_linked_west(right));
+/// ```
+pub fn symmetric_maze(grid: &mut Grid) {
+    symmetric_maze_rng(grid, &mut thread_rng());
+}
+
+/// Symmetric maze algorithm, using the given random number generator.  See
+/// `symmetric_maze`.
+pub fn symmetric_maze_rng(grid: &mut Grid, rng: &mut impl Rng) {
+    grid.clear();
+
+    let rows = grid.num_rows();
+    let cols = grid.num_cols();
+    let half = cols / 2;
+    let center = rng.gen_range(0, rows);
+
+    // FIRST, carve the left half and copy it into both halves of the grid.
+    if half > 0 {
+        let mut left = Grid::new(rows, half);
+        recursive_backtracker_rng(&mut left, rng);
+
+        for cell in 0..left.num_cells() {
+            let (i, j) = left.ij(cell);
+            for other in left.links(cell) {
+                let (i2, j2) = left.ij(other);
+                if cell < other {
+                    grid.link(grid.cell(i, j), grid.cell(i2, j2));
+                    grid.link(grid.cell(i, cols - 1 - j), grid.cell(i2, cols - 1 - j2));
+                }
+            }
+        }
+    }
+
+    // NEXT, join the halves across the center.
+    if cols % 2 == 1 {
+        for i in 1..rows {
+            grid.link(grid.cell(i - 1, half), grid.cell(i, half));
+        }
+        if half > 0 {
+            grid.link(grid.cell(center, half - 1), grid.cell(center, half));
+            grid.link(grid.cell(center, half), grid.cell(center, half + 1));
+        }
+    } else if half > 0 {
+        grid.link(grid.cell(center, half - 1), grid.cell(center, half));
+    }
+}
+
+/// Dungeon maze algorithm, for roguelike dungeons: places up to `room_count` random
+/// rectangular rooms, none of which overlap or touch, fills the rest of the grid with a
+/// maze, and gives each room one or two doors.  Then, if `braid` is greater than 0.0,
+/// braids the maze with that probability, removing dead ends; see `Grid::braid`.  Any
+/// existing links are cleared first.
+///
+/// Rooms are at least 2x2 cells, if the grid is that large, and at most a third of the
+/// grid's height and width.  Fewer than `room_count` rooms are placed if they don't fit.
+/// Returns the rooms as (top, left, height, width) tuples, as for `Grid::carve_room`.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(20, 30);
+/// let rooms = dungeon_maze(&mut grid, 4, 0.5);
+///
+/// assert!(!rooms.is_empty());
+/// assert!(grid.is_connected());
+/// ```
+pub fn dungeon_maze(
+    grid: &mut Grid,
+    room_count: usize,
+    braid: f64,
+) -> Vec<(usize, usize, usize, usize)> {
+    dungeon_maze_rng(grid, room_count, braid, &mut thread_rng())
+}
+
+/// Dungeon maze algorithm, using the given random number generator.  See `dungeon_maze`.
+pub fn dungeon_maze_rng(
+    grid: &mut Grid,
+    room_count: usize,
+    braid: f64,
+    rng: &mut impl Rng,
+) -> Vec<(usize, usize, usize, usize)> {
+    const TRIES_PER_ROOM: usize = 20;
+    grid.clear();
+
+    // FIRST, place the rooms, keeping a one-cell gap between them.
+    let (rows, cols) = (grid.num_rows(), grid.num_cols());
+    let (min_h, min_w) = (2.min(rows), 2.min(cols));
+    let (max_h, max_w) = ((rows / 3).max(min_h), (cols / 3).max(min_w));
+    let mut rooms: Vec<(usize, usize, usize, usize)> = Vec::new();
+
+    for _ in 0..room_count * TRIES_PER_ROOM {
+        if rooms.len() == room_count {
+            break;
+        }
+
+        let height = rng.gen_range(min_h, max_h + 1);
+        let width = rng.gen_range(min_w, max_w + 1);
+        let top = rng.gen_range(0, rows - height + 1);
+        let left = rng.gen_range(0, cols - width + 1);
+
+        let clear = rooms.iter().all(|&(t, l, h, w)| {
+            top > t + h || t > top + height || left > l + w || l > left + width
+        });
+
+        if clear {
+            grid.carve_room(top, left, height, width);
+            rooms.push((top, left, height, width));
+        }
+    }
+
+    // NEXT, carve the maze around the rooms.  The walk enters each room once, which gives
+    // it its first door.
+    recursive_backtracker_preserve_rng(grid, rng);
+
+    // NEXT, give about half of the rooms a second door.
+    for &(top, left, height, width) in &rooms {
+        if !flip_rng(rng) {
+            continue;
+        }
+
+        let inside = |c: Cell| {
+            let (i, j) = grid.ij(c);
+            i >= top && i < top + height && j >= left && j < left + width
+        };
+
+        let mut walls = Vec::new();
+        for i in top..top + height {
+            for j in left..left + width {
+                let cell = grid.cell(i, j);
+                for other in grid.neighbors(cell) {
+                    if !inside(other) && !grid.is_linked(cell, other) {
+                        walls.push((cell, other));
+                    }
+                }
+            }
+        }
+
+        if let Some((cell, other)) = sample_rng(&walls, rng) {
+            grid.link(cell, other);
+        }
+    }
+
+    // FINALLY, braid the maze.
+    if braid > 0.0 {
+        grid.braid_rng(braid, rng);
+    }
+
+    rooms
+}
+
+/// Recursive Division maze algorithm: links every cell, and then recursively divides the
+/// grid into two regions with a wall, leaving a single passage through it, until the
+/// regions are a single row or column wide.  It's the only one of the common algorithms
+/// that adds walls rather than carving passages, and gives a distinctively boxy maze.
+/// Any existing links are cleared first.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(10, 12);
+/// recursive_division(&mut grid);
+/// assert!(grid.is_perfect_maze());
+/// ```
+pub fn recursive_division(grid: &mut Grid) {
+    recursive_division_rng(grid, &mut thread_rng());
+}
+
+/// Recursive Division maze algorithm, using the given random number generator.  See
+/// `recursive_division`.
+pub fn recursive_division_rng(grid: &mut Grid, rng: &mut impl Rng) {
+    recursive_division_with_rooms_rng(grid, 0, 0.0, rng);
+}
+
+/// Recursive Division maze algorithm with rooms, e.g., for dungeon maps: as for
+/// `recursive_division`, but a region no more than `max_room_size` cells high and wide is
+/// left undivided, as an open room, with probability `room_probability`.  Any existing
+/// links are cleared first.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(20, 20);
+/// recursive_division_with_rooms(&mut grid, 4, 0.5);
+/// assert!(grid.is_connected());
+/// ```
+pub fn recursive_division_with_rooms(
+    grid: &mut Grid,
+    max_room_size: usize,
+    room_probability: f64,
+) {
+    recursive_division_with_rooms_rng(grid, max_room_size, room_probability, &mut thread_rng());
+}
+
+/// Recursive Division maze algorithm with rooms, using the given random number
+/// generator.  See `recursive_division_with_rooms`.
+pub fn recursive_division_with_rooms_rng(
+    grid: &mut Grid,
+    max_room_size: usize,
+    room_probability: f64,
+    rng: &mut impl Rng,
+) {
+    grid.clear();
+    grid.link_all();
+
+    // Each region is a (top, left, height, width) tuple.
+    let mut regions = vec![(0, 0, grid.num_rows(), grid.num_cols())];
+
+    while let Some((top, left, height, width)) = regions.pop() {
+        if height <= 1 || width <= 1 {
+            continue;
+        }
+
+        if height <= max_room_size
+            && width <= max_room_size
+            && rng.gen_range(0.0, 1.0) < room_probability
+        {
+            continue;
+        }
+
+        // Divide across the longer dimension, or either if they're the same.
+        let horizontal = if height == width {
+            flip_rng(rng)
         } else {
-            let neighbor = sample(&neighbors);
-            grid.link(current, neighbor);
-            stack.push(neighbor);
+            height > width
+        };
+
+        if horizontal {
+            // The wall runs along the south side of row `top + k`.
+            let k = rng.gen_range(0, height - 1);
+            let door = rng.gen_range(0, width);
+
+            for j in (0..width).filter(|j| *j != door) {
+                let cell = grid.cell(top + k, left + j);
+                grid.unlink(cell, grid.cell(top + k + 1, left + j));
+            }
+
+            regions.push((top, left, k + 1, width));
+            regions.push((top + k + 1, left, height - k - 1, width));
+        } else {
+            // The wall runs along the east side of column `left + k`.
+            let k = rng.gen_range(0, width - 1);
+            let door = rng.gen_range(0, height);
+
+            for i in (0..height).filter(|i| *i != door) {
+                let cell = grid.cell(top + i, left + k);
+                grid.unlink(cell, grid.cell(top + i, left + k + 1));
+            }
+
+            regions.push((top, left, height, k + 1));
+            regions.push((top, left + k + 1, height, width - k - 1));
         }
     }
 }
 
-/// Picks a random cell from a slice of cells.
-pub fn sample<T: Copy>(vec: &[T]) -> T {
-    assert!(!vec.is_empty());
+/// Recursive Backtracker maze algorithm for polar grids.  Any existing links are cleared
+/// first.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = PolarGrid::new(8);
+/// polar_recursive_backtracker(&mut grid);
+/// assert!(grid.is_perfect_maze());
+/// ```
+pub fn polar_recursive_backtracker(grid: &mut PolarGrid) {
+    recursive_backtracker_rng(grid, &mut thread_rng());
+}
+
+/// Picks a random element from a slice, or returns None if the slice is empty.
+///
+/// ```
+/// use mazegen::*;
+///
+/// assert_eq!(sample(&[7]), Some(7));
+/// assert_eq!(sample::<usize>(&[]), None);
+/// ```
+pub fn sample<T: Copy>(vec: &[T]) -> Option<T> {
+    sample_rng(vec, &mut thread_rng())
+}
 
-    if vec.len() == 1 {
-        return vec[0];
+/// Picks a random element from a slice using the given random number generator, or
+/// returns None if the slice is empty.  See `sample`.
+pub fn sample_rng<T: Copy>(vec: &[T], rng: &mut impl Rng) -> Option<T> {
+    match vec.len() {
+        0 => None,
+        1 => Some(vec[0]),
+        len => Some(vec[rng.gen_range(0, len)]),
     }
+}
 
-    let mut rng = thread_rng();
-    let ind: usize = rng.gen_range(0, vec.len());
-    vec[ind]
+/// Picks `n` distinct elements from a slice, in random order; or all of them, if there
+/// are fewer than `n`.  Elements are distinct by position, so a value that appears in
+/// the slice twice may be picked twice.
+pub fn sample_n<T: Copy>(vec: &[T], n: usize) -> Vec<T> {
+    sample_n_rng(vec, n, &mut thread_rng())
+}
+
+/// Picks `n` distinct elements from a slice using the given random number generator.  See
+/// `sample_n`.
+pub fn sample_n_rng<T: Copy>(vec: &[T], n: usize, rng: &mut impl Rng) -> Vec<T> {
+    rand::seq::index::sample(rng, vec.len(), n.min(vec.len()))
+        .into_iter()
+        .map(|i| vec[i])
+        .collect()
 }
 
 /// Flips a coin, returning true for heads and false for tails.
 pub fn flip() -> bool {
-    thread_rng().gen_bool(0.5)
+    flip_rng(&mut thread_rng())
+}
+
+/// Flips a coin using the given random number generator, returning true for heads and
+/// false for tails.
+pub fn flip_rng(rng: &mut impl Rng) -> bool {
+    rng.gen_bool(0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generates a maze of the given size with each algorithm, checking that it's a perfect
+    // maze and that it can be rendered.
+    fn check_algorithms(num_rows: usize, num_cols: usize) {
+        let algorithms: [fn(&mut Grid); 4] = [
+            binary_tree_maze,
+            sidewinder_maze,
+            hunt_and_kill,
+            recursive_backtracker,
+        ];
+
+        for algorithm in &algorithms {
+            let mut grid = Grid::new(num_rows, num_cols);
+            algorithm(&mut grid);
+            assert!(grid.is_perfect_maze());
+
+            let text = TextGridRenderer::new().render(&grid);
+            assert_eq!(text.lines().count(), 2 * num_rows + 1);
+
+            let image = ImageGridRenderer::new().render(&grid);
+            assert_eq!(
+                image.dimensions(),
+                (11 * num_cols as u32 + 1, 11 * num_rows as u32 + 1)
+            );
+
+            let image = ImageGridRenderer::new().antialias(2).render(&grid);
+            assert_eq!(
+                image.dimensions(),
+                (11 * num_cols as u32 + 1, 11 * num_rows as u32 + 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_single_row() {
+        check_algorithms(1, 10);
+    }
+
+    #[test]
+    fn test_single_column() {
+        check_algorithms(10, 1);
+    }
+
+    #[test]
+    fn test_single_cell() {
+        check_algorithms(1, 1);
+    }
+
+    // The cells along the grid's boundary on the given side, in order.
+    fn boundary(grid: &Grid, side: GridDirection) -> Vec<Cell> {
+        let (rows, cols) = (grid.num_rows(), grid.num_cols());
+        match side {
+            GridDirection::North => (0..cols).map(|j| grid.cell(0, j)).collect(),
+            GridDirection::South => (0..cols).map(|j| grid.cell(rows - 1, j)).collect(),
+            GridDirection::West => (0..rows).map(|i| grid.cell(i, 0)).collect(),
+            GridDirection::East => (0..rows).map(|i| grid.cell(i, cols - 1)).collect(),
+        }
+    }
+
+    // Returns true if the cells form an unbroken corridor.
+    fn is_corridor(grid: &Grid, cells: &[Cell]) -> bool {
+        cells.windows(2).all(|w| grid.is_linked(w[0], w[1]))
+    }
+
+    #[test]
+    fn test_biased_directions() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use GridDirection::*;
+        let mut rng = StdRng::seed_from_u64(5);
+
+        for &(dir1, dir2) in &[
+            (North, East),
+            (North, West),
+            (South, East),
+            (South, West),
+            (East, North),
+            (West, South),
+        ] {
+            // The binary tree's boundaries in both directions are corridors.
+            let mut grid = Grid::new(7, 9);
+            binary_tree_maze_with_rng(&mut grid, dir1, dir2, &mut rng).unwrap();
+            assert!(grid.is_perfect_maze());
+            assert!(is_corridor(&grid, &boundary(&grid, dir1)));
+            assert!(is_corridor(&grid, &boundary(&grid, dir2)));
+        }
+
+        for &(run_dir, carve_dir) in &[
+            (East, North),
+            (East, South),
+            (West, North),
+            (West, South),
+            (North, East),
+            (North, West),
+            (South, East),
+            (South, West),
+        ] {
+            // The sidewinder's boundary in the carve direction is a corridor.
+            let mut grid = Grid::new(7, 9);
+            sidewinder_maze_with_rng(&mut grid, run_dir, carve_dir, &mut rng).unwrap();
+            assert!(grid.is_perfect_maze());
+            assert!(is_corridor(&grid, &boundary(&grid, carve_dir)));
+        }
+
+        let mut grid = Grid::new(3, 3);
+        grid.link(0, 1);
+        assert!(binary_tree_maze_with(&mut grid, North, North).is_err());
+        assert!(sidewinder_maze_with(&mut grid, East, West).is_err());
+        assert!(grid.is_linked(0, 1));
+    }
+
+    #[test]
+    fn test_multi_origin_backtracker() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        for &seeds in &[0, 1, 2, 7, 500] {
+            let mut grid = Grid::new(15, 20);
+            multi_origin_backtracker_rng(&mut grid, seeds, &mut StdRng::seed_from_u64(3));
+
+            // Connected, covering every cell, and a tree.
+            assert!(grid.distances(0).iter().all(|d| d.is_some()));
+            assert!(grid.is_perfect_maze());
+        }
+
+        let grid = Grid::new(3, 3);
+        let mut points = grid.seed_points(20, &mut StdRng::seed_from_u64(1));
+        points.sort();
+        assert_eq!(points, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sample() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let mut rng = StdRng::seed_from_u64(4);
+
+        assert_eq!(sample_rng::<Cell>(&[], &mut rng), None);
+        assert_eq!(sample_rng(&[(1, 2)], &mut rng), Some((1, 2)));
+
+        let items = [3, 5, 8, 13];
+        for _ in 0..20 {
+            assert!(items.contains(&sample_rng(&items, &mut rng).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_sample_n() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let mut rng = StdRng::seed_from_u64(4);
+        let items: Vec<Cell> = (0..10).collect();
+
+        let mut picked = sample_n_rng(&items, 4, &mut rng);
+        assert_eq!(picked.len(), 4);
+        picked.sort();
+        picked.dedup();
+        assert_eq!(picked.len(), 4);
+
+        // Asking for more than there are gets all of them.
+        let mut picked = sample_n_rng(&items, 20, &mut rng);
+        picked.sort();
+        assert_eq!(picked, items);
+
+        assert!(sample_n_rng::<Cell>(&[], 3, &mut rng).is_empty());
+        assert!(sample_n_rng(&items, 0, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_dungeon_maze() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let mut rng = StdRng::seed_from_u64(9);
+
+        for &braid in &[0.0, 1.0] {
+            let mut grid = Grid::new(24, 32);
+            let rooms = dungeon_maze_rng(&mut grid, 6, braid, &mut rng);
+            assert!(!rooms.is_empty() && rooms.len() <= 6);
+
+            // Every room is reachable.
+            let dists = grid.distances(0);
+            for &(top, left, _, _) in &rooms {
+                assert!(dists[grid.cell(top, left)].is_some());
+                assert!(grid.is_linked_east(grid.cell(top, left)));
+            }
+
+            // No two rooms overlap.
+            let mut owner = vec![None; grid.num_cells()];
+            for (r, &(top, left, height, width)) in rooms.iter().enumerate() {
+                for i in top..top + height {
+                    for j in left..left + width {
+                        assert_eq!(owner[grid.cell(i, j)], None);
+                        owner[grid.cell(i, j)] = Some(r);
+                    }
+                }
+            }
+        }
+
+        // A tiny grid still works.
+        let mut grid = Grid::new(1, 1);
+        assert_eq!(dungeon_maze_rng(&mut grid, 3, 0.0, &mut rng).len(), 1);
+    }
+
+    #[test]
+    fn test_symmetric_maze() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        for &(rows, cols) in &[(8, 10), (7, 9), (5, 1), (4, 2), (1, 6)] {
+            let mut grid = Grid::new(rows, cols);
+            symmetric_maze_rng(&mut grid, &mut StdRng::seed_from_u64(5));
+            assert!(grid.is_perfect_maze());
+
+            // Each cell's passages mirror those of its mirror cell.
+            for i in 0..rows {
+                for j in 0..cols {
+                    let cell = grid.cell(i, j);
+                    let mirror = grid.cell(i, cols - 1 - j);
+                    assert_eq!(grid.is_linked_north(cell), grid.is_linked_north(mirror));
+                    assert_eq!(grid.is_linked_south(cell), grid.is_linked_south(mirror));
+                    assert_eq!(grid.is_linked_east(cell), grid.is_linked_west(mirror));
+                    assert_eq!(grid.is_linked_west(cell), grid.is_linked_east(mirror));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_backtracker_preserves_rooms() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rooms = Grid::new(12, 12);
+        rooms.carve_room(1, 1, 3, 4);
+        rooms.carve_room(7, 5, 4, 3);
+        let room_links = rooms.link_pairs();
+
+        for seed in 0..10 {
+            let mut grid = rooms.clone();
+            recursive_backtracker_preserve_rng(&mut grid, &mut StdRng::seed_from_u64(seed));
+
+            // The rooms are still fully open, and the whole grid is connected.
+            for &(a, b) in &room_links {
+                assert!(grid.is_linked(a, b));
+            }
+            assert!(grid.is_connected());
+
+            // Outside the rooms, it's a tree: each room adds (h-1)(w-1) loops.
+            assert_eq!(grid.num_links(), grid.num_cells() - 1 + 6 + 6);
+        }
+
+        // Unlinking a cell disconnects it entirely.
+        let mut grid = rooms;
+        let corner = grid.cell(1, 1);
+        grid.unlink_all(corner);
+        assert!(grid.links(corner).is_empty());
+        assert!(!grid.is_isolated(corner));
+    }
+
+    #[test]
+    fn test_recursive_division() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        for &(rows, cols) in &[(12, 15), (1, 6), (5, 1), (2, 2)] {
+            let mut grid = Grid::new(rows, cols);
+            grid.link_all();
+            recursive_division_rng(&mut grid, &mut StdRng::seed_from_u64(7));
+            assert!(grid.is_perfect_maze());
+        }
+    }
+
+    #[test]
+    fn test_recursive_division_with_rooms() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        // Is the 2x2 block whose upper-left cell is (i,j) fully linked?
+        let open_block = |grid: &Grid, i: usize, j: usize| {
+            let (a, b) = (grid.cell(i, j), grid.cell(i, j + 1));
+            let (c, d) = (grid.cell(i + 1, j), grid.cell(i + 1, j + 1));
+            grid.is_linked(a, b)
+                && grid.is_linked(c, d)
+                && grid.is_linked(a, c)
+                && grid.is_linked(b, d)
+        };
+
+        for seed in 0..5 {
+            let mut grid = Grid::new(20, 20);
+            let mut rng = StdRng::seed_from_u64(seed);
+            recursive_division_with_rooms_rng(&mut grid, 4, 1.0, &mut rng);
+            assert!(grid.is_connected());
+            assert!(!grid.is_perfect_maze());
+            assert!((0..19).any(|i| (0..19).any(|j| open_block(&grid, i, j))));
+        }
+
+        // With no rooms, it's a perfect maze.
+        let mut grid = Grid::new(20, 20);
+        recursive_division_with_rooms_rng(&mut grid, 4, 0.0, &mut StdRng::seed_from_u64(1));
+        assert!(grid.is_perfect_maze());
+    }
+
+    // The original Hunt-and-Kill, which rescans the grid from the first cell on each
+    // hunt.
+    fn naive_hunt_and_kill<G: MazeGrid>(grid: &mut G, rng: &mut impl Rng) {
+        grid.clear();
+        let mut current: Cell = grid.random_cell_with(rng);
+
+        while current != grid.num_cells() {
+            if let Some(neighbor) = sample_rng(&grid.unvisited_neighbors(current), rng) {
+                grid.link(current, neighbor);
+                current = neighbor;
+            } else {
+                current = grid.num_cells();
+
+                for cell in grid.unvisited_cells() {
+                    if let Some(neighbor) = sample_rng(&grid.visited_neighbors(cell), rng) {
+                        current = cell;
+                        grid.link(current, neighbor);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hunt_and_kill_matches_rescanning() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        for seed in 0..10 {
+            let mut grid = Grid::new(17, 23);
+            hunt_and_kill_rng(&mut grid, &mut StdRng::seed_from_u64(seed));
+            assert!(grid.is_perfect_maze());
+
+            let mut naive = Grid::new(17, 23);
+            naive_hunt_and_kill(&mut naive, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(grid, naive);
+        }
+
+        // A grid with isolated cells
+        let mut grid = Grid::from_mask(&Mask::from_text("XooX\nooo.\nXooo").unwrap());
+        let mut naive = grid.clone();
+        hunt_and_kill_rng(&mut grid, &mut StdRng::seed_from_u64(3));
+        naive_hunt_and_kill(&mut naive, &mut StdRng::seed_from_u64(3));
+        assert_eq!(grid, naive);
+    }
+
+    // The C header is maintained by hand, and ffi.rs is only built with the "ffi" feature,
+    // so check that they agree in every build: each function and constant in ffi.rs is in
+    // the header, with the same value, and vice versa.
+    #[test]
+    fn test_ffi_header_matches_source() {
+        let header = include_str!("../include/mazegen.h");
+        let source = include_str!("ffi.rs");
+
+        // A constant's value: a number, or a number shifted left.
+        let value = |text: &str| -> u64 {
+            let mut parts = text
+                .split("<<")
+                .map(|part| part.trim().parse::<u64>().unwrap());
+            let number = parts.next().unwrap();
+            parts.next().map_or(number, |shift| number << shift)
+        };
+
+        // FIRST, the functions: every name followed by an argument list.
+        let mut header_fns: Vec<&str> = header
+            .lines()
+            .filter(|line| !line.starts_with(' ') && !line.starts_with('#'))
+            .filter_map(|line| line.find("mazegen_").map(|k| &line[k..]))
+            .filter_map(|rest| rest.find('(').map(|k| &rest[..k]))
+            .collect();
+        header_fns.sort();
+        let mut source_fns: Vec<&str> = source
+            .lines()
+            .filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+            .filter_map(|rest| rest.find('(').map(|k| &rest[..k]))
+            .collect();
+        source_fns.sort();
+        assert!(!source_fns.is_empty());
+        assert_eq!(header_fns, source_fns);
+
+        // NEXT, the constants.
+        let mut header_consts: Vec<(String, u64)> = header
+            .lines()
+            .filter_map(|line| line.strip_prefix("#define MAZEGEN_"))
+            .filter_map(|rest| {
+                let mut words = rest.splitn(2, ' ');
+                let name = words.next()?;
+                Some((format!("MAZEGEN_{}", name), value(words.next()?)))
+            })
+            .collect();
+        header_consts.sort();
+        let mut source_consts: Vec<(String, u64)> = source
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub const "))
+            .map(|rest| {
+                let name = rest.split(':').next().unwrap().to_string();
+                let text = rest.split('=').nth(1).unwrap().trim_end_matches(';');
+                (name, value(text))
+            })
+            .collect();
+        source_consts.sort();
+        assert!(!source_consts.is_empty());
+        assert_eq!(header_consts, source_consts);
+    }
 }
@@ -1,4 +1,3 @@
-use mazegen::molt_grid::make_grid_object;
 use mazegen::Cell;
 use mazegen::Grid;
 use mazegen::ImageGridRenderer;
@@ -19,11 +18,12 @@ fn main() {
     // NEXT, initialize the interpreter
     let mut interp = Interp::new();
     interp.add_command("doit", cmd_doit);
-    interp.add_command("maze", cmd_maze);
 
     // Install a Molt extension
+    mazegen::molt_generator::install(&mut interp);
     mazegen::molt_grid::install(&mut interp);
     mazegen::molt_image::install(&mut interp);
+    mazegen::molt_maze::install(&mut interp);
     mazegen::molt_rand::install(&mut interp);
 
     // NEXT, evaluate the file, if any.
@@ -97,90 +97,3 @@ fn cmd_doit(_interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
 
     molt_ok!(out)
 }
-
-fn cmd_maze(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
-    interp.call_subcommand(ctx, argv, 1, &MAZE_SUBCOMMANDS)
-}
-
-const MAZE_SUBCOMMANDS: [Subcommand; 4] = [
-    Subcommand("backtracker", cmd_maze_backtracker),
-    Subcommand("bintree", cmd_maze_bintree),
-    Subcommand("huntandkill", cmd_maze_huntandkill),
-    Subcommand("sidewinder", cmd_maze_sidewinder),
-];
-
-fn cmd_maze_backtracker(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
-    // Correct number of arguments?
-    check_args(2, argv, 5, 5, "name rows cols")?;
-
-    let name = argv[2].as_str();
-    let rows = argv[3].as_int()?;
-    let cols = argv[4].as_int()?;
-
-    if rows < 2 || cols < 2 {
-        return molt_err!("expected a max of size at least 2x2, got {}x{}", rows, cols);
-    }
-
-    let mut grid = Grid::new(rows as usize, cols as usize);
-    mazegen::recursive_backtracker(&mut grid);
-    make_grid_object(interp, name, grid);
-
-    molt_ok!(name)
-}
-
-fn cmd_maze_bintree(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
-    // Correct number of arguments?
-    check_args(2, argv, 5, 5, "name rows cols")?;
-
-    let name = argv[2].as_str();
-    let rows = argv[3].as_int()?;
-    let cols = argv[4].as_int()?;
-
-    if rows < 2 || cols < 2 {
-        return molt_err!("expected a max of size at least 2x2, got {}x{}", rows, cols);
-    }
-
-    let mut grid = Grid::new(rows as usize, cols as usize);
-    mazegen::binary_tree_maze(&mut grid);
-    make_grid_object(interp, name, grid);
-
-    molt_ok!(name)
-}
-
-fn cmd_maze_huntandkill(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
-    // Correct number of arguments?
-    check_args(2, argv, 5, 5, "name rows cols")?;
-
-    let name = argv[2].as_str();
-    let rows = argv[3].as_int()?;
-    let cols = argv[4].as_int()?;
-
-    if rows < 2 || cols < 2 {
-        return molt_err!("expected a max of size at least 2x2, got {}x{}", rows, cols);
-    }
-
-    let mut grid = Grid::new(rows as usize, cols as usize);
-    mazegen::hunt_and_kill(&mut grid);
-    make_grid_object(interp, name, grid);
-
-    molt_ok!(name)
-}
-
-fn cmd_maze_sidewinder(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
-    // Correct number of arguments?
-    check_args(2, argv, 5, 5, "name rows cols")?;
-
-    let name = argv[2].as_str();
-    let rows = argv[3].as_int()?;
-    let cols = argv[4].as_int()?;
-
-    if rows < 2 || cols < 2 {
-        return molt_err!("expected a max of size at least 2x2, got {}x{}", rows, cols);
-    }
-
-    let mut grid = Grid::new(rows as usize, cols as usize);
-    mazegen::sidewinder_maze(&mut grid);
-    make_grid_object(interp, name, grid);
-
-    molt_ok!(name)
-}
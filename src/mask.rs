@@ -7,6 +7,8 @@
 
 use crate::Cell;
 use crate::sample;
+use image::GenericImageView;
+use image::RgbaImage;
 use std::ops::Index;
 use std::ops::IndexMut;
 
@@ -40,6 +42,109 @@ impl Mask {
         mask
     }
 
+    /// Creates a mask from a text template, one line per row.  `X` and `.` mark dead
+    /// cells, and any other non-whitespace character marks a live cell.  Blank lines at the
+    /// beginning and end, and whitespace at the beginning and end of each line, are
+    /// ignored, so the template can be indented.  All rows must have the same length.
+    ///
+    /// ```
+    /// use mazegen::Mask;
+    ///
+    /// let mask = Mask::from_text("ooo\no.o\nooo").unwrap();
+    /// assert_eq!(mask.live_count(), 8);
+    /// assert!(!mask[(1, 1)]);
+    /// ```
+    pub fn from_text(text: &str) -> Result<Mask, String> {
+        // FIRST, get the rows, ignoring blank lines at the beginning and end.
+        let lines: Vec<&str> = text.lines().map(|line| line.trim()).collect();
+        let first = lines.iter().position(|line| !line.is_empty());
+        let last = lines.iter().rposition(|line| !line.is_empty());
+
+        let rows: Vec<Vec<char>> = match (first, last) {
+            (Some(first), Some(last)) => lines[first..=last]
+                .iter()
+                .map(|line| line.chars().collect())
+                .collect(),
+            _ => return Err("expected a mask template, got an empty string".into()),
+        };
+
+        // NEXT, check the dimensions.
+        let num_cols = rows[0].len();
+
+        for (i, row) in rows.iter().enumerate() {
+            if row.len() != num_cols {
+                return Err(format!(
+                    "row {}: expected {} cells, got {}",
+                    i + 1,
+                    num_cols,
+                    row.len()
+                ));
+            }
+        }
+
+        // NEXT, set the cells.
+        let mut mask = Mask::new(rows.len(), num_cols);
+
+        for (i, row) in rows.iter().enumerate() {
+            for (j, ch) in row.iter().enumerate() {
+                match ch {
+                    'X' | '.' => mask[(i, j)] = false,
+                    ch if ch.is_whitespace() => {
+                        return Err(format!(
+                            "row {}: unexpected whitespace in column {}",
+                            i + 1,
+                            j + 1
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(mask)
+    }
+
+    /// Creates a mask from an image file, e.g., a black-and-white PNG: one cell per pixel,
+    /// with black pixels marking dead cells and all others live cells.  Returns an error
+    /// if the file can't be read as an image.
+    pub fn from_image(path: &str) -> Result<Mask, String> {
+        match image::open(path) {
+            Ok(image) => {
+                let (width, height) = image.dimensions();
+                let rgba = RgbaImage::from_fn(width, height, |x, y| image.get_pixel(x, y));
+                Ok(Mask::from_rgba_image(&rgba))
+            }
+            Err(e) => Err(format!("error reading mask image \"{}\": {}", path, e)),
+        }
+    }
+
+    /// Creates a mask from an image in memory, as for `from_image`.  Black pixels are
+    /// those whose red, green, and blue values are all zero, whatever their alpha.
+    ///
+    /// ```
+    /// use mazegen::Mask;
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let mut image = RgbaImage::from_pixel(4, 3, Rgba([255, 255, 255, 255]));
+    /// image.put_pixel(1, 2, Rgba([0, 0, 0, 255]));
+    ///
+    /// let mask = Mask::from_rgba_image(&image);
+    /// assert_eq!((mask.num_rows(), mask.num_cols()), (3, 4));
+    /// assert!(!mask[(2, 1)]);
+    /// assert_eq!(mask.live_count(), 11);
+    /// ```
+    pub fn from_rgba_image(image: &RgbaImage) -> Mask {
+        let mut mask = Mask::new(image.height() as usize, image.width() as usize);
+
+        for (x, y, pixel) in image.enumerate_pixels() {
+            if pixel[0] == 0 && pixel[1] == 0 && pixel[2] == 0 {
+                mask[(y as usize, x as usize)] = false;
+            }
+        }
+
+        mask
+    }
+
     /// The number of rows in the mask.
     pub fn num_rows(&self) -> usize {
         self.num_rows
@@ -106,13 +211,7 @@ impl Mask {
     /// Returns a random cell, guaranteed to be alive.  Only returns None if there
     /// are no live cells.
     pub fn random_cell(&self) -> Option<(usize,usize)> {
-        let live_cells = self.live_cells();
-
-        if live_cells.len() > 0 {
-            Some(sample(&live_cells))
-        } else {
-            None
-        }
+        sample(&self.live_cells())
     }
 }
 
@@ -196,6 +295,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_mask_from_rgba_image() {
+        use image::Rgba;
+
+        let mut image = RgbaImage::from_pixel(3, 2, Rgba([255, 255, 255, 255]));
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        image.put_pixel(2, 1, Rgba([0, 0, 0, 0]));
+        image.put_pixel(1, 1, Rgba([1, 0, 0, 255]));
+
+        let mask = Mask::from_rgba_image(&image);
+        assert_eq!(mask, Mask::from_text("Xoo\nooX").unwrap());
+
+        let err = Mask::from_image("no-such-mask.png").unwrap_err();
+        assert!(err.starts_with("error reading mask image"), "{}", err);
+    }
+
+    #[test]
+    fn test_mask_from_text() {
+        let mask = Mask::from_text("\n  #X#\n  .##  \r\n  ##X\n\n").unwrap();
+
+        assert_eq!(mask.num_rows(), 3);
+        assert_eq!(mask.num_cols(), 3);
+        assert_eq!(mask.live_cells(), vec![(0,0), (0,2), (1,1), (1,2), (2,0), (2,1)]);
+
+        assert!(Mask::from_text("").is_err());
+        assert!(Mask::from_text("  \n").is_err());
+        assert_eq!(
+            Mask::from_text("###\n##\n"),
+            Err("row 2: expected 3 cells, got 2".to_string())
+        );
+        assert_eq!(
+            Mask::from_text("# #\n###\n"),
+            Err("row 1: unexpected whitespace in column 2".to_string())
+        );
+    }
+
     #[test]
     fn test_live_cells() {
         let mut mask = Mask::new(2, 2);
@@ -0,0 +1,220 @@
+//! This module defines MazeGenerator, which carves a maze a step at a time so that
+//! generation can be spread across many short time slices, e.g., one per frame of a game.
+
+use crate::Cell;
+use crate::Grid;
+use rand::rngs::StdRng;
+use rand::{thread_rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+/// The number of steps `MazeGenerator::run_for` takes between checks of the clock.  A step
+/// costs well under a microsecond, so checking this often costs little and overshoots the
+/// budget by only a few microseconds.
+const STEPS_PER_CHECK: usize = 64;
+
+/// A resumable Recursive Backtracker.  Each call to `step` carves at most one link, and
+/// `run_for` takes steps until a time budget is spent; between calls the grid is always
+/// a valid, partially carved maze.
+///
+/// Given the same seed, a generator produces exactly the same maze as
+/// `recursive_backtracker_rng` with a `StdRng` seeded the same way, however the work is
+/// divided.  The grid must not be modified by anyone else until generation is finished.
+///
+/// ```
+/// use mazegen::*;
+/// use std::time::Duration;
+///
+/// let mut grid = Grid::new(20, 20);
+/// let mut gen = MazeGenerator::with_seed(7);
+///
+/// // E.g., once per frame.
+/// while !gen.run_for(&mut grid, Duration::from_micros(100)).finished {}
+///
+/// assert!(grid.is_perfect_maze());
+/// assert_eq!(gen.progress().completed, 400);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MazeGenerator {
+    rng: StdRng,
+
+    /// The backtracking stack.
+    stack: Vec<Cell>,
+
+    /// Whether the first step has been taken.
+    started: bool,
+
+    progress: GenProgress,
+}
+
+/// How far a `MazeGenerator` has gotten.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct GenProgress {
+    /// The number of cells that have been carved into the maze.
+    pub completed: usize,
+
+    /// The number of cells that can be carved, i.e., that haven't been isolated.  If the
+    /// grid has several separate regions, only one is carved, and `completed` stays below
+    /// `total`.
+    pub total: usize,
+
+    /// Whether generation is finished.
+    pub finished: bool,
+}
+
+impl MazeGenerator {
+    /// Creates a generator with a random seed.
+    pub fn new() -> Self {
+        Self::from_rng(StdRng::from_rng(thread_rng()).expect("seeded rng"))
+    }
+
+    /// Creates a generator with the given seed, so that it always produces the same maze.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::from_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng(rng: StdRng) -> Self {
+        Self {
+            rng,
+            stack: Vec::new(),
+            started: false,
+            progress: GenProgress::default(),
+        }
+    }
+
+    /// Gets the progress so far.
+    pub fn progress(&self) -> GenProgress {
+        self.progress
+    }
+
+    /// Takes one step, carving at most one link.  The first step clears the grid and
+    /// picks the starting cell.  Returns true if there's more to do, and false once
+    /// generation is finished.
+    pub fn step(&mut self, grid: &mut Grid) -> bool {
+        if self.progress.finished {
+            return false;
+        }
+
+        // FIRST, start from a random cell.
+        if !self.started {
+            grid.clear();
+            self.started = true;
+            self.progress.total = (0..grid.num_cells())
+                .filter(|c| !grid.is_isolated(*c))
+                .count();
+            self.progress.completed = 1;
+            self.stack.push(grid.random_cell_with(&mut self.rng));
+            return true;
+        }
+
+        // NEXT, carve into an unvisited neighbor of the current cell, or backtrack.
+        let current = match self.stack.last() {
+            Some(&cell) => cell,
+            None => {
+                self.progress.finished = true;
+                return false;
+            }
+        };
+
+        let neighbors: Vec<Cell> = grid
+            .neighbors(current)
+            .into_iter()
+            .filter(|n| grid.links(*n).is_empty())
+            .collect();
+
+        if let Some(neighbor) = crate::sample_rng(&neighbors, &mut self.rng) {
+            grid.link(current, neighbor);
+            self.stack.push(neighbor);
+            self.progress.completed += 1;
+        } else {
+            self.stack.pop();
+        }
+
+        true
+    }
+
+    /// Takes steps until the budget is spent or generation is finished, and returns the
+    /// progress.  The clock is checked every few dozen steps, so the budget may be
+    /// overrun slightly; and at least that many steps are taken, even if the budget is
+    /// zero, so that repeated calls always finish eventually.
+    pub fn run_for(&mut self, grid: &mut Grid, budget: Duration) -> GenProgress {
+        let start = Instant::now();
+
+        'outer: loop {
+            for _ in 0..STEPS_PER_CHECK {
+                if !self.step(grid) {
+                    break 'outer;
+                }
+            }
+
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+
+        self.progress
+    }
+
+    /// Takes steps until generation is finished, and returns the progress.
+    pub fn run(&mut self, grid: &mut Grid) -> GenProgress {
+        while self.step(grid) {}
+        self.progress
+    }
+}
+
+impl Default for MazeGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_budgets() {
+        let mut grid = Grid::new(30, 30);
+        let mut gen = MazeGenerator::with_seed(5);
+        let mut last = GenProgress::default();
+        let mut calls = 0;
+
+        loop {
+            let progress = gen.run_for(&mut grid, Duration::from_micros(0));
+            calls += 1;
+
+            // Progress is monotonic, and the grid is always a valid partial maze.
+            assert!(progress.completed >= last.completed);
+            assert_eq!(progress.total, 900);
+            assert_eq!(grid.num_links() + 1, progress.completed);
+            last = progress;
+
+            if progress.finished {
+                break;
+            }
+        }
+
+        assert!(calls > 1);
+        assert_eq!(last.completed, 900);
+        assert!(grid.is_perfect_maze());
+
+        // The result is the same as a one-shot run with the same seed.
+        let mut oneshot = Grid::new(30, 30);
+        crate::recursive_backtracker_rng(&mut oneshot, &mut StdRng::seed_from_u64(5));
+        assert_eq!(grid, oneshot);
+
+        // Once finished, it stays finished.
+        assert!(!gen.step(&mut grid));
+        assert_eq!(gen.run_for(&mut grid, Duration::from_secs(1)), last);
+    }
+
+    #[test]
+    fn test_huge_budget() {
+        let mut grid = Grid::new(25, 40);
+        let mut gen = MazeGenerator::new();
+
+        let progress = gen.run_for(&mut grid, Duration::from_secs(3600));
+        assert!(progress.finished);
+        assert_eq!(progress.completed, 1000);
+        assert!(grid.is_perfect_maze());
+    }
+}
@@ -0,0 +1,293 @@
+//! This module defines MazeGrid, the trait shared by the different kinds of grid.  The
+//! maze algorithms that depend only on the graph of cells and links are written in terms
+//! of this trait, and so work with any kind of grid.
+
+use crate::Cell;
+use rand::{thread_rng, Rng};
+use std::collections::HashSet;
+
+/// A grid of cells, any of which may be linked to its neighbors.  In graph theory terms,
+/// each cell is a node; if two cells are linked there is a bidirectional edge between them.
+///
+/// Cells are identified by cell IDs from 0 to `num_cells() - 1`.  Implementors need only
+/// define the structure of the grid; distances and paths are computed in terms of it.
+pub trait MazeGrid {
+    /// The number of cells in the grid.
+    fn num_cells(&self) -> usize;
+
+    /// Gets the cell's neighbors, whether they are linked or not.
+    fn neighbors(&self, cell: Cell) -> Vec<Cell>;
+
+    /// Links cell 1 to cell 2.  Links are bidirectional.
+    fn link(&mut self, cell1: Cell, cell2: Cell);
+
+    /// Unlinks cell 1 from cell 2.
+    fn unlink(&mut self, cell1: Cell, cell2: Cell);
+
+    /// Gets the cells to which the cell is linked.
+    fn links(&self, cell: Cell) -> Vec<Cell>;
+
+    /// Indicates whether or not the cells are linked.
+    fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool;
+
+    /// Returns a random cell from the grid, using the given random number generator.
+    fn random_cell_with(&self, rng: &mut impl Rng) -> Cell {
+        rng.gen_range(0, self.num_cells())
+    }
+
+    /// Returns a random cell from the grid.
+    fn random_cell(&self) -> Cell {
+        self.random_cell_with(&mut thread_rng())
+    }
+
+    /// Indicates whether the cell has been visited, i.e., is linked to any other cell.
+    fn is_visited(&self, cell: Cell) -> bool {
+        !self.links(cell).is_empty()
+    }
+
+    /// Gets the cells that haven't been visited, in order of cell ID.
+    fn unvisited_cells(&self) -> Vec<Cell> {
+        (0..self.num_cells()).filter(|c| !self.is_visited(*c)).collect()
+    }
+
+    /// Gets the cell's neighbors that haven't been visited.
+    fn unvisited_neighbors(&self, cell: Cell) -> Vec<Cell> {
+        self.neighbors(cell)
+            .into_iter()
+            .filter(|c| !self.is_visited(*c))
+            .collect()
+    }
+
+    /// Gets the cell's neighbors that have been visited.
+    fn visited_neighbors(&self, cell: Cell) -> Vec<Cell> {
+        self.neighbors(cell)
+            .into_iter()
+            .filter(|c| self.is_visited(*c))
+            .collect()
+    }
+
+    /// Returns the grid to its initial state:  no cell is linked to any other cell.
+    fn clear(&mut self) {
+        for cell in 0..self.num_cells() {
+            for other in self.links(cell) {
+                self.unlink(cell, other);
+            }
+        }
+    }
+
+    /// Computes the shortest distance from the cell to each other cell.
+    /// Returns the distances as a vector of length `num_cells`; cells that can't be
+    /// reached have no distance.
+    fn distances(&self, cell: Cell) -> Vec<Option<usize>> {
+        self.multi_source_distances(&[cell])
+    }
+
+    /// Computes the shortest distance from the nearest of the source cells to each other
+    /// cell.  Returns the distances as a vector of length `num_cells`; each of the sources
+    /// has distance 0.
+    fn multi_source_distances(&self, sources: &[Cell]) -> Vec<Option<usize>> {
+        // FIRST, create a working vector.  Initially, no distances are computed.
+        let mut dists: Vec<Option<usize>> = vec![None; self.num_cells()];
+
+        // NEXT, use a (simplified) Dijkstra's algorithm to compute the distances.
+        // See "Mazes for Programmers" Ch. 3.
+        let mut frontier = HashSet::new();
+
+        for &cell in sources {
+            assert!(cell < self.num_cells());
+            dists[cell] = Some(0);
+            frontier.insert(cell);
+        }
+
+        while !frontier.is_empty() {
+            let mut new_frontier = HashSet::new();
+
+            for c in frontier {
+                for d in self.links(c) {
+                    if dists[d].is_none() {
+                        dists[d] = Some(dists[c].expect("valid distance") + 1);
+                        new_frontier.insert(d);
+                    }
+                }
+            }
+            frontier = new_frontier;
+        }
+
+        // NEXT, return the distances.
+        dists
+    }
+
+    /// Computes the shortest path from the first cell to the second, returning the path
+    /// as a vector of cells.  If there is no path, the vector will be empty.
+    fn shortest_path(&self, start: Cell, goal: Cell) -> Vec<Cell> {
+        // FIRST, compute distances from the starting cell.  If they don't reach the goal,
+        // there's no path.
+        let dists = self.distances(start);
+
+        if dists[goal].is_none() {
+            return Vec::new();
+        }
+
+        // NEXT, compute a path from the goal back to start.
+        let mut path = path_back(self, &dists, goal);
+
+        // FINALLY, return the computed path.
+        path.reverse();
+        path
+    }
+}
+
+/// Follows a vector of distances back from the given cell to the cell at distance 0,
+/// returning the path as a vector of cells.  If there is no path, including when the
+/// cell has no distance, the vector will be empty.
+pub(crate) fn path_back<G: MazeGrid + ?Sized>(
+    grid: &G,
+    dists: &[Option<usize>],
+    from: Cell,
+) -> Vec<Cell> {
+    let mut path = Vec::new();
+
+    let mut current = from;
+    let mut cdist = match dists[current] {
+        Some(dist) => dist,
+        None => return path,
+    };
+    path.push(current);
+
+    while cdist != 0 {
+        // FIRST, get the next step in the path: a linked neighbor nearer the start.
+        let next = grid
+            .links(current)
+            .into_iter()
+            .find_map(|neighbor| match dists[neighbor] {
+                Some(ndist) if ndist < cdist => Some((neighbor, ndist)),
+                _ => None,
+            });
+
+        // NEXT, if there's no next step then there is no path to start.
+        match next {
+            Some((neighbor, ndist)) => {
+                path.push(neighbor);
+                current = neighbor;
+                cdist = ndist;
+            }
+            None => {
+                path.clear();
+                break;
+            }
+        }
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // A minimal grid: a ring of cells, each of which neighbors the cells on either side.
+    struct RingGrid {
+        links: Vec<HashSet<Cell>>,
+    }
+
+    impl RingGrid {
+        fn new(num_cells: usize) -> Self {
+            Self {
+                links: vec![HashSet::new(); num_cells],
+            }
+        }
+    }
+
+    impl MazeGrid for RingGrid {
+        fn num_cells(&self) -> usize {
+            self.links.len()
+        }
+
+        fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+            let n = self.num_cells();
+            vec![(cell + n - 1) % n, (cell + 1) % n]
+        }
+
+        fn link(&mut self, cell1: Cell, cell2: Cell) {
+            self.links[cell1].insert(cell2);
+            self.links[cell2].insert(cell1);
+        }
+
+        fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+            self.links[cell1].remove(&cell2);
+            self.links[cell2].remove(&cell1);
+        }
+
+        fn links(&self, cell: Cell) -> Vec<Cell> {
+            self.links[cell].iter().cloned().collect()
+        }
+
+        fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+            self.links[cell1].contains(&cell2)
+        }
+    }
+
+    // Checks that the ring has been carved into a single corridor: every cell is
+    // reachable, with one fewer link than cells.
+    fn check_corridor(grid: &RingGrid) {
+        let num_links: usize = (0..grid.num_cells()).map(|c| grid.links(c).len()).sum();
+        assert_eq!(num_links / 2, grid.num_cells() - 1);
+        assert!(grid.distances(0).iter().all(|d| d.is_some()));
+    }
+
+    #[test]
+    fn test_algorithms_are_generic() {
+        let mut rng = StdRng::seed_from_u64(16);
+
+        let mut grid = RingGrid::new(12);
+        crate::recursive_backtracker_rng(&mut grid, &mut rng);
+        check_corridor(&grid);
+
+        let mut grid = RingGrid::new(12);
+        crate::hunt_and_kill_rng(&mut grid, &mut rng);
+        check_corridor(&grid);
+    }
+
+    #[test]
+    fn test_visited_helpers() {
+        let mut grid = crate::Grid::new(3, 3);
+        grid.link(0, 1);
+        grid.link(1, 4);
+
+        assert!(grid.is_visited(0));
+        assert!(grid.is_visited(4));
+        assert!(!grid.is_visited(2));
+        assert_eq!(grid.unvisited_cells(), vec![2, 3, 5, 6, 7, 8]);
+        assert_eq!(grid.unvisited_neighbors(4), vec![7, 5, 3]);
+        assert_eq!(grid.visited_neighbors(4), vec![1]);
+        assert_eq!(grid.visited_neighbors(3), vec![0, 4]);
+        assert!(grid.visited_neighbors(8).is_empty());
+
+        let mut ring = RingGrid::new(4);
+        ring.link(0, 1);
+        assert_eq!(ring.unvisited_cells(), vec![2, 3]);
+        assert_eq!(ring.visited_neighbors(2), vec![1]);
+        assert_eq!(ring.unvisited_neighbors(1), vec![2]);
+    }
+
+    #[test]
+    fn test_distances_and_paths() {
+        let mut grid = RingGrid::new(6);
+        for c in 0..5 {
+            grid.link(c, c + 1);
+        }
+
+        assert_eq!(
+            grid.distances(0),
+            vec![Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)]
+        );
+        assert_eq!(grid.shortest_path(4, 1), vec![4, 3, 2, 1]);
+
+        // Clearing the grid removes every link.
+        grid.clear();
+        assert!(grid.links(2).is_empty());
+        assert_eq!(grid.distances(0)[1], None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Ensemble commands whose subcommands can describe themselves.
+//!
+//! Each ensemble's table lists, for each subcommand, its name, its handler, and its
+//! argument signature, which `check_subcommand_args` uses when reporting the wrong number
+//! of arguments.  Dispatching and
+//! the `subcommands` and `help` introspection subcommands are both driven from the
+//! table, so the lists a shell sees for tab completion can't drift from what the
+//! ensemble accepts.
+
+use molt::check_args;
+use molt::molt_err;
+use molt::molt_ok;
+use molt::types::*;
+use molt::Interp;
+
+/// A subcommand of an ensemble: its name, its handler, and its argument signature.
+pub(crate) struct SubcommandSpec(pub &'static str, pub CommandFunc, pub &'static str);
+
+/// Calls the subcommand named by argv[1], as `Interp::call_subcommand` does for a table
+/// of plain `Subcommand`s, with the same error messages.
+pub(crate) fn call_ensemble(
+    interp: &mut Interp,
+    ctx: ContextID,
+    argv: &[Value],
+    subcommands: &[SubcommandSpec],
+) -> MoltResult {
+    check_args(1, argv, 2, 0, "subcommand ?arg ...?")?;
+    let spec = find_subcommand(subcommands, argv[1].as_str())?;
+    (spec.1)(interp, ctx, argv)
+}
+
+/// Checks the number of arguments passed to the subcommand named by argv[1], as
+/// `check_args` does, taking the argument signature for the error message from the
+/// subcommand's entry in the table.
+pub(crate) fn check_subcommand_args(
+    subcommands: &[SubcommandSpec],
+    argv: &[Value],
+    min: usize,
+    max: usize,
+) -> MoltResult {
+    let spec = find_subcommand(subcommands, argv[1].as_str())?;
+    check_args(2, argv, min, max, spec.2)
+}
+
+/// Returns the error `check_subcommand_args` would for the subcommand named by argv[1],
+/// for subcommands whose valid numbers of arguments aren't a simple range.
+pub(crate) fn wrong_subcommand_args(subcommands: &[SubcommandSpec], argv: &[Value]) -> MoltResult {
+    let spec = find_subcommand(subcommands, argv[1].as_str())?;
+    molt_err!(
+        "wrong # args: should be \"{} {} {}\"",
+        argv[0].as_str(),
+        argv[1].as_str(),
+        spec.2
+    )
+}
+
+/// Returns the names of the subcommands in the table, in table order.
+pub(crate) fn subcommand_names(subcommands: &[SubcommandSpec]) -> MoltResult {
+    let names: MoltList = subcommands.iter().map(|s| Value::from(s.0)).collect();
+    molt_ok!(names)
+}
+
+/// Returns the usage of the named subcommand: its name followed by its argument
+/// signature, if any.
+pub(crate) fn subcommand_help(subcommands: &[SubcommandSpec], name: &Value) -> MoltResult {
+    let spec = find_subcommand(subcommands, name.as_str())?;
+
+    if spec.2.is_empty() {
+        molt_ok!(spec.0)
+    } else {
+        molt_ok!("{} {}", spec.0, spec.2)
+    }
+}
+
+/// Finds the named subcommand in the table.
+fn find_subcommand<'a>(
+    subcommands: &'a [SubcommandSpec],
+    name: &str,
+) -> Result<&'a SubcommandSpec, Exception> {
+    match subcommands.iter().find(|s| s.0 == name) {
+        Some(spec) => Ok(spec),
+        None => {
+            let names: Vec<&str> = subcommands.iter().map(|s| s.0).collect();
+            molt_err!(
+                "unknown or ambiguous subcommand \"{}\", must be: {}.",
+                name,
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// Checks an ensemble's introspection subcommands against its dispatching: the
+/// subcommand names must match those listed when an unknown subcommand is called, and
+/// each usage must match the one given when the subcommand is called with the wrong
+/// number of arguments.  For the ensembles' tests.
+#[cfg(test)]
+pub(crate) fn check_introspection(interp: &mut Interp, cmd: &str) {
+    let err = interp.eval(&format!("{} nonesuch", cmd)).unwrap_err();
+    let msg = err.value().as_str().to_string();
+    let listed = msg.split("must be: ").nth(1).unwrap().trim_end_matches('.');
+
+    let names: Vec<String> = interp
+        .eval(&format!("{} subcommands", cmd))
+        .unwrap()
+        .as_list()
+        .unwrap()
+        .iter()
+        .map(|v| v.as_str().to_string())
+        .collect();
+    assert_eq!(names.join(", "), listed);
+
+    let mut checked = 0;
+
+    for name in &names {
+        let usage = interp.eval(&format!("{} help {}", cmd, name)).unwrap();
+        let usage = usage.as_str();
+        assert!(usage == name || usage.starts_with(&format!("{} ", name)));
+
+        // Call it with too many arguments, or failing that with none.
+        let expected = format!("wrong # args: should be \"{} {}\"", cmd, usage);
+        let calls = [
+            format!("{} {}{}", cmd, name, " x".repeat(20)),
+            format!("{} {}", cmd, name),
+        ];
+
+        for call in &calls {
+            if let Err(e) = interp.eval(call) {
+                if e.value().as_str().starts_with("wrong # args") {
+                    assert_eq!(e.value().as_str(), expected);
+                    checked += 1;
+                    break;
+                }
+            }
+        }
+    }
+
+    // Only a subcommand that takes any number of arguments can't be checked.
+    assert!(
+        checked + 1 >= names.len(),
+        "checked {} of {}",
+        checked,
+        names.len()
+    );
+}
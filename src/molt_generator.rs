@@ -0,0 +1,173 @@
+//! Molt Generator Commands
+use crate::molt_grid::make_grid_object;
+use crate::molt_install::add_commands;
+use crate::molt_install::object_name;
+use crate::GenProgress;
+use crate::Grid;
+use crate::MazeGenerator;
+use molt::check_args;
+use molt::molt_err;
+use molt::molt_ok;
+use molt::types::*;
+use molt::Interp;
+use std::time::Duration;
+
+/// Installs the Molt generator command unprefixed; see `install_with_prefix`, which reports
+/// whether it could be installed.
+pub fn install(interp: &mut Interp) {
+    let _ = install_with_prefix(interp, "");
+}
+
+/// Installs the Molt generator command, "generator", into the interpreter with the given
+/// prefix: given "mz", "generator" is installed as "mz::generator".  An empty prefix
+/// installs it unprefixed.  Installing it again does nothing; returns an error, installing
+/// nothing, if a command of the same name exists that it didn't install.
+pub fn install_with_prefix(interp: &mut Interp, prefix: &str) -> Result<(), String> {
+    add_commands(interp, prefix, &GENERATOR_COMMANDS)
+}
+
+const GENERATOR_COMMANDS: [(&str, CommandFunc); 1] = [("generator", cmd_generator)];
+
+/// A generator object's state: the generator and the grid it's carving.
+struct GenObject {
+    gen: MazeGenerator,
+    grid: Grid,
+}
+
+/// Generator constructor: creates a new generator called "name" that carves a maze with
+/// the specified number of rows and columns, a little at a time.
+pub fn cmd_generator(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_args(1, argv, 4, 5, "name rows cols ?seed?")?;
+
+    let name = object_name(&argv[0], argv[1].as_str());
+    let rows = argv[2].as_int()?;
+    let cols = argv[3].as_int()?;
+
+    if rows < 1 || cols < 1 {
+        return molt_err!(
+            "expected a grid of size at least 1x1, got {}x{}",
+            rows,
+            cols
+        );
+    }
+
+    let gen = if argv.len() == 5 {
+        MazeGenerator::with_seed(argv[4].as_int()? as u64)
+    } else {
+        MazeGenerator::new()
+    };
+
+    let obj = GenObject {
+        gen,
+        grid: Grid::new(rows as usize, cols as usize),
+    };
+
+    let ctx = interp.save_context(obj);
+    interp.add_context_command(&name, obj_gen, ctx);
+    molt_ok!(name)
+}
+
+fn obj_gen(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    interp.call_subcommand(ctx, argv, 1, &OBJ_GEN_SUBCOMMANDS)
+}
+
+const OBJ_GEN_SUBCOMMANDS: [Subcommand; 3] = [
+    Subcommand("grid", obj_gen_grid),
+    Subcommand("progress", obj_gen_progress),
+    Subcommand("runfor", obj_gen_runfor),
+];
+
+// $gen grid name
+//
+// Creates a grid object called "name" containing a copy of the maze as carved so far.
+fn obj_gen_grid(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_args(2, argv, 3, 3, "name")?;
+    let grid = interp.context::<GenObject>(ctx).grid.clone();
+
+    let name = object_name(&argv[0], argv[2].as_str());
+    make_grid_object(interp, &name, grid);
+    molt_ok!(name)
+}
+
+// $gen progress
+//
+// Returns the generator's progress as a flat dictionary.
+fn obj_gen_progress(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_args(2, argv, 2, 2, "")?;
+    let obj = interp.context::<GenObject>(ctx);
+
+    molt_ok!(progress_dict(obj.gen.progress()))
+}
+
+// $gen runfor milliseconds
+//
+// Carves the maze for up to the given number of milliseconds, and returns the progress
+// as a flat dictionary.
+fn obj_gen_runfor(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_args(2, argv, 3, 3, "milliseconds")?;
+    let millis = argv[2].as_float()?;
+
+    if !millis.is_finite() || millis < 0.0 {
+        return molt_err!("expected a non-negative budget, got \"{}\"", argv[2]);
+    }
+
+    let obj = interp.context::<GenObject>(ctx);
+    let progress = obj
+        .gen
+        .run_for(&mut obj.grid, Duration::from_secs_f64(millis / 1000.0));
+
+    molt_ok!(progress_dict(progress))
+}
+
+/// Converts the progress into a flat dictionary.
+fn progress_dict(progress: GenProgress) -> MoltList {
+    vec![
+        Value::from("completed"),
+        Value::from(progress.completed as MoltInt),
+        Value::from("total"),
+        Value::from(progress.total as MoltInt),
+        Value::from("finished"),
+        Value::from(progress.finished),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Creates an interpreter with the generator and grid commands installed.
+    fn test_interp() -> Interp {
+        let mut interp = Interp::new();
+        install(&mut interp);
+        crate::molt_grid::install(&mut interp);
+        interp
+    }
+
+    #[test]
+    fn test_generator_runfor() {
+        let mut interp = test_interp();
+        interp.eval("generator gen 10 10 42").unwrap();
+
+        let progress = interp.eval("gen progress").unwrap();
+        let dict = progress.as_dict().unwrap();
+        assert_eq!(dict[&Value::from("completed")].as_int().unwrap(), 0);
+        assert!(!dict[&Value::from("finished")].as_bool().unwrap());
+
+        let progress = interp.eval("gen runfor 10000").unwrap();
+        let dict = progress.as_dict().unwrap();
+        assert_eq!(dict[&Value::from("completed")].as_int().unwrap(), 100);
+        assert_eq!(dict[&Value::from("total")].as_int().unwrap(), 100);
+        assert!(dict[&Value::from("finished")].as_bool().unwrap());
+
+        // The grid object gets a copy of the finished maze.
+        interp.eval("gen grid g").unwrap();
+        assert!(interp.eval("g isperfect").unwrap().as_bool().unwrap());
+
+        assert!(interp.eval("gen runfor -1").is_err());
+        assert!(interp.eval("generator bad 0 10").is_err());
+    }
+}
@@ -1,100 +1,397 @@
 //! Molt Grid Commands
+use crate::image_grid_renderer::data_shade;
+use crate::molt_ensemble::call_ensemble;
+use crate::molt_ensemble::check_subcommand_args;
+use crate::molt_ensemble::subcommand_help;
+use crate::molt_ensemble::subcommand_names;
+use crate::molt_ensemble::SubcommandSpec;
+use crate::molt_image::make_image_object;
+use crate::molt_install::add_commands;
+use crate::molt_install::molt_state;
+use crate::molt_install::object_name;
+use crate::read_bundle;
+use crate::write_bundle;
+use crate::Cell;
+use crate::CellPath;
 use crate::Grid;
 use crate::GridDirection;
 use crate::ImageGridRenderer;
+use crate::LabelFormat;
+use crate::Mask;
+use crate::MazeBundle;
+use crate::MoltPixel;
 use crate::TextGridRenderer;
+use image::DynamicImage;
+use image::ImageOutputFormat;
+use image::Rgba;
+use image::RgbaImage;
 use molt::check_args;
 use molt::molt_err;
 use molt::molt_ok;
 use molt::types::*;
 use molt::Interp;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
 
-/// Installs the Molt grid commands into the interpreter.
+/// Installs the Molt grid commands unprefixed; see `install_with_prefix`, which reports
+/// whether they could be installed.
 pub fn install(interp: &mut Interp) {
-    interp.add_command("grid", cmd_grid);
+    let _ = install_with_prefix(interp, "");
+}
+
+/// Installs the Molt grid commands, "grid" and "mazegen", into the interpreter with the
+/// given prefix: given "mz", "grid" is installed as "mz::grid".  An empty prefix installs
+/// them unprefixed.  Installing them again does nothing; returns an error, installing
+/// nothing, if a command of the same name exists that they didn't install.
+pub fn install_with_prefix(interp: &mut Interp, prefix: &str) -> Result<(), String> {
+    add_commands(interp, prefix, &GRID_COMMANDS)
+}
+
+const GRID_COMMANDS: [(&str, CommandFunc); 2] = [("grid", cmd_grid), ("mazegen", cmd_mazegen)];
+
+fn cmd_mazegen(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    interp.call_subcommand(ctx, argv, 1, &MAZEGEN_SUBCOMMANDS)
+}
+
+const MAZEGEN_SUBCOMMANDS: [Subcommand; 1] = [Subcommand("defaults", cmd_mazegen_defaults)];
+
+// mazegen defaults
+//
+// Returns the renderers' default settings as a dictionary with keys "image" and "text".
+// The "image" value is a dictionary with keys "cellsize", "borderwidth", "wallcolor", and
+// "floorcolor"; the "text" value is a dictionary with key "cellwidth".
+fn cmd_mazegen_defaults(_interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_args(1, argv, 2, 2, "")?;
+
+    let image: MoltList = vec![
+        Value::from("cellsize"),
+        Value::from(ImageGridRenderer::DEFAULT_CELL_SIZE as MoltInt),
+        Value::from("borderwidth"),
+        Value::from(ImageGridRenderer::DEFAULT_BORDER_WIDTH as MoltInt),
+        Value::from("wallcolor"),
+        Value::from_other(MoltPixel::from_ipixel(
+            ImageGridRenderer::DEFAULT_WALL_COLOR,
+        )),
+        Value::from("floorcolor"),
+        Value::from_other(MoltPixel::from_ipixel(
+            ImageGridRenderer::DEFAULT_FLOOR_COLOR,
+        )),
+    ];
+
+    let text: MoltList = vec![
+        Value::from("cellwidth"),
+        Value::from(TextGridRenderer::DEFAULT_CELL_WIDTH as MoltInt),
+    ];
+
+    let list: MoltList = vec![
+        Value::from("image"),
+        Value::from(image),
+        Value::from("text"),
+        Value::from(text),
+    ];
+
+    molt_ok!(list)
 }
 
 /// Grid constructor: creates a new grid called "name" with the specified number of
-/// rows and columns.
+/// rows and columns.  Alternatively, `grid shaped name text` creates a grid shaped by the
+/// ASCII mask template, `grid load name filename` loads a grid saved by `$grid save`, and
+/// `grid frombundle name filename` loads the grid from a bundle written by `$grid bundle`.
 pub fn cmd_grid(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
     check_args(1, argv, 4, 4, "name rows cols")?;
 
-    let name = argv[1].as_str();
+    match argv[1].as_str() {
+        "shaped" => return cmd_grid_shaped(interp, argv),
+        "load" => return cmd_grid_load(interp, argv),
+        "frombundle" => return cmd_grid_frombundle(interp, argv),
+        _ => (),
+    }
+
+    let name = object_name(&argv[0], argv[1].as_str());
     let rows = argv[2].as_int()?;
     let cols = argv[3].as_int()?;
 
-    if rows < 2 || cols < 2 {
+    if rows < 1 || cols < 1 {
         return molt_err!(
-            "expected a grid of size at least 2x2, got {}x{}",
+            "expected a grid of size at least 1x1, got {}x{}",
             rows,
             cols
         );
     }
 
     let grid = Grid::new(rows as usize, cols as usize);
-    make_grid_object(interp, name, grid);
+    make_grid_object(interp, &name, grid);
     molt_ok!(name)
 }
 
+/// Shaped grid constructor: creates a new grid called "name" from an ASCII mask template,
+/// in which the mask's dead cells are isolated.
+fn cmd_grid_shaped(interp: &mut Interp, argv: &[Value]) -> MoltResult {
+    let name = object_name(&argv[0], argv[2].as_str());
+
+    let mask = match Mask::from_text(argv[3].as_str()) {
+        Ok(mask) => mask,
+        Err(msg) => return molt_err!("invalid mask: {}", msg),
+    };
+
+    let grid = Grid::from_mask(&mask);
+    make_grid_object(interp, &name, grid);
+    molt_ok!(name)
+}
+
+/// Load constructor: creates a new grid called "name" from a JSON file written by
+/// `$grid save`.
+fn cmd_grid_load(interp: &mut Interp, argv: &[Value]) -> MoltResult {
+    let name = object_name(&argv[0], argv[2].as_str());
+    let filename = argv[3].as_str();
+
+    let json = match std::fs::read_to_string(filename) {
+        Ok(json) => json,
+        Err(e) => return molt_err!("error reading grid file \"{}\": {}", filename, e),
+    };
+
+    let grid = match Grid::from_json(&json) {
+        Ok(grid) => grid,
+        Err(msg) => return molt_err!("invalid grid file \"{}\": {}", filename, msg),
+    };
+
+    make_grid_object(interp, &name, grid);
+    molt_ok!(name)
+}
+
+/// Bundle constructor: creates a new grid called "name" from the grid in a bundle file
+/// written by `$grid bundle`.
+fn cmd_grid_frombundle(interp: &mut Interp, argv: &[Value]) -> MoltResult {
+    let name = object_name(&argv[0], argv[2].as_str());
+    let filename = argv[3].as_str();
+
+    let grid = match read_bundle(Path::new(filename)).and_then(|b| b.grid()) {
+        Ok(grid) => grid,
+        Err(e) => return molt_err!("error reading bundle file \"{}\": {}", filename, e),
+    };
+
+    make_grid_object(interp, &name, grid);
+    molt_ok!(name)
+}
+
+/// The grid objects' contexts, by command name, so that one grid object can find
+/// another's grid; Molt can't say which context a command has.  A grid object is entered
+/// when it's created and again whenever it's invoked by another name, e.g., after a
+/// rename, and is removed when Molt drops it.
+pub(crate) type GridRegistry = RefCell<HashMap<String, ContextID>>;
+
+/// The context of a grid object command: the grid, and the registry it's entered in.
+struct GridObject {
+    grid: Grid,
+    registry: Rc<GridRegistry>,
+    ctx: Option<ContextID>,
+}
+
+impl Drop for GridObject {
+    fn drop(&mut self) {
+        let ctx = self.ctx;
+        self.registry.borrow_mut().retain(|_, c| Some(*c) != ctx);
+    }
+}
+
 /// Makes a Molt object command for the given Grid with the given name.
 pub fn make_grid_object(interp: &mut Interp, name: &str, grid: Grid) {
-    let ctx = interp.save_context(grid);
+    let registry = Rc::clone(&molt_state(interp).grids);
+    let ctx = interp.save_context(GridObject {
+        grid,
+        registry: Rc::clone(&registry),
+        ctx: None,
+    });
+    grid_object(interp, ctx).ctx = Some(ctx);
+
     interp.add_context_command(name, obj_grid, ctx);
+    registry.borrow_mut().insert(name.to_string(), ctx);
+}
+
+/// Gets a grid object's context.
+fn grid_object(interp: &mut Interp, ctx: ContextID) -> &mut GridObject {
+    interp.context::<GridObject>(ctx)
+}
+
+/// Gets a grid object's grid.
+fn grid_context(interp: &mut Interp, ctx: ContextID) -> &mut Grid {
+    &mut grid_object(interp, ctx).grid
 }
 
 fn obj_grid(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
-    interp.call_subcommand(ctx, argv, 1, &OBJ_GRID_SUBCOMMANDS)
-}
-
-const OBJ_GRID_SUBCOMMANDS: [Subcommand; 20] = [
-    Subcommand("cell", obj_grid_cell),
-    Subcommand("cells", obj_grid_cells),
-    Subcommand("cellto", obj_grid_cell_to),
-    Subcommand("clear", obj_grid_clear),
-    Subcommand("cols", obj_grid_cols),
-    Subcommand("deadends", obj_grid_deadends),
-    Subcommand("distances", obj_grid_distances),
-    Subcommand("i", obj_grid_i),
-    Subcommand("ij", obj_grid_ij),
-    Subcommand("j", obj_grid_j),
-    Subcommand("linked", obj_grid_linked),
-    Subcommand("linkedto", obj_grid_linked_to),
-    Subcommand("link", obj_grid_link),
-    Subcommand("links", obj_grid_links),
-    Subcommand("longest", obj_grid_longest),
-    Subcommand("neighbors", obj_grid_neighbors),
-    Subcommand("render", obj_grid_render),
-    Subcommand("rows", obj_grid_rows),
-    Subcommand("text", obj_grid_text),
-    Subcommand("unlink", obj_grid_unlink),
+    // FIRST, keep the registry up to date with the name the object goes by.
+    let registry = Rc::clone(&grid_object(interp, ctx).registry);
+    let mut grids = registry.borrow_mut();
+    let name = argv[0].as_str();
+
+    if grids.get(name) != Some(&ctx) {
+        grids.retain(|_, c| *c != ctx);
+        grids.insert(name.to_string(), ctx);
+    }
+    drop(grids);
+
+    // NEXT, call the subcommand.
+    call_ensemble(interp, ctx, argv, &OBJ_GRID_SUBCOMMANDS)
+}
+
+const OBJ_GRID_SUBCOMMANDS: [SubcommandSpec; 38] = [
+    SubcommandSpec("braid", obj_grid_braid, "?p?"),
+    SubcommandSpec("bundle", obj_grid_bundle, "filename ?meta?"),
+    SubcommandSpec("cell", obj_grid_cell, "i j"),
+    SubcommandSpec("cells", obj_grid_cells, ""),
+    SubcommandSpec("cellto", obj_grid_cell_to, "cell dir"),
+    SubcommandSpec("clear", obj_grid_clear, ""),
+    SubcommandSpec("cols", obj_grid_cols, ""),
+    SubcommandSpec("components", obj_grid_components, "?-flat|-pairs?"),
+    SubcommandSpec("deadendcount", obj_grid_deadendcount, ""),
+    SubcommandSpec("deadends", obj_grid_deadends, ""),
+    SubcommandSpec("distances", obj_grid_distances, "cell ?options...?"),
+    SubcommandSpec("fairstarts", obj_grid_fairstarts, "i j ?tolerance?"),
+    SubcommandSpec("farthest", obj_grid_farthest, "i j"),
+    SubcommandSpec("help", obj_grid_help, "subcommand"),
+    SubcommandSpec("i", obj_grid_i, "cell"),
+    SubcommandSpec("ij", obj_grid_ij, "cell"),
+    SubcommandSpec("info", obj_grid_info, ""),
+    SubcommandSpec("isperfect", obj_grid_isperfect, ""),
+    SubcommandSpec("j", obj_grid_j, "cell"),
+    SubcommandSpec("json", obj_grid_json, ""),
+    SubcommandSpec("linked", obj_grid_linked, "cell1 cell2"),
+    SubcommandSpec("linkedto", obj_grid_linked_to, "cell dir"),
+    SubcommandSpec("link", obj_grid_link, "cell1 cell2"),
+    SubcommandSpec("links", obj_grid_links, "cell"),
+    SubcommandSpec("longest", obj_grid_longest, "?-flat|-pairs?"),
+    SubcommandSpec("neighbors", obj_grid_neighbors, "cell"),
+    SubcommandSpec("render", obj_grid_render, "filename ?options...?"),
+    SubcommandSpec("renderimage", obj_grid_renderimage, "name ?options...?"),
+    SubcommandSpec(
+        "renderpages",
+        obj_grid_renderpages,
+        "directory -pagesize {w h} ?options...?",
+    ),
+    SubcommandSpec("rows", obj_grid_rows, ""),
+    SubcommandSpec("save", obj_grid_save, "filename"),
+    SubcommandSpec("shortest", obj_grid_shortest, "start ?goal? ?-flat|-pairs?"),
+    SubcommandSpec("stats", obj_grid_stats, ""),
+    SubcommandSpec("subcommands", obj_grid_subcommands, ""),
+    SubcommandSpec("text", obj_grid_text, "?options...?"),
+    SubcommandSpec("textdiff", obj_grid_textdiff, "othergrid"),
+    SubcommandSpec("unlink", obj_grid_unlink, "cell1 cell2"),
+    SubcommandSpec("validate", obj_grid_validate, ""),
 ];
 
-// Converts an (i,j) pair into a cell ID
+// $grid help subcommand
+//
+// Returns the usage of the named subcommand: its name and argument signature.
+fn obj_grid_help(_: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 3)?;
+    subcommand_help(&OBJ_GRID_SUBCOMMANDS, &argv[2])
+}
+
+// $grid subcommands
+//
+// Returns the names of the grid object's subcommands, e.g., for tab completion.
+fn obj_grid_subcommands(_: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    subcommand_names(&OBJ_GRID_SUBCOMMANDS)
+}
+
+// $grid braid ?p?
+//
+// Braids the maze, linking each dead end to a neighbor with probability p, which
+// defaults to 1.0.  Returns the number of dead ends removed.
+fn obj_grid_braid(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 3)?;
+
+    let p = if argv.len() == 3 {
+        let p = argv[2].as_float()?;
+        if !(0.0..=1.0).contains(&p) {
+            return molt_err!("expected probability from 0.0 to 1.0, got \"{}\"", argv[2]);
+        }
+        p
+    } else {
+        1.0
+    };
+
+    let grid = grid_context(interp, ctx);
+    let before = grid.dead_end_count();
+    grid.braid(p);
+    molt_ok!((before - grid.dead_end_count()) as MoltInt)
+}
+
+// $grid bundle filename ?meta?
+//
+// Writes the grid, the metadata dictionary, and a rendering of the grid as "maze.png"
+// to a single bundle file.
+fn obj_grid_bundle(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 4)?;
+    let filename = argv[2].as_str();
+    let grid = grid_context(interp, ctx).clone();
+
+    let mut meta = HashMap::new();
+
+    if argv.len() == 4 {
+        for (key, val) in argv[3].as_dict()?.iter() {
+            meta.insert(key.as_str().to_string(), val.as_str().to_string());
+        }
+    }
+
+    let mut png = Vec::new();
+
+    if DynamicImage::ImageRgba8(grid.to_image())
+        .write_to(&mut png, ImageOutputFormat::Png)
+        .is_err()
+    {
+        return molt_err!("error rendering grid image");
+    }
+
+    let bundle = MazeBundle {
+        grid,
+        meta,
+        blobs: vec![("maze.png".to_string(), png)],
+    };
+
+    match write_bundle(Path::new(filename), &bundle) {
+        Ok(_) => molt_ok!(),
+        Err(e) => molt_err!("error saving bundle file \"{}\": {}", filename, e),
+    }
+}
+
+// Converts an (i,j) pair or a cell name into a cell ID
 fn obj_grid_cell(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 4, 4, "i j")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 4)?;
+    let grid = grid_context(interp, ctx);
 
-    let i = get_grid_row(grid, &argv[2])?;
-    let j = get_grid_col(grid, &argv[3])?;
+    let (cell, rest) = get_grid_ij(grid, &argv[2..])?;
 
-    molt_ok!(grid.cell(i, j) as MoltInt)
+    if !rest.is_empty() {
+        return wrong_num_args(argv, "i j");
+    }
+
+    molt_ok!(cell as MoltInt)
 }
 
 // Gets the number of cells in the grid.  Cells have IDs in the range `[0..cells)`.
 fn obj_grid_cells(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
     molt_ok!(grid.num_cells() as MoltInt)
 }
 
 // Returns the cell in the given direction, or the empty string if none.
 fn obj_grid_cell_to(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 4, 4, "cell dir")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 4, 4)?;
+    let grid = grid_context(interp, ctx);
 
     let cell = get_grid_cell(grid, &argv[2])?;
     let dir = get_dir(&argv[3])?;
@@ -109,8 +406,8 @@ fn obj_grid_cell_to(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> Molt
 // Clears the links in the grid.
 fn obj_grid_clear(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
 
     grid.clear();
 
@@ -120,75 +417,190 @@ fn obj_grid_clear(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRe
 // Gets the number of columns in the grid.  Columns are indexed `[0..cols)`.
 fn obj_grid_cols(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
     molt_ok!(grid.num_cols() as MoltInt)
 }
 
-// $grid deadends
+// $grid components ?-flat|-pairs?
 //
-// Returns a list of the cells that are dead-ends (i.e., that link to one other cell).
-fn obj_grid_deadends(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+// Returns the connected components, the sets of cells that can reach each other through
+// links, as a list of lists of cell IDs, or, with -pairs, of i j pairs.  The components
+// are in order by their lowest cell IDs.
+fn obj_grid_components(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 3)?;
+    let grid = grid_context(interp, ctx);
 
+    let pairs = argv.len() == 3 && get_path_format(&argv[2])?;
     let list: MoltList = grid
-        .dead_ends()
+        .connected_components()
         .iter()
-        .map(|c| Value::from(*c as MoltInt))
+        .map(|component| {
+            if pairs {
+                let ij: MoltList = component
+                    .iter()
+                    .map(|c| {
+                        let (i, j) = grid.ij(*c);
+                        Value::from(vec![Value::from(i as MoltInt), Value::from(j as MoltInt)])
+                    })
+                    .collect();
+                Value::from(ij)
+            } else {
+                Value::from(cell_list(component))
+            }
+        })
         .collect();
 
     molt_ok!(list)
 }
 
-// $grid distances *cell* ?-list|-dict?
+// $grid deadendcount
+//
+// Returns the number of cells that are dead-ends.
+fn obj_grid_deadendcount(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
+
+    molt_ok!(grid.dead_end_count() as MoltInt)
+}
+
+// $grid deadends
+//
+// Returns a list of the cells that are dead-ends (i.e., that link to one other cell).
+fn obj_grid_deadends(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
+
+    let list = cell_list(&grid.dead_ends());
+
+    molt_ok!(list)
+}
+
+// $grid distances *cell* ?-list|-dict? ?-unreachable *value*?
 //
 // Gets the distances from a given cell as a list indexed by cell ID, or as a
 // dictionary of cells and distances.  Defaults to -list.  If a cell is unreachable from
-// the given cell, the distance is the empty string.
+// the given cell, the distance is the -unreachable value, which defaults to the empty
+// string.
 fn obj_grid_distances(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 3, 4, "cell ?opt?")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 0)?;
+    let grid = grid_context(interp, ctx);
     let cell = get_grid_cell(grid, &argv[2])?;
 
-    let as_dict = if argv.len() == 4 {
-        let opt = argv[3].as_str();
-        match opt {
-            "-list" => false,
-            "-dict" => true,
+    let mut as_dict = false;
+    let mut unreachable = Value::empty();
+
+    let mut queue = argv[3..].iter();
+
+    while let Some(opt) = queue.next() {
+        match opt.as_str() {
+            "-list" => as_dict = false,
+            "-dict" => as_dict = true,
+            "-unreachable" => {
+                if let Some(val) = queue.next() {
+                    unreachable = val.clone();
+                } else {
+                    return molt_err!("missing option value");
+                }
+            }
             _ => {
-                return molt_err!("invalid option \"{}\", should be one of: -list, -dict", opt);
+                return molt_err!(
+                    "invalid option \"{}\", should be one of: -list, -dict, -unreachable",
+                    opt.as_str()
+                );
             }
         }
-    } else {
-        false
+    }
+
+    // NEXT, make one value per distinct distance and share it among the cells at that
+    // distance; on large grids this saves most of the allocations, and the string
+    // representation of each distance is computed only once.
+    let dists = grid.distances(cell);
+    let max = dists.iter().filter_map(|d| *d).max().unwrap_or(0);
+    let values: Vec<Value> = (0..=max).map(|d| Value::from(d as MoltInt)).collect();
+
+    let distance = |d: &Option<usize>| match d {
+        Some(d) => values[*d].clone(),
+        None => unreachable.clone(),
     };
 
     if as_dict {
-        let dict: MoltDict = grid
-            .distances(cell)
+        let dict: MoltDict = dists
             .iter()
             .enumerate()
-            .map(|(k, v)| (Value::from(k as MoltInt), from_option(*v)))
+            .map(|(k, v)| (Value::from(k as MoltInt), distance(v)))
             .collect();
         molt_ok!(dict)
     } else {
-        let list: MoltList = grid
-            .distances(cell)
-            .iter()
-            .map(|c| from_option(*c))
-            .collect();
+        let list: MoltList = dists.iter().map(distance).collect();
         molt_ok!(list)
     }
 }
 
+// $grid fairstarts *i* *j* ?*tolerance*?
+//
+// Returns a list of pairs of cell IDs that are fair start positions for a race to the
+// goal cell at (i,j), ranked by distance from the goal.  The tolerance defaults to 0.
+fn obj_grid_fairstarts(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 5)?;
+    let grid = grid_context(interp, ctx);
+
+    let (goal, rest) = get_grid_ij(grid, &argv[2..])?;
+
+    if rest.len() > 1 {
+        return wrong_num_args(argv, "i j ?tolerance?");
+    }
+
+    let tolerance = if let Some(tol) = rest.first() {
+        let tol = tol.as_int()?;
+        if tol < 0 {
+            return molt_err!("invalid tolerance, expected non-negative integer");
+        }
+        tol as usize
+    } else {
+        0
+    };
+
+    let list: MoltList = grid
+        .fair_starts(goal, tolerance)
+        .iter()
+        .map(|(a, b)| Value::from(vec![Value::from(*a as MoltInt), Value::from(*b as MoltInt)]))
+        .collect();
+
+    molt_ok!(list)
+}
+
+// $grid farthest i j
+//
+// Returns the coordinates {i j} of the cell farthest from the cell at (i,j).
+fn obj_grid_farthest(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 4)?;
+    let grid = grid_context(interp, ctx);
+
+    let (start, rest) = get_grid_ij(grid, &argv[2..])?;
+
+    if !rest.is_empty() {
+        return wrong_num_args(argv, "i j");
+    }
+
+    let (i, j) = grid.ij(grid.farthest(start));
+    molt_ok!(Value::from(vec![
+        Value::from(i as MoltInt),
+        Value::from(j as MoltInt)
+    ]))
+}
+
 // Gets the cell's row coordinate given its cell ID
 fn obj_grid_i(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 3, 3, "cell")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 3)?;
+    let grid = grid_context(interp, ctx);
 
     let cell = get_grid_cell(grid, &argv[2])?;
 
@@ -198,8 +610,8 @@ fn obj_grid_i(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult
 // Gets the cell's row/col coordinate pair given its cell ID
 fn obj_grid_ij(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 3, 3, "cell")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 3)?;
+    let grid = grid_context(interp, ctx);
 
     let cell = get_grid_cell(grid, &argv[2])?;
 
@@ -211,19 +623,30 @@ fn obj_grid_ij(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResul
 // Gets the cell's column coordinate given its cell ID
 fn obj_grid_j(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 3, 3, "cell")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 3)?;
+    let grid = grid_context(interp, ctx);
 
     let cell = get_grid_cell(grid, &argv[2])?;
 
     molt_ok!(grid.j(cell) as MoltInt)
 }
 
+// $grid json
+//
+// Returns the grid as a JSON string.
+fn obj_grid_json(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
+
+    molt_ok!(grid.to_json())
+}
+
 // Links the two cells, which must be neighbors.
 fn obj_grid_link(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 4, 4, "cell1 cell2")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 4, 4)?;
+    let grid = grid_context(interp, ctx);
 
     let cell1 = get_grid_cell(grid, &argv[2])?;
     let cell2 = get_grid_cell(grid, &argv[3])?;
@@ -239,8 +662,8 @@ fn obj_grid_link(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRes
 // Returns true if the cells are linked, and false otherwise
 fn obj_grid_linked(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 4, 4, "cell1 cell2")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 4, 4)?;
+    let grid = grid_context(interp, ctx);
 
     let cell1 = get_grid_cell(grid, &argv[2])?;
     let cell2 = get_grid_cell(grid, &argv[3])?;
@@ -251,8 +674,8 @@ fn obj_grid_linked(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltR
 // Returns true if the cell is linked in the given direction, and false otherwise
 fn obj_grid_linked_to(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 4, 4, "cell dir")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 4, 4)?;
+    let grid = grid_context(interp, ctx);
 
     let cell = get_grid_cell(grid, &argv[2])?;
     let dir = get_dir(&argv[3])?;
@@ -263,49 +686,38 @@ fn obj_grid_linked_to(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> Mo
 // Gets a list of the IDs of the cell's linked neighbors
 fn obj_grid_links(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 3, 3, "cell")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 3)?;
+    let grid = grid_context(interp, ctx);
 
     let cell = get_grid_cell(grid, &argv[2])?;
 
-    let list: MoltList = grid
-        .links(cell)
-        .iter()
-        .map(|c| Value::from(*c as MoltInt))
-        .collect();
+    let list = cell_list(&grid.links(cell));
 
     molt_ok!(list)
 }
 
-// $grid longest
+// $grid longest ?-flat|-pairs?
 //
-// Returns the longest path through the maze as a list of cell IDs
+// Returns the longest path through the maze as a list of cell IDs, or, with -pairs, as
+// a list of i j pairs.
 fn obj_grid_longest(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 3)?;
+    let grid = grid_context(interp, ctx);
 
-    let list: MoltList = grid
-        .longest_path()
-        .iter()
-        .map(|c| Value::from(*c as MoltInt))
-        .collect();
-    molt_ok!(list)
+    let pairs = argv.len() == 3 && get_path_format(&argv[2])?;
+    molt_ok!(path_to_molt(&grid.longest_path(), pairs))
 }
 
 // Gets a list of the IDs of the cell's neighbors
 fn obj_grid_neighbors(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 3, 3, "cell")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 3)?;
+    let grid = grid_context(interp, ctx);
 
     let cell = get_grid_cell(grid, &argv[2])?;
 
-    let list: MoltList = grid
-        .neighbors(cell)
-        .iter()
-        .map(|c| Value::from(*c as MoltInt))
-        .collect();
+    let list = cell_list(&grid.neighbors(cell));
 
     molt_ok!(list)
 }
@@ -313,14 +725,150 @@ fn obj_grid_neighbors(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> Mo
 // Renders the grid as an image, saving it to disk.
 fn obj_grid_render(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 3, 0, "filename ?options...?")?;
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 0)?;
     let filename = argv[2].as_str();
-    let grid = interp.context::<Grid>(ctx);
+    let grid = grid_context(interp, ctx);
+    let image = render_grid_image(grid, &argv[3..argv.len()])?;
+
+    match image.save(filename) {
+        Ok(_) => molt_ok!(),
+        Err(_) => molt_err!("error saving grid image"),
+    }
+}
+
+// $grid renderimage name ?options...?
+//
+// Renders the grid into a Molt image object with the given name, returning the name.
+// An existing command of the same name is replaced.  The options are the same as for
+// "$grid render".
+fn obj_grid_renderimage(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 0)?;
+    let name = object_name(&argv[0], argv[2].as_str());
+    let grid = grid_context(interp, ctx);
+    let image = render_grid_image(grid, &argv[3..argv.len()])?;
+
+    make_image_object(interp, &name, image);
+    molt_ok!(name)
+}
+
+// Renders the grid given the "$grid render" options.
+fn render_grid_image(grid: &Grid, opt_args: &[Value]) -> Result<RgbaImage, Exception> {
+    let mut queue = opt_args.iter();
+
+    let mut renderer = ImageGridRenderer::new();
+    let mut colors: HashMap<Cell, Rgba<u8>> = HashMap::new();
+    let mut shades: Vec<Option<i64>> = Vec::new();
+    let mut cell_size = ImageGridRenderer::DEFAULT_CELL_SIZE as MoltInt;
+    let mut inset = 0;
+
+    while let Some(opt) = queue.next() {
+        let val = if let Some(opt_val) = queue.next() {
+            opt_val
+        } else {
+            return molt_err!("missing option value");
+        };
+
+        match opt.as_str() {
+            "-colordict" => {
+                for (key, color) in val.as_dict()?.iter() {
+                    let cell = get_grid_cell(grid, key)?;
+                    colors.insert(cell, MoltPixel::from_molt(color)?.ipixel());
+                }
+            }
+            "-shade" => {
+                let list = val.as_list()?;
+                if list.len() != grid.num_cells() {
+                    return molt_err!("invalid -shade, expected one value per cell");
+                }
+                shades.clear();
+                for item in list.iter() {
+                    if item.as_str().is_empty() {
+                        shades.push(None);
+                    } else {
+                        shades.push(Some(item.as_int()?));
+                    }
+                }
+            }
+            "-cellsize" => {
+                let size = val.as_int()?;
+                if size < 1 {
+                    return molt_err!("invalid -cellsize, expected positive integer");
+                }
+                cell_size = size;
+                renderer.cell_size(size as usize);
+            }
+            "-borderwidth" => {
+                let wid = val.as_int()?;
+                if wid < 1 {
+                    return molt_err!("invalid -borderwidth, expected positive integer");
+                }
+                renderer.border_width(wid as usize);
+            }
+            "-wallwidth" => {
+                let wid = val.as_int()?;
+                if wid < 1 {
+                    return molt_err!("invalid -wallwidth, expected positive integer");
+                }
+                renderer.wall_width(wid as usize);
+            }
+            "-inset" => {
+                inset = val.as_int()?;
+                if inset < 0 {
+                    return molt_err!("invalid -inset, expected non-negative integer");
+                }
+            }
+            "-channel" => {
+                let fraction = val.as_float()?;
+                if fraction <= 0.0 || fraction > 1.0 {
+                    return molt_err!("invalid -channel, expected number in (0.0, 1.0]");
+                }
+                renderer.channel_mode(fraction as f32);
+            }
+            _ => {
+                return molt_err!("invalid option: \"{}\"", opt);
+            }
+        }
+    }
+
+    if 2 * inset >= cell_size {
+        return molt_err!("invalid -inset, expected less than half the cell size");
+    }
+    renderer.inset(inset as usize);
+
+    // NEXT, shade the cells with data as render_with does; explicit colors take precedence.
+    let min = shades.iter().flatten().min().copied().unwrap_or(0);
+    let max = shades.iter().flatten().max().copied().unwrap_or(0);
+
+    Ok(renderer.render_colored(grid, |cell| {
+        colors.get(&cell).copied().or_else(|| {
+            shades.get(cell).copied().flatten().map(|value| {
+                let shade = data_shade(value, min, max);
+                Rgba([255 - shade, 255 - shade, 255, 255])
+            })
+        })
+    }))
+}
+
+// $grid renderpages directory -pagesize {w h} ?options...?
+//
+// Renders the grid as a set of page-sized images for printing, saving them in the
+// directory as page-001.png, page-002.png, etc., in row major order.  Also writes
+// index.txt, which has one line per page: the file name, the page's row and column in
+// the grid of pages, and the page's range of maze rows and columns.  Returns the number
+// of pages.
+fn obj_grid_renderpages(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 0)?;
+    let dir = std::path::Path::new(argv[2].as_str());
+    let grid = grid_context(interp, ctx);
 
     let opt_args = &argv[3..argv.len()];
     let mut queue = opt_args.iter();
 
     let mut renderer = ImageGridRenderer::new();
+    let mut page_size: Option<(u32, u32)> = None;
+    let mut overlap = 0;
 
     while let Some(opt) = queue.next() {
         let val = if let Some(opt_val) = queue.next() {
@@ -330,6 +878,25 @@ fn obj_grid_render(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltR
         };
 
         match opt.as_str() {
+            "-pagesize" => {
+                let list = val.as_list()?;
+                if list.len() != 2 {
+                    return molt_err!("invalid -pagesize, expected {{width height}}");
+                }
+                let w = list[0].as_int()?;
+                let h = list[1].as_int()?;
+                if w < 1 || h < 1 {
+                    return molt_err!("invalid -pagesize, expected positive integers");
+                }
+                page_size = Some((w as u32, h as u32));
+            }
+            "-overlap" => {
+                let n = val.as_int()?;
+                if n < 0 {
+                    return molt_err!("invalid -overlap, expected non-negative integer");
+                }
+                overlap = n as usize;
+            }
             "-cellsize" => {
                 let size = val.as_int()?;
                 if size < 1 {
@@ -344,33 +911,206 @@ fn obj_grid_render(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltR
                 }
                 renderer.border_width(wid as usize);
             }
+            "-wallwidth" => {
+                let wid = val.as_int()?;
+                if wid < 1 {
+                    return molt_err!("invalid -wallwidth, expected positive integer");
+                }
+                renderer.wall_width(wid as usize);
+            }
             _ => {
                 return molt_err!("invalid option: \"{}\"", opt);
             }
         }
     }
 
-    let image = renderer.render(&grid);
+    let page_size = match page_size {
+        Some(size) => size,
+        None => return molt_err!("missing option: \"-pagesize\""),
+    };
 
-    match image.save(filename) {
-        Ok(_) => molt_ok!(),
-        Err(_) => molt_err!("error saving grid image"),
+    let (across, down) = renderer.cells_per_page(page_size);
+    if across <= overlap || down <= overlap {
+        return molt_err!(
+            "page of {}x{} pixels is too small for an overlap of {} cells",
+            page_size.0,
+            page_size.1,
+            overlap
+        );
     }
+
+    let tiles = renderer.render_paged(grid, page_size, overlap);
+
+    if std::fs::create_dir_all(dir).is_err() {
+        return molt_err!("error creating directory: \"{}\"", dir.display());
+    }
+
+    let mut index = String::new();
+
+    for (n, tile) in tiles.iter().enumerate() {
+        let filename = format!("page-{:03}.png", n + 1);
+
+        if tile.image.save(dir.join(&filename)).is_err() {
+            return molt_err!("error saving page image");
+        }
+
+        index.push_str(&format!(
+            "{} {} {} {} {} {} {}\n",
+            filename,
+            tile.page.0,
+            tile.page.1,
+            tile.rows.start,
+            tile.rows.end,
+            tile.cols.start,
+            tile.cols.end
+        ));
+    }
+
+    if std::fs::write(dir.join("index.txt"), index).is_err() {
+        return molt_err!("error saving page index");
+    }
+
+    molt_ok!(tiles.len() as MoltInt)
 }
 
 // Gets the number of rows in the grid.  Rows are indexed `[0..rows)`.
 fn obj_grid_rows(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
     molt_ok!(grid.num_rows() as MoltInt)
 }
 
+// $grid shortest start ?goal? ?-flat|-pairs?
+//
+// Returns the list of cell IDs on the shortest path from the start cell to the goal
+// cell, or the empty list if there's no path.  If the goal is omitted, it's the cell
+// farthest from the start.  With -pairs, the path is a list of i j pairs.
+fn obj_grid_shortest(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 5)?;
+    let grid = grid_context(interp, ctx);
+
+    // FIRST, get the path format, if given.
+    let mut args = &argv[3..];
+    let mut pairs = false;
+    if let Some(last) = args.last() {
+        if last.as_str().starts_with('-') {
+            pairs = get_path_format(last)?;
+            args = &args[..args.len() - 1];
+        } else if args.len() == 2 {
+            return wrong_num_args(argv, "start ?goal? ?-flat|-pairs?");
+        }
+    }
+
+    // NEXT, get the path.
+    let start = get_grid_cell(grid, &argv[2])?;
+    let goal = if let Some(arg) = args.first() {
+        get_grid_cell(grid, arg)?
+    } else {
+        grid.farthest(start)
+    };
+
+    molt_ok!(path_to_molt(&grid.shortest_path(start, goal), pairs))
+}
+
+// $grid isperfect
+//
+// Returns 1 if the grid is a perfect maze, with exactly one path between any two cells,
+// and 0 otherwise.
+fn obj_grid_isperfect(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
+    molt_ok!(grid.is_perfect_maze())
+}
+
+// $grid info
+//
+// Returns an overview of the grid as a dictionary with keys "rows", "cols", "cells",
+// "links", "deadends", "connected", "components", "longest", and "revision".  The
+// longest path length is cached until the grid changes, so repeated calls are cheap.
+fn obj_grid_info(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
+
+    let list: MoltList = vec![
+        Value::from("rows"),
+        Value::from(grid.num_rows() as MoltInt),
+        Value::from("cols"),
+        Value::from(grid.num_cols() as MoltInt),
+        Value::from("cells"),
+        Value::from(grid.num_cells() as MoltInt),
+        Value::from("links"),
+        Value::from(grid.num_links() as MoltInt),
+        Value::from("deadends"),
+        Value::from(grid.dead_end_count() as MoltInt),
+        Value::from("connected"),
+        Value::from(grid.is_connected()),
+        Value::from("components"),
+        Value::from(grid.num_components() as MoltInt),
+        Value::from("longest"),
+        Value::from(grid.diameter() as MoltInt),
+        Value::from("revision"),
+        Value::from(grid.revision() as MoltInt),
+    ];
+
+    molt_ok!(list)
+}
+
+// $grid save filename
+//
+// Saves the grid's dimensions and links to the file as JSON; see "$grid json" and
+// "grid load".
+fn obj_grid_save(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 3)?;
+    let filename = argv[2].as_str();
+    let grid = grid_context(interp, ctx);
+
+    match std::fs::write(filename, grid.to_json()) {
+        Ok(_) => molt_ok!(),
+        Err(e) => molt_err!("error saving grid file \"{}\": {}", filename, e),
+    }
+}
+
+// $grid stats
+//
+// Returns summary statistics about the maze as a flat dictionary.
+fn obj_grid_stats(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
+
+    let stats = grid.stats();
+
+    let list: MoltList = vec![
+        ("rows", grid.num_rows()),
+        ("cols", grid.num_cols()),
+        ("cells", grid.num_cells()),
+        ("links", grid.num_links()),
+        ("deadends", stats.dead_ends),
+        ("junctions3", stats.junctions3),
+        ("junctions4", stats.junctions4),
+        ("corridors", stats.corridors),
+        ("horizlinks", stats.horiz_links),
+        ("vertlinks", stats.vert_links),
+        ("longest", stats.longest),
+        ("perfect", grid.is_perfect_maze() as usize),
+    ]
+    .into_iter()
+    .flat_map(|(key, val)| vec![Value::from(key), Value::from(val as MoltInt)])
+    .collect();
+
+    molt_ok!(list)
+}
+
 // Renders the grid as a text string, which is returned.
 fn obj_grid_text(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 0, "?options...?")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 0)?;
+    let grid = grid_context(interp, ctx);
 
     let mut renderer = TextGridRenderer::new();
 
@@ -407,6 +1147,16 @@ fn obj_grid_text(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRes
                 }
                 renderer.auto_width(margin as usize);
             }
+            "-format" => {
+                renderer.label_format(get_label_format(val)?);
+            }
+            "-maxcellwidth" => {
+                let size = val.as_int()?;
+                if size < 1 {
+                    return molt_err!("invalid -maxcellwidth, expected positive integer");
+                }
+                renderer.max_cell_width(size as usize);
+            }
             "-datalist" => {
                 let list = val.as_list()?;
                 if list.len() != grid.num_cells() {
@@ -418,6 +1168,23 @@ fn obj_grid_text(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRes
                 let _ = val.as_dict()?; // Just verify that it's a valid dict.
                 data = Data::Dict(val.clone());
             }
+            "-viewport" => {
+                let list = val.as_list()?;
+                let nums: Vec<MoltInt> =
+                    list.iter().map(|v| v.as_int()).collect::<Result<_, _>>()?;
+                if nums.len() != 4 || nums.iter().any(|n| *n < 0) {
+                    return molt_err!(
+                        "invalid -viewport, expected {{i j rows cols}}, got \"{}\"",
+                        val
+                    );
+                }
+                renderer.viewport(
+                    nums[0] as usize,
+                    nums[1] as usize,
+                    nums[2] as usize,
+                    nums[3] as usize,
+                );
+            }
             _ => {
                 return molt_err!("invalid option: \"{}\"", opt);
             }
@@ -439,11 +1206,28 @@ fn obj_grid_text(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRes
     }
 }
 
+// $grid textdiff othergrid
+//
+// Renders the grid as text, marking the walls that differ from the other grid, which must
+// have the same dimensions.  Passages opened since the other grid are drawn as "=" or
+// '"', and passages closed are drawn as "#".
+fn obj_grid_textdiff(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 3, 3)?;
+    let before = get_other_grid(interp, ctx, &argv[2])?;
+    let grid = grid_context(interp, ctx);
+
+    match TextGridRenderer::new().render_diff(&before, grid) {
+        Ok(text) => molt_ok!(text),
+        Err(msg) => molt_err!("{}", msg),
+    }
+}
+
 // Unlinks the two cells, which must be neighbors.
 fn obj_grid_unlink(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 4, 4, "cell1 cell2")?;
-    let grid = interp.context::<Grid>(ctx);
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 4, 4)?;
+    let grid = grid_context(interp, ctx);
 
     let cell1 = get_grid_cell(grid, &argv[2])?;
     let cell2 = get_grid_cell(grid, &argv[3])?;
@@ -456,6 +1240,42 @@ fn obj_grid_unlink(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltR
     }
 }
 
+// $grid validate
+//
+// Checks whether the grid can be rendered, returning a list of human-readable issues.
+// The list is empty if the grid is clean.
+fn obj_grid_validate(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_GRID_SUBCOMMANDS, argv, 2, 2)?;
+    let grid = grid_context(interp, ctx);
+
+    let list: MoltList = match grid.validate_renderable() {
+        Ok(()) => MoltList::new(),
+        Err(issues) => issues.iter().map(|i| Value::from(i.to_string())).collect(),
+    };
+
+    molt_ok!(list)
+}
+
+/// Gets a label format for "$grid text": decimal, base36, hex, or {modulo n}.
+fn get_label_format(arg: &Value) -> Result<LabelFormat, Exception> {
+    let list = arg.as_list()?;
+
+    match (list.len(), list.first().map(|v| v.as_str())) {
+        (1, Some("decimal")) => Ok(LabelFormat::Decimal),
+        (1, Some("base36")) => Ok(LabelFormat::Base36),
+        (1, Some("hex")) => Ok(LabelFormat::Hex),
+        (2, Some("modulo")) => match list[1].as_int()? {
+            n if n > 0 => Ok(LabelFormat::Modulo(n as u64)),
+            _ => molt_err!("invalid modulus, expected positive integer"),
+        },
+        _ => molt_err!(
+            "invalid -format, expected decimal, base36, hex, or {{modulo n}}, got \"{}\"",
+            arg
+        ),
+    }
+}
+
 /// Get a grid row for the given grid.
 fn get_grid_row(grid: &Grid, arg: &Value) -> Result<usize, Exception> {
     let num = arg.as_int()?;
@@ -478,9 +1298,12 @@ fn get_grid_col(grid: &Grid, arg: &Value) -> Result<usize, Exception> {
     }
 }
 
-/// Get a grid cell for the given grid.
-fn get_grid_cell(grid: &Grid, arg: &Value) -> Result<usize, Exception> {
-    let num = arg.as_int()?;
+/// Get a grid cell for the given grid, given its cell ID or its name, e.g., "B3".
+pub(crate) fn get_grid_cell(grid: &Grid, arg: &Value) -> Result<usize, Exception> {
+    let num = match arg.as_int() {
+        Ok(num) => num,
+        Err(_) => return get_grid_cell_by_name(grid, arg),
+    };
 
     if num >= 0 && num < grid.num_cells() as MoltInt {
         Ok(num as usize)
@@ -489,15 +1312,79 @@ fn get_grid_cell(grid: &Grid, arg: &Value) -> Result<usize, Exception> {
     }
 }
 
-fn from_option(val: Option<usize>) -> Value {
-    if let Some(t) = val {
-        Value::from(t as MoltInt)
+/// Converts a slice of cell IDs to a Molt list.
+fn cell_list(cells: &[Cell]) -> MoltList {
+    cells.iter().map(|c| Value::from(*c as MoltInt)).collect()
+}
+
+/// Gets a path format option: returns true for "-pairs" and false for "-flat".
+fn get_path_format(arg: &Value) -> Result<bool, Exception> {
+    match arg.as_str() {
+        "-flat" => Ok(false),
+        "-pairs" => Ok(true),
+        opt => molt_err!("invalid path format \"{}\", expected -flat or -pairs", opt),
+    }
+}
+
+/// Converts a path to a Molt list of cell IDs, or of i j pairs.
+fn path_to_molt(path: &CellPath, pairs: bool) -> Value {
+    if pairs {
+        path.to_molt_pairs()
     } else {
-        Value::empty()
+        path.to_molt_flat()
+    }
+}
+
+/// Returns the standard error for a subcommand called with the wrong number of arguments,
+/// for cases `check_args` can't catch.
+fn wrong_num_args(argv: &[Value], argsig: &str) -> MoltResult {
+    molt_err!(
+        "wrong # args: should be \"{} {} {}\"",
+        argv[0].as_str(),
+        argv[1].as_str(),
+        argsig
+    )
+}
+
+/// Get a grid cell for the given grid given its name, e.g., "B3".
+fn get_grid_cell_by_name(grid: &Grid, arg: &Value) -> Result<usize, Exception> {
+    grid.cell_from_name(arg.as_str())
+        .map_err(|msg| Exception::molt_err(Value::from(msg)))
+}
+
+/// Get a grid cell for the given grid from the leading arguments, which may be an *i j*
+/// pair or a cell name, e.g., "B3".  Returns the cell and the remaining arguments.
+fn get_grid_ij<'a>(grid: &Grid, args: &'a [Value]) -> Result<(usize, &'a [Value]), Exception> {
+    if args[0].as_int().is_err() {
+        return Ok((get_grid_cell_by_name(grid, &args[0])?, &args[1..]));
+    }
+
+    if args.len() < 2 {
+        return molt_err!("expected an i j pair or a cell name");
+    }
+
+    let i = get_grid_row(grid, &args[0])?;
+    let j = get_grid_col(grid, &args[1])?;
+
+    Ok((grid.cell(i, j), &args[2..]))
+}
+
+/// Gets a copy of the grid of another grid object command, given its name and the context
+/// of the grid object asking.
+fn get_other_grid(interp: &mut Interp, ctx: ContextID, name: &Value) -> Result<Grid, Exception> {
+    let other = grid_object(interp, ctx)
+        .registry
+        .borrow()
+        .get(name.as_str())
+        .copied();
+
+    match other.filter(|_| interp.has_command(name.as_str())) {
+        Some(other) => Ok(grid_context(interp, other).clone()),
+        None => molt_err!("expected a grid, got \"{}\"", name),
     }
 }
 
-fn get_dir(value: &Value) -> Result<GridDirection, Exception> {
+pub(crate) fn get_dir(value: &Value) -> Result<GridDirection, Exception> {
     if let Some(x) = value.as_copy::<GridDirection>() {
         Ok(x)
     } else {
@@ -506,3 +1393,735 @@ fn get_dir(value: &Value) -> Result<GridDirection, Exception> {
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Creates an interpreter with the grid commands installed.
+    fn test_interp() -> Interp {
+        let mut interp = Interp::new();
+        install(&mut interp);
+        interp
+    }
+
+    // Creates a 2x2 grid with a known set of links:
+    //
+    // +---+---+
+    // |       |
+    // +   +---+
+    // |       |
+    // +---+---+
+    fn test_grid(interp: &mut Interp) {
+        interp.eval("grid g 2 2").unwrap();
+        interp.eval("g link 0 1").unwrap();
+        interp.eval("g link 0 2").unwrap();
+        interp.eval("g link 2 3").unwrap();
+    }
+
+    #[test]
+    fn test_grid_introspection() {
+        let mut interp = test_interp();
+        test_grid(&mut interp);
+        crate::molt_ensemble::check_introspection(&mut interp, "g");
+
+        assert_eq!(
+            interp.eval("g help linked").unwrap().as_str(),
+            "linked cell1 cell2"
+        );
+        assert_eq!(interp.eval("g help cells").unwrap().as_str(), "cells");
+        assert!(interp.eval("g help nonesuch").is_err());
+    }
+
+    #[test]
+    fn test_grid_stats() {
+        let mut interp = test_interp();
+        test_grid(&mut interp);
+
+        let stats = interp.eval("g stats").unwrap();
+        let dict = stats.as_dict().unwrap();
+        let get = |key: &str| dict[&Value::from(key)].as_int().unwrap();
+
+        assert_eq!(dict.len(), 12);
+        assert_eq!(get("rows"), 2);
+        assert_eq!(get("cols"), 2);
+        assert_eq!(get("cells"), 4);
+        assert_eq!(get("links"), 3);
+        assert_eq!(get("deadends"), 2);
+        assert_eq!(get("junctions3"), 0);
+        assert_eq!(get("junctions4"), 0);
+        assert_eq!(get("corridors"), 2);
+        assert_eq!(get("horizlinks"), 2);
+        assert_eq!(get("vertlinks"), 1);
+        assert_eq!(get("longest"), 3);
+        assert_eq!(get("perfect"), 1);
+    }
+
+    #[test]
+    fn test_grid_stats_generated() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(5, 6);
+        crate::recursive_backtracker(&mut grid);
+        let dead_ends = grid.dead_ends().len() as MoltInt;
+        make_grid_object(&mut interp, "g", grid);
+
+        let stats = interp.eval("g stats").unwrap();
+        let dict = stats.as_dict().unwrap();
+        let get = |key: &str| dict[&Value::from(key)].as_int().unwrap();
+
+        assert_eq!(get("cells"), 30);
+        assert_eq!(get("links"), 29);
+        assert_eq!(get("deadends"), dead_ends);
+        assert_eq!(get("perfect"), 1);
+
+        interp.eval("g clear").unwrap();
+        let stats = interp.eval("g stats").unwrap();
+        assert_eq!(
+            stats.as_dict().unwrap()[&Value::from("perfect")]
+                .as_int()
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_grid_info() {
+        let mut interp = test_interp();
+        test_grid(&mut interp);
+
+        let info = interp.eval("g info").unwrap();
+        let dict = info.as_dict().unwrap();
+        let get = |key: &str| dict[&Value::from(key)].clone();
+
+        let keys: Vec<String> = info
+            .as_list()
+            .unwrap()
+            .iter()
+            .step_by(2)
+            .map(|k| k.to_string())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "rows",
+                "cols",
+                "cells",
+                "links",
+                "deadends",
+                "connected",
+                "components",
+                "longest",
+                "revision"
+            ]
+        );
+
+        assert_eq!(get("rows"), interp.eval("g rows").unwrap());
+        assert_eq!(get("cols"), interp.eval("g cols").unwrap());
+        assert_eq!(get("cells").as_int().unwrap(), 4);
+        assert_eq!(get("links").as_int().unwrap(), 3);
+        assert_eq!(get("deadends"), interp.eval("g deadendcount").unwrap());
+        assert!(get("connected").as_bool().unwrap());
+        assert_eq!(get("components").as_int().unwrap(), 1);
+        assert_eq!(get("longest").as_int().unwrap(), 3);
+
+        // The revision only changes when the grid does.
+        let rev = get("revision").as_int().unwrap();
+        assert_eq!(interp.eval("g info").unwrap(), info);
+        interp.eval("g unlink 0 1").unwrap();
+        let info = interp.eval("g info").unwrap();
+        let dict = info.as_dict().unwrap();
+        assert!(dict[&Value::from("revision")].as_int().unwrap() > rev);
+        assert!(!dict[&Value::from("connected")].as_bool().unwrap());
+        assert_eq!(dict[&Value::from("components")].as_int().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_mazegen_defaults() {
+        let mut interp = test_interp();
+
+        let defaults = interp.eval("mazegen defaults").unwrap();
+        let dict = defaults.as_dict().unwrap();
+        assert_eq!(dict.len(), 2);
+
+        let image = dict[&Value::from("image")].as_dict().unwrap();
+        let get = |key: &str| image[&Value::from(key)].to_string();
+        assert_eq!(image.len(), 4);
+        assert_eq!(get("cellsize"), "10");
+        assert_eq!(get("borderwidth"), "1");
+        assert_eq!(get("wallcolor"), "#000000");
+        assert_eq!(get("floorcolor"), "#ffffff");
+
+        let text = dict[&Value::from("text")].as_dict().unwrap();
+        assert_eq!(text.len(), 1);
+        assert_eq!(text[&Value::from("cellwidth")].as_int().unwrap(), 3);
+
+        // The defaults are the renderers' defaults.
+        interp.eval("grid g 2 3").unwrap();
+        interp.eval("g renderimage img").unwrap();
+        assert_eq!(interp.eval("img width").unwrap().as_int().unwrap(), 34);
+    }
+
+    #[test]
+    fn test_grid_text_viewport() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(6, 8);
+        crate::recursive_backtracker(&mut grid);
+        let expected = TextGridRenderer::new().viewport(2, 3, 2, 4).render(&grid);
+        make_grid_object(&mut interp, "g", grid);
+
+        assert_eq!(
+            interp.eval("g text -viewport {2 3 2 4}").unwrap().as_str(),
+            expected
+        );
+        assert!(interp.eval("g text -viewport {2 3 2}").is_err());
+        assert!(interp.eval("g text -viewport {2 3 2 -1}").is_err());
+        assert!(interp.eval("g text -viewport {2 3 2 x}").is_err());
+    }
+
+    #[test]
+    fn test_grid_text_format() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(1, 4);
+        grid.link_all();
+        make_grid_object(&mut interp, "g", grid);
+
+        let text = |interp: &mut Interp, opts: &str| {
+            let script = format!("g text -datalist {{5 35 255 1296}} -autowidth 1 {}", opts);
+            interp.eval(&script).unwrap().as_str().to_string()
+        };
+
+        assert_eq!(
+            text(&mut interp, "-format base36"),
+            "+-----+-----+-----+-----+\n|  5     z    73    100 |\n+-----+-----+-----+-----+\n"
+        );
+        assert_eq!(
+            text(&mut interp, "-format hex -maxcellwidth 2"),
+            "+--+--+--+--+\n|5  23 ff 5~|\n+--+--+--+--+\n"
+        );
+        assert_eq!(
+            text(&mut interp, "-format {modulo 100}"),
+            text(&mut interp, "-format decimal -datalist {5 35 55 96}")
+        );
+
+        assert!(interp.eval("g text -format octal").is_err());
+        assert!(interp.eval("g text -format {modulo 0}").is_err());
+        assert!(interp.eval("g text -maxcellwidth 0").is_err());
+    }
+
+    #[test]
+    fn test_grid_save_load() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(6, 8);
+        crate::hunt_and_kill(&mut grid);
+        make_grid_object(&mut interp, "g", grid);
+
+        let path = std::env::temp_dir().join(format!("mazegen-save-{}.json", std::process::id()));
+        let path = path.display().to_string();
+
+        interp.eval(&format!("g save {{{}}}", path)).unwrap();
+        let name = interp.eval(&format!("grid load h {{{}}}", path)).unwrap();
+        assert_eq!(name.as_str(), "h");
+        assert_eq!(
+            interp.eval("h text").unwrap(),
+            interp.eval("g text").unwrap()
+        );
+
+        // Bad JSON
+        std::fs::write(&path, "{\"rows\": 2").unwrap();
+        let err = interp
+            .eval(&format!("grid load k {{{}}}", path))
+            .unwrap_err();
+        assert!(err.value().as_str().starts_with("invalid grid file"));
+
+        // Links that don't fit the dimensions
+        std::fs::write(&path, r#"{"rows":2,"cols":2,"links":[[0,5]]}"#).unwrap();
+        let err = interp
+            .eval(&format!("grid load k {{{}}}", path))
+            .unwrap_err();
+        assert!(err.value().as_str().starts_with("invalid grid file"));
+
+        // Missing file
+        std::fs::remove_file(&path).unwrap();
+        let err = interp
+            .eval(&format!("grid load k {{{}}}", path))
+            .unwrap_err();
+        assert!(err.value().as_str().starts_with("error reading grid file"));
+    }
+
+    #[test]
+    fn test_grid_bundle() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(5, 7);
+        crate::hunt_and_kill(&mut grid);
+        make_grid_object(&mut interp, "g", grid);
+
+        let path = std::env::temp_dir().join(format!("mazegen-bundle-{}.mzb", std::process::id()));
+        let path_str = path.display().to_string();
+
+        interp
+            .eval(&format!("g bundle {{{}}} {{title Test seed 42}}", path_str))
+            .unwrap();
+        let name = interp
+            .eval(&format!("grid frombundle h {{{}}}", path_str))
+            .unwrap();
+        assert_eq!(name.as_str(), "h");
+        assert_eq!(
+            interp.eval("h text").unwrap(),
+            interp.eval("g text").unwrap()
+        );
+
+        let reader = read_bundle(&path).unwrap();
+        assert_eq!(reader.meta().unwrap()["seed"], "42");
+        assert_eq!(reader.blob_names(), vec!["maze.png"]);
+        assert!(reader.blob("maze.png").unwrap().starts_with(b"\x89PNG"));
+
+        // Not a bundle
+        std::fs::write(&path, "{\"rows\": 2}").unwrap();
+        let err = interp
+            .eval(&format!("grid frombundle k {{{}}}", path_str))
+            .unwrap_err();
+        assert!(err
+            .value()
+            .as_str()
+            .starts_with("error reading bundle file"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_grid_components() {
+        let mut interp = test_interp();
+
+        // No links
+        interp.eval("grid g 2 2").unwrap();
+        assert!(!interp.eval("g isperfect").unwrap().as_bool().unwrap());
+        assert_eq!(interp.eval("g components").unwrap().as_str(), "0 1 2 3");
+
+        // Two components
+        interp.eval("g link 0 1; g link 1 3").unwrap();
+        assert_eq!(interp.eval("g components").unwrap().as_str(), "{0 1 3} 2");
+        assert_eq!(
+            interp.eval("g components -pairs").unwrap().as_str(),
+            "{{0 0} {0 1} {1 1}} {{1 0}}"
+        );
+        assert!(interp.eval("g components -nonesuch").is_err());
+
+        // A perfect maze, and then a braided one
+        interp.eval("g link 2 3").unwrap();
+        assert!(interp.eval("g isperfect").unwrap().as_bool().unwrap());
+        assert_eq!(interp.eval("g components").unwrap().as_str(), "{0 1 2 3}");
+
+        interp.eval("g link 0 2").unwrap();
+        assert!(!interp.eval("g isperfect").unwrap().as_bool().unwrap());
+        assert_eq!(interp.eval("g components").unwrap().as_str(), "{0 1 2 3}");
+    }
+
+    #[test]
+    fn test_grid_size() {
+        let mut interp = test_interp();
+
+        assert!(interp.eval("grid g 1 10").is_ok());
+        assert!(interp.eval("grid h 10 1").is_ok());
+        assert!(interp.eval("grid z 0 5").is_err());
+        assert!(interp.eval("grid z 5 0").is_err());
+    }
+
+    #[test]
+    fn test_grid_shaped() {
+        let mut interp = test_interp();
+
+        interp
+            .eval("grid shaped g {\n    XoooX\n    ooooo\n    XoooX\n}")
+            .unwrap();
+        assert_eq!(interp.eval("g rows").unwrap().as_int().unwrap(), 3);
+        assert_eq!(interp.eval("g cols").unwrap().as_int().unwrap(), 5);
+        assert_eq!(interp.eval("g neighbors 0").unwrap().as_str(), "");
+
+        assert!(interp.eval("grid shaped h {oo\no}").is_err());
+    }
+
+    #[test]
+    fn test_grid_textdiff() {
+        let mut interp = test_interp();
+        test_grid(&mut interp);
+        interp.eval("grid h 2 2").unwrap();
+        interp.eval("h link 0 2").unwrap();
+        interp.eval("h link 2 3").unwrap();
+
+        assert_eq!(
+            interp.eval("g textdiff h").unwrap().as_str(),
+            "+---+---+\n|   =   |\n+   +---+\n|       |\n+---+---+\n"
+        );
+        assert_eq!(
+            interp.eval("g textdiff g").unwrap().as_str(),
+            interp.eval("g text").unwrap().as_str()
+        );
+
+        interp.eval("grid w 2 3").unwrap();
+        assert!(interp.eval("g textdiff w").is_err());
+        assert!(interp.eval("g textdiff nonesuch").is_err());
+
+        // Commands that aren't grid objects aren't called.
+        let err = interp.eval("g textdiff set").unwrap_err();
+        assert_eq!(err.value().as_str(), "expected a grid, got \"set\"");
+
+        // A renamed object is found by its new name once it's been used; a deleted object
+        // isn't a grid.
+        interp.eval("rename h h2").unwrap();
+        interp.eval("h2 cells").unwrap();
+        assert!(interp.eval("g textdiff h").is_err());
+        assert!(interp.eval("h2 textdiff g").is_ok());
+        assert!(interp.eval("g textdiff h2").is_ok());
+        interp.eval("rename h2 {}").unwrap();
+        assert!(interp.eval("g textdiff h2").is_err());
+
+        // Overwriting the state variable doesn't forge a registry.
+        interp.eval("set mazegen::state bogus").unwrap();
+        interp.eval("grid k 2 2").unwrap();
+        assert!(interp.eval("k textdiff k").is_ok());
+        assert!(interp.eval("k textdiff g").is_err());
+    }
+
+    #[test]
+    fn test_grid_json() {
+        let mut interp = test_interp();
+        test_grid(&mut interp);
+
+        assert_eq!(
+            interp.eval("g json").unwrap().as_str(),
+            r#"{"rows":2,"cols":2,"links":[[0,1],[0,2],[2,3]]}"#
+        );
+        assert!(interp.eval("g json extra").is_err());
+    }
+
+    #[test]
+    fn test_grid_distances() {
+        let mut interp = test_interp();
+        interp.eval("grid g 2 2").unwrap();
+        interp.eval("g link 0 1").unwrap();
+
+        // By default, unreachable cells have no distance.
+        assert_eq!(interp.eval("g distances 0").unwrap().as_str(), "0 1 {} {}");
+
+        // With a sentinel, every cell has a distance.
+        let list = interp.eval("g distances 0 -unreachable -1").unwrap();
+        let list: Vec<MoltInt> = list
+            .as_list()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_int().unwrap())
+            .collect();
+        assert_eq!(list, vec![0, 1, -1, -1]);
+
+        let dict = interp.eval("g distances 0 -dict -unreachable -1").unwrap();
+        let dict = dict.as_dict().unwrap();
+        assert_eq!(dict.len(), 4);
+        assert!(dict.values().all(|v| v.as_int().is_ok()));
+
+        assert!(interp.eval("g distances 0 -unreachable").is_err());
+        assert!(interp.eval("g distances 0 -bogus").is_err());
+    }
+
+    #[test]
+    fn test_grid_cell_names() {
+        let mut interp = test_interp();
+        interp.eval("grid g 4 5").unwrap();
+
+        // A name where i j was expected
+        assert_eq!(interp.eval("g cell B3").unwrap().as_int().unwrap(), 11);
+        assert_eq!(interp.eval("g cell 2 1").unwrap().as_int().unwrap(), 11);
+        assert!(interp.eval("g cell 2").is_err());
+        assert!(interp.eval("g cell B3 1").is_err());
+        assert!(interp.eval("g cell F1").is_err());
+
+        // A name where a cell ID was expected
+        interp.eval("g link B3 C3").unwrap();
+        assert!(interp.eval("g linked 11 12").unwrap().as_bool().unwrap());
+        assert_eq!(interp.eval("g ij B3").unwrap().as_str(), "2 1");
+        assert!(interp.eval("g links nonesuch").is_err());
+
+        assert!(interp.eval("g fairstarts B3").is_ok());
+        assert!(interp.eval("g fairstarts B3 2").is_ok());
+        assert!(interp.eval("g fairstarts B3 2 2").is_err());
+    }
+
+    #[test]
+    fn test_grid_deadendcount() {
+        let mut interp = test_interp();
+        test_grid(&mut interp);
+
+        assert_eq!(interp.eval("g deadendcount").unwrap().as_int().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_grid_renderpages() {
+        let mut interp = test_interp();
+        interp.eval("grid g 10 12").unwrap();
+
+        assert!(interp.eval("g renderpages out").is_err());
+        assert!(interp
+            .eval("g renderpages out -pagesize {30 30} -overlap 1")
+            .is_err());
+        assert!(interp.eval("g renderpages out -pagesize 100").is_err());
+
+        let dir = std::env::temp_dir().join(format!("mazegen-pages-{}", std::process::id()));
+        let script = format!(
+            "g renderpages {{{}}} -pagesize {{72 72}} -overlap 1",
+            dir.display()
+        );
+
+        // 5x5 cells fit on a page, so there are 3x3 pages.
+        assert_eq!(interp.eval(&script).unwrap().as_int().unwrap(), 9);
+        assert!(dir.join("page-009.png").exists());
+
+        let index = std::fs::read_to_string(dir.join("index.txt")).unwrap();
+        assert_eq!(index.lines().count(), 9);
+        assert_eq!(index.lines().next().unwrap(), "page-001.png 0 0 0 5 0 5");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_grid_render_colordict() {
+        let mut interp = test_interp();
+        interp.eval("grid g 3 3").unwrap();
+
+        assert!(interp
+            .eval("g render out.png -colordict {0 nonesuch}")
+            .is_err());
+        assert!(interp
+            .eval("g render out.png -colordict {9 #FF0000}")
+            .is_err());
+
+        let path = std::env::temp_dir().join(format!("mazegen-color-{}.png", std::process::id()));
+        let script = format!(
+            "g render {{{}}} -colordict {{0 #FF0000 B2 #00FF00}}",
+            path.display()
+        );
+        interp.eval(&script).unwrap();
+
+        // Cells are 10 pixels with 1 pixel borders; B2 is cell (1,1).
+        let image = image::open(&path).unwrap();
+        let image = image.as_rgba8().unwrap();
+        assert_eq!(image.get_pixel(5, 5).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(16, 16).0, [0, 255, 0, 255]);
+        assert_eq!(image.get_pixel(27, 27).0, [255, 255, 255, 255]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_grid_render_shade() {
+        let mut interp = test_interp();
+        interp.eval("grid g 1 3").unwrap();
+
+        assert!(interp.eval("g renderimage img -shade {0 1}").is_err());
+        assert!(interp.eval("g renderimage img -shade {0 x 2}").is_err());
+
+        // Shades run from white to blue; cells without values, and colored cells, aren't
+        // shaded.
+        interp.eval("g renderimage img -shade {0 {} 10}").unwrap();
+        assert_eq!(interp.eval("img get 5 5").unwrap().as_str(), "#ffffff");
+        assert_eq!(interp.eval("img get 27 5").unwrap().as_str(), "#0000ff");
+
+        interp
+            .eval("g renderimage img -shade {0 5 10} -colordict {2 #FF0000}")
+            .unwrap();
+        assert_eq!(interp.eval("img get 16 5").unwrap().as_str(), "#8080ff");
+        assert_eq!(interp.eval("img get 27 5").unwrap().as_str(), "#ff0000");
+    }
+
+    #[test]
+    fn test_grid_renderimage() {
+        let mut interp = test_interp();
+        interp.eval("grid g 3 4").unwrap();
+
+        assert!(interp.eval("g renderimage img -cellsize 0").is_err());
+
+        // Width is 4 cells of 8 pixels and 5 borders of 2 pixels; height is 3 and 4.
+        let name = interp
+            .eval("g renderimage img -cellsize 8 -borderwidth 2")
+            .unwrap();
+        assert_eq!(name.as_str(), "img");
+        assert_eq!(interp.eval("img width").unwrap().as_int().unwrap(), 42);
+        assert_eq!(interp.eval("img height").unwrap().as_int().unwrap(), 32);
+
+        // Rendering again replaces the image.
+        interp.eval("g renderimage img").unwrap();
+        assert_eq!(interp.eval("img width").unwrap().as_int().unwrap(), 45);
+        assert_eq!(interp.eval("img height").unwrap().as_int().unwrap(), 34);
+    }
+
+    #[test]
+    fn test_grid_render_wallwidth() {
+        let mut interp = test_interp();
+        interp.eval("grid g 3 4").unwrap();
+
+        assert!(interp.eval("g renderimage img -wallwidth 0").is_err());
+
+        // Width is 2 borders of 6 pixels, 4 cells of 8 pixels, and 3 walls of 1 pixel.
+        interp
+            .eval("g renderimage img -cellsize 8 -borderwidth 6 -wallwidth 1")
+            .unwrap();
+        assert_eq!(interp.eval("img width").unwrap().as_int().unwrap(), 47);
+        assert_eq!(interp.eval("img height").unwrap().as_int().unwrap(), 38);
+    }
+
+    #[test]
+    fn test_grid_render_inset() {
+        let mut interp = test_interp();
+        interp.eval("grid g 1 2").unwrap();
+        interp.eval("g link 0 1").unwrap();
+
+        assert!(interp.eval("g renderimage img -inset -1").is_err());
+        assert!(interp.eval("g renderimage img -inset 5").is_err());
+        assert!(interp
+            .eval("g renderimage img -inset 4 -cellsize 8")
+            .is_err());
+
+        // Cell 0's floor is 3..9, and the corridor to cell 1 crosses the wall at x = 11.
+        interp.eval("g renderimage img -inset 2").unwrap();
+        assert_eq!(interp.eval("img width").unwrap().as_int().unwrap(), 23);
+        assert_eq!(interp.eval("img get 11 5").unwrap().as_str(), "#ffffff");
+        assert_eq!(interp.eval("img get 11 1").unwrap().as_str(), "#000000");
+        assert_eq!(interp.eval("img get 2 5").unwrap().as_str(), "#000000");
+    }
+
+    #[test]
+    fn test_grid_render_channel() {
+        let mut interp = test_interp();
+        interp.eval("grid g 3 4").unwrap();
+
+        assert!(interp.eval("g renderimage img -channel 0").is_err());
+        assert!(interp.eval("g renderimage img -channel 1.5").is_err());
+
+        // The border width is ignored in channel mode.
+        interp
+            .eval("g renderimage img -cellsize 8 -borderwidth 2 -channel 0.5")
+            .unwrap();
+        assert_eq!(interp.eval("img width").unwrap().as_int().unwrap(), 32);
+        assert_eq!(interp.eval("img height").unwrap().as_int().unwrap(), 24);
+    }
+
+    #[test]
+    fn test_grid_braid() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(10, 10);
+        crate::recursive_backtracker(&mut grid);
+        make_grid_object(&mut interp, "g", grid);
+
+        let before = interp.eval("g deadendcount").unwrap().as_int().unwrap();
+        let removed = interp.eval("g braid 0.5").unwrap().as_int().unwrap();
+        let after = interp.eval("g deadendcount").unwrap().as_int().unwrap();
+        assert_eq!(before - removed, after);
+
+        interp.eval("g braid 1.0").unwrap();
+        assert_eq!(interp.eval("g deadends").unwrap().as_str(), "");
+        assert_eq!(interp.eval("g braid 1.0").unwrap().as_int().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_grid_braid_farthest_shortest() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(6, 8);
+        crate::recursive_backtracker(&mut grid);
+        make_grid_object(&mut interp, "g", grid);
+
+        assert!(interp.eval("g braid 1.5").is_err());
+        assert!(interp.eval("g braid -0.1").is_err());
+        assert!(interp.eval("g braid x").is_err());
+        assert!(interp.eval("g farthest 6 0").is_err());
+        assert!(interp.eval("g farthest 0").is_err());
+        assert!(interp.eval("g shortest 0 48").is_err());
+
+        // The path to the farthest cell ends at the farthest cell's coordinates.
+        let far = interp
+            .eval("g farthest 0 0")
+            .unwrap()
+            .as_list()
+            .unwrap()
+            .to_vec();
+        assert_eq!(far.len(), 2);
+        let path = interp
+            .eval("g shortest 0")
+            .unwrap()
+            .as_list()
+            .unwrap()
+            .to_vec();
+        let last = path[path.len() - 1].as_int().unwrap();
+        assert_eq!(last / 8, far[0].as_int().unwrap());
+        assert_eq!(last % 8, far[1].as_int().unwrap());
+
+        let path = interp
+            .eval("g shortest A1 H6")
+            .unwrap()
+            .as_list()
+            .unwrap()
+            .to_vec();
+        assert_eq!(path[0].as_int().unwrap(), 0);
+        assert_eq!(path[path.len() - 1].as_int().unwrap(), 47);
+
+        assert_eq!(interp.eval("g braid 0.0").unwrap().as_int().unwrap(), 0);
+        let count = interp.eval("g deadendcount").unwrap().as_int().unwrap();
+        assert!(count > 0);
+        assert_eq!(interp.eval("g braid").unwrap().as_int().unwrap(), count);
+        assert_eq!(interp.eval("g deadends").unwrap().as_str(), "");
+        assert_eq!(interp.eval("g validate").unwrap().as_str(), "");
+    }
+
+    #[test]
+    fn test_grid_path_formats() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(5, 6);
+        crate::recursive_backtracker(&mut grid);
+        let longest = grid.longest_path();
+        let shortest = grid.shortest_path(0, 29);
+        make_grid_object(&mut interp, "g", grid.clone());
+
+        let flat = interp.eval("g longest -flat").unwrap();
+        assert_eq!(flat, interp.eval("g longest").unwrap());
+        assert_eq!(CellPath::from_molt(&grid, &flat).unwrap(), longest);
+
+        let pairs = interp.eval("g longest -pairs").unwrap();
+        assert_eq!(CellPath::from_molt(&grid, &pairs).unwrap(), longest);
+
+        let pairs = interp.eval("g shortest 0 29 -pairs").unwrap();
+        assert_eq!(CellPath::from_molt(&grid, &pairs).unwrap(), shortest);
+        let pairs = interp.eval("g shortest 0 -pairs").unwrap();
+        assert_eq!(pairs.as_list().unwrap()[0].as_str(), "0 0");
+
+        assert!(interp.eval("g longest -bogus").is_err());
+        assert!(interp.eval("g shortest 0 29 -bogus").is_err());
+        assert!(interp.eval("g shortest 0 29 5").is_err());
+    }
+
+    // Builds the distance list the straightforward way, one value per cell.
+    fn naive_distances(grid: &Grid, cell: Cell) -> Value {
+        let list: MoltList = grid
+            .distances(cell)
+            .iter()
+            .map(|d| match d {
+                Some(d) => Value::from(*d as MoltInt),
+                None => Value::empty(),
+            })
+            .collect();
+        Value::from(list)
+    }
+
+    #[test]
+    fn test_grid_distances_shared_values() {
+        let mut interp = test_interp();
+        let mut grid = Grid::new(12, 15);
+        crate::recursive_backtracker(&mut grid);
+        grid.isolate(7);
+        make_grid_object(&mut interp, "g", grid.clone());
+
+        let list = interp.eval("g distances 0").unwrap();
+        assert_eq!(list.as_str(), naive_distances(&grid, 0).as_str());
+
+        let dict = interp.eval("g distances 0 -dict -unreachable x").unwrap();
+        let dict = dict.as_dict().unwrap();
+        assert_eq!(dict.get(&Value::from(7)).unwrap().as_str(), "x");
+        assert_eq!(dict.get(&Value::from(0)).unwrap().as_int().unwrap(), 0);
+    }
+}
@@ -2,24 +2,41 @@
 use crate::MoltPixel;
 use image::ImageBuffer;
 use image::RgbaImage;
+use crate::molt_ensemble::call_ensemble;
+use crate::molt_ensemble::check_subcommand_args;
+use crate::molt_ensemble::subcommand_help;
+use crate::molt_ensemble::subcommand_names;
+use crate::molt_ensemble::SubcommandSpec;
+use crate::molt_install::add_commands;
+use crate::molt_install::object_name;
 use molt::check_args;
 use molt::molt_err;
 use molt::molt_ok;
 use molt::types::*;
 use molt::Interp;
 
-/// Installs the Molt image commands into the interpreter.
+/// Installs the Molt image commands unprefixed; see `install_with_prefix`, which reports
+/// whether they could be installed.
 pub fn install(interp: &mut Interp) {
-    interp.add_command("image", cmd_image);
-    interp.add_command("pixel", cmd_pixel);
+    let _ = install_with_prefix(interp, "");
 }
 
+/// Installs the Molt image commands, "image" and "pixel", into the interpreter with the
+/// given prefix: given "mz", "image" is installed as "mz::image".  An empty prefix installs
+/// them unprefixed.  Installing them again does nothing; returns an error, installing
+/// nothing, if a command of the same name exists that they didn't install.
+pub fn install_with_prefix(interp: &mut Interp, prefix: &str) -> Result<(), String> {
+    add_commands(interp, prefix, &IMAGE_COMMANDS)
+}
+
+const IMAGE_COMMANDS: [(&str, CommandFunc); 2] = [("image", cmd_image), ("pixel", cmd_pixel)];
+
 /// Image constructor: creates a new grid called "name" with a specified width and height
 pub fn cmd_image(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
     check_args(1, argv, 4, 4, "name width height")?;
 
-    let name = argv[1].as_str();
+    let name = object_name(&argv[0], argv[1].as_str());
     let width = argv[2].as_int()?;
     let height = argv[3].as_int()?;
 
@@ -29,7 +46,7 @@ pub fn cmd_image(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResul
 
     let image: RgbaImage = ImageBuffer::new(width as u32, height as u32);
 
-    make_image_object(interp, name, image);
+    make_image_object(interp, &name, image);
     molt_ok!(name)
 }
 
@@ -40,23 +57,49 @@ pub fn make_image_object(interp: &mut Interp, name: &str, image: RgbaImage) {
 }
 
 fn obj_image(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
-    interp.call_subcommand(ctx, argv, 1, &OBJ_IMAGE_SUBCOMMANDS)
+    call_ensemble(interp, ctx, argv, &OBJ_IMAGE_SUBCOMMANDS)
 }
 
-const OBJ_IMAGE_SUBCOMMANDS: [Subcommand; 7] = [
-    Subcommand("clear", obj_image_clear),
-    Subcommand("dump", obj_image_dump),
-    Subcommand("get", obj_image_get),
-    Subcommand("height", obj_image_height),
-    Subcommand("put", obj_image_put),
-    Subcommand("save", obj_image_save),
-    Subcommand("width", obj_image_width),
+const OBJ_IMAGE_SUBCOMMANDS: [SubcommandSpec; 15] = [
+    SubcommandSpec("clear", obj_image_clear, "fill"),
+    SubcommandSpec("dump", obj_image_dump, ""),
+    SubcommandSpec("fillrect", obj_image_rect, "x y width height color"),
+    SubcommandSpec("get", obj_image_get, "x y"),
+    SubcommandSpec("getpixel", obj_image_get, "x y"),
+    SubcommandSpec("height", obj_image_height, ""),
+    SubcommandSpec("help", obj_image_help, "subcommand"),
+    SubcommandSpec("line", obj_image_line, "x0 y0 x1 y1 color"),
+    SubcommandSpec("put", obj_image_put, "x y ?pixel?"),
+    SubcommandSpec("rect", obj_image_rect, "x y width height color"),
+    SubcommandSpec("save", obj_image_save, "filename"),
+    SubcommandSpec("set", obj_image_put, "x y ?pixel?"),
+    SubcommandSpec("setpixel", obj_image_put, "x y ?pixel?"),
+    SubcommandSpec("subcommands", obj_image_subcommands, ""),
+    SubcommandSpec("width", obj_image_width, ""),
 ];
 
+// $image help subcommand
+//
+// Returns the usage of the named subcommand: its name and argument signature.
+fn obj_image_help(_: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 3, 3)?;
+    subcommand_help(&OBJ_IMAGE_SUBCOMMANDS, &argv[2])
+}
+
+// $image subcommands
+//
+// Returns the names of the image object's subcommands, e.g., for tab completion.
+fn obj_image_subcommands(_: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 2, 2)?;
+    subcommand_names(&OBJ_IMAGE_SUBCOMMANDS)
+}
+
 // Clears the image to a given pixel.
 fn obj_image_clear(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 3, "fill")?;
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 2, 3)?;
     let image = interp.context::<RgbaImage>(ctx);
 
     let pixel: MoltPixel = if argv.len() == 3 {
@@ -77,7 +120,7 @@ fn obj_image_clear(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltR
 // Dumps the pixels in the image to std out.
 fn obj_image_dump(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "dump")?;
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 2, 2)?;
     let image = interp.context::<RgbaImage>(ctx);
 
     for x in 0..image.width() {
@@ -92,15 +135,87 @@ fn obj_image_dump(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRe
 // Gets the height of the image, in pixels.
 fn obj_image_height(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "")?;
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 2, 2)?;
     let image = interp.context::<RgbaImage>(ctx);
     molt_ok!(image.height() as MoltInt)
 }
 
+// $image line x0 y0 x1 y1 color
+//
+// Draws a one-pixel line from (x0,y0) to (x1,y1), inclusive.
+fn obj_image_line(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 7, 7)?;
+    let image = interp.context::<RgbaImage>(ctx);
+
+    let (x0, y0) = get_image_coords(image, &argv[2], &argv[3])?;
+    let (x1, y1) = get_image_coords(image, &argv[4], &argv[5])?;
+    let pixel = MoltPixel::from_molt(&argv[6])?;
+
+    // Bresenham's algorithm, generalized to all octants.
+    let (mut x, mut y) = (x0 as i64, y0 as i64);
+    let (x1, y1) = (x1 as i64, y1 as i64);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    loop {
+        image.put_pixel(x as u32, y as u32, pixel.ipixel());
+
+        if x == x1 && y == y1 {
+            break;
+        }
+
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+
+    molt_ok!()
+}
+
+// $image rect x y width height color
+//
+// Fills the rectangle whose upper-left corner is at (x,y) with the color.
+fn obj_image_rect(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 7, 7)?;
+    let image = interp.context::<RgbaImage>(ctx);
+
+    let (x, y) = get_image_coords(image, &argv[2], &argv[3])?;
+    let w = argv[4].as_int()?;
+    let h = argv[5].as_int()?;
+    let pixel = MoltPixel::from_molt(&argv[6])?;
+
+    if w < 1 || x as MoltInt + w > image.width() as MoltInt {
+        return molt_err!("width is out of range: \"{}\"", w);
+    }
+
+    if h < 1 || y as MoltInt + h > image.height() as MoltInt {
+        return molt_err!("height is out of range: \"{}\"", h);
+    }
+
+    for px in x..x + w as u32 {
+        for py in y..y + h as u32 {
+            image.put_pixel(px, py, pixel.ipixel());
+        }
+    }
+
+    molt_ok!()
+}
+
 // Sets a pixel.
 fn obj_image_put(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 4, 5, "x y ?pixel?")?;
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 4, 5)?;
     let image = interp.context::<RgbaImage>(ctx);
 
     let (x, y) = get_image_coords(&image, &argv[2], &argv[3])?;
@@ -108,7 +223,7 @@ fn obj_image_put(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRes
     let pixel: MoltPixel = if argv.len() == 5 {
         MoltPixel::from_molt(&argv[4])?
     } else {
-        MoltPixel::rgb(0, 0, 0) // White
+        MoltPixel::rgb(0, 0, 0) // Black
     };
 
     image.put_pixel(x, y, pixel.ipixel());
@@ -119,7 +234,7 @@ fn obj_image_put(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRes
 // Gets a pixel.
 fn obj_image_get(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 4, 4, "x y")?;
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 4, 4)?;
     let image = interp.context::<RgbaImage>(ctx);
 
     let (x, y) = get_image_coords(&image, &argv[2], &argv[3])?;
@@ -134,7 +249,7 @@ fn obj_image_get(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRes
 // Saves the content of the image to disk.
 fn obj_image_save(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 3, 3, "filename")?;
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 3, 3)?;
     let image = interp.context::<RgbaImage>(ctx);
     let filename = argv[2].as_str();
 
@@ -148,7 +263,7 @@ fn obj_image_save(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltRe
 // Gets the width of the image, in pixels.
 fn obj_image_width(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
     // Correct number of arguments?
-    check_args(2, argv, 2, 2, "")?;
+    check_subcommand_args(&OBJ_IMAGE_SUBCOMMANDS, argv, 2, 2)?;
     let image = interp.context::<RgbaImage>(ctx);
     molt_ok!(image.width() as MoltInt)
 }
@@ -190,9 +305,15 @@ const PIXEL_SUBCOMMANDS: [Subcommand; 5] = [
 ];
 
 // pixel from *r g b* ?*a*?
+// pixel from *color*
 //
-// Constructs a pixel from components.
+// Constructs a pixel from components, or from a pixel string or color name.
 fn cmd_pixel_from(_: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Is it a pixel string or color name?
+    if argv.len() == 3 {
+        return molt_ok!(Value::from_other(MoltPixel::from_molt(&argv[2])?));
+    }
+
     // Correct number of arguments?
     check_args(2, argv, 5, 6, "r g b ?a?")?;
 
@@ -266,3 +387,131 @@ fn get_unsigned_byte(arg: &Value) -> Result<u8, Exception> {
         molt_err!("expected unsigned byte, got \"{}\"", num)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Creates an interpreter with the image commands installed, and a 10x8 white image
+    // called "img".
+    fn test_interp() -> Interp {
+        let mut interp = Interp::new();
+        install(&mut interp);
+        interp.eval("image img 10 8").unwrap();
+        interp.eval("img clear").unwrap();
+        interp
+    }
+
+    // Gets the pixel at (x,y) as a string.
+    fn pixel_at(interp: &mut Interp, x: u32, y: u32) -> String {
+        interp
+            .eval(&format!("img getpixel {} {}", x, y))
+            .unwrap()
+            .as_string()
+    }
+
+    #[test]
+    fn test_image_introspection() {
+        let mut interp = test_interp();
+        crate::molt_ensemble::check_introspection(&mut interp, "img");
+        assert_eq!(interp.eval("img help get").unwrap().as_str(), "get x y");
+    }
+
+    #[test]
+    fn test_image_setpixel() {
+        let mut interp = test_interp();
+
+        interp.eval("img setpixel 3 4 #ff0000").unwrap();
+        assert_eq!(pixel_at(&mut interp, 3, 4), "#ff0000");
+        assert_eq!(pixel_at(&mut interp, 4, 3), "#ffffff");
+
+        assert!(interp.eval("img setpixel 10 0 #ff0000").is_err());
+        assert!(interp.eval("img getpixel 0 -1").is_err());
+    }
+
+    #[test]
+    fn test_image_line() {
+        let mut interp = test_interp();
+
+        // A diagonal line, drawn from either end.
+        interp.eval("img line 0 0 3 3 #000000").unwrap();
+        interp.eval("img line 9 7 6 4 #000000").unwrap();
+        for i in 0..4 {
+            assert_eq!(pixel_at(&mut interp, i, i), "#000000");
+            assert_eq!(pixel_at(&mut interp, 9 - i, 7 - i), "#000000");
+        }
+        assert_eq!(pixel_at(&mut interp, 1, 0), "#ffffff");
+
+        // A shallow line touches every column once.
+        interp.eval("img clear").unwrap();
+        interp.eval("img line 0 7 9 5 #0000ff").unwrap();
+        for x in 0..10 {
+            let count = (0..8)
+                .filter(|y| pixel_at(&mut interp, x, *y) == "#0000ff")
+                .count();
+            assert_eq!(count, 1);
+        }
+        assert_eq!(pixel_at(&mut interp, 0, 7), "#0000ff");
+        assert_eq!(pixel_at(&mut interp, 9, 5), "#0000ff");
+
+        assert!(interp.eval("img line 0 0 10 0 #000000").is_err());
+    }
+
+    #[test]
+    fn test_image_rect() {
+        let mut interp = test_interp();
+
+        interp.eval("img rect 2 3 4 2 #00ff00").unwrap();
+        assert_eq!(pixel_at(&mut interp, 2, 3), "#00ff00");
+        assert_eq!(pixel_at(&mut interp, 5, 4), "#00ff00");
+        assert_eq!(pixel_at(&mut interp, 6, 4), "#ffffff");
+        assert_eq!(pixel_at(&mut interp, 5, 5), "#ffffff");
+        assert_eq!(pixel_at(&mut interp, 1, 3), "#ffffff");
+
+        // The whole image
+        assert!(interp.eval("img rect 0 0 10 8 #00ff00").is_ok());
+
+        assert!(interp.eval("img rect 2 3 9 2 #00ff00").is_err());
+        assert!(interp.eval("img rect 2 3 4 0 #00ff00").is_err());
+    }
+
+    #[test]
+    fn test_image_aliases() {
+        let mut interp = test_interp();
+
+        interp.eval("img set 1 2 #123456").unwrap();
+        assert_eq!(interp.eval("img get 1 2").unwrap().as_str(), "#123456");
+        interp.eval("img set 1 2 #12345680").unwrap_err();
+        interp.eval("img set 1 2 #123456.80").unwrap();
+        assert_eq!(interp.eval("img get 1 2").unwrap().as_str(), "#123456.80");
+
+        interp.eval("img fillrect 0 0 2 2 #ff0000").unwrap();
+        assert_eq!(pixel_at(&mut interp, 1, 1), "#ff0000");
+        assert_eq!(pixel_at(&mut interp, 1, 2), "#123456.80");
+    }
+
+    #[test]
+    fn test_image_errors() {
+        let mut interp = test_interp();
+        let error = |interp: &mut Interp, script: &str| {
+            interp.eval(script).unwrap_err().value().as_string()
+        };
+
+        assert_eq!(
+            error(&mut interp, "img set 12 0 #ff0000"),
+            "x coordinate is out of range: \"12\""
+        );
+        assert_eq!(
+            error(&mut interp, "img get 0 8"),
+            "y coordinate is out of range: \"8\""
+        );
+        assert_eq!(
+            error(&mut interp, "img line 0 0 3 -5 #000000"),
+            "y coordinate is out of range: \"-5\""
+        );
+        assert_eq!(
+            error(&mut interp, "img fillrect 5 5 3 4 #000000"),
+            "height is out of range: \"4\""
+        );
+    }
+}
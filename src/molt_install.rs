@@ -0,0 +1,244 @@
+//! Installing the Molt commands, optionally with a prefix.
+//!
+//! Each Molt module's `install_with_prefix` function installs its commands with names of
+//! the form "prefix::name", e.g., "mz::grid", so that they don't collide with a host
+//! application's own commands; its `install` function installs them unprefixed.  Object
+//! commands, such as grids and images, get the prefix of the command that creates them:
+//! `mz::grid g 2 3` creates "mz::g", and `mz::g renderimage img` creates "mz::img".
+
+use crate::molt_grid::GridRegistry;
+use molt::types::*;
+use molt::Interp;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Returns the name of a command installed with the given prefix: "prefix::name", or just
+/// "name" if the prefix is empty.
+pub(crate) fn prefixed_name(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", prefix, name)
+    }
+}
+
+/// Returns the name of an object called `name` created by the command invoked as
+/// `creator`, e.g., `argv[0]`: the object gets the creator's prefix, the part of its name
+/// before the last "::".  Molt can't say how a command was installed, so a renamed
+/// command's objects get its new prefix.
+pub(crate) fn object_name(creator: &Value, name: &str) -> String {
+    let prefix = creator
+        .as_str()
+        .rsplit_once("::")
+        .map_or("", |(prefix, _)| prefix);
+    prefixed_name(prefix, name)
+}
+
+/// The state that the mazegen commands share within an interpreter.
+#[derive(Default)]
+pub(crate) struct MoltState {
+    /// The grid objects, by name; see `molt_grid::make_grid_object`.
+    pub grids: Rc<GridRegistry>,
+
+    /// The names of the commands that `add_commands` has added, so that installing them
+    /// again can tell them from a host's commands of the same names; Molt can't say which
+    /// function a command is bound to.
+    pub installed: HashSet<String>,
+}
+
+/// The variable holding a handle to the interpreter's `MoltState`.
+const STATE_VAR: &str = "mazegen::state";
+
+/// A handle to an interpreter's `MoltState`.  Only Rust code can create one: a script can
+/// overwrite `STATE_VAR`, but not with a handle, so the commands then start over with a
+/// new, empty state rather than using a forged one.  In particular, the installed
+/// commands are then taken for a host's, and can't be installed over.
+#[derive(Debug, Clone, Copy)]
+struct StateHandle(ContextID);
+
+impl Display for StateHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "mazegen state")
+    }
+}
+
+impl FromStr for StateHandle {
+    type Err = String;
+
+    fn from_str(_: &str) -> Result<Self, Self::Err> {
+        Err("a mazegen state handle can't be parsed".into())
+    }
+}
+
+/// Gets the interpreter's `MoltState`, creating it if need be.  The state is saved as
+/// context data that no command owns, so Molt never drops it.
+pub(crate) fn molt_state(interp: &mut Interp) -> &mut MoltState {
+    let handle = interp
+        .scalar(STATE_VAR)
+        .ok()
+        .and_then(|value| value.as_other::<StateHandle>());
+
+    let ctx = match handle {
+        Some(handle) => handle.0,
+        None => {
+            let ctx = interp.save_context(MoltState::default());
+            interp
+                .set_scalar(STATE_VAR, Value::from_other(StateHandle(ctx)))
+                .expect("settable variable");
+            ctx
+        }
+    };
+
+    interp.context::<MoltState>(ctx)
+}
+
+/// Adds the commands to the interpreter with the given prefix, skipping any it has added
+/// before.  Returns an error, adding none of them, if a command of the same name exists
+/// that it didn't add, so that an installation never replaces a host's command.
+pub(crate) fn add_commands(
+    interp: &mut Interp,
+    prefix: &str,
+    commands: &[(&str, CommandFunc)],
+) -> Result<(), String> {
+    // FIRST, get the names, and the names installed so far.
+    let names: Vec<String> = commands
+        .iter()
+        .map(|(name, _)| prefixed_name(prefix, name))
+        .collect();
+
+    let installed = molt_state(interp).installed.clone();
+
+    // NEXT, make sure that no other command has any of the names.
+    if let Some(name) = names
+        .iter()
+        .find(|name| interp.has_command(name) && !installed.contains(*name))
+    {
+        return Err(format!("command already exists: \"{}\"", name));
+    }
+
+    // FINALLY, add the commands that aren't already there, and record them.
+    for (name, (_, func)) in names.iter().zip(commands) {
+        if !interp.has_command(name) {
+            interp.add_command(name, *func);
+        }
+    }
+
+    molt_state(interp).installed.extend(names);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Installs every module's commands with the prefix.
+    fn install_all(interp: &mut Interp, prefix: &str) -> Result<(), String> {
+        crate::molt_generator::install_with_prefix(interp, prefix)?;
+        crate::molt_grid::install_with_prefix(interp, prefix)?;
+        crate::molt_image::install_with_prefix(interp, prefix)?;
+        crate::molt_maze::install_with_prefix(interp, prefix)?;
+        crate::molt_rand::install_with_prefix(interp, prefix)
+    }
+
+    #[test]
+    fn test_install_with_prefix() {
+        let mut interp = Interp::new();
+        install_all(&mut interp, "mz").unwrap();
+        install_all(&mut interp, "").unwrap();
+
+        for name in &[
+            "generator",
+            "grid",
+            "image",
+            "mazegen",
+            "maze",
+            "pixel",
+            "rand",
+        ] {
+            assert!(interp.has_command(name));
+            assert!(interp.has_command(&format!("mz::{}", name)));
+        }
+
+        // The two sets of commands make separate objects, even with the same names.
+        let eval = |interp: &mut Interp, script| interp.eval(script).unwrap().to_string();
+        assert_eq!(eval(&mut interp, "mz::grid g 2 3"), "mz::g");
+        assert_eq!(eval(&mut interp, "grid g 4 5"), "g");
+        assert_eq!(eval(&mut interp, "mz::g cells"), "6");
+        assert_eq!(eval(&mut interp, "g cells"), "20");
+
+        // Objects get the prefix of the command that creates them, whether it's a
+        // constructor or another object.
+        assert_eq!(eval(&mut interp, "mz::maze generate m 3 3"), "mz::m");
+        assert_eq!(eval(&mut interp, "mz::m cells"), "9");
+        assert_eq!(eval(&mut interp, "mz::image img 4 4"), "mz::img");
+        assert_eq!(eval(&mut interp, "mz::g renderimage pic"), "mz::pic");
+        assert_eq!(eval(&mut interp, "mz::generator gen 2 2"), "mz::gen");
+        assert_eq!(eval(&mut interp, "mz::gen grid carved"), "mz::carved");
+        eval(
+            &mut interp,
+            "mz::maze batch backtracker 2 2 3 -keep k%d -best 2",
+        );
+        assert_eq!(eval(&mut interp, "mz::k2 cells"), "4");
+        assert_eq!(eval(&mut interp, "g renderimage pic"), "pic");
+
+        for name in &["m", "img", "gen", "carved", "k1", "mz::mz::pic"] {
+            assert!(!interp.has_command(name));
+        }
+
+        // Objects find each other by their full names.
+        eval(&mut interp, "mz::grid h 2 3");
+        eval(&mut interp, "mz::g textdiff mz::h");
+        assert!(interp.eval("mz::g textdiff h").is_err());
+
+        // Errors name the prefixed command.
+        let err = interp.eval("mz::grid g3").unwrap_err();
+        assert!(err.value().as_str().contains("\"mz::grid "));
+    }
+
+    #[test]
+    fn test_install_twice() {
+        let mut interp = Interp::new();
+        install_all(&mut interp, "mz").unwrap();
+
+        // Installing again does nothing, with or without a prefix.
+        install_all(&mut interp, "mz").unwrap();
+        interp.eval("mz::grid g 2 2").unwrap();
+        crate::molt_grid::install_with_prefix(&mut interp, "mz").unwrap();
+        assert_eq!(interp.eval("mz::g cells").unwrap().as_int().unwrap(), 4);
+
+        crate::molt_grid::install(&mut interp);
+        interp.eval("grid h 2 3").unwrap();
+        crate::molt_grid::install(&mut interp);
+        assert_eq!(interp.eval("h cells").unwrap().as_int().unwrap(), 6);
+
+        // A host's command is never replaced; installing over it is an error, and then
+        // none of the module's commands are installed.  The unprefixed `install` leaves
+        // it alone as well.
+        interp.add_command("pixel", cmd_host);
+        assert_eq!(
+            crate::molt_image::install_with_prefix(&mut interp, ""),
+            Err("command already exists: \"pixel\"".to_string())
+        );
+        crate::molt_image::install(&mut interp);
+        assert_eq!(interp.eval("pixel").unwrap().as_str(), "host");
+        assert!(!interp.has_command("image"));
+
+        // The record of installed commands is kept out of a script's reach: setting the
+        // old record's variable does nothing, and overwriting the state's handle forgets
+        // the installed commands, which can then no longer be installed over.
+        interp.eval("set mazegen::installed {pixel image}").unwrap();
+        assert!(crate::molt_image::install_with_prefix(&mut interp, "").is_err());
+        interp.eval("set mazegen::state bogus").unwrap();
+        assert_eq!(
+            crate::molt_rand::install_with_prefix(&mut interp, "mz"),
+            Err("command already exists: \"mz::rand\"".to_string())
+        );
+        assert_eq!(interp.eval("pixel").unwrap().as_str(), "host");
+    }
+
+    fn cmd_host(_: &mut Interp, _: ContextID, _: &[Value]) -> MoltResult {
+        Ok(Value::from("host"))
+    }
+}
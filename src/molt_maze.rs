@@ -0,0 +1,940 @@
+//! Molt Maze Commands
+use crate::histogram_text;
+use crate::molt_ensemble::call_ensemble;
+use crate::molt_ensemble::check_subcommand_args;
+use crate::molt_ensemble::subcommand_help;
+use crate::molt_ensemble::subcommand_names;
+use crate::molt_ensemble::wrong_subcommand_args;
+use crate::molt_ensemble::SubcommandSpec;
+use crate::molt_grid::get_dir;
+use crate::molt_grid::get_grid_cell;
+use crate::molt_grid::make_grid_object;
+use crate::molt_install::add_commands;
+use crate::molt_install::object_name;
+use crate::Algorithm;
+use crate::Constraints;
+use crate::Grid;
+use crate::GridDirection;
+use molt::molt_err;
+use molt::molt_ok;
+use molt::types::*;
+use molt::Interp;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Installs the Molt maze command unprefixed; see `install_with_prefix`, which reports
+/// whether it could be installed.
+pub fn install(interp: &mut Interp) {
+    let _ = install_with_prefix(interp, "");
+}
+
+/// Installs the Molt maze command, "maze", into the interpreter with the given prefix:
+/// given "mz", "maze" is installed as "mz::maze".  An empty prefix installs it unprefixed.
+/// Installing it again does nothing; returns an error, installing nothing, if a command
+/// of the same name exists that it didn't install.
+pub fn install_with_prefix(interp: &mut Interp, prefix: &str) -> Result<(), String> {
+    add_commands(interp, prefix, &MAZE_COMMANDS)
+}
+
+const MAZE_COMMANDS: [(&str, CommandFunc); 1] = [("maze", cmd_maze)];
+
+fn cmd_maze(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
+    call_ensemble(interp, ctx, argv, &MAZE_SUBCOMMANDS)
+}
+
+const MAZE_SUBCOMMANDS: [SubcommandSpec; 11] = [
+    SubcommandSpec("backtracker", cmd_maze_backtracker, "name rows cols"),
+    SubcommandSpec("banner", cmd_maze_banner, "filename text ?cellpx?"),
+    SubcommandSpec(
+        "batch",
+        cmd_maze_batch,
+        "algorithm rows cols count ?options...?",
+    ),
+    SubcommandSpec(
+        "bintree",
+        cmd_maze_bintree,
+        "name rows cols ?vertical horizontal?",
+    ),
+    SubcommandSpec(
+        "constrained-walls",
+        cmd_maze_constrained_walls,
+        "name rows cols constraints ?-algorithm name?",
+    ),
+    SubcommandSpec("division", cmd_maze_division, "name rows cols"),
+    SubcommandSpec("generate", cmd_maze_generate, "name rows cols ?options...?"),
+    SubcommandSpec("help", cmd_maze_help, "subcommand"),
+    SubcommandSpec("huntandkill", cmd_maze_huntandkill, "name rows cols"),
+    SubcommandSpec(
+        "sidewinder",
+        cmd_maze_sidewinder,
+        "name rows cols ?run carve?",
+    ),
+    SubcommandSpec("subcommands", cmd_maze_subcommands, ""),
+];
+
+// maze help subcommand
+//
+// Returns the usage of the named subcommand: its name and argument signature.
+fn cmd_maze_help(_: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 3, 3)?;
+    subcommand_help(&MAZE_SUBCOMMANDS, &argv[2])
+}
+
+// maze subcommands
+//
+// Returns the names of the maze subcommands, e.g., for tab completion.
+fn cmd_maze_subcommands(_: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 2, 2)?;
+    subcommand_names(&MAZE_SUBCOMMANDS)
+}
+
+// maze banner filename text ?cellpx?
+//
+// Renders the text as a banner in which each character is a maze in the shape of its
+// glyph, as by maze_text_banner, and saves it to disk.  Each maze cell is cellpx pixels
+// on a side; defaults to 4.
+fn cmd_maze_banner(_: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 4, 5)?;
+    let filename = argv[2].as_str();
+    let text = argv[3].as_str();
+
+    let cell_px = if argv.len() == 5 {
+        let cell_px = argv[4].as_int()?;
+        if cell_px < 1 {
+            return molt_err!("invalid cellpx, expected positive integer");
+        }
+        cell_px as usize
+    } else {
+        4
+    };
+
+    let image = match crate::maze_text_banner(text, cell_px, &mut rand::thread_rng()) {
+        Ok(image) => image,
+        Err(msg) => return molt_err!("{}", msg),
+    };
+
+    match image.save(filename) {
+        Ok(_) => molt_ok!(),
+        Err(_) => molt_err!("error saving banner image"),
+    }
+}
+
+// maze batch algorithm rows cols count ?options...?
+//
+// Generates count mazes with the given number of rows and columns using the named
+// algorithm, and returns statistics about them: a dictionary whose "count" is the number
+// of mazes, and whose "deadends", "longest", and "links" are dictionaries of the "mean",
+// "min", and "max" of each maze's number of dead ends, longest path length, and number of
+// links.  These are empty if count is 0.  The options are as follows:
+//
+// -seed n        Maze k, counting from 0, is generated with a StdRng seeded with n + k.
+// -keep pattern  Keep the best mazes as grid objects, named by replacing "%d" in the
+//                pattern with the maze's rank, counting from 1.  The names are returned
+//                as the dictionary's "kept" value.
+// -best k        The number of mazes to keep; defaults to 1.
+// -rank how      Rank the mazes by "longest" path, the default, or by "deadends"; the
+//                larger the better.  Ties go to the earlier maze.
+// -chart flag    If true, the dictionary's "chart" value is a text histogram of the
+//                values used for ranking.  Defaults to false.
+//
+// Only the best mazes found so far are retained during the run.
+fn cmd_maze_batch(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 6, 0)?;
+
+    let algo: Algorithm = match argv[2].as_str().parse() {
+        Ok(algo) => algo,
+        Err(msg) => return molt_err!("{}", msg),
+    };
+    new_grid(argv)?;
+
+    let count = argv[5].as_int()?;
+    if count < 0 {
+        return molt_err!("invalid count, expected non-negative integer");
+    }
+
+    // NEXT, get the options.
+    let mut seed: Option<u64> = None;
+    let mut pattern: Option<String> = None;
+    let mut best = 1;
+    let mut by_dead_ends = false;
+    let mut chart = false;
+    let mut queue = argv[6..].iter();
+
+    while let Some(opt) = queue.next() {
+        let val = if let Some(opt_val) = queue.next() {
+            opt_val
+        } else {
+            return molt_err!("missing option value");
+        };
+
+        match opt.as_str() {
+            "-seed" => seed = Some(val.as_int()? as u64),
+            "-keep" => pattern = Some(val.to_string()),
+            "-best" => {
+                best = val.as_int()?;
+                if best < 1 {
+                    return molt_err!("invalid -best, expected positive integer");
+                }
+            }
+            "-rank" => match val.as_str() {
+                "longest" => by_dead_ends = false,
+                "deadends" => by_dead_ends = true,
+                _ => return molt_err!("invalid -rank, expected longest or deadends"),
+            },
+            "-chart" => chart = val.as_bool()?,
+            _ => return molt_err!("invalid option: \"{}\"", opt),
+        }
+    }
+
+    if let Some(pattern) = &pattern {
+        if best > 1 && !pattern.contains("%d") {
+            return molt_err!("invalid -keep, expected a pattern containing %d");
+        }
+    }
+
+    // NEXT, generate the mazes, retaining the best.
+    let mut dead_ends = Summary::default();
+    let mut longest = Summary::default();
+    let mut links = Summary::default();
+    let mut kept: Vec<(usize, Grid)> = Vec::new();
+    let mut scores: Vec<f64> = Vec::new();
+
+    for k in 0..count as u64 {
+        let mut grid = new_grid(argv)?;
+
+        match seed {
+            Some(seed) => {
+                algo.generate_rng(&mut grid, &mut StdRng::seed_from_u64(seed.wrapping_add(k)))
+            }
+            None => algo.generate(&mut grid),
+        }
+
+        let stats = grid.stats();
+        dead_ends.add(stats.dead_ends);
+        longest.add(stats.longest);
+        links.add(grid.num_links());
+
+        let score = if by_dead_ends {
+            stats.dead_ends
+        } else {
+            stats.longest
+        };
+        scores.push(score as f64);
+
+        if pattern.is_some() {
+            retain_best(&mut kept, best as usize, score, grid);
+        }
+    }
+
+    // FINALLY, register the kept mazes and return the statistics.
+    let mut names = MoltList::new();
+
+    if let Some(pattern) = &pattern {
+        for (rank, (_, grid)) in kept.into_iter().enumerate() {
+            let name = object_name(&argv[0], &pattern.replace("%d", &(rank + 1).to_string()));
+            make_grid_object(interp, &name, grid);
+            names.push(Value::from(name));
+        }
+    }
+
+    let mut list: MoltList = vec![
+        Value::from("count"),
+        Value::from(count),
+        Value::from("deadends"),
+        dead_ends.to_molt(),
+        Value::from("longest"),
+        longest.to_molt(),
+        Value::from("links"),
+        links.to_molt(),
+        Value::from("kept"),
+        Value::from(names),
+    ];
+
+    if chart {
+        list.push(Value::from("chart"));
+        list.push(Value::from(histogram_text(
+            &scores,
+            CHART_BINS,
+            CHART_WIDTH,
+        )));
+    }
+
+    molt_ok!(list)
+}
+
+/// The number of bins in a `maze batch -chart` histogram.
+const CHART_BINS: usize = 8;
+
+/// The width of the longest bar in a `maze batch -chart` histogram.
+const CHART_WIDTH: usize = 30;
+
+/// Inserts the grid into the list of the best `k` grids, sorted by score from highest to
+/// lowest, if it belongs there.  A grid goes after those with the same score.
+fn retain_best(best: &mut Vec<(usize, Grid)>, k: usize, score: usize, grid: Grid) {
+    let index = best
+        .iter()
+        .position(|(s, _)| *s < score)
+        .unwrap_or(best.len());
+
+    if index < k {
+        best.insert(index, (score, grid));
+        best.truncate(k);
+    }
+}
+
+/// The mean, minimum, and maximum of a set of values.
+#[derive(Default)]
+struct Summary {
+    count: usize,
+    sum: usize,
+    min: usize,
+    max: usize,
+}
+
+impl Summary {
+    fn add(&mut self, value: usize) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+
+        self.count += 1;
+        self.sum += value;
+    }
+
+    /// Returns the summary as a dictionary with keys "mean", "min", and "max", or an
+    /// empty dictionary if there are no values.
+    fn to_molt(&self) -> Value {
+        if self.count == 0 {
+            return Value::from(MoltList::new());
+        }
+
+        let list: MoltList = vec![
+            Value::from("mean"),
+            Value::from(self.sum as MoltFloat / self.count as MoltFloat),
+            Value::from("min"),
+            Value::from(self.min as MoltInt),
+            Value::from("max"),
+            Value::from(self.max as MoltInt),
+        ];
+
+        Value::from(list)
+    }
+}
+
+// maze constrained-walls name rows cols constraints ?-algorithm name?
+//
+// Creates a grid object called "name" containing a maze with the given number of rows and
+// columns whose walls obey the constraints, a dictionary with optional keys "forbid" and
+// "require".  Each is a list of {cell dir} pairs naming links that mustn't or must be
+// carved.  The algorithm may be "backtracker", the default, or "huntandkill".
+fn cmd_maze_constrained_walls(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 6, 8)?;
+
+    let algo = match &argv[6..] {
+        [] => Algorithm::Backtracker,
+        [opt, val] if opt.as_str() == "-algorithm" => match val.as_str().parse() {
+            Ok(algo @ Algorithm::Backtracker) | Ok(algo @ Algorithm::HuntAndKill) => algo,
+            Ok(algo) => return molt_err!("algorithm doesn't support constraints: \"{}\"", algo),
+            Err(msg) => return molt_err!("{}", msg),
+        },
+        [opt, _] => return molt_err!("invalid option: \"{}\"", opt),
+        _ => return molt_err!("missing option value"),
+    };
+
+    let mut grid = new_grid(argv)?;
+    let mut constraints = Constraints::new();
+
+    for (key, links) in argv[5].as_dict()?.iter() {
+        let forbid = match key.as_str() {
+            "forbid" => true,
+            "require" => false,
+            _ => return molt_err!("invalid constraint: \"{}\"", key),
+        };
+
+        for link in links.as_list()?.iter() {
+            let pair = link.as_list()?;
+            if pair.len() != 2 {
+                return molt_err!("expected {{cell dir}}, got \"{}\"", link);
+            }
+
+            let cell = get_grid_cell(&grid, &pair[0])?;
+            let dir = get_dir(&pair[1])?;
+
+            if forbid {
+                constraints.forbid_link(cell, dir);
+            } else {
+                constraints.require_link(cell, dir);
+            }
+        }
+    }
+
+    let result = if algo == Algorithm::HuntAndKill {
+        crate::hunt_and_kill_constrained(&mut grid, &constraints)
+    } else {
+        crate::recursive_backtracker_constrained(&mut grid, &constraints)
+    };
+
+    if let Err(msg) = result {
+        return molt_err!("{}", msg);
+    }
+
+    let name = object_name(&argv[0], argv[2].as_str());
+    make_grid_object(interp, &name, grid);
+    molt_ok!(name)
+}
+
+// maze generate name rows cols ?options...?
+//
+// Creates a grid object called "name" containing a maze with the given number of rows and
+// columns.  The options are as follows:
+//
+// -algorithm name   The algorithm that carves the maze; defaults to "backtracker".
+// -seed n           Carves (and braids) the maze with a StdRng seeded with n, so that the
+//                   same seed always gives the same maze.
+// -braid p          Braids the maze afterwards, as "$grid braid p".
+// -ordervar varname Sets the variable to a list of the order in which the algorithm
+//                   reached the cells, as by generate_with_order: for each cell, the
+//                   number of cells reached before it, or "" if it was never reached.
+//                   Pass the list to "$grid render -shade" to see how the algorithm works.
+fn cmd_maze_generate(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 5, 0)?;
+
+    // NEXT, get the options.
+    let mut algo = Algorithm::Backtracker;
+    let mut seed: Option<u64> = None;
+    let mut braid: Option<f64> = None;
+    let mut order_var: Option<&Value> = None;
+    let mut queue = argv[5..].iter();
+
+    while let Some(opt) = queue.next() {
+        let val = if let Some(opt_val) = queue.next() {
+            opt_val
+        } else {
+            return molt_err!("missing option value");
+        };
+
+        match opt.as_str() {
+            "-algorithm" => match val.as_str().parse() {
+                Ok(a) => algo = a,
+                Err(msg) => return molt_err!("{}", msg),
+            },
+            "-seed" => seed = Some(val.as_int()? as u64),
+            "-braid" => {
+                let p = val.as_float()?;
+                if !(0.0..=1.0).contains(&p) {
+                    return molt_err!("invalid -braid, expected probability from 0.0 to 1.0");
+                }
+                braid = Some(p);
+            }
+            "-ordervar" => order_var = Some(val),
+            _ => return molt_err!("invalid option: \"{}\"", opt),
+        }
+    }
+
+    // NEXT, carve the maze, recording the order if need be.
+    let name = object_name(&argv[0], argv[2].as_str());
+    let mut grid = new_grid(argv)?;
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let order = crate::generate_with_order(&mut grid, |g| algo.generate_rng(g, &mut rng));
+
+    if let Some(p) = braid {
+        grid.braid_rng(p, &mut rng);
+    }
+
+    // FINALLY, save the order and the grid.
+    if let Some(var_name) = order_var {
+        let list: MoltList = order
+            .iter()
+            .map(|o| match o {
+                Some(o) => Value::from(*o as MoltInt),
+                None => Value::empty(),
+            })
+            .collect();
+        interp.set_scalar(var_name.as_str(), Value::from(list))?;
+    }
+
+    make_grid_object(interp, &name, grid);
+    molt_ok!(name)
+}
+
+fn cmd_maze_backtracker(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 5, 5)?;
+    make_maze(interp, argv, Algorithm::Backtracker)
+}
+
+// maze bintree name rows cols ?vertical horizontal?
+//
+// Creates a binary-tree maze.  If the directions are given, each cell is linked in one
+// of them rather than to the north or east; they must be perpendicular.
+fn cmd_maze_bintree(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 5, 7)?;
+
+    match get_directions(argv)? {
+        Some((vertical, horizontal)) => make_biased_maze(interp, argv, |grid, rng| {
+            crate::binary_tree_maze_with_rng(grid, vertical, horizontal, rng)
+        }),
+        None => make_maze(interp, argv, Algorithm::BinaryTree),
+    }
+}
+
+fn cmd_maze_division(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 5, 5)?;
+    make_maze(interp, argv, Algorithm::Division)
+}
+
+fn cmd_maze_huntandkill(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 5, 5)?;
+    make_maze(interp, argv, Algorithm::HuntAndKill)
+}
+
+// maze sidewinder name rows cols ?run carve?
+//
+// Creates a sidewinder maze.  If the directions are given, the runs go in the "run"
+// direction and are carved out in the "carve" direction, rather than east and north;
+// they must be perpendicular.
+fn cmd_maze_sidewinder(interp: &mut Interp, _: ContextID, argv: &[Value]) -> MoltResult {
+    // Correct number of arguments?
+    check_subcommand_args(&MAZE_SUBCOMMANDS, argv, 5, 7)?;
+
+    match get_directions(argv)? {
+        Some((run_dir, carve_dir)) => make_biased_maze(interp, argv, |grid, rng| {
+            crate::sidewinder_maze_with_rng(grid, run_dir, carve_dir, rng)
+        }),
+        None => make_maze(interp, argv, Algorithm::Sidewinder),
+    }
+}
+
+/// Gets the optional pair of directions following "name rows cols", if any.
+fn get_directions(argv: &[Value]) -> Result<Option<(GridDirection, GridDirection)>, Exception> {
+    match &argv[5..] {
+        [] => Ok(None),
+        [dir1, dir2] => Ok(Some((get_dir(dir1)?, get_dir(dir2)?))),
+        _ => wrong_subcommand_args(&MAZE_SUBCOMMANDS, argv).map(|_| None),
+    }
+}
+
+/// Creates the grid object for a `maze` subcommand whose arguments begin with
+/// "name rows cols", carving it with a biased algorithm that can fail.
+fn make_biased_maze<F>(interp: &mut Interp, argv: &[Value], carve: F) -> MoltResult
+where
+    F: FnOnce(&mut Grid, &mut rand::rngs::ThreadRng) -> Result<(), String>,
+{
+    let name = object_name(&argv[0], argv[2].as_str());
+    let mut grid = new_grid(argv)?;
+
+    if let Err(msg) = carve(&mut grid, &mut rand::thread_rng()) {
+        return molt_err!("{}", msg);
+    }
+
+    make_grid_object(interp, &name, grid);
+    molt_ok!(name)
+}
+
+/// Creates the grid object for a `maze` subcommand whose arguments begin with
+/// "name rows cols", carving it with the given algorithm.
+fn make_maze(interp: &mut Interp, argv: &[Value], algo: Algorithm) -> MoltResult {
+    let name = object_name(&argv[0], argv[2].as_str());
+    let mut grid = new_grid(argv)?;
+    algo.generate(&mut grid);
+    make_grid_object(interp, &name, grid);
+
+    molt_ok!(name)
+}
+
+/// Creates an empty grid for a `maze` subcommand whose arguments begin with
+/// "name rows cols".
+fn new_grid(argv: &[Value]) -> Result<Grid, Exception> {
+    let rows = argv[3].as_int()?;
+    let cols = argv[4].as_int()?;
+
+    if rows < 1 || cols < 1 {
+        return molt_err!(
+            "expected a maze of size at least 1x1, got {}x{}",
+            rows,
+            cols
+        );
+    }
+
+    Ok(Grid::new(rows as usize, cols as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Creates an interpreter with the maze and grid commands installed.
+    fn test_interp() -> Interp {
+        let mut interp = Interp::new();
+        install(&mut interp);
+        crate::molt_grid::install(&mut interp);
+        interp
+    }
+
+    #[test]
+    fn test_maze_introspection() {
+        let mut interp = test_interp();
+        crate::molt_ensemble::check_introspection(&mut interp, "maze");
+        assert_eq!(
+            interp.eval("maze help generate").unwrap().as_str(),
+            "generate name rows cols ?options...?"
+        );
+    }
+
+    #[test]
+    fn test_maze_banner() {
+        let mut interp = test_interp();
+        let path = std::env::temp_dir().join(format!("mazegen-banner-{}.png", std::process::id()));
+
+        let script = format!("maze banner {{{}}} {{Hi 2}} 3", path.display());
+        interp.eval(&script).unwrap();
+        let image = image::open(&path).unwrap();
+        assert_eq!(image.as_rgba8().unwrap().dimensions(), (3 * (18 * 4 - 3), 3 * 21));
+        std::fs::remove_file(&path).unwrap();
+
+        let err = interp.eval("maze banner out.png {a+b}").unwrap_err();
+        assert_eq!(
+            err.value().as_str(),
+            "unsupported characters in banner text: '+'"
+        );
+        assert!(interp.eval("maze banner out.png Hi 0").is_err());
+        assert!(interp.eval("maze banner out.png").is_err());
+    }
+
+    #[test]
+    fn test_maze_generate() {
+        let mut interp = test_interp();
+
+        for algo in &Algorithm::ALL {
+            let script = format!("maze generate m 4 6 -algorithm {}", algo);
+            assert_eq!(interp.eval(&script).unwrap().as_str(), "m");
+            assert_eq!(interp.eval("m cells").unwrap().as_int().unwrap(), 24);
+            assert!(interp.eval("m deadendcount").unwrap().as_int().unwrap() > 0);
+        }
+
+        assert_eq!(interp.eval("maze generate d 2 3").unwrap().as_str(), "d");
+        assert_eq!(interp.eval("d cells").unwrap().as_int().unwrap(), 6);
+
+        assert!(interp.eval("maze generate e 0 3").is_err());
+        assert!(interp
+            .eval("maze generate e 2 3 -algorithm nonesuch")
+            .is_err());
+        assert!(interp.eval("maze generate e 2 3 -size 4").is_err());
+        assert!(interp.eval("maze generate e 2 3 -algorithm").is_err());
+    }
+
+    #[test]
+    fn test_maze_generate_seed_and_braid() {
+        let mut interp = test_interp();
+
+        // The same seed gives the same maze, with any algorithm.
+        for algo in &Algorithm::ALL {
+            let script = format!("maze generate m 8 8 -algorithm {} -seed 2047", algo);
+            interp.eval(&script).unwrap();
+            let first = interp.eval("m text").unwrap();
+            interp.eval(&script).unwrap();
+            assert_eq!(interp.eval("m text").unwrap().as_str(), first.as_str());
+
+            let mut grid = Grid::new(8, 8);
+            algo.generate_rng(&mut grid, &mut StdRng::seed_from_u64(2047));
+            make_grid_object(&mut interp, "expected", grid);
+            assert_eq!(
+                first.as_str(),
+                interp.eval("expected text").unwrap().as_str()
+            );
+        }
+
+        let script = "maze generate m 8 8 -algorithm sidewinder -braid 1.0";
+        interp.eval(script).unwrap();
+        assert_eq!(interp.eval("m deadendcount").unwrap().as_int().unwrap(), 0);
+
+        assert!(interp.eval("maze generate e 2 3 -braid 1.5").is_err());
+        assert!(interp.eval("maze generate e 2 3 -seed x").is_err());
+
+        let err = interp
+            .eval("maze generate e 2 3 -algorithm wilson")
+            .unwrap_err();
+        assert_eq!(
+            err.value().as_str(),
+            "expected algorithm (backtracker, bintree, division, huntandkill, sidewinder), \
+             got \"wilson\""
+        );
+    }
+
+    #[test]
+    fn test_maze_generate_ordervar() {
+        let mut interp = test_interp();
+
+        let script = "maze generate m 4 6 -ordervar order -algorithm sidewinder";
+        assert_eq!(interp.eval(script).unwrap().as_str(), "m");
+
+        let order = interp.scalar("order").unwrap();
+        let order: Vec<MoltInt> = order
+            .as_list()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_int().unwrap())
+            .collect();
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..24).collect::<Vec<_>>());
+
+        // The order can be used for shading: the last cell reached is darkest.
+        interp.eval("m renderimage img -shade $order").unwrap();
+        let last = order.iter().position(|o| *o == 23).unwrap();
+        let script = format!("img get {} {}", 11 * (last % 6) + 5, 11 * (last / 6) + 5);
+        assert_eq!(interp.eval(&script).unwrap().as_str(), "#0000ff");
+
+        assert!(interp.eval("maze generate e 2 3 -ordervar").is_err());
+    }
+
+    #[test]
+    fn test_maze_subcommands() {
+        let mut interp = test_interp();
+
+        for algo in &Algorithm::ALL {
+            let script = format!("maze {} m 3 5", algo);
+            assert_eq!(interp.eval(&script).unwrap().as_str(), "m");
+            assert_eq!(interp.eval("m cells").unwrap().as_int().unwrap(), 15);
+        }
+    }
+
+    #[test]
+    fn test_maze_biased_directions() {
+        let mut interp = test_interp();
+
+        interp.eval("maze bintree m 4 6 south west").unwrap();
+        assert!(interp
+            .eval("m linkedto 23 west")
+            .unwrap()
+            .as_bool()
+            .unwrap());
+        assert!(interp
+            .eval("m linkedto 0 south")
+            .unwrap()
+            .as_bool()
+            .unwrap());
+
+        interp.eval("maze sidewinder m 4 6 north west").unwrap();
+        assert!(interp
+            .eval("m linkedto 6 north")
+            .unwrap()
+            .as_bool()
+            .unwrap());
+
+        let err = interp.eval("maze bintree m 4 6 north south").unwrap_err();
+        assert_eq!(
+            err.value().as_str(),
+            "expected perpendicular directions, got north and south"
+        );
+        let err = interp.eval("maze sidewinder m 4 6 east").unwrap_err();
+        assert_eq!(
+            err.value().as_str(),
+            "wrong # args: should be \"maze sidewinder name rows cols ?run carve?\""
+        );
+        assert!(interp.eval("maze sidewinder m 4 6 east up").is_err());
+    }
+
+    #[test]
+    fn test_maze_constrained_walls() {
+        let mut interp = test_interp();
+
+        // A fence east of column 1, with a gate in the bottom row.
+        let fence = "forbid {{0 east} {4 east} {8 east}} require {{12 east} {0 south}}";
+
+        for algo in &["backtracker", "huntandkill"] {
+            let script = format!(
+                "maze constrained-walls m 4 4 {{{}}} -algorithm {}",
+                fence, algo
+            );
+            assert_eq!(interp.eval(&script).unwrap().as_str(), "m");
+            assert!(!interp.eval("m linkedto 4 east").unwrap().as_bool().unwrap());
+            assert!(interp
+                .eval("m linkedto 13 west")
+                .unwrap()
+                .as_bool()
+                .unwrap());
+            assert!(interp
+                .eval("m linkedto A1 south")
+                .unwrap()
+                .as_bool()
+                .unwrap());
+
+            assert!(interp.eval("m isperfect").unwrap().as_bool().unwrap());
+        }
+
+        assert!(interp
+            .eval("maze constrained-walls m 4 4 {forbid {{0 east} {4 east} {8 east} {12 east}}}")
+            .is_err());
+        assert!(interp
+            .eval("maze constrained-walls m 4 4 {forbid {{3 east}}}")
+            .is_err());
+        assert!(interp
+            .eval("maze constrained-walls m 4 4 {allow {}}")
+            .is_err());
+        assert!(interp
+            .eval("maze constrained-walls m 4 4 {forbid {0}}")
+            .is_err());
+        assert!(interp
+            .eval("maze constrained-walls m 4 4 {} -algorithm sidewinder")
+            .is_err());
+        assert!(interp.eval("maze constrained-walls m 4 4 {}").is_ok());
+    }
+
+    #[test]
+    fn test_maze_batch() {
+        let mut interp = test_interp();
+
+        // Generate the same mazes by hand.
+        let mut samples = Vec::new();
+        for k in 0..6 {
+            let mut grid = Grid::new(5, 7);
+            let mut rng = StdRng::seed_from_u64(100 + k);
+            Algorithm::HuntAndKill.generate_rng(&mut grid, &mut rng);
+            let stats = grid.stats();
+            samples.push((stats.dead_ends as MoltInt, stats.longest as MoltInt));
+        }
+
+        let result = interp
+            .eval("maze batch huntandkill 5 7 6 -seed 100 -keep best%d -best 2")
+            .unwrap();
+        let dict = result.as_dict().unwrap();
+        let stat = |key: &str, field: &str| {
+            dict[&Value::from(key)].as_dict().unwrap()[&Value::from(field)].clone()
+        };
+
+        assert_eq!(dict[&Value::from("count")].as_int().unwrap(), 6);
+        let dead_ends: Vec<MoltInt> = samples.iter().map(|s| s.0).collect();
+        let mut longest: Vec<MoltInt> = samples.iter().map(|s| s.1).collect();
+        assert_eq!(
+            stat("deadends", "min").as_int().unwrap(),
+            *dead_ends.iter().min().unwrap()
+        );
+        assert_eq!(
+            stat("deadends", "max").as_int().unwrap(),
+            *dead_ends.iter().max().unwrap()
+        );
+        let mean = dead_ends.iter().sum::<MoltInt>() as MoltFloat / 6.0;
+        assert!((stat("deadends", "mean").as_float().unwrap() - mean).abs() < 1e-9);
+        assert_eq!(stat("links", "mean").as_float().unwrap(), 34.0);
+        assert_eq!(stat("links", "min").as_int().unwrap(), 34);
+
+        // The kept mazes are the two with the longest paths.
+        longest.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(stat("longest", "max").as_int().unwrap(), longest[0]);
+        assert_eq!(
+            dict[&Value::from("kept")].to_string(),
+            "best1 best2".to_string()
+        );
+        let mut diameter = |name: &str| {
+            let info = interp.eval(&format!("{} stats", name)).unwrap();
+            info.as_dict().unwrap()[&Value::from("longest")]
+                .as_int()
+                .unwrap()
+        };
+        assert_eq!(diameter("best1"), longest[0]);
+        assert_eq!(diameter("best2"), longest[1]);
+        assert!(interp.eval("best3 cells").is_err());
+    }
+
+    #[test]
+    fn test_maze_batch_chart() {
+        let mut interp = test_interp();
+
+        let result = interp
+            .eval("maze batch backtracker 6 6 20 -seed 5 -chart 1")
+            .unwrap();
+        let dict = result.as_dict().unwrap();
+        let chart = dict[&Value::from("chart")].to_string();
+
+        // One line per bin, whose counts add up to the number of mazes.
+        let total: usize = chart
+            .lines()
+            .map(|line| line.rsplit(' ').next().unwrap().parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(total, 20);
+        assert!(chart.contains('█'));
+
+        let result = interp
+            .eval("maze batch backtracker 6 6 3 -chart 0")
+            .unwrap();
+        assert!(!result
+            .as_dict()
+            .unwrap()
+            .contains_key(&Value::from("chart")));
+        assert!(interp
+            .eval("maze batch backtracker 6 6 3 -chart x")
+            .is_err());
+    }
+
+    #[test]
+    fn test_maze_batch_edge_cases() {
+        let mut interp = test_interp();
+
+        // No mazes
+        let result = interp
+            .eval("maze batch backtracker 3 3 0 -keep none")
+            .unwrap();
+        let dict = result.as_dict().unwrap();
+        assert_eq!(dict[&Value::from("count")].as_int().unwrap(), 0);
+        assert_eq!(dict[&Value::from("longest")].as_str(), "");
+        assert_eq!(dict[&Value::from("kept")].as_str(), "");
+        assert!(interp.eval("none cells").is_err());
+
+        // Fewer mazes than requested
+        let result = interp
+            .eval("maze batch sidewinder 3 3 2 -keep m%d -best 5 -rank deadends")
+            .unwrap();
+        let dict = result.as_dict().unwrap();
+        assert_eq!(dict[&Value::from("kept")].as_str(), "m1 m2");
+        let mut dead_ends = |name: &str| {
+            let count = interp.eval(&format!("{} deadendcount", name)).unwrap();
+            count.as_int().unwrap()
+        };
+        let (first, second) = (dead_ends("m1"), dead_ends("m2"));
+        assert!(first >= second);
+
+        // Errors
+        assert!(interp.eval("maze batch nonesuch 3 3 2").is_err());
+        assert!(interp.eval("maze batch backtracker 0 3 2").is_err());
+        assert!(interp.eval("maze batch backtracker 3 3 -1").is_err());
+        assert!(interp.eval("maze batch backtracker 3 3 2 -best 0").is_err());
+        assert!(interp
+            .eval("maze batch backtracker 3 3 2 -rank nonesuch")
+            .is_err());
+        assert!(interp
+            .eval("maze batch backtracker 3 3 2 -keep m -best 2")
+            .is_err());
+        assert!(interp.eval("maze batch backtracker 3 3 2 -seed").is_err());
+    }
+
+    #[test]
+    fn test_retain_best() {
+        let mut best = Vec::new();
+        for (id, score) in [3, 5, 1, 5, 4].iter().enumerate() {
+            let grid = Grid::new(1, id + 1);
+            retain_best(&mut best, 3, *score, grid);
+        }
+
+        // Ties go to the earlier grid.
+        let kept: Vec<(usize, usize)> = best.iter().map(|(s, g)| (*s, g.num_cols())).collect();
+        assert_eq!(kept, vec![(5, 2), (5, 4), (4, 5)]);
+    }
+}
@@ -1,4 +1,5 @@
 //! Molt "rand" Command
+use crate::molt_install::add_commands;
 use molt::check_args;
 use molt::molt_err;
 use molt::molt_ok;
@@ -6,11 +7,22 @@ use molt::types::*;
 use molt::Interp;
 use rand::{thread_rng, Rng};
 
-/// Installs the Molt "rand" command into the interpreter.
+/// Installs the Molt "rand" command unprefixed; see `install_with_prefix`, which reports
+/// whether it could be installed.
 pub fn install(interp: &mut Interp) {
-    interp.add_command("rand", cmd_rand);
+    let _ = install_with_prefix(interp, "");
 }
 
+/// Installs the Molt "rand" command, "rand", into the interpreter with the given prefix:
+/// given "mz", "rand" is installed as "mz::rand".  An empty prefix installs it unprefixed.
+/// Installing it again does nothing; returns an error, installing nothing, if a command
+/// of the same name exists that it didn't install.
+pub fn install_with_prefix(interp: &mut Interp, prefix: &str) -> Result<(), String> {
+    add_commands(interp, prefix, &RAND_COMMANDS)
+}
+
+const RAND_COMMANDS: [(&str, CommandFunc); 1] = [("rand", cmd_rand)];
+
 // Random number generation.  Uses rand::thread_rng().
 // TODO: Should save thread_rng and reuse it?
 fn cmd_rand(interp: &mut Interp, ctx: ContextID, argv: &[Value]) -> MoltResult {
@@ -1,13 +1,70 @@
 //! Implementation of RGBA Pixel type for use with Molt.
 //!
 //! The internal representation is an image::Rgba.  The TCL representation is
-//! a string "#rrggbb?.aa?".  The alpha defaults to 255.
+//! a string "#rrggbb?.aa?".  The alpha defaults to 255.  The short form "#rgb" and common
+//! CSS color names, e.g., "red" or "cornflowerblue", are also accepted as input.
 
 use image::Rgba;
 use molt::types::*;
 use std::fmt;
 use std::str::FromStr;
 
+/// The error message for an invalid pixel string.
+const INVALID_PIXEL: &str =
+    "invalid pixel string, expected #rrggbb, #rrggbb.aa, #rgb, or a color name";
+
+/// The CSS color names accepted by `MoltPixel::from_name`, in alphabetical order.
+const COLOR_NAMES: [(&str, [u8; 3]); 48] = [
+    ("aqua", [0, 255, 255]),
+    ("beige", [245, 245, 220]),
+    ("black", [0, 0, 0]),
+    ("blue", [0, 0, 255]),
+    ("brown", [165, 42, 42]),
+    ("chartreuse", [127, 255, 0]),
+    ("coral", [255, 127, 80]),
+    ("cornflowerblue", [100, 149, 237]),
+    ("crimson", [220, 20, 60]),
+    ("cyan", [0, 255, 255]),
+    ("darkblue", [0, 0, 139]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgreen", [0, 100, 0]),
+    ("darkgrey", [169, 169, 169]),
+    ("darkred", [139, 0, 0]),
+    ("fuchsia", [255, 0, 255]),
+    ("gold", [255, 215, 0]),
+    ("gray", [128, 128, 128]),
+    ("green", [0, 128, 0]),
+    ("grey", [128, 128, 128]),
+    ("indigo", [75, 0, 130]),
+    ("ivory", [255, 255, 240]),
+    ("khaki", [240, 230, 140]),
+    ("lavender", [230, 230, 250]),
+    ("lightblue", [173, 216, 230]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgreen", [144, 238, 144]),
+    ("lightgrey", [211, 211, 211]),
+    ("lime", [0, 255, 0]),
+    ("magenta", [255, 0, 255]),
+    ("maroon", [128, 0, 0]),
+    ("navy", [0, 0, 128]),
+    ("olive", [128, 128, 0]),
+    ("orange", [255, 165, 0]),
+    ("pink", [255, 192, 203]),
+    ("purple", [128, 0, 128]),
+    ("red", [255, 0, 0]),
+    ("salmon", [250, 128, 114]),
+    ("silver", [192, 192, 192]),
+    ("skyblue", [135, 206, 235]),
+    ("steelblue", [70, 130, 180]),
+    ("tan", [210, 180, 140]),
+    ("teal", [0, 128, 128]),
+    ("tomato", [255, 99, 71]),
+    ("turquoise", [64, 224, 208]),
+    ("violet", [238, 130, 238]),
+    ("white", [255, 255, 255]),
+    ("yellow", [255, 255, 0]),
+];
+
 /// A struct representing an `image::Rgba` pixel.  This is a simple wrapper around
 /// the `image` type, but it allows us to define the Display and FromStr traits along
 /// with a variety of convenience methods, and also to insulate the Molt code from the
@@ -53,6 +110,42 @@ impl MoltPixel {
         self.ipixel[3]
     }
 
+    /// Interpolate linearly between two pixels, component by component, including the
+    /// alpha.  `t` is clamped to the range `[0,1]`; 0 yields `a` and 1 yields `b`.
+    pub fn lerp(a: MoltPixel, b: MoltPixel, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |i: usize| {
+            let (x, y) = (a.ipixel[i] as f64, b.ipixel[i] as f64);
+            (x + (y - x) * t).round() as u8
+        };
+
+        MoltPixel::rgba(mix(0), mix(1), mix(2), mix(3))
+    }
+
+    /// Interpolate linearly between two pixels, e.g., to compute the colors of a gradient.
+    /// This is the same as `lerp`.
+    pub fn interpolate(a: MoltPixel, b: MoltPixel, t: f64) -> Self {
+        MoltPixel::lerp(a, b, t)
+    }
+
+    /// Composite this pixel over the background pixel, using straight (not premultiplied)
+    /// alpha.  A fully transparent pixel leaves the background unchanged.
+    pub fn over(self, background: MoltPixel) -> Self {
+        if self.alpha() == 0 {
+            return background;
+        }
+
+        let fa = self.alpha() as f64 / 255.0;
+        let ba = background.alpha() as f64 / 255.0 * (1.0 - fa);
+        let alpha = fa + ba;
+        let mix = |i: usize| {
+            let c = self.ipixel[i] as f64 * fa + background.ipixel[i] as f64 * ba;
+            (c / alpha).round() as u8
+        };
+
+        MoltPixel::rgba(mix(0), mix(1), mix(2), (alpha * 255.0).round() as u8)
+    }
+
     /// Get the underlying image::Rgba pixel
     /// TODO: Should be "inner"?  Or a normal conversion?
     pub fn ipixel(&self) -> Rgba<u8> {
@@ -66,6 +159,74 @@ impl MoltPixel {
             ipixel
         }
     }
+
+    /// Create a pixel from hue, saturation, and value, with an alpha of 255.  The hue is
+    /// in degrees, and is taken modulo 360; the saturation and value are clamped to the
+    /// range `[0,1]`.
+    pub fn from_hsv(h: f64, s: f64, v: f64) -> Self {
+        let h = h.rem_euclid(360.0);
+        let s = s.clamp(0.0, 1.0);
+        let v = v.clamp(0.0, 1.0);
+
+        // FIRST, compute the chroma, and the RGB components of the nearest pure hue.
+        let c = v * s;
+        let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+        let (r, g, b) = match (h / 60.0) as usize {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+
+        // NEXT, add the same amount to each to match the value.
+        let m = v - c;
+        let byte = |f: f64| ((f + m) * 255.0).round() as u8;
+
+        MoltPixel::rgb(byte(r), byte(g), byte(b))
+    }
+
+    /// Get the pixel's hue, saturation, and value.  The hue is in degrees in the range
+    /// `[0,360)`, and is 0 for grays; the saturation and value are in the range `[0,1]`.
+    /// The alpha is ignored.
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let r = self.red() as f64 / 255.0;
+        let g = self.green() as f64 / 255.0;
+        let b = self.blue() as f64 / 255.0;
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let c = max - min;
+
+        let h = if c == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / c).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / c + 2.0)
+        } else {
+            60.0 * ((r - g) / c + 4.0)
+        };
+
+        let s = if max == 0.0 { 0.0 } else { c / max };
+
+        (h, s, max)
+    }
+
+    /// Looks up a CSS color name, e.g., "red" or "cornflowerblue", ignoring case.  The
+    /// alpha is 255.  Returns None if the name is unknown.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+
+        COLOR_NAMES
+            .binary_search_by(|(n, _)| (*n).cmp(&name))
+            .ok()
+            .map(|i| {
+                let [r, g, b] = COLOR_NAMES[i].1;
+                MoltPixel::rgb(r, g, b)
+            })
+    }
 }
 
 impl fmt::Display for MoltPixel {
@@ -95,18 +256,32 @@ impl FromStr for MoltPixel {
     type Err = String;
 
     fn from_str(pixel: &str) -> Result<Self, Self::Err> {
-        // FIRST, check the length.
+        // FIRST, is it a color name?  Pixel strings always begin with "#".
+        if !pixel.starts_with('#') {
+            return MoltPixel::from_name(pixel).ok_or_else(|| INVALID_PIXEL.into());
+        }
+
+        // NEXT, check the length.
         let len = pixel.chars().count();
 
-        if len != 7 && len != 10 {
-            return Err("invalid pixel string".into());
+        if len != 4 && len != 7 && len != 10 {
+            return Err(INVALID_PIXEL.into());
         }
 
         // NEXT, check the leading character.
         let pixel = pixel.to_lowercase();
         let ch = pixel.chars().next().expect("first character");
         if ch != '#' {
-            return Err("invalid pixel string".into());
+            return Err(INVALID_PIXEL.into());
+        }
+
+        // NEXT, handle the short form, in which each digit is doubled.
+        if len == 4 {
+            let r = parse_hex(&pixel[1..2])?;
+            let g = parse_hex(&pixel[2..3])?;
+            let b = parse_hex(&pixel[3..4])?;
+
+            return Ok(MoltPixel::rgb(r * 17, g * 17, b * 17));
         }
 
         // NEXT, get the RGB
@@ -121,7 +296,7 @@ impl FromStr for MoltPixel {
             let mut chars = pixel.chars().skip(7);
             let ch = chars.next().expect("period");
             if ch != '.' {
-                return Err("invalid pixel string".into());
+                return Err(INVALID_PIXEL.into());
             }
             a = parse_hex(&pixel[8..10])?;
         }
@@ -133,7 +308,7 @@ impl FromStr for MoltPixel {
 /// Parse a hex string, returning a String error on error.
 fn parse_hex(hex: &str) -> Result<u8, String> {
     match u8::from_str_radix(hex, 16) {
-        Err(_) => Err("invalid pixel string".into()),
+        Err(_) => Err(INVALID_PIXEL.into()),
         Ok(val) => Ok(val),
     }
 }
@@ -202,25 +377,149 @@ mod tests {
         // Wrong hex digits
         assert_eq!(
             MoltPixel::from_str("#faXY68"),
-            Err("invalid pixel string".into())
+            Err(INVALID_PIXEL.into())
         );
 
         // Wrong length
         assert_eq!(
             MoltPixel::from_str("#fa"),
-            Err("invalid pixel string".into())
+            Err(INVALID_PIXEL.into())
         );
 
         // Wrong lead character
         assert_eq!(
             MoltPixel::from_str("-012345"),
-            Err("invalid pixel string".into())
+            Err(INVALID_PIXEL.into())
         );
 
         // Wrong separator character
         assert_eq!(
             MoltPixel::from_str("#012345-67"),
-            Err("invalid pixel string".into())
+            Err(INVALID_PIXEL.into())
+        );
+
+        // The error lists the accepted formats.
+        assert_eq!(
+            INVALID_PIXEL,
+            "invalid pixel string, expected #rrggbb, #rrggbb.aa, #rgb, or a color name"
+        );
+    }
+
+    #[test]
+    fn test_pixel_fromstr_short() {
+        assert_eq!(MoltPixel::from_str("#f80").unwrap(), MoltPixel::rgb(255, 136, 0));
+        assert_eq!(MoltPixel::from_str("#F80").unwrap(), MoltPixel::rgb(255, 136, 0));
+        assert_eq!(MoltPixel::from_str("#000").unwrap(), MoltPixel::rgb(0, 0, 0));
+
+        // Display is always the long form.
+        assert_eq!(&format!("{}", MoltPixel::from_str("#abc").unwrap()), "#aabbcc");
+
+        assert_eq!(MoltPixel::from_str("#fg0"), Err(INVALID_PIXEL.into()));
+        assert_eq!(MoltPixel::from_str("#f800"), Err(INVALID_PIXEL.into()));
+    }
+
+    #[test]
+    fn test_pixel_lerp() {
+        let a = MoltPixel::rgba(0, 100, 200, 255);
+        let b = MoltPixel::rgba(100, 50, 0, 55);
+
+        assert_eq!(MoltPixel::lerp(a, b, 0.0), a);
+        assert_eq!(MoltPixel::lerp(a, b, 1.0), b);
+        assert_eq!(MoltPixel::lerp(a, b, 0.5), MoltPixel::rgba(50, 75, 100, 155));
+
+        // t is clamped.
+        assert_eq!(MoltPixel::lerp(a, b, -1.0), a);
+        assert_eq!(MoltPixel::lerp(a, b, 2.0), b);
+    }
+
+    #[test]
+    fn test_pixel_interpolate() {
+        let black = MoltPixel::from_str("black").unwrap();
+        let white = MoltPixel::from_str("white").unwrap();
+
+        assert_eq!(MoltPixel::interpolate(black, white, 0.0), black);
+        assert_eq!(MoltPixel::interpolate(black, white, 1.0), white);
+        assert_eq!(MoltPixel::interpolate(black, white, 0.5), MoltPixel::rgb(128, 128, 128));
+    }
+
+    #[test]
+    fn test_pixel_over() {
+        let bg = MoltPixel::rgba(10, 20, 30, 200);
+
+        // A fully transparent pixel leaves the background unchanged.
+        assert_eq!(MoltPixel::rgba(255, 0, 0, 0).over(bg), bg);
+
+        // An opaque pixel hides the background.
+        let red = MoltPixel::rgb(255, 0, 0);
+        assert_eq!(red.over(bg), red);
+
+        // A half-transparent pixel over an opaque background blends them.
+        let half = MoltPixel::rgba(255, 255, 255, 128);
+        assert_eq!(half.over(MoltPixel::rgb(0, 0, 0)), MoltPixel::rgb(128, 128, 128));
+    }
+
+    // Asserts that two HSV triples are equal, within a small tolerance.
+    fn assert_hsv(actual: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-3;
+        assert!(
+            close(actual.0, expected.0)
+                && close(actual.1, expected.1)
+                && close(actual.2, expected.2),
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_pixel_hsv() {
+        // Known conversions
+        assert_eq!(MoltPixel::from_hsv(0.0, 1.0, 1.0), MoltPixel::rgb(255, 0, 0));
+        assert_eq!(MoltPixel::from_hsv(120.0, 1.0, 1.0), MoltPixel::rgb(0, 255, 0));
+        assert_eq!(MoltPixel::from_hsv(240.0, 1.0, 1.0), MoltPixel::rgb(0, 0, 255));
+        assert_eq!(MoltPixel::from_hsv(60.0, 1.0, 0.5), MoltPixel::rgb(128, 128, 0));
+        assert_eq!(MoltPixel::from_hsv(0.0, 0.0, 0.5), MoltPixel::rgb(128, 128, 128));
+
+        // Hues wrap around; saturation and value are clamped.
+        assert_eq!(MoltPixel::from_hsv(360.0, 1.0, 1.0), MoltPixel::rgb(255, 0, 0));
+        assert_eq!(MoltPixel::from_hsv(-120.0, 1.0, 1.0), MoltPixel::rgb(0, 0, 255));
+        assert_eq!(MoltPixel::from_hsv(0.0, 2.0, -1.0), MoltPixel::rgb(0, 0, 0));
+
+        assert_hsv(MoltPixel::rgb(255, 0, 0).to_hsv(), (0.0, 1.0, 1.0));
+        assert_hsv(MoltPixel::rgb(255, 0, 255).to_hsv(), (300.0, 1.0, 1.0));
+        assert_hsv(MoltPixel::rgb(128, 128, 128).to_hsv(), (0.0, 0.0, 128.0 / 255.0));
+        assert_hsv(MoltPixel::rgb(0, 0, 0).to_hsv(), (0.0, 0.0, 0.0));
+
+        // Round trips
+        for h in (0..360).step_by(15) {
+            let (h1, s1, v1) = MoltPixel::from_hsv(h as f64, 1.0, 1.0).to_hsv();
+            assert!((h1 - h as f64).abs() < 1.0);
+            assert_hsv((0.0, s1, v1), (0.0, 1.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn test_pixel_from_name() {
+        assert_eq!(MoltPixel::from_name("red"), Some(MoltPixel::rgb(255, 0, 0)));
+        assert_eq!(
+            MoltPixel::from_name("CornflowerBlue"),
+            Some(MoltPixel::rgb(100, 149, 237))
+        );
+        assert_eq!(MoltPixel::from_name("nonesuch"), None);
+
+        // The table must stay sorted for the lookup to work.
+        for pair in COLOR_NAMES.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+        }
+
+        // FromStr falls back to the names.
+        assert_eq!(&format!("{}", MoltPixel::from_str("red").unwrap()), "#ff0000");
+        assert_eq!(MoltPixel::from_str("white").unwrap(), MoltPixel::rgb(255, 255, 255));
+        assert_eq!(MoltPixel::from_str("YELLOW").unwrap(), MoltPixel::rgb(255, 255, 0));
+        assert_eq!(MoltPixel::from_str("Magenta").unwrap(), MoltPixel::rgb(255, 0, 255));
+        assert_eq!(
+            MoltPixel::from_str("nonesuch"),
+            Err(INVALID_PIXEL.into())
         );
     }
 }
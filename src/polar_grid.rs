@@ -0,0 +1,337 @@
+//! This module defines PolarGrid, a grid of concentric rings of cells for building circular
+//! ("theta") mazes.
+
+use crate::Cell;
+use crate::MazeGrid;
+use rand::{thread_rng, Rng};
+use std::collections::HashSet;
+use std::f64::consts::PI;
+
+/// A circular grid of cells, arranged in concentric rings around a single center cell.
+/// The number of cells in each ring grows with its radius so that the cells stay roughly
+/// square: each cell in ring r is split into one or more cells in ring r + 1.
+///
+/// Each cell has an inward neighbor (except the center cell), clockwise and
+/// counter-clockwise neighbors in its own ring, and one or more outward neighbors (except
+/// in the outermost ring).  As with `Grid`, two neighboring cells may be linked.
+///
+/// Each cell is identified by a unique integer cell ID, and also by an (r,k) ring/index
+/// pair.  Ring 0 is the center cell; within a ring, indices increase clockwise.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PolarGrid {
+    /// The number of cells in each ring.
+    ring_sizes: Vec<usize>,
+
+    /// The cell ID of the first cell in each ring.
+    ring_starts: Vec<Cell>,
+
+    /// The number of cells in the grid.
+    num_cells: usize,
+
+    /// The links for each cell.
+    links: Vec<HashSet<Cell>>,
+}
+
+impl PolarGrid {
+    /// Creates a new polar grid with the given number of rings, including the center cell.
+    /// Initially no cell is linked to any other cell.  Panics if the number of rings is
+    /// zero.
+    pub fn new(num_rings: usize) -> Self {
+        assert!(num_rings > 0, "a polar grid must have at least one ring");
+
+        // FIRST, size the rings.  Each ring is one unit high; a ring's cells are split
+        // when they'd otherwise be more than about twice as wide as they are high.
+        let mut ring_sizes = vec![1];
+
+        for r in 1..num_rings {
+            let previous = ring_sizes[r - 1];
+            let circumference = 2.0 * PI * r as f64;
+            let cell_width = circumference / previous as f64;
+            let ratio = cell_width.round().max(1.0) as usize;
+
+            ring_sizes.push(previous * ratio);
+        }
+
+        // NEXT, number the cells.
+        let mut ring_starts = Vec::with_capacity(num_rings);
+        let mut num_cells = 0;
+
+        for size in &ring_sizes {
+            ring_starts.push(num_cells);
+            num_cells += size;
+        }
+
+        Self {
+            ring_sizes,
+            ring_starts,
+            num_cells,
+            links: vec![HashSet::new(); num_cells],
+        }
+    }
+
+    /// The number of rings in the grid, including the center cell.
+    pub fn num_rings(&self) -> usize {
+        self.ring_sizes.len()
+    }
+
+    /// The number of cells in the given ring.
+    pub fn ring_size(&self, r: usize) -> usize {
+        self.ring_sizes[r]
+    }
+
+    /// The number of cells in the grid.
+    pub fn num_cells(&self) -> usize {
+        self.num_cells
+    }
+
+    /// Computes the cell from the ring and the index within the ring.
+    pub fn cell(&self, r: usize, k: usize) -> Cell {
+        assert!(r < self.num_rings() && k < self.ring_sizes[r]);
+        self.ring_starts[r] + k
+    }
+
+    /// Computes the ring and the index within the ring from the cell ID.
+    pub fn rk(&self, cell: Cell) -> (usize, usize) {
+        assert!(self.contains(cell));
+
+        // The ring is the last one that starts at or before the cell.
+        let r = match self.ring_starts.binary_search(&cell) {
+            Ok(r) => r,
+            Err(r) => r - 1,
+        };
+
+        (r, cell - self.ring_starts[r])
+    }
+
+    /// Returns true if the cell ID is valid for this grid.
+    pub fn contains(&self, cell: Cell) -> bool {
+        cell < self.num_cells
+    }
+
+    /// Links cell 1 to cell 2.  Links are bidirectional.
+    pub fn link(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].insert(cell2);
+        self.links[cell2].insert(cell1);
+    }
+
+    /// Unlinks cell 1 from cell 2.
+    pub fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].remove(&cell2);
+        self.links[cell2].remove(&cell1);
+    }
+
+    /// Gets the cells to which the cell is linked.
+    pub fn links(&self, cell: Cell) -> Vec<Cell> {
+        assert!(self.contains(cell));
+        self.links[cell].iter().cloned().collect()
+    }
+
+    /// Indicates whether or not the cells are linked.
+    pub fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].contains(&cell2)
+    }
+
+    /// Clears all links.
+    pub fn clear(&mut self) {
+        for links in &mut self.links {
+            links.clear();
+        }
+    }
+
+    /// Gets the cell's inward neighbor, if any.  The center cell has none.
+    pub fn inward(&self, cell: Cell) -> Option<Cell> {
+        let (r, k) = self.rk(cell);
+
+        if r == 0 {
+            None
+        } else {
+            let ratio = self.ring_sizes[r] / self.ring_sizes[r - 1];
+            Some(self.cell(r - 1, k / ratio))
+        }
+    }
+
+    /// Gets the cell's outward neighbors.  Cells in the outermost ring have none.
+    pub fn outward(&self, cell: Cell) -> Vec<Cell> {
+        let (r, k) = self.rk(cell);
+
+        if r + 1 == self.num_rings() {
+            Vec::new()
+        } else {
+            let ratio = self.ring_sizes[r + 1] / self.ring_sizes[r];
+            (k * ratio..(k + 1) * ratio)
+                .map(|k1| self.cell(r + 1, k1))
+                .collect()
+        }
+    }
+
+    /// Gets the cell's clockwise neighbor, if any.  The center cell has none.
+    pub fn cw(&self, cell: Cell) -> Option<Cell> {
+        let (r, k) = self.rk(cell);
+        let size = self.ring_sizes[r];
+
+        if size == 1 {
+            None
+        } else {
+            Some(self.cell(r, (k + 1) % size))
+        }
+    }
+
+    /// Gets the cell's counter-clockwise neighbor, if any.  The center cell has none.
+    pub fn ccw(&self, cell: Cell) -> Option<Cell> {
+        let (r, k) = self.rk(cell);
+        let size = self.ring_sizes[r];
+
+        if size == 1 {
+            None
+        } else {
+            Some(self.cell(r, (k + size - 1) % size))
+        }
+    }
+
+    /// Gets the cell's neighbors: inward, clockwise, counter-clockwise, and outward.
+    pub fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        let mut vec = Vec::new();
+
+        for c in self
+            .inward(cell)
+            .into_iter()
+            .chain(self.cw(cell))
+            .chain(self.ccw(cell))
+            .chain(self.outward(cell))
+        {
+            if !vec.contains(&c) {
+                vec.push(c);
+            }
+        }
+
+        vec
+    }
+
+    /// Returns a random cell from the grid.
+    pub fn random_cell(&self) -> Cell {
+        thread_rng().gen_range(0, self.num_cells)
+    }
+
+    /// Computes the shortest distance from the cell to each other cell.
+    /// Returns the distances as a vector of length `num_cells`; cells that can't be
+    /// reached have no distance.
+    pub fn distances(&self, cell: Cell) -> Vec<Option<usize>> {
+        MazeGrid::distances(self, cell)
+    }
+
+    /// Gets the number of links in the grid, counting each link between two cells once.
+    pub fn num_links(&self) -> usize {
+        self.links.iter().map(|l| l.len()).sum::<usize>() / 2
+    }
+
+    /// Returns true if the grid is a perfect maze: one in which there is exactly one
+    /// path between any two cells.
+    pub fn is_perfect_maze(&self) -> bool {
+        self.distances(0).iter().all(|d| d.is_some()) && self.num_links() + 1 == self.num_cells
+    }
+}
+
+impl MazeGrid for PolarGrid {
+    fn num_cells(&self) -> usize {
+        PolarGrid::num_cells(self)
+    }
+
+    fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        PolarGrid::neighbors(self, cell)
+    }
+
+    fn link(&mut self, cell1: Cell, cell2: Cell) {
+        PolarGrid::link(self, cell1, cell2)
+    }
+
+    fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        PolarGrid::unlink(self, cell1, cell2)
+    }
+
+    fn links(&self, cell: Cell) -> Vec<Cell> {
+        PolarGrid::links(self, cell)
+    }
+
+    fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        PolarGrid::is_linked(self, cell1, cell2)
+    }
+
+    fn clear(&mut self) {
+        PolarGrid::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polar_grid_new() {
+        let grid = PolarGrid::new(4);
+
+        assert_eq!(grid.num_rings(), 4);
+        assert_eq!(grid.ring_size(0), 1);
+        assert_eq!(grid.ring_size(1), 6);
+        assert_eq!(grid.ring_size(2), 12);
+        assert_eq!(grid.ring_size(3), 24);
+        assert_eq!(grid.num_cells(), 43);
+
+        assert_eq!(grid.cell(2, 3), 10);
+        assert_eq!(grid.rk(10), (2, 3));
+        assert_eq!(grid.rk(0), (0, 0));
+        assert_eq!(grid.rk(42), (3, 23));
+    }
+
+    #[test]
+    fn test_polar_grid_neighbors() {
+        let grid = PolarGrid::new(3);
+
+        // The center cell
+        assert_eq!(grid.inward(0), None);
+        assert_eq!(grid.cw(0), None);
+        assert_eq!(grid.outward(0), vec![1, 2, 3, 4, 5, 6]);
+
+        // Ring 1 wraps around.
+        let first = grid.cell(1, 0);
+        assert_eq!(grid.inward(first), Some(0));
+        assert_eq!(grid.cw(first), Some(grid.cell(1, 1)));
+        assert_eq!(grid.ccw(first), Some(grid.cell(1, 5)));
+        assert_eq!(grid.outward(first), vec![grid.cell(2, 0), grid.cell(2, 1)]);
+
+        // The outer ring
+        let cell = grid.cell(2, 3);
+        assert_eq!(grid.inward(cell), Some(grid.cell(1, 1)));
+        assert!(grid.outward(cell).is_empty());
+        assert_eq!(grid.neighbors(cell).len(), 3);
+
+        // Neighbors are symmetric.
+        for c in 0..grid.num_cells() {
+            for n in grid.neighbors(c) {
+                assert!(grid.neighbors(n).contains(&c));
+            }
+        }
+    }
+
+    #[test]
+    fn test_polar_grid_maze() {
+        let mut grid = PolarGrid::new(6);
+        crate::polar_recursive_backtracker(&mut grid);
+
+        assert!(grid.is_perfect_maze());
+
+        for c in 0..grid.num_cells() {
+            for l in grid.links(c) {
+                assert!(grid.neighbors(c).contains(&l));
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::image_grid_renderer::draw_segment;
+use crate::polar_grid::PolarGrid;
+use image::ImageBuffer;
+use image::Rgba;
+use image::RgbaImage;
+use std::f64::consts::PI;
+
+/// The maximum length of the line segments used to approximate an arc, in pixels.
+const ARC_STEP: f64 = 4.0;
+
+/// A line segment, from one (x,y) point to another.
+type Segment = ((f64, f64), (f64, f64));
+
+/// A struct for rendering a PolarGrid as an Image.  Uses the builder pattern.
+///
+/// The image is square; the center cell is at the center of the image, and each ring is
+/// `cell_size` pixels deep.  Arcs are approximated by short line segments.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PolarGridRenderer {
+    /// The depth of each ring in pixels.
+    cell_size: usize,
+
+    /// The border width, in pixels.
+    border_width: usize,
+}
+
+impl PolarGridRenderer {
+    /// Creates a new renderer for the PolarGrid with default settings
+    pub fn new() -> Self {
+        Self {
+            cell_size: 10,
+            border_width: 1,
+        }
+    }
+
+    /// Adds the desired depth of each ring.
+    pub fn cell_size(&mut self, cell_size: usize) -> &mut Self {
+        assert!(cell_size > 0);
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Adds the desired border width.
+    pub fn border_width(&mut self, border_width: usize) -> &mut Self {
+        assert!(border_width > 0);
+        self.border_width = border_width;
+        self
+    }
+
+    /// Render the grid using the current parameters.
+    pub fn render(&self, grid: &PolarGrid) -> RgbaImage {
+        // FIRST, size and create the image, and clear it to white.
+        let size = (2 * (grid.num_rings() * self.cell_size + self.border_width)) as u32;
+        let center = size as f64 / 2.0;
+        let cs = self.cell_size as f64;
+
+        let black = Rgba([0, 0, 0, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+        let mut image: RgbaImage = ImageBuffer::from_pixel(size, size, white);
+
+        // NEXT, get the walls as line segments.  Each cell draws its inward wall and its
+        // clockwise wall; the outer wall is drawn as a whole.
+        let mut walls: Vec<Segment> = Vec::new();
+
+        for cell in 1..grid.num_cells() {
+            let (r, k) = grid.rk(cell);
+            let theta = 2.0 * PI / grid.ring_size(r) as f64;
+            let inner = r as f64 * cs;
+            let outer = (r + 1) as f64 * cs;
+            let theta_ccw = k as f64 * theta;
+            let theta_cw = (k + 1) as f64 * theta;
+
+            if let Some(inward) = grid.inward(cell) {
+                if !grid.is_linked(cell, inward) {
+                    arc(&mut walls, center, inner, theta_ccw, theta_cw);
+                }
+            }
+
+            if let Some(cw) = grid.cw(cell) {
+                if !grid.is_linked(cell, cw) {
+                    let p0 = polar_point(center, inner, theta_cw);
+                    let p1 = polar_point(center, outer, theta_cw);
+                    walls.push((p0, p1));
+                }
+            }
+        }
+
+        arc(
+            &mut walls,
+            center,
+            grid.num_rings() as f64 * cs,
+            0.0,
+            2.0 * PI,
+        );
+
+        // FINALLY, draw the walls: a pixel is part of a wall if its center is within
+        // half the border width of the wall's segment.
+        let radius = self.border_width as f64 / 2.0;
+
+        for (a, b) in walls {
+            draw_segment(&mut image, a, b, radius, black);
+        }
+
+        image
+    }
+}
+
+/// Gets the point at the given radius and angle from the center.  Angles are in radians,
+/// and increase clockwise from the positive x axis.
+fn polar_point(center: f64, radius: f64, theta: f64) -> (f64, f64) {
+    (center + radius * theta.cos(), center + radius * theta.sin())
+}
+
+/// Adds line segments approximating the arc at the given radius from one angle to another.
+fn arc(walls: &mut Vec<Segment>, center: f64, radius: f64, from: f64, to: f64) {
+    let steps = ((radius * (to - from)) / ARC_STEP).ceil().max(1.0) as usize;
+    let step = (to - from) / steps as f64;
+
+    for n in 0..steps {
+        let p0 = polar_point(center, radius, from + n as f64 * step);
+        let p1 = polar_point(center, radius, from + (n + 1) as f64 * step);
+        walls.push((p0, p1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polar_render() {
+        let mut grid = PolarGrid::new(5);
+        crate::polar_recursive_backtracker(&mut grid);
+
+        let image = PolarGridRenderer::new()
+            .cell_size(12)
+            .border_width(2)
+            .render(&grid);
+
+        assert_eq!(image.dimensions(), (2 * (5 * 12 + 2), 2 * (5 * 12 + 2)));
+
+        // The center cell is open, and the outer wall is closed.
+        let white = Rgba([255, 255, 255, 255]);
+        let black = Rgba([0, 0, 0, 255]);
+        assert_eq!(*image.get_pixel(62, 62), white);
+        assert_eq!(*image.get_pixel(62 + 60, 62), black);
+        assert_eq!(*image.get_pixel(62, 62 - 60), black);
+    }
+}
@@ -0,0 +1,170 @@
+//! Tiny text charts, for quick visual feedback in a terminal or REPL without rendering
+//! images.  NaN values are ignored.
+
+/// The block characters used by `sparkline`, from lowest to highest.
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Renders the values as a sparkline, one block character per value, scaled so that the
+/// minimum value is the lowest block and the maximum is the highest.  If there are more
+/// values than `width`, they are divided into `width` consecutive groups, and each
+/// group's mean is shown.  If all of the values are the same, the line is flat at the
+/// lowest block.  Returns the empty string if there are no values.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+/// assert_eq!(sparkline(&values, 10), "▁▂▃▄▅▆▇█");
+/// assert_eq!(sparkline(&[1.0, 3.0, 5.0, 7.0], 2), "▁█");
+/// ```
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    let values = finite_values(values);
+
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    // FIRST, group the values if there are too many.
+    let points: Vec<f64> = if values.len() <= width {
+        values
+    } else {
+        (0..width)
+            .map(|k| {
+                let group = &values[k * values.len() / width..(k + 1) * values.len() / width];
+                group.iter().sum::<f64>() / group.len() as f64
+            })
+            .collect()
+    };
+
+    // NEXT, scale the points to the blocks.
+    let (min, max) = min_max(&points);
+    let top = (BLOCKS.len() - 1) as f64;
+
+    points
+        .iter()
+        .map(|v| {
+            if max > min {
+                BLOCKS[((v - min) / (max - min) * top).round() as usize]
+            } else {
+                BLOCKS[0]
+            }
+        })
+        .collect()
+}
+
+/// Renders a histogram of the values as horizontal bars, one line per bin.  The range
+/// from the minimum to the maximum value is divided into `bins` bins of equal width;
+/// each line shows the bin's range, a bar scaled so that the fullest bin is `width`
+/// characters long, and the number of values in the bin.  If all of the values are the
+/// same, there's a single bin.  Returns the empty string if there are no values.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let text = histogram_text(&[1.0, 2.0, 2.0, 3.0, 4.0, 4.0, 4.0, 5.0], 2, 6);
+/// assert_eq!(text, "1..3 |████ 3\n3..5 |██████ 5\n");
+/// ```
+pub fn histogram_text(values: &[f64], bins: usize, width: usize) -> String {
+    let values = finite_values(values);
+
+    if values.is_empty() || bins == 0 {
+        return String::new();
+    }
+
+    // FIRST, count the values in each bin.
+    let (min, max) = min_max(&values);
+    let bins = if max > min { bins } else { 1 };
+    let bin_width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+
+    for v in &values {
+        let bin = if max > min {
+            ((v - min) / bin_width).floor() as usize
+        } else {
+            0
+        };
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    // NEXT, label the bins.
+    let labels: Vec<String> = (0..bins)
+        .map(|k| {
+            let lo = min + bin_width * k as f64;
+            let hi = if k + 1 == bins { max } else { lo + bin_width };
+            format!("{}..{}", format_value(lo), format_value(hi))
+        })
+        .collect();
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    // FINALLY, draw the bars.  A non-empty bin always gets at least one character.
+    let most = counts.iter().copied().max().unwrap_or(0);
+    let mut text = String::new();
+
+    for (label, count) in labels.iter().zip(counts) {
+        let mut len = (count * width + most / 2) / most;
+        if count > 0 {
+            len = len.max(1);
+        }
+
+        text.push_str(&format!(
+            "{:>lw$} |{} {}\n",
+            label,
+            "█".repeat(len),
+            count,
+            lw = label_width
+        ));
+    }
+
+    text
+}
+
+/// Gets the values that aren't NaN.
+fn finite_values(values: &[f64]) -> Vec<f64> {
+    values.iter().copied().filter(|v| !v.is_nan()).collect()
+}
+
+/// Gets the minimum and maximum of a non-empty list of values.
+fn min_max(values: &[f64]) -> (f64, f64) {
+    values.iter().fold((values[0], values[0]), |(lo, hi), v| {
+        (lo.min(*v), hi.max(*v))
+    })
+}
+
+/// Formats a value for a bin label: whole numbers without a fraction, and others with two
+/// decimal places.
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[], 5), "");
+        assert_eq!(sparkline(&[1.0, 2.0], 0), "");
+        assert_eq!(sparkline(&[f64::NAN], 5), "");
+        assert_eq!(sparkline(&[4.0, 4.0, 4.0], 5), "▁▁▁");
+        assert_eq!(sparkline(&[0.0, f64::NAN, 7.0, 3.5], 5), "▁█▅");
+        assert_eq!(sparkline(&[2.0, 0.0, 1.0], 3), "█▁▅");
+    }
+
+    #[test]
+    fn test_histogram_text() {
+        assert_eq!(histogram_text(&[], 3, 10), "");
+        assert_eq!(histogram_text(&[1.0], 0, 10), "");
+        assert_eq!(
+            histogram_text(&[2.0, 2.0, f64::NAN], 4, 5),
+            "2..2 |█████ 2\n"
+        );
+        assert_eq!(
+            histogram_text(&[0.0, 1.0, 9.0, 10.0, 10.0, 5.0], 4, 4),
+            " 0..2.50 |███ 2\n 2.50..5 | 0\n 5..7.50 |█ 1\n7.50..10 |████ 3\n"
+        );
+    }
+}
@@ -1,7 +1,75 @@
 use crate::grid::Grid;
 use crate::Cell;
+use crate::GridDirection;
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::ops::Range;
+
+/// How `TextGridRenderer` writes labels that are integers, so that large values, such as
+/// the distances in a big maze, can be kept short.  Other labels are written as they are.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LabelFormat {
+    /// In decimal, as given.
+    #[default]
+    Decimal,
+
+    /// In base 36, using the digits 0-9 and a-z.
+    Base36,
+
+    /// In hexadecimal, using the digits 0-9 and a-f.
+    Hex,
+
+    /// In decimal, modulo n, e.g., to show distances in bands.  The result is never
+    /// negative.
+    Modulo(u64),
+}
+
+impl LabelFormat {
+    /// Formats the label: if it's an integer, it's written in this format, and otherwise
+    /// it's returned unchanged.
+    ///
+    /// ```
+    /// use mazegen::LabelFormat;
+    ///
+    /// assert_eq!(LabelFormat::Base36.format("1295".into()), "zz");
+    /// assert_eq!(LabelFormat::Hex.format("-255".into()), "-ff");
+    /// assert_eq!(LabelFormat::Modulo(10).format("1234".into()), "4");
+    /// assert_eq!(LabelFormat::Hex.format("A1".into()), "A1");
+    /// ```
+    pub fn format(&self, label: String) -> String {
+        let value: i64 = match label.parse() {
+            Ok(value) => value,
+            Err(_) => return label,
+        };
+
+        match *self {
+            LabelFormat::Decimal => label,
+            LabelFormat::Base36 => to_radix(value, 36),
+            LabelFormat::Hex => to_radix(value, 16),
+            LabelFormat::Modulo(n) => (value as i128).rem_euclid(n as i128).to_string(),
+        }
+    }
+}
+
+/// Writes the value in the given radix, which must be at most 36, with lowercase digits.
+fn to_radix(value: i64, radix: u32) -> String {
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+
+    loop {
+        digits.push(std::char::from_digit((magnitude % radix as u64) as u32, radix).unwrap());
+        magnitude /= radix as u64;
+        if magnitude == 0 {
+            break;
+        }
+    }
+
+    if value < 0 {
+        digits.push('-');
+    }
+
+    digits.iter().rev().collect()
+}
 
 /// A struct for rendering a grid, optionally with some data.  Uses the builder pattern.
 #[derive(Debug, Default, Copy, Clone)]
@@ -14,16 +82,32 @@ pub struct TextGridRenderer {
 
     /// The margin, when computing auto width.
     margin: usize,
+
+    /// The rectangle of cells to render, as (top row, left column, rows, columns), or
+    /// None for the whole grid.
+    viewport: Option<(usize, usize, usize, usize)>,
+
+    /// How integer labels are written.
+    label_format: LabelFormat,
+
+    /// The maximum cell width, if labels that don't fit are to be truncated with '~'.
+    max_cell_width: Option<usize>,
     // TODO: Could add character style, but this will do for now.
 }
 
 impl TextGridRenderer {
+    /// The default cell width, in monospace characters.
+    pub const DEFAULT_CELL_WIDTH: usize = 3;
+
     /// Creates a new renderer for the Grid with default settings
     pub fn new() -> Self {
         Self {
-            cell_width: 3,
+            cell_width: Self::DEFAULT_CELL_WIDTH,
             auto_width: false,
             margin: 0,
+            viewport: None,
+            label_format: LabelFormat::Decimal,
+            max_cell_width: None,
         }
     }
 
@@ -42,7 +126,72 @@ impl TextGridRenderer {
         self
     }
 
+    /// Sets how integer labels are written; see `LabelFormat`.  The labels are formatted
+    /// before the auto width is computed.  Defaults to `LabelFormat::Decimal`.
+    ///
+    /// Panics if the format is `Modulo(0)`.
+    pub fn label_format(&mut self, format: LabelFormat) -> &mut Self {
+        assert!(format != LabelFormat::Modulo(0), "modulus must be positive");
+        self.label_format = format;
+        self
+    }
+
+    /// Caps the cell width, e.g., as computed by `auto_width`.  Labels too long to fit in
+    /// the cell are truncated, and end with a '~' to show it.  The cap must be at least 1.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let grid = Grid::new(1, 2);
+    /// let text = TextGridRenderer::new()
+    ///     .auto_width(1)
+    ///     .max_cell_width(4)
+    ///     .render_with(&grid, |c| Some(if c == 0 { 123456 } else { 7 }));
+    /// assert_eq!(text, "+----+----+\n|123~| 7  |\n+----+----+\n");
+    /// ```
+    pub fn max_cell_width(&mut self, max_width: usize) -> &mut Self {
+        assert!(max_width > 0);
+        self.max_cell_width = Some(max_width);
+        self
+    }
+
+    /// Renders only the given rectangle of cells, starting at the top row and left column
+    /// and clamped to the grid's bounds.  Walls on the edge of the viewport show their
+    /// true state: a passage leading out of the viewport is drawn open.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(5, 5);
+    /// recursive_backtracker(&mut grid);
+    /// let text = TextGridRenderer::new().viewport(1, 1, 2, 3).render(&grid);
+    /// assert_eq!(text.lines().count(), 2 * 2 + 1);
+    /// assert!(text.lines().all(|line| line.chars().count() == 4 * 3 + 1));
+    /// ```
+    pub fn viewport(&mut self, top: usize, left: usize, rows: usize, cols: usize) -> &mut Self {
+        self.viewport = Some((top, left, rows, cols));
+        self
+    }
+
     /// Render the grid using the current parameters.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(1, 2);
+    /// grid.link(0, 1);
+    /// assert_eq!(
+    ///     TextGridRenderer::new().render(&grid),
+    ///     "+---+---+\n|       |\n+---+---+\n"
+    /// );
+    ///
+    /// // Each row of cells is a line of text, with a line of borders above and below.
+    /// let mut grid = Grid::new(4, 5);
+    /// recursive_backtracker(&mut grid);
+    /// let text = TextGridRenderer::new().render(&grid);
+    /// assert_eq!(text.lines().count(), 2 * 4 + 1);
+    /// assert!(text.lines().all(|line| line.chars().count() == 4 * 5 + 1));
+    /// ```
     pub fn render(&self, grid: &Grid) -> String {
         self.render_with(grid, |_| None as Option<usize>)
     }
@@ -54,15 +203,101 @@ impl TextGridRenderer {
         F: Fn(Cell) -> Option<T>,
         T: Display,
     {
-        // FIRST, compute the labels and the max label width.
+        crate::validation::debug_check_renderable(grid);
+
+        self.render_walls(
+            grid,
+            f,
+            |cell| if grid.is_linked_east(cell) { ' ' } else { '|' },
+            |cell| if grid.is_linked_south(cell) { ' ' } else { '-' },
+        )
+    }
+
+    /// Renders the `after` grid, marking the walls that differ from the `before` grid:
+    /// passages newly opened to the east are drawn as '=', passages newly opened to the
+    /// south are drawn as '"', and passages newly closed are drawn as '#'.  The grids must
+    /// have the same dimensions.
+    pub fn render_diff(&self, before: &Grid, after: &Grid) -> Result<String, String> {
+        check_same_size(before, after)?;
+        crate::validation::debug_check_renderable(after);
+
+        Ok(self.render_walls(
+            after,
+            |_| None as Option<usize>,
+            |cell| {
+                diff_wall(
+                    before.is_linked_east(cell),
+                    after.is_linked_east(cell),
+                    '|',
+                    '=',
+                )
+            },
+            |cell| {
+                diff_wall(
+                    before.is_linked_south(cell),
+                    after.is_linked_south(cell),
+                    '-',
+                    '"',
+                )
+            },
+        ))
+    }
+
+    /// Returns the number of passages opened and closed going from the `before` grid to
+    /// the `after` grid, i.e., the counts of the markings made by `render_diff`.
+    pub fn render_diff_summary(
+        &self,
+        before: &Grid,
+        after: &Grid,
+    ) -> Result<(usize, usize), String> {
+        check_same_size(before, after)?;
+        let diff = before.link_diff(after);
+
+        Ok((diff.added.len(), diff.removed.len()))
+    }
+
+    /// Returns the rows and columns to render, clamped to the grid's bounds.
+    fn visible(&self, grid: &Grid) -> (Range<usize>, Range<usize>) {
+        let (top, left, rows, cols) =
+            self.viewport
+                .unwrap_or((0, 0, grid.num_rows(), grid.num_cols()));
+        let bottom = top.saturating_add(rows).min(grid.num_rows());
+        let right = left.saturating_add(cols).min(grid.num_cols());
+
+        (top.min(bottom)..bottom, left.min(right)..right)
+    }
+
+    /// Renders the grid, writing each data item into the corresponding cell, and drawing
+    /// each cell's east and south walls with the characters returned by the `east` and
+    /// `south` functions.  The outer walls are drawn closed, except for the grid's
+    /// openings.  Only the cells in the viewport are rendered; an empty viewport renders
+    /// as the empty string.
+    fn render_walls<F, T, E, S>(&self, grid: &Grid, f: F, east: E, south: S) -> String
+    where
+        F: Fn(Cell) -> Option<T>,
+        T: Display,
+        E: Fn(Cell) -> char,
+        S: Fn(Cell) -> char,
+    {
+        // FIRST, get the cells to render.
+        let (rows, cols) = self.visible(grid);
+
+        if rows.is_empty() || cols.is_empty() {
+            return String::new();
+        }
+
+        // NEXT, compute the labels and the max label width.
         let mut labwidth = 0;
         let mut labels = HashMap::new();
 
-        for c in 0..grid.num_cells() {
-            if let Some(val) = f(c) {
-                let label = val.to_string();
-                labwidth = std::cmp::max(labwidth, label.chars().count());
-                labels.insert(c, label);
+        for i in rows.clone() {
+            for j in cols.clone() {
+                let c = grid.cell(i, j);
+                if let Some(val) = f(c) {
+                    let label = self.label_format.format(val.to_string());
+                    labwidth = std::cmp::max(labwidth, label.chars().count());
+                    labels.insert(c, label);
+                }
             }
         }
 
@@ -73,21 +308,42 @@ impl TextGridRenderer {
             cwidth = std::cmp::max(cwidth, labwidth + 2 * self.margin);
         }
 
+        if let Some(max_width) = self.max_cell_width {
+            cwidth = std::cmp::min(cwidth, max_width);
+
+            for label in labels.values_mut() {
+                if label.chars().count() > cwidth {
+                    *label = label.chars().take(cwidth - 1).chain(Some('~')).collect();
+                }
+            }
+        }
+
         // NEXT, create the String to hold the output.
         let mut buff = String::new();
 
-        // NEXT, write the top border.
+        // NEXT, write the top border.  Inside the grid, it's the south wall of the row
+        // above.
         buff.push('+');
-        for _ in 0..grid.num_cols() {
-            self.write_south(&mut buff, false, cwidth);
+        for j in cols.clone() {
+            let cell = grid.cell(rows.start, j);
+            let ch = match grid.north_of(cell) {
+                Some(north) => south(north),
+                None => outer_wall(grid, cell, GridDirection::North, '-'),
+            };
+            self.write_south(&mut buff, ch, cwidth);
         }
 
         // NEXT, write each row.
-        for i in 0..grid.num_rows() {
-            buff.push_str("\n|");
+        for i in rows {
+            buff.push('\n');
+            let cell = grid.cell(i, cols.start);
+            buff.push(match grid.west_of(cell) {
+                Some(west) => east(west),
+                None => outer_wall(grid, cell, GridDirection::West, '|'),
+            });
 
             // FIRST, write the cell row
-            for j in 0..grid.num_cols() {
+            for j in cols.clone() {
                 let cell = grid.cell(i, j);
 
                 if let Some(label) = labels.get(&cell) {
@@ -96,20 +352,26 @@ impl TextGridRenderer {
                     self.write_cell(&mut buff, &"", cwidth);
                 }
 
-                if grid.is_linked_east(cell) {
-                    buff.push(' ');
+                if grid.east_of(cell).is_some() {
+                    buff.push(east(cell));
                 } else {
-                    buff.push('|');
+                    buff.push(outer_wall(grid, cell, GridDirection::East, '|'));
                 }
             }
 
             // NEXT, write the row of borders below
             buff.push_str("\n+");
 
-            for j in 0..grid.num_cols() {
+            for j in cols.clone() {
                 let cell = grid.cell(i, j);
 
-                self.write_south(&mut buff, grid.is_linked_south(cell), cwidth);
+                let ch = if grid.south_of(cell).is_some() {
+                    south(cell)
+                } else {
+                    outer_wall(grid, cell, GridDirection::South, '-')
+                };
+
+                self.write_south(&mut buff, ch, cwidth);
             }
         }
 
@@ -128,10 +390,488 @@ impl TextGridRenderer {
         buff.push_str(&val);
     }
 
-    fn write_south(&self, buff: &mut String, open: bool, width: usize) {
+    fn write_south(&self, buff: &mut String, ch: char, width: usize) {
         for _ in 0..width {
-            buff.push(if open { ' ' } else { '-' });
+            buff.push(ch);
         }
         buff.push('+');
     }
 }
+
+/// Gets the character for an outer wall on the given side of the cell: a space if it's an
+/// opening, and the wall character otherwise.
+fn outer_wall(grid: &Grid, cell: Cell, dir: GridDirection, wall: char) -> char {
+    if grid.has_opening(cell, dir) {
+        ' '
+    } else {
+        wall
+    }
+}
+
+/// Gets the character for a wall in a diff, given whether it was open before and is open
+/// after, and the characters for a closed wall and a newly opened wall.
+fn diff_wall(was_open: bool, is_open: bool, closed: char, opened: char) -> char {
+    match (was_open, is_open) {
+        (false, true) => opened,
+        (true, false) => '#',
+        (_, true) => ' ',
+        (_, false) => closed,
+    }
+}
+
+/// Returns an error if the two grids have different dimensions.
+fn check_same_size(grid1: &Grid, grid2: &Grid) -> Result<(), String> {
+    if grid1.num_rows() != grid2.num_rows() || grid1.num_cols() != grid2.num_cols() {
+        return Err(format!(
+            "grid dimensions differ: {}x{} vs. {}x{}",
+            grid1.num_rows(),
+            grid1.num_cols(),
+            grid2.num_rows(),
+            grid2.num_cols()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Parses a grid from the ASCII form produced by `TextGridRenderer` with its default
+/// character style.  The cell width is inferred from the top border, any labels written
+/// into the cells are ignored, and gaps in the outer walls are openings.  Whitespace
+/// beyond the right-hand edge of the grid and Windows line endings are allowed.
+pub(crate) fn parse_ascii(text: &str) -> Result<Grid, String> {
+    // FIRST, get the lines, ignoring blank lines at the end.
+    let mut lines: Vec<&str> = text.lines().collect();
+
+    while lines.last().map(|line| line.trim().is_empty()) == Some(true) {
+        lines.pop();
+    }
+
+    let num_rows = lines.len().saturating_sub(1) / 2;
+
+    if num_rows == 0 || lines.len() != 2 * num_rows + 1 {
+        return Err(format!(
+            "expected an odd number of lines, at least 3, got {}",
+            lines.len()
+        ));
+    }
+
+    // NEXT, get the column positions of the corners from the top border, which always
+    // ends with a corner.
+    let top: Vec<char> = lines[0].trim_end().chars().collect();
+    let corners: Vec<usize> = top
+        .iter()
+        .enumerate()
+        .filter(|(_, ch)| **ch == '+')
+        .map(|(x, _)| x)
+        .collect();
+
+    if corners.len() < 2 || corners[0] != 0 || corners[corners.len() - 1] != top.len() - 1 {
+        return Err("line 1: expected a top border of the form +---+---+".into());
+    }
+
+    let width = corners[1] - corners[0];
+
+    if width < 2 || corners.windows(2).any(|w| w[1] - w[0] != width) {
+        return Err("line 1: expected cells of equal width".into());
+    }
+
+    let num_cols = corners.len() - 1;
+    let mut grid = Grid::new(num_rows, num_cols);
+
+    // NEXT, drop any whitespace beyond the right-hand edge, and check the line lengths.
+    // Whitespace within the grid is significant: the last column may be an opening.
+    let mut rows: Vec<Vec<char>> = Vec::new();
+
+    for (n, line) in lines.iter().enumerate() {
+        let mut chars: Vec<char> = line.chars().collect();
+
+        while chars.len() > top.len() && chars[chars.len() - 1].is_whitespace() {
+            chars.pop();
+        }
+
+        if chars.len() != top.len() {
+            return Err(format!(
+                "line {}: expected {} characters, got {}",
+                n + 1,
+                top.len(),
+                chars.len()
+            ));
+        }
+
+        rows.push(chars);
+    }
+
+    // NEXT, parse the rows.  Line 2*i is the north border of row i, and line 2*i + 2 is
+    // its south border; the cells themselves are on the line between.  A gap in an outer
+    // wall is an opening.
+    for (j, open) in check_border(&rows, 0, &corners)?.into_iter().enumerate() {
+        if open {
+            grid.open_boundary(grid.cell(0, j), GridDirection::North);
+        }
+    }
+
+    for i in 0..num_rows {
+        let n = 2 * i + 1;
+        let chars = &rows[n];
+
+        for (j, x) in corners.iter().enumerate() {
+            match chars[*x] {
+                '|' => {}
+                ' ' if j == 0 => grid.open_boundary(grid.cell(i, 0), GridDirection::West),
+                ' ' if j == num_cols => {
+                    grid.open_boundary(grid.cell(i, j - 1), GridDirection::East)
+                }
+                ' ' => {
+                    let cell = grid.cell(i, j - 1);
+                    grid.link(cell, cell + 1);
+                }
+                _ => return Err(format!("line {}: expected '|' or ' ' at wall", n + 1)),
+            }
+        }
+
+        for (j, open) in check_border(&rows, n + 1, &corners)?
+            .into_iter()
+            .enumerate()
+        {
+            if !open {
+                continue;
+            }
+
+            let cell = grid.cell(i, j);
+            if i == num_rows - 1 {
+                grid.open_boundary(cell, GridDirection::South);
+            } else {
+                grid.link(cell, cell + num_cols);
+            }
+        }
+    }
+
+    Ok(grid)
+}
+
+/// Checks border line n, returning whether each cell's segment of the border is open.
+fn check_border(rows: &[Vec<char>], n: usize, corners: &[usize]) -> Result<Vec<bool>, String> {
+    let chars = &rows[n];
+    let mut result = Vec::new();
+
+    for j in 0..corners.len() - 1 {
+        if chars[corners[j]] != '+' || chars[corners[j + 1]] != '+' {
+            return Err(format!("line {}: expected '+' at cell corners", n + 1));
+        }
+
+        let wall = &chars[corners[j] + 1..corners[j + 1]];
+
+        if wall.iter().all(|ch| *ch == ' ') {
+            result.push(true);
+        } else if wall.iter().all(|ch| *ch == '-') {
+            result.push(false);
+        } else {
+            return Err(format!("line {}: expected wall of '-' or ' '", n + 1));
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trip() {
+        let mut grid = Grid::new(6, 8);
+        crate::recursive_backtracker(&mut grid);
+
+        let text = TextGridRenderer::new().render(&grid);
+        assert_eq!(parse_ascii(&text), Ok(grid.clone()));
+
+        // Wider cells with labels
+        let text = TextGridRenderer::new()
+            .cell_width(5)
+            .render_with(&grid, |c| Some(c));
+        assert_eq!(parse_ascii(&text), Ok(grid));
+    }
+
+    #[test]
+    fn test_render_openings() {
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 1);
+        grid.link(1, 3);
+        grid.link(3, 2);
+        grid.open_boundary(0, GridDirection::North);
+        grid.open_boundary(2, GridDirection::West);
+        grid.open_boundary(3, GridDirection::East);
+
+        assert_eq!(
+            TextGridRenderer::new().render(&grid),
+            "+   +---+\n|       |\n+---+   +\n         \n+---+---+\n"
+        );
+
+        // The parser reads them back.
+        assert_eq!(
+            parse_ascii(&TextGridRenderer::new().render(&grid)),
+            Ok(grid)
+        );
+    }
+
+    #[test]
+    fn test_render_top_border_opening() {
+        let mut grid = Grid::new(2, 3);
+        grid.link_all();
+        grid.open_boundary(1, GridDirection::North);
+
+        // The top border has spaces where the opening was carved, and only there.
+        let text = TextGridRenderer::new().render(&grid);
+        assert_eq!(text.lines().next(), Some("+---+   +---+"));
+
+        grid.clear();
+        let text = TextGridRenderer::new().render(&grid);
+        assert_eq!(text.lines().next(), Some("+---+   +---+"));
+        assert_eq!(text.lines().last(), Some("+---+---+---+"));
+    }
+
+    #[test]
+    fn test_parse_openings_round_trip() {
+        let mut grid = Grid::new(3, 4);
+        crate::recursive_backtracker(&mut grid);
+        grid.open_boundary(1, GridDirection::North);
+        grid.open_boundary(9, GridDirection::South);
+        grid.open_boundary(4, GridDirection::West);
+        grid.open_boundary(3, GridDirection::East);
+        grid.open_boundary(11, GridDirection::East);
+
+        let text = TextGridRenderer::new().render(&grid);
+        assert_eq!(parse_ascii(&text), Ok(grid.clone()));
+
+        // Trailing whitespace beyond the east wall is still ignored.
+        let padded: String = text.lines().map(|line| format!("{}  \n", line)).collect();
+        assert_eq!(parse_ascii(&padded), Ok(grid));
+    }
+
+    // A 4x4 grid, not a perfect maze, for the viewport tests.
+    fn viewport_grid() -> Grid {
+        let text = "\
++---+---+---+---+
+|               |
++---+---+---+   +
+|   |           |
++   +   +---+---+
+|       |       |
++---+   +   +   +
+|           |   |
++---+---+---+---+
+";
+        parse_ascii(text).unwrap()
+    }
+
+    #[test]
+    fn test_render_viewport_middle() {
+        let grid = viewport_grid();
+
+        // The passages leading out of the viewport to the east and west are open.
+        assert_eq!(
+            TextGridRenderer::new().viewport(1, 1, 2, 2).render(&grid),
+            "+---+---+\n|        \n+   +---+\n    |    \n+   +   +\n"
+        );
+    }
+
+    #[test]
+    fn test_render_viewport_corner() {
+        let grid = viewport_grid();
+
+        // Clipped to the bottom-right corner of the grid.
+        assert_eq!(
+            TextGridRenderer::new().viewport(2, 2, 5, 5).render(&grid),
+            "+---+---+\n|       |\n+   +   +\n    |   |\n+---+---+\n"
+        );
+
+        // Wholly outside the grid.
+        assert_eq!(
+            TextGridRenderer::new().viewport(4, 0, 2, 2).render(&grid),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_render_viewport_larger_than_grid() {
+        let grid = viewport_grid();
+
+        assert_eq!(
+            TextGridRenderer::new().viewport(0, 0, 10, 10).render(&grid),
+            TextGridRenderer::new().render(&grid)
+        );
+    }
+
+    #[test]
+    fn test_render_diff_added() {
+        let mut before = Grid::new(2, 2);
+        before.link(0, 2);
+        let mut after = before.clone();
+        after.link(0, 1);
+
+        let diff = TextGridRenderer::new()
+            .render_diff(&before, &after)
+            .unwrap();
+        assert_eq!(
+            diff,
+            "+---+---+\n|   =   |\n+   +---+\n|   |   |\n+---+---+\n"
+        );
+        assert_eq!(diff.matches('=').count(), 1);
+
+        let summary = TextGridRenderer::new().render_diff_summary(&before, &after);
+        assert_eq!(summary, Ok((1, 0)));
+    }
+
+    #[test]
+    fn test_render_diff_removed() {
+        let mut before = Grid::new(2, 2);
+        before.link(0, 2);
+        before.link(0, 1);
+        let mut after = before.clone();
+        after.unlink(0, 2);
+
+        let diff = TextGridRenderer::new()
+            .render_diff(&before, &after)
+            .unwrap();
+        assert_eq!(
+            diff,
+            "+---+---+\n|       |\n+###+---+\n|   |   |\n+---+---+\n"
+        );
+
+        let summary = TextGridRenderer::new().render_diff_summary(&before, &after);
+        assert_eq!(summary, Ok((0, 1)));
+    }
+
+    #[test]
+    fn test_render_diff_identical() {
+        let mut grid = Grid::new(5, 7);
+        crate::recursive_backtracker(&mut grid);
+
+        let renderer = TextGridRenderer::new();
+        assert_eq!(
+            renderer.render_diff(&grid, &grid),
+            Ok(renderer.render(&grid))
+        );
+        assert_eq!(renderer.render_diff_summary(&grid, &grid), Ok((0, 0)));
+    }
+
+    #[test]
+    fn test_render_diff_mismatch() {
+        let renderer = TextGridRenderer::new();
+        let err = Err("grid dimensions differ: 2x3 vs. 3x2".to_string());
+
+        assert_eq!(
+            renderer.render_diff(&Grid::new(2, 3), &Grid::new(3, 2)),
+            err
+        );
+        assert!(renderer
+            .render_diff_summary(&Grid::new(2, 3), &Grid::new(3, 2))
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_line_endings() {
+        let text = "+---+---+  \r\n|       |\r\n+---+---+\r\n\r\n";
+        let grid = parse_ascii(text).unwrap();
+
+        assert_eq!(grid.num_rows(), 1);
+        assert_eq!(grid.num_cols(), 2);
+        assert!(grid.is_linked(0, 1));
+    }
+
+    #[test]
+    fn test_render_label_formats() {
+        let mut grid = Grid::new(1, 4);
+        grid.link_all();
+        let values = [5, 35, 255, 1296];
+
+        let render = |format: LabelFormat| {
+            TextGridRenderer::new()
+                .auto_width(1)
+                .label_format(format)
+                .render_with(&grid, |c| Some(values[c]))
+        };
+
+        assert_eq!(
+            render(LabelFormat::Decimal),
+            "+------+------+------+------+\n\
+             |  5      35    255    1296 |\n\
+             +------+------+------+------+\n"
+        );
+        assert_eq!(
+            render(LabelFormat::Base36),
+            "+-----+-----+-----+-----+\n|  5     z    73    100 |\n+-----+-----+-----+-----+\n"
+        );
+        assert_eq!(
+            render(LabelFormat::Hex),
+            "+-----+-----+-----+-----+\n|  5    23    ff    510 |\n+-----+-----+-----+-----+\n"
+        );
+        assert_eq!(
+            render(LabelFormat::Modulo(100)),
+            "+----+----+----+----+\n| 5    35   55   96 |\n+----+----+----+----+\n"
+        );
+
+        // Labels that aren't integers are left alone.
+        assert_eq!(LabelFormat::Base36.format("B2".into()), "B2");
+        assert_eq!(LabelFormat::Modulo(7).format("-1".into()), "6");
+        assert_eq!(
+            LabelFormat::Base36.format(i64::MIN.to_string()),
+            "-1y2p0ij32e8e8"
+        );
+    }
+
+    #[test]
+    fn test_render_max_cell_width() {
+        let mut grid = Grid::new(1, 4);
+        grid.link_all();
+        let values = [5, 35, 255, 1296];
+
+        // Only the labels that don't fit are truncated.
+        assert_eq!(
+            TextGridRenderer::new()
+                .auto_width(1)
+                .max_cell_width(3)
+                .render_with(&grid, |c| Some(values[c])),
+            "+---+---+---+---+\n| 5  35  255 12~|\n+---+---+---+---+\n"
+        );
+
+        // The cap doesn't widen the cells.
+        assert_eq!(
+            TextGridRenderer::new()
+                .max_cell_width(5)
+                .render_with(&grid, |c| Some(values[c])),
+            "+---+---+---+---+\n| 5  35  255 12~|\n+---+---+---+---+\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        assert!(parse_ascii("").is_err());
+        assert!(parse_ascii("+---+\n|   |\n").is_err());
+
+        // Ragged line
+        assert_eq!(
+            parse_ascii("+---+---+\n|   |\n+---+---+\n"),
+            Err("line 2: expected 9 characters, got 5".to_string())
+        );
+
+        // Uneven cells
+        assert!(parse_ascii("+---+--+\n|   |  |\n+---+--+\n").is_err());
+
+        // Bad characters
+        assert_eq!(
+            parse_ascii("+---+---+\n|   x   |\n+---+---+\n"),
+            Err("line 2: expected '|' or ' ' at wall".to_string())
+        );
+        assert_eq!(
+            parse_ascii("+---+---+\n|   |   |\n+-=-+---+\n"),
+            Err("line 3: expected wall of '-' or ' '".to_string())
+        );
+
+        // A partial gap in the outer wall
+        assert_eq!(
+            parse_ascii("+-  +---+\n|   |   |\n+---+---+\n"),
+            Err("line 1: expected wall of '-' or ' '".to_string())
+        );
+    }
+}
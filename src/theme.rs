@@ -0,0 +1,317 @@
+//! Themes: bundles of rendering options for drawing a grid in a particular style.
+
+use crate::grid::Grid;
+use crate::image_grid_renderer::blend;
+use crate::Cell;
+use crate::CellPath;
+use crate::ImageGridRenderer;
+use image::ImageBuffer;
+use image::Rgba;
+use image::RgbaImage;
+use std::collections::HashSet;
+
+/// A set of colors and sizes for rendering a grid as an image with `Grid::render_themed`,
+/// so that a maze can be drawn in one call.  Start with one of the presets and adjust it
+/// as needed; uses the builder pattern.
+///
+/// ```
+/// use mazegen::*;
+/// use image::Rgba;
+///
+/// let mut grid = Grid::new(5, 8);
+/// recursive_backtracker(&mut grid);
+///
+/// let image = grid.render_themed(Theme::dark().cell_size(16));
+/// assert_eq!(image.width(), 8 * 16 + 9 * 2);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Theme {
+    /// The color of the walls.
+    wall: Rgba<u8>,
+
+    /// The color of the cells' floors.
+    background: Rgba<u8>,
+
+    /// The color of the cells on the solution path, if the path is to be shown.
+    path: Option<Rgba<u8>>,
+
+    /// The palette for distance maps: the colors of the nearest and farthest cells.
+    near: Rgba<u8>,
+    far: Rgba<u8>,
+
+    /// The width and height of each cell, in pixels.
+    cell_size: usize,
+
+    /// The width of the walls, in pixels.
+    border_width: usize,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl Theme {
+    /// Black walls on a white background, with the solution shown in light red.
+    pub fn classic() -> Self {
+        Self {
+            wall: Rgba([0, 0, 0, 255]),
+            background: Rgba([255, 255, 255, 255]),
+            path: Some(Rgba([255, 160, 160, 255])),
+            near: Rgba([255, 255, 255, 255]),
+            far: Rgba([200, 30, 30, 255]),
+            cell_size: 10,
+            border_width: 2,
+        }
+    }
+
+    /// Light gray walls on a near-black background, with the solution shown in amber.
+    pub fn dark() -> Self {
+        Self {
+            wall: Rgba([200, 200, 200, 255]),
+            background: Rgba([24, 24, 32, 255]),
+            path: Some(Rgba([160, 110, 0, 255])),
+            near: Rgba([40, 40, 80, 255]),
+            far: Rgba([255, 190, 60, 255]),
+            cell_size: 10,
+            border_width: 2,
+        }
+    }
+
+    /// White walls on a blueprint blue background, without the solution.
+    pub fn blueprint() -> Self {
+        Self {
+            wall: Rgba([255, 255, 255, 255]),
+            background: Rgba([16, 64, 160, 255]),
+            path: None,
+            near: Rgba([16, 64, 160, 255]),
+            far: Rgba([140, 220, 255, 255]),
+            cell_size: 12,
+            border_width: 1,
+        }
+    }
+
+    /// Sets the color of the walls.
+    pub fn wall_color(&mut self, color: Rgba<u8>) -> &mut Self {
+        self.wall = color;
+        self
+    }
+
+    /// Sets the color of the cells' floors.
+    pub fn background_color(&mut self, color: Rgba<u8>) -> &mut Self {
+        self.background = color;
+        self
+    }
+
+    /// Sets the color of the cells on the solution path, or None to hide the path.
+    pub fn path_color(&mut self, color: Option<Rgba<u8>>) -> &mut Self {
+        self.path = color;
+        self
+    }
+
+    /// Sets the palette for distance maps: the colors of the nearest and farthest cells.
+    /// The cells in between are shaded by blending the two.
+    pub fn distance_colors(&mut self, near: Rgba<u8>, far: Rgba<u8>) -> &mut Self {
+        self.near = near;
+        self.far = far;
+        self
+    }
+
+    /// Sets the width and height of each cell, in pixels.
+    pub fn cell_size(&mut self, cell_size: usize) -> &mut Self {
+        assert!(cell_size > 0);
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Sets the width of the walls, in pixels.
+    pub fn border_width(&mut self, border_width: usize) -> &mut Self {
+        assert!(border_width > 0);
+        self.border_width = border_width;
+        self
+    }
+
+    /// Renders the grid in this theme.  The solution path, if shown, runs between the
+    /// grid's two openings if it has exactly two, and otherwise is the longest path
+    /// through the maze.
+    pub fn render(&self, grid: &Grid) -> RgbaImage {
+        let path: HashSet<Cell> = match self.path {
+            Some(_) => solution(grid).into_iter().collect(),
+            None => HashSet::new(),
+        };
+
+        ImageGridRenderer::new()
+            .cell_size(self.cell_size)
+            .border_width(self.border_width)
+            .wall_color(self.wall)
+            .floor_color(self.background)
+            .render_colored(grid, |cell| {
+                if path.contains(&cell) {
+                    self.path
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Renders a map of the distances from the start cell in this theme: each cell is
+    /// shaded from the near color, at the start, to the far color, at the farthest cells,
+    /// and cells that can't be reached show the background.  A legend strip is added
+    /// below the maze, with a gradient bar in the lower-left corner running from distance
+    /// 0 on the left to the maximum distance on the right.
+    pub fn render_distance_map(&self, grid: &Grid, start: Cell) -> RgbaImage {
+        let dists = grid.distances(start);
+        let max = dists.iter().filter_map(|d| *d).max().unwrap_or(0);
+
+        // FIRST, render the maze, shading each cell by its distance.
+        let maze = ImageGridRenderer::new()
+            .cell_size(self.cell_size)
+            .border_width(self.border_width)
+            .wall_color(self.wall)
+            .floor_color(self.background)
+            .render_colored(grid, |cell| {
+                dists[cell].map(|d| self.distance_color(d, max))
+            });
+
+        // NEXT, copy it into a taller image, leaving room for the legend.
+        let (width, height) = maze.dimensions();
+        let inset = self.border_width as u32;
+        let bar_height = self.cell_size as u32;
+        let mut image: RgbaImage =
+            ImageBuffer::from_pixel(width, height + bar_height + 2 * inset, self.background);
+
+        for (x, y, pixel) in maze.enumerate_pixels() {
+            image.put_pixel(x, y, *pixel);
+        }
+
+        // FINALLY, draw the gradient bar.
+        let bar_width = ((width - 2 * inset) / 2).max(1);
+
+        for dx in 0..bar_width {
+            let color = blend(
+                self.near,
+                self.far,
+                dx as usize,
+                (bar_width as usize - 1).max(1),
+            );
+
+            for dy in 0..bar_height {
+                image.put_pixel(inset + dx, height + inset + dy, color);
+            }
+        }
+
+        image
+    }
+
+    /// Gets the color for a distance from 0 to `max`.
+    fn distance_color(&self, distance: usize, max: usize) -> Rgba<u8> {
+        if max == 0 {
+            self.near
+        } else {
+            blend(self.near, self.far, distance, max)
+        }
+    }
+}
+
+/// Finds the path to show as the solution: between the two openings, if there are
+/// exactly two, and otherwise the longest path.
+fn solution(grid: &Grid) -> CellPath {
+    match grid.openings() {
+        [(start, _), (goal, _)] => grid.shortest_path(*start, *goal),
+        _ => grid.longest_path(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GridDirection;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // The mean brightness of the image's pixels, from 0.0 to 255.0.
+    fn brightness(image: &RgbaImage) -> f64 {
+        let total: f64 = image
+            .pixels()
+            .map(|p| (p[0] as f64 + p[1] as f64 + p[2] as f64) / 3.0)
+            .sum();
+        total / (image.width() * image.height()) as f64
+    }
+
+    fn test_maze() -> Grid {
+        let mut grid = Grid::new(6, 9);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(2034));
+        grid
+    }
+
+    #[test]
+    fn test_theme_dark() {
+        let grid = test_maze();
+        let image = grid.render_themed(&Theme::dark());
+
+        // Without the path, most of the image is the dark background.  With it, the image
+        // is still darker than the classic theme.
+        let dark = Rgba([24, 24, 32, 255]);
+        let plain = grid.render_themed(Theme::dark().path_color(None));
+        let background = plain.pixels().filter(|p| **p == dark).count();
+        assert!(2 * background > (plain.width() * plain.height()) as usize);
+        assert!(brightness(&image) < brightness(&grid.render_themed(&Theme::classic())));
+
+        // A cell off the solution path shows the background.
+        let path = solution(&grid);
+        let cell = (0..grid.num_cells()).find(|c| !path.contains(*c)).unwrap();
+        let (i, j) = grid.ij(cell);
+        let pixel = image.get_pixel(2 + 12 * j as u32 + 5, 2 + 12 * i as u32 + 5);
+        assert_eq!(*pixel, dark);
+    }
+
+    #[test]
+    fn test_theme_distance_map() {
+        let grid = test_maze();
+        let near = Rgba([0, 0, 255, 255]);
+        let far = Rgba([255, 255, 0, 255]);
+        let mut theme = Theme::classic();
+        theme.distance_colors(near, far);
+
+        let image = grid.render_distance_map(grid.cell(2, 3), &theme);
+
+        // The maze is 9x6 cells of 10 pixels with 2-pixel walls; the legend adds a cell's
+        // height plus two walls.
+        assert_eq!(image.dimensions(), (9 * 12 + 2, 6 * 12 + 2 + 14));
+
+        // The start cell and the farthest cell get the palette's endpoints.
+        let center = |cell| {
+            let (i, j) = grid.ij(cell);
+            *image.get_pixel(2 + 12 * j as u32 + 5, 2 + 12 * i as u32 + 5)
+        };
+        assert_eq!(center(grid.cell(2, 3)), near);
+        assert_eq!(center(grid.farthest(grid.cell(2, 3))), far);
+
+        // So do the ends of the legend's gradient bar.
+        let y = 6 * 12 + 2 + 2 + 5;
+        assert_eq!(*image.get_pixel(2, y), near);
+        assert_eq!(*image.get_pixel(2 + 53 - 1, y), far);
+        assert_eq!(*image.get_pixel(2 + 53, y), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_theme_path() {
+        let mut grid = test_maze();
+        grid.open_boundary(0, GridDirection::North);
+        grid.open_boundary(grid.num_cells() - 1, GridDirection::South);
+
+        let path = solution(&grid);
+        assert_eq!(path[0], 0);
+        assert_eq!(path[path.len() - 1], grid.num_cells() - 1);
+
+        let amber = Rgba([160, 110, 0, 255]);
+        let count = |image: &RgbaImage| image.pixels().filter(|p| **p == amber).count();
+        let image = grid.render_themed(&Theme::dark());
+        assert_eq!(*image.get_pixel(7, 7), amber);
+        assert!(count(&image) >= path.len() * 100);
+
+        let image = grid.render_themed(Theme::dark().path_color(None));
+        assert_eq!(count(&image), 0);
+    }
+}
@@ -0,0 +1,303 @@
+//! This module defines TriGrid, a grid of triangular cells for building triangle
+//! ("delta") mazes.
+
+use crate::Cell;
+use crate::MazeGrid;
+use std::collections::HashSet;
+
+/// The directions between cells in a TriGrid.  Every cell has neighbors to the east and
+/// west; an upward-pointing triangle also has one to the south, across its base, and a
+/// downward-pointing triangle has one to the north.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum TriDirection {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl TriDirection {
+    /// All four directions, clockwise from north.  No cell has neighbors in all four.
+    pub const ALL: [TriDirection; 4] = [
+        TriDirection::North,
+        TriDirection::East,
+        TriDirection::South,
+        TriDirection::West,
+    ];
+}
+
+/// A grid of triangular cells, which can be used to represent a maze.
+///
+/// The cells are arranged in rows and columns, and each cell is identified by a unique
+/// integer cell ID and also by an (i,j) row/column pair, just as in `Grid`.  The
+/// triangles alternate between pointing up and pointing down: cell (0,0) points up, as
+/// does every cell (i,j) for which i + j is even.  Each cell has at most three
+/// neighbors.  As with `Grid`, two neighboring cells may be linked.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let grid = TriGrid::new(3, 4);
+///
+/// // Cell (0,0) points up, so it has a neighbor across its base but none above.
+/// assert!(grid.is_upward(grid.cell(0, 0)));
+/// assert_eq!(grid.cell_to(grid.cell(0, 0), TriDirection::South), Some(grid.cell(1, 0)));
+/// assert_eq!(grid.cell_to(grid.cell(1, 0), TriDirection::North), Some(grid.cell(0, 0)));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TriGrid {
+    num_rows: usize,
+    num_cols: usize,
+    num_cells: usize,
+
+    /// The links for each cell.
+    links: Vec<HashSet<Cell>>,
+}
+
+impl TriGrid {
+    /// Creates a new triangle grid with the given number of rows and columns.  Initially
+    /// no cell is linked to any other cell.  Panics if either dimension is zero.
+    pub fn new(num_rows: usize, num_cols: usize) -> Self {
+        assert!(
+            num_rows > 0 && num_cols > 0,
+            "a triangle grid must have at least one row and one column, got {}x{}",
+            num_rows,
+            num_cols
+        );
+
+        let num_cells = num_rows * num_cols;
+
+        Self {
+            num_rows,
+            num_cols,
+            num_cells,
+            links: vec![HashSet::new(); num_cells],
+        }
+    }
+
+    /// The number of rows in the grid.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// The number of columns in the grid.
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    /// The number of cells in the grid.
+    pub fn num_cells(&self) -> usize {
+        self.num_cells
+    }
+
+    /// Computes the cell from the row and column.
+    pub fn cell(&self, i: usize, j: usize) -> Cell {
+        assert!(i < self.num_rows && j < self.num_cols);
+        i * self.num_cols + j
+    }
+
+    /// Computes the row and column from the cell ID.
+    pub fn ij(&self, cell: Cell) -> (usize, usize) {
+        assert!(self.contains(cell));
+        (cell / self.num_cols, cell % self.num_cols)
+    }
+
+    /// Returns true if the cell ID is valid for this grid.
+    pub fn contains(&self, cell: Cell) -> bool {
+        cell < self.num_cells
+    }
+
+    /// Returns true if the cell is an upward-pointing triangle, and false if it points
+    /// down.
+    pub fn is_upward(&self, cell: Cell) -> bool {
+        let (i, j) = self.ij(cell);
+        (i + j) % 2 == 0
+    }
+
+    /// Links cell 1 to cell 2.  Links are bidirectional.
+    pub fn link(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].insert(cell2);
+        self.links[cell2].insert(cell1);
+    }
+
+    /// Unlinks cell 1 from cell 2.
+    pub fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].remove(&cell2);
+        self.links[cell2].remove(&cell1);
+    }
+
+    /// Gets the cells to which the cell is linked.
+    pub fn links(&self, cell: Cell) -> Vec<Cell> {
+        assert!(self.contains(cell));
+        self.links[cell].iter().cloned().collect()
+    }
+
+    /// Indicates whether or not the cells are linked.
+    pub fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        self.links[cell1].contains(&cell2)
+    }
+
+    /// Indicates whether the cell is linked to the cell in the given direction.
+    /// Returns false if there is no cell in that direction.
+    pub fn is_linked_to(&self, cell: Cell, dir: TriDirection) -> bool {
+        match self.cell_to(cell, dir) {
+            Some(other) => self.is_linked(cell, other),
+            None => false,
+        }
+    }
+
+    /// Returns the grid to its initial state:  no cell is linked to any other cell.
+    pub fn clear(&mut self) {
+        for links in &mut self.links {
+            links.clear();
+        }
+    }
+
+    /// Gets the cell in the given direction, if any.  An upward-pointing triangle has no
+    /// cell to the north, and a downward-pointing triangle has none to the south.
+    pub fn cell_to(&self, cell: Cell, dir: TriDirection) -> Option<Cell> {
+        let (i, j) = self.ij(cell);
+        let upward = self.is_upward(cell);
+
+        match dir {
+            TriDirection::North if !upward && i > 0 => Some(self.cell(i - 1, j)),
+            TriDirection::East if j + 1 < self.num_cols => Some(self.cell(i, j + 1)),
+            TriDirection::South if upward && i + 1 < self.num_rows => Some(self.cell(i + 1, j)),
+            TriDirection::West if j > 0 => Some(self.cell(i, j - 1)),
+            _ => None,
+        }
+    }
+
+    /// Gets the cell's neighbors, clockwise from north.
+    pub fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        TriDirection::ALL
+            .iter()
+            .filter_map(|dir| self.cell_to(cell, *dir))
+            .collect()
+    }
+
+    /// Computes the shortest distance from the cell to each other cell.
+    /// Returns the distances as a vector of length `num_cells`; cells that can't be
+    /// reached have no distance.
+    pub fn distances(&self, cell: Cell) -> Vec<Option<usize>> {
+        MazeGrid::distances(self, cell)
+    }
+
+    /// Gets the number of links in the grid, counting each link between two cells once.
+    pub fn num_links(&self) -> usize {
+        self.links.iter().map(|l| l.len()).sum::<usize>() / 2
+    }
+
+    /// Returns true if the grid is a perfect maze: one in which there is exactly one
+    /// path between any two cells.
+    pub fn is_perfect_maze(&self) -> bool {
+        self.distances(0).iter().all(|d| d.is_some()) && self.num_links() + 1 == self.num_cells
+    }
+}
+
+impl MazeGrid for TriGrid {
+    fn num_cells(&self) -> usize {
+        TriGrid::num_cells(self)
+    }
+
+    fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        TriGrid::neighbors(self, cell)
+    }
+
+    fn link(&mut self, cell1: Cell, cell2: Cell) {
+        TriGrid::link(self, cell1, cell2)
+    }
+
+    fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        TriGrid::unlink(self, cell1, cell2)
+    }
+
+    fn links(&self, cell: Cell) -> Vec<Cell> {
+        TriGrid::links(self, cell)
+    }
+
+    fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        TriGrid::is_linked(self, cell1, cell2)
+    }
+
+    fn clear(&mut self) {
+        TriGrid::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_tri_grid_neighbors() {
+        let grid = TriGrid::new(3, 4);
+
+        // An up-triangle and the down-triangle to its east are each other's neighbors.
+        let up = grid.cell(1, 1);
+        let down = grid.cell(1, 2);
+        assert!(grid.is_upward(up));
+        assert!(!grid.is_upward(down));
+        assert!(grid.neighbors(up).contains(&down));
+        assert!(grid.neighbors(down).contains(&up));
+
+        // Interior cells have three neighbors.
+        assert_eq!(
+            grid.neighbors(up),
+            vec![grid.cell(1, 2), grid.cell(2, 1), grid.cell(1, 0)]
+        );
+        assert_eq!(
+            grid.neighbors(down),
+            vec![grid.cell(0, 2), grid.cell(1, 3), grid.cell(1, 1)]
+        );
+
+        // A corner cell
+        assert_eq!(grid.neighbors(0), vec![grid.cell(0, 1), grid.cell(1, 0)]);
+    }
+
+    #[test]
+    fn test_tri_grid_symmetry() {
+        let grid = TriGrid::new(5, 6);
+
+        let opposite = |dir: TriDirection| TriDirection::ALL[(dir as usize + 2) % 4];
+
+        for c in 0..grid.num_cells() {
+            assert!(grid.neighbors(c).len() <= 3);
+
+            for dir in &TriDirection::ALL {
+                if let Some(n) = grid.cell_to(c, *dir) {
+                    assert_eq!(grid.cell_to(n, opposite(*dir)), Some(c));
+                    assert!(grid.neighbors(n).contains(&c));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tri_grid_maze() {
+        let mut grid = TriGrid::new(6, 11);
+        crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(23));
+        assert!(grid.is_perfect_maze());
+
+        let mut grid = TriGrid::new(6, 11);
+        crate::hunt_and_kill_rng(&mut grid, &mut StdRng::seed_from_u64(23));
+        assert!(grid.is_perfect_maze());
+
+        for c in 0..grid.num_cells() {
+            for l in grid.links(c) {
+                assert!(grid.neighbors(c).contains(&l));
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::image_grid_renderer::draw_segment;
+use crate::tri_grid::TriDirection;
+use crate::tri_grid::TriGrid;
+use image::ImageBuffer;
+use image::Rgba;
+use image::RgbaImage;
+
+/// A struct for rendering a TriGrid as an Image.  Uses the builder pattern.
+///
+/// The cells are drawn as equilateral triangles whose sides are `cell_size` pixels long,
+/// so that each row is `sqrt(3) / 2 * cell_size` pixels high and neighboring cells in a
+/// row overlap by half a side.  The image is `border_width + cell_size * (cols + 1) / 2`
+/// pixels wide and `border_width + sqrt(3) / 2 * cell_size * rows` pixels high, rounded
+/// up.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TriGridRenderer {
+    /// The length of each side of a cell, in pixels.
+    cell_size: usize,
+
+    /// The border width, in pixels.
+    border_width: usize,
+}
+
+impl TriGridRenderer {
+    /// Creates a new renderer for the TriGrid with default settings
+    pub fn new() -> Self {
+        Self {
+            cell_size: 20,
+            border_width: 1,
+        }
+    }
+
+    /// Adds the desired length of each side of a cell.
+    pub fn cell_size(&mut self, cell_size: usize) -> &mut Self {
+        assert!(cell_size > 0);
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Adds the desired border width.
+    pub fn border_width(&mut self, border_width: usize) -> &mut Self {
+        assert!(border_width > 0);
+        self.border_width = border_width;
+        self
+    }
+
+    /// Render the grid using the current parameters.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = TriGrid::new(4, 5);
+    /// recursive_backtracker_rng(&mut grid, &mut rand::thread_rng());
+    ///
+    /// let image = TriGridRenderer::new().cell_size(10).border_width(2).render(&grid);
+    /// assert_eq!(image.dimensions(), (32, 37));
+    /// ```
+    pub fn render(&self, grid: &TriGrid) -> RgbaImage {
+        // FIRST, size and create the image, and clear it to white.  The half border on
+        // each side keeps the outer walls from being clipped.
+        let s = self.cell_size as f64;
+        let h = s * 3.0f64.sqrt() / 2.0;
+        let margin = self.border_width as f64 / 2.0;
+        let nr = grid.num_rows() as f64;
+        let nc = grid.num_cols() as f64;
+
+        let width = (2.0 * margin + s * (nc + 1.0) / 2.0).ceil() as u32;
+        let height = (2.0 * margin + h * nr).ceil() as u32;
+
+        let black = Rgba([0, 0, 0, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+        let mut image: RgbaImage = ImageBuffer::from_pixel(width, height, white);
+
+        // NEXT, draw the walls.  Each cell draws the walls between it and the neighbors
+        // with higher cell IDs, and its walls on the edge of the grid.
+        for cell in 0..grid.num_cells() {
+            let (i, j) = grid.ij(cell);
+            let left = margin + s / 2.0 * j as f64;
+            let top = margin + h * i as f64;
+
+            // The corners: the apex and the two ends of the base.  The base is on the
+            // south side of an up-triangle and the north side of a down-triangle.
+            let (apex, base_w, base_e, base) = if grid.is_upward(cell) {
+                let apex = (left + s / 2.0, top);
+                (
+                    apex,
+                    (left, top + h),
+                    (left + s, top + h),
+                    TriDirection::South,
+                )
+            } else {
+                let apex = (left + s / 2.0, top + h);
+                (apex, (left, top), (left + s, top), TriDirection::North)
+            };
+
+            let sides = [
+                (base, (base_w, base_e)),
+                (TriDirection::East, (apex, base_e)),
+                (TriDirection::West, (base_w, apex)),
+            ];
+
+            for (dir, (a, b)) in &sides {
+                let open = match grid.cell_to(cell, *dir) {
+                    Some(other) => other < cell || grid.is_linked(cell, other),
+                    None => false,
+                };
+
+                if !open {
+                    draw_segment(&mut image, *a, *b, margin, black);
+                }
+            }
+        }
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tri_render() {
+        let mut grid = TriGrid::new(4, 5);
+        crate::recursive_backtracker_rng(&mut grid, &mut rand::thread_rng());
+
+        let image = TriGridRenderer::new()
+            .cell_size(10)
+            .border_width(2)
+            .render(&grid);
+
+        // 2 + 10 * (5 + 1) / 2 by 2 + 10 * sqrt(3) / 2 * 4, rounded up
+        assert_eq!(image.dimensions(), (32, 37));
+
+        // The center of a cell is open, and the outer walls are closed: cell (0,1) points
+        // down, so its base is on the top edge.
+        let white = Rgba([255, 255, 255, 255]);
+        let black = Rgba([0, 0, 0, 255]);
+        assert_eq!(*image.get_pixel(6, 6), white);
+        assert_eq!(*image.get_pixel(11, 0), black);
+        assert_eq!(*image.get_pixel(11, 36), black);
+    }
+}
@@ -0,0 +1,50 @@
+//! A union-find forest, for tracking which pieces of a maze have been joined.
+
+/// A set of items, numbered from 0, partitioned into disjoint sets that can be joined.
+#[derive(Debug, Clone)]
+pub(crate) struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    /// Creates a forest of `len` items, each in a set by itself.
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    /// Returns the root of the item's set, flattening the path to it as it goes.
+    pub fn root(&mut self, mut item: usize) -> usize {
+        while self.parent[item] != item {
+            self.parent[item] = self.parent[self.parent[item]];
+            item = self.parent[item];
+        }
+        item
+    }
+
+    /// Joins the sets containing the two items.  Returns false if they were already in
+    /// the same set.
+    pub fn join(&mut self, item1: usize, item2: usize) -> bool {
+        let (root1, root2) = (self.root(item1), self.root(item2));
+        self.parent[root1] = root2;
+        root1 != root2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find() {
+        let mut sets = UnionFind::new(5);
+        assert!(sets.join(0, 1));
+        assert!(sets.join(3, 4));
+        assert!(!sets.join(1, 0));
+        assert!(sets.join(1, 4));
+        assert!(!sets.join(0, 3));
+        assert_eq!(sets.root(0), sets.root(4));
+        assert_ne!(sets.root(0), sets.root(2));
+    }
+}
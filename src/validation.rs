@@ -0,0 +1,303 @@
+//! Validation of a grid's links prior to rendering.
+
+use crate::grid::Grid;
+use crate::Cell;
+use std::fmt;
+
+/// The maximum number of link pairs the crossing check will examine.  The check compares
+/// each link that doesn't join neighboring cells against every other link, which is
+/// quadratic in the worst case; beyond this limit the crossing check is skipped.
+pub const MAX_CROSSING_CHECKS: usize = 1_000_000;
+
+/// A problem with a grid's links that prevents it from being rendered correctly.
+/// Coordinates are (i,j) row/column pairs.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RenderabilityIssue {
+    /// A cell is linked to itself.
+    SelfLink { cell: Cell, ij: (usize, usize) },
+
+    /// Two linked cells are not neighbors.
+    NotAdjacent {
+        cell1: Cell,
+        cell2: Cell,
+        ij1: (usize, usize),
+        ij2: (usize, usize),
+    },
+
+    /// The first cell is linked to the second, but not vice versa.  Since a cell's links
+    /// are stored as a set, a link can't be duplicated; but it can be recorded on only one
+    /// side.
+    OneSided {
+        cell1: Cell,
+        cell2: Cell,
+        ij1: (usize, usize),
+        ij2: (usize, usize),
+    },
+
+    /// The passages drawn for the two links cross each other.
+    Crossing {
+        link1: ((usize, usize), (usize, usize)),
+        link2: ((usize, usize), (usize, usize)),
+    },
+}
+
+impl fmt::Display for RenderabilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderabilityIssue::SelfLink { cell, ij } => {
+                write!(f, "cell {} at {:?} is linked to itself", cell, ij)
+            }
+            RenderabilityIssue::NotAdjacent {
+                cell1,
+                cell2,
+                ij1,
+                ij2,
+            } => write!(
+                f,
+                "cell {} at {:?} is linked to non-adjacent cell {} at {:?}",
+                cell1, ij1, cell2, ij2
+            ),
+            RenderabilityIssue::OneSided {
+                cell1,
+                cell2,
+                ij1,
+                ij2,
+            } => write!(
+                f,
+                "cell {} at {:?} is linked to cell {} at {:?}, but not vice versa",
+                cell1, ij1, cell2, ij2
+            ),
+            RenderabilityIssue::Crossing { link1, link2 } => write!(
+                f,
+                "passage {:?}-{:?} crosses passage {:?}-{:?}",
+                link1.0, link1.1, link2.0, link2.1
+            ),
+        }
+    }
+}
+
+/// Finds all of the renderability issues in the grid.
+pub(crate) fn renderability_issues(grid: &Grid) -> Vec<RenderabilityIssue> {
+    let mut issues = Vec::new();
+
+    // FIRST, check each link individually.  Each undirected link is examined once, from
+    // its lower-numbered cell, except for one-sided links, which are examined from the
+    // side that has them.
+    let mut links = Vec::new();
+    let mut odd_links = Vec::new();
+
+    for cell1 in 0..grid.num_cells() {
+        let mut cell_links = grid.links(cell1);
+        cell_links.sort();
+
+        for cell2 in cell_links {
+            let ij1 = grid.ij(cell1);
+            let ij2 = grid.ij(cell2);
+
+            if cell1 == cell2 {
+                issues.push(RenderabilityIssue::SelfLink { cell: cell1, ij: ij1 });
+                continue;
+            }
+
+            let two_sided = grid.is_linked(cell2, cell1);
+
+            if !two_sided {
+                issues.push(RenderabilityIssue::OneSided {
+                    cell1,
+                    cell2,
+                    ij1,
+                    ij2,
+                });
+            } else if cell2 < cell1 {
+                continue;
+            }
+
+            if grid.neighbors(cell1).contains(&cell2) {
+                links.push((ij1, ij2));
+            } else {
+                issues.push(RenderabilityIssue::NotAdjacent {
+                    cell1,
+                    cell2,
+                    ij1,
+                    ij2,
+                });
+                odd_links.push((ij1, ij2));
+            }
+        }
+    }
+
+    // NEXT, look for crossings.  Passages between neighbors can't cross each other, so
+    // only the links between non-neighbors need to be checked.
+    if odd_links.len() * (odd_links.len() + links.len()) <= MAX_CROSSING_CHECKS {
+        for (n, link1) in odd_links.iter().enumerate() {
+            for link2 in odd_links[n + 1..].iter().chain(links.iter()) {
+                if segments_cross(*link1, *link2) {
+                    issues.push(RenderabilityIssue::Crossing {
+                        link1: *link1,
+                        link2: *link2,
+                    });
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+/// Panics with a report of all issues if the grid can't be rendered.  Renderers call this
+/// in debug builds.
+pub(crate) fn debug_check_renderable(grid: &Grid) {
+    if cfg!(debug_assertions) {
+        if let Err(issues) = grid.validate_renderable() {
+            let report: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+            panic!("grid is not renderable:\n{}", report.join("\n"));
+        }
+    }
+}
+
+type Point = (usize, usize);
+
+/// Determines whether two passages, drawn as line segments between cell centers, cross.
+/// Segments that merely share an endpoint don't cross, unless they overlap.
+fn segments_cross(s1: (Point, Point), s2: (Point, Point)) -> bool {
+    let p = to_i64(s1.0);
+    let q = to_i64(s1.1);
+    let r = to_i64(s2.0);
+    let s = to_i64(s2.1);
+
+    let o1 = orientation(p, q, r);
+    let o2 = orientation(p, q, s);
+    let o3 = orientation(r, s, p);
+    let o4 = orientation(r, s, q);
+
+    // Segments that share an endpoint cross only if they are collinear and overlap.
+    if p == r || p == s || q == r || q == s {
+        if o1 != 0 || o2 != 0 {
+            return false;
+        }
+        let (shared, a, b) = if p == r {
+            (p, q, s)
+        } else if p == s {
+            (p, q, r)
+        } else if q == r {
+            (q, p, s)
+        } else {
+            (q, p, r)
+        };
+
+        // They overlap if the other ends are on the same side of the shared endpoint.
+        let da = (a.0 - shared.0, a.1 - shared.1);
+        let db = (b.0 - shared.0, b.1 - shared.1);
+        return da.0 * db.0 + da.1 * db.1 > 0;
+    }
+
+    if o1 != o2 && o3 != o4 && o1 * o2 <= 0 && o3 * o4 <= 0 {
+        return true;
+    }
+
+    // Collinear cases: an endpoint of one lies on the other.
+    (o1 == 0 && on_segment(p, r, q))
+        || (o2 == 0 && on_segment(p, s, q))
+        || (o3 == 0 && on_segment(r, p, s))
+        || (o4 == 0 && on_segment(r, q, s))
+}
+
+fn to_i64(p: Point) -> (i64, i64) {
+    (p.0 as i64, p.1 as i64)
+}
+
+/// Returns the sign of the cross product (q - p) x (r - p): 1, -1, or 0 if collinear.
+fn orientation(p: (i64, i64), q: (i64, i64), r: (i64, i64)) -> i64 {
+    let val = (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0);
+    val.signum()
+}
+
+/// Given collinear points, is q on the segment from p to r?
+fn on_segment(p: (i64, i64), q: (i64, i64), r: (i64, i64)) -> bool {
+    q.0 >= p.0.min(r.0) && q.0 <= p.0.max(r.0) && q.1 >= p.1.min(r.1) && q.1 <= p.1.max(r.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_clean() {
+        let mut grid = Grid::new(5, 6);
+        crate::recursive_backtracker(&mut grid);
+        assert_eq!(grid.validate_renderable(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_self_link() {
+        let mut grid = Grid::new(3, 3);
+        grid.link(4, 4);
+
+        assert_eq!(
+            grid.validate_renderable(),
+            Err(vec![RenderabilityIssue::SelfLink { cell: 4, ij: (1, 1) }])
+        );
+    }
+
+    #[test]
+    fn test_validate_not_adjacent() {
+        let mut grid = Grid::new(3, 3);
+        grid.link(0, 1);
+        grid.link(0, 8);
+
+        assert_eq!(
+            grid.validate_renderable(),
+            Err(vec![RenderabilityIssue::NotAdjacent {
+                cell1: 0,
+                cell2: 8,
+                ij1: (0, 0),
+                ij2: (2, 2),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_validate_crossing() {
+        // An X of two diagonal passages
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 3);
+        grid.link(1, 2);
+
+        let issues = grid.validate_renderable().unwrap_err();
+        assert_eq!(issues.len(), 3);
+        assert_eq!(
+            issues[2],
+            RenderabilityIssue::Crossing {
+                link1: ((0, 0), (1, 1)),
+                link2: ((0, 1), (1, 0)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_segments_cross() {
+        // Shared endpoint, at an angle
+        assert!(!segments_cross(((0, 0), (0, 1)), ((0, 0), (1, 0))));
+
+        // Shared endpoint, overlapping
+        assert!(segments_cross(((0, 0), (0, 2)), ((0, 0), (0, 1))));
+
+        // Shared endpoint, continuing in a line
+        assert!(!segments_cross(((0, 0), (0, 1)), ((0, 1), (0, 2))));
+
+        // Parallel
+        assert!(!segments_cross(((0, 0), (0, 1)), ((1, 0), (1, 1))));
+
+        // A long passage running through a cell center
+        assert!(segments_cross(((0, 1), (2, 1)), ((1, 1), (1, 2))));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_render_unrenderable() {
+        let mut grid = Grid::new(2, 2);
+        grid.link(0, 3);
+        let _ = grid.to_image();
+    }
+}
@@ -0,0 +1,484 @@
+//! This module defines WeaveGrid, a Grid whose passages can cross over and under each
+//! other, for building weave mazes.
+
+use crate::Cell;
+use crate::Grid;
+use crate::GridDirection;
+use crate::MazeGrid;
+use rand::Rng;
+
+/// A rectangular grid whose passages can cross over and under one another.
+///
+/// The visible level is an ordinary `Grid`.  A passage can tunnel under a cell that is a
+/// straight-through corridor perpendicular to the passage; e.g., a passage running east
+/// can tunnel under a cell that is linked to the north and south but not to the east or
+/// west.  The tunnel is represented as a hidden "under" cell beneath the corridor cell,
+/// linked to the cells on either side of it.
+///
+/// The grid's own cells have the same IDs as in the `Grid`; under cells are numbered
+/// from `num_rows * num_cols` in the order in which they were created.  For the purposes
+/// of `MazeGrid`, the cells on either side of a tunnelable corridor are neighbors, and
+/// linking them creates the under cell; thus the maze algorithms that work with any
+/// `MazeGrid` produce weave mazes.
+///
+/// ```
+/// use mazegen::*;
+///
+/// // Make a north-south corridor through the center of a 3x3 grid, and tunnel under it
+/// // from west to east.
+/// let mut grid = WeaveGrid::new(3, 3);
+/// grid.link(1, 4);
+/// grid.link(4, 7);
+/// grid.link(3, 5);
+///
+/// let under = grid.under_cell(4).unwrap();
+/// assert!(grid.is_under_link(3, under));
+/// assert_eq!(grid.shortest_path(3, 5), vec![3, under, 5]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WeaveGrid {
+    /// The visible level.
+    grid: Grid,
+
+    /// The under cells, indexed by cell ID less the number of grid cells.  Unlinked under
+    /// cells are None.
+    unders: Vec<Option<UnderCell>>,
+
+    /// The under cell beneath each grid cell, if any.
+    under_of: Vec<Option<Cell>>,
+}
+
+/// A hidden cell beneath a corridor cell.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct UnderCell {
+    /// The grid cell above this cell.
+    over: Cell,
+
+    /// Whether the tunnel runs east-west, rather than north-south.
+    horizontal: bool,
+}
+
+impl WeaveGrid {
+    /// Creates a new weave grid with the given number of rows and columns.  Initially no
+    /// cell is linked to any other cell, and there are no under cells.  Panics if either
+    /// dimension is zero.
+    pub fn new(num_rows: usize, num_cols: usize) -> Self {
+        let grid = Grid::new(num_rows, num_cols);
+        let under_of = vec![None; grid.num_cells()];
+
+        Self {
+            grid,
+            unders: Vec::new(),
+            under_of,
+        }
+    }
+
+    /// The visible level of the grid.  Its links don't include the tunnels.
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// The number of rows in the grid.
+    pub fn num_rows(&self) -> usize {
+        self.grid.num_rows()
+    }
+
+    /// The number of columns in the grid.
+    pub fn num_cols(&self) -> usize {
+        self.grid.num_cols()
+    }
+
+    /// The number of cells in the grid, including the under cells.
+    pub fn num_cells(&self) -> usize {
+        self.grid.num_cells() + self.unders.len()
+    }
+
+    /// Computes the cell ID of the grid cell at row i, column j.
+    pub fn cell(&self, i: usize, j: usize) -> Cell {
+        self.grid.cell(i, j)
+    }
+
+    /// Returns true if the cell ID is valid for this grid.
+    pub fn contains(&self, cell: Cell) -> bool {
+        cell < self.num_cells()
+    }
+
+    /// Returns true if the cell is an under cell.
+    pub fn is_under(&self, cell: Cell) -> bool {
+        assert!(self.contains(cell));
+        cell >= self.grid.num_cells()
+    }
+
+    /// Gets the under cell beneath the given grid cell, if any.
+    pub fn under_cell(&self, cell: Cell) -> Option<Cell> {
+        assert!(self.grid.contains(cell));
+        self.under_of[cell]
+    }
+
+    /// Gets the grid cell above the given under cell.
+    pub fn over_cell(&self, under: Cell) -> Cell {
+        self.under(under).over
+    }
+
+    /// Returns true if the under cell's tunnel runs east-west, and false if it runs
+    /// north-south.
+    pub fn is_horizontal(&self, under: Cell) -> bool {
+        self.under(under).horizontal
+    }
+
+    /// Returns true if the two cells are linked by a passage that runs under another cell,
+    /// i.e., if one of them is an under cell.
+    pub fn is_under_link(&self, cell1: Cell, cell2: Cell) -> bool {
+        (self.is_under(cell1) || self.is_under(cell2)) && self.is_linked(cell1, cell2)
+    }
+
+    /// Links cell 1 to cell 2.  Links are bidirectional.  If the cells are on either side
+    /// of a tunnelable corridor, a new under cell is created beneath the corridor and
+    /// linked to both of them.  Panics if the cells aren't neighbors, or if either is an
+    /// under cell.
+    pub fn link(&mut self, cell1: Cell, cell2: Cell) {
+        assert!(
+            !self.is_under(cell1) && !self.is_under(cell2),
+            "under cells are linked when they are created"
+        );
+
+        if self.grid.neighbors(cell1).contains(&cell2) {
+            self.grid.link(cell1, cell2);
+            return;
+        }
+
+        let (over, dir) = self
+            .tunnel_between(cell1, cell2)
+            .expect("cells to link must be neighbors");
+
+        let horizontal = dir == GridDirection::East || dir == GridDirection::West;
+        let under = self.num_cells();
+        self.unders.push(Some(UnderCell { over, horizontal }));
+        self.under_of[over] = Some(under);
+    }
+
+    /// Unlinks cell 1 from cell 2.  Unlinking either end of a tunnel removes the tunnel;
+    /// the under cell is left in place, but has no links.
+    pub fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        if !self.is_under(cell1) && !self.is_under(cell2) {
+            self.grid.unlink(cell1, cell2);
+            return;
+        }
+
+        let under = if self.is_under(cell1) { cell1 } else { cell2 };
+
+        if self.is_linked(cell1, cell2) {
+            let over = self.over_cell(under);
+            self.under_of[over] = None;
+            self.unders[under - self.grid.num_cells()] = None;
+        }
+    }
+
+    /// Gets the cells to which the cell is linked.
+    pub fn links(&self, cell: Cell) -> Vec<Cell> {
+        if self.is_under(cell) {
+            return match self.unders[cell - self.grid.num_cells()] {
+                Some(_) => self.tunnel_ends(cell).to_vec(),
+                None => Vec::new(),
+            };
+        }
+
+        let mut links = self.grid.links(cell);
+
+        for dir in &DIRECTIONS {
+            if let Some(under) = self.under_to(cell, *dir) {
+                links.push(under);
+            }
+        }
+
+        links
+    }
+
+    /// Indicates whether or not the cells are linked.
+    pub fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        assert!(self.contains(cell1));
+        assert!(self.contains(cell2));
+
+        if self.is_under(cell1) || self.is_under(cell2) {
+            self.links(cell1).contains(&cell2)
+        } else {
+            self.grid.is_linked(cell1, cell2)
+        }
+    }
+
+    /// Gets the cell's neighbors: the cells adjacent to it, plus the cells it could reach
+    /// by tunneling under an adjacent corridor.  An under cell's neighbors are the cells at
+    /// either end of its tunnel.
+    pub fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        if self.is_under(cell) {
+            return self.links(cell);
+        }
+
+        let mut neighbors = self.grid.neighbors(cell);
+
+        for dir in &DIRECTIONS {
+            if let Some((_, beyond)) = self.tunnel_to(cell, *dir) {
+                neighbors.push(beyond);
+            }
+        }
+
+        neighbors
+    }
+
+    /// Returns the grid to its initial state:  no cell is linked to any other cell, and
+    /// there are no under cells.
+    pub fn clear(&mut self) {
+        self.grid.clear();
+        self.unders.clear();
+
+        for under in &mut self.under_of {
+            *under = None;
+        }
+    }
+
+    /// Computes the shortest distance from the cell to each other cell, including the
+    /// under cells.  Returns the distances as a vector of length `num_cells`; cells that
+    /// can't be reached have no distance.
+    pub fn distances(&self, cell: Cell) -> Vec<Option<usize>> {
+        MazeGrid::distances(self, cell)
+    }
+
+    /// Computes the shortest path from the first cell to the second, returning the path
+    /// as a vector of cells.  A path through a tunnel includes the under cell.
+    pub fn shortest_path(&self, start: Cell, goal: Cell) -> Vec<Cell> {
+        MazeGrid::shortest_path(self, start, goal)
+    }
+
+    /// Gets the number of links in the grid, counting each link between two cells once.
+    pub fn num_links(&self) -> usize {
+        (0..self.num_cells())
+            .map(|c| self.links(c).len())
+            .sum::<usize>()
+            / 2
+    }
+
+    /// Gets the under cell record.
+    fn under(&self, under: Cell) -> UnderCell {
+        assert!(self.is_under(under));
+        self.unders[under - self.grid.num_cells()].expect("linked under cell")
+    }
+
+    /// Gets the cells at either end of the under cell's tunnel.
+    fn tunnel_ends(&self, under: Cell) -> [Cell; 2] {
+        let UnderCell { over, horizontal } = self.under(under);
+
+        let ends = if horizontal {
+            (self.grid.west_of(over), self.grid.east_of(over))
+        } else {
+            (self.grid.north_of(over), self.grid.south_of(over))
+        };
+
+        [ends.0.expect("tunnel end"), ends.1.expect("tunnel end")]
+    }
+
+    /// Gets the under cell that the cell's tunnel in the given direction passes through,
+    /// if there is one.
+    pub(crate) fn under_to(&self, cell: Cell, dir: GridDirection) -> Option<Cell> {
+        let over = self.grid.cell_to(cell, dir)?;
+        let under = self.under_of[over]?;
+
+        if self.is_horizontal(under) == is_horizontal(dir) {
+            Some(under)
+        } else {
+            None
+        }
+    }
+
+    /// If the cell could tunnel in the given direction, returns the corridor cell it would
+    /// tunnel under and the cell on the far side.
+    fn tunnel_to(&self, cell: Cell, dir: GridDirection) -> Option<(Cell, Cell)> {
+        let over = self.grid.cell_to(cell, dir)?;
+        let beyond = self.grid.cell_to(over, dir)?;
+
+        if self.under_of[over].is_some() {
+            return None;
+        }
+
+        // The corridor must run straight through, perpendicular to the tunnel.
+        let g = &self.grid;
+        let corridor = if is_horizontal(dir) {
+            g.is_linked_north(over)
+                && g.is_linked_south(over)
+                && !g.is_linked_east(over)
+                && !g.is_linked_west(over)
+        } else {
+            g.is_linked_east(over)
+                && g.is_linked_west(over)
+                && !g.is_linked_north(over)
+                && !g.is_linked_south(over)
+        };
+
+        if corridor {
+            Some((over, beyond))
+        } else {
+            None
+        }
+    }
+
+    /// If cell 2 can be reached from cell 1 by tunneling, returns the corridor cell and
+    /// the direction of the tunnel.
+    fn tunnel_between(&self, cell1: Cell, cell2: Cell) -> Option<(Cell, GridDirection)> {
+        DIRECTIONS
+            .iter()
+            .find_map(|dir| match self.tunnel_to(cell1, *dir) {
+                Some((over, beyond)) if beyond == cell2 => Some((over, *dir)),
+                _ => None,
+            })
+    }
+}
+
+/// The four directions, for iteration.
+const DIRECTIONS: [GridDirection; 4] = [
+    GridDirection::North,
+    GridDirection::South,
+    GridDirection::East,
+    GridDirection::West,
+];
+
+/// Returns true if the direction is east or west.
+fn is_horizontal(dir: GridDirection) -> bool {
+    dir == GridDirection::East || dir == GridDirection::West
+}
+
+impl MazeGrid for WeaveGrid {
+    fn num_cells(&self) -> usize {
+        WeaveGrid::num_cells(self)
+    }
+
+    fn neighbors(&self, cell: Cell) -> Vec<Cell> {
+        WeaveGrid::neighbors(self, cell)
+    }
+
+    fn link(&mut self, cell1: Cell, cell2: Cell) {
+        WeaveGrid::link(self, cell1, cell2)
+    }
+
+    fn unlink(&mut self, cell1: Cell, cell2: Cell) {
+        WeaveGrid::unlink(self, cell1, cell2)
+    }
+
+    fn links(&self, cell: Cell) -> Vec<Cell> {
+        WeaveGrid::links(self, cell)
+    }
+
+    fn is_linked(&self, cell1: Cell, cell2: Cell) -> bool {
+        WeaveGrid::is_linked(self, cell1, cell2)
+    }
+
+    /// Returns a random grid cell; under cells are never chosen.
+    fn random_cell_with(&self, rng: &mut impl Rng) -> Cell {
+        self.grid.random_cell_with(rng)
+    }
+
+    fn clear(&mut self) {
+        WeaveGrid::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // A 3x3 grid with a north-south corridor through the center, and a tunnel under it
+    // from west to east:
+    //
+    // +---+---+---+
+    // |   |   |   |
+    // +   +   +   +
+    // |   |   |   |
+    // +---+   +---+
+    fn crossing() -> WeaveGrid {
+        let mut grid = WeaveGrid::new(3, 3);
+        grid.link(0, 3);
+        grid.link(2, 5);
+        grid.link(1, 4);
+        grid.link(4, 7);
+        grid.link(3, 5);
+        grid
+    }
+
+    #[test]
+    fn test_weave_crossing() {
+        let grid = crossing();
+
+        assert_eq!(grid.num_cells(), 10);
+        assert!(grid.is_under(9));
+        assert_eq!(grid.under_cell(4), Some(9));
+        assert_eq!(grid.over_cell(9), 4);
+        assert!(grid.is_horizontal(9));
+
+        // The tunnel links the cells on either side of the corridor, under it.
+        assert_eq!(grid.links(3), vec![0, 9]);
+        let mut links = grid.links(9);
+        links.sort();
+        assert_eq!(links, vec![3, 5]);
+        assert!(grid.is_under_link(3, 9));
+        assert!(grid.is_under_link(9, 5));
+        assert!(!grid.is_under_link(1, 4));
+        assert!(!grid.is_linked(3, 4));
+        assert!(!grid.is_linked(3, 5));
+
+        // The corridor isn't connected to the tunnel.
+        let dists = grid.distances(3);
+        assert_eq!(dists[9], Some(1));
+        assert_eq!(dists[5], Some(2));
+        assert_eq!(dists[4], None);
+        assert_eq!(dists[1], None);
+        assert_eq!(grid.distances(1)[7], Some(2));
+
+        // A path routes through the tunnel.
+        assert_eq!(grid.shortest_path(0, 2), vec![0, 3, 9, 5, 2]);
+    }
+
+    #[test]
+    fn test_weave_tunnel_rules() {
+        let mut grid = WeaveGrid::new(3, 3);
+
+        // The center isn't a corridor, so there's no tunnel.
+        assert!(!grid.neighbors(3).contains(&5));
+
+        // A perpendicular corridor can be tunneled under, but a parallel one can't.
+        grid.link(1, 4);
+        grid.link(4, 7);
+        assert!(grid.neighbors(3).contains(&5));
+        assert!(!grid.neighbors(1).contains(&7));
+
+        // A corridor can only be tunneled under once.
+        grid.link(3, 5);
+        assert!(!grid.neighbors(3).contains(&5));
+
+        // Unlinking the tunnel removes it.
+        grid.unlink(3, 9);
+        assert_eq!(grid.under_cell(4), None);
+        assert!(grid.links(3).is_empty());
+        assert!(grid.links(9).is_empty());
+
+        grid.clear();
+        assert_eq!(grid.num_cells(), 9);
+    }
+
+    #[test]
+    fn test_weave_maze() {
+        let mut tunnels = 0;
+
+        for seed in 0..5 {
+            let mut grid = WeaveGrid::new(10, 10);
+            crate::recursive_backtracker_rng(&mut grid, &mut StdRng::seed_from_u64(seed));
+
+            // It's a perfect maze, counting the under cells.
+            assert!(grid.distances(0).iter().all(|d| d.is_some()));
+            assert_eq!(grid.num_links() + 1, grid.num_cells());
+
+            tunnels += grid.num_cells() - 100;
+        }
+
+        assert!(tunnels > 0);
+    }
+}
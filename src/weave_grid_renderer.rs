@@ -0,0 +1,222 @@
+use crate::weave_grid::WeaveGrid;
+use crate::GridDirection;
+use image::ImageBuffer;
+use image::Rgba;
+use image::RgbaImage;
+
+/// A struct for rendering a WeaveGrid as an Image.  Uses the builder pattern.
+///
+/// The passages are drawn inset from the cell boundaries, so that crossings are visible:
+/// each cell's floor is a square inset `inset` pixels from the cell's sides, with passages
+/// of the same width leading to the linked cells.  A passage that runs under a cell is
+/// drawn on either side of the passage above it, its walls stopping short of the upper
+/// passage's walls.  The image is `cols * cell_size + border_width` pixels wide and
+/// `rows * cell_size + border_width` pixels high.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct WeaveGridRenderer {
+    /// The width and height of each cell, in pixels.
+    cell_size: usize,
+
+    /// The distance from each cell's sides to its floor, in pixels.
+    inset: usize,
+
+    /// The border width, in pixels.
+    border_width: usize,
+}
+
+impl WeaveGridRenderer {
+    /// Creates a new renderer for the WeaveGrid with default settings
+    pub fn new() -> Self {
+        Self {
+            cell_size: 20,
+            inset: 5,
+            border_width: 1,
+        }
+    }
+
+    /// Adds the desired cell size.
+    pub fn cell_size(&mut self, cell_size: usize) -> &mut Self {
+        assert!(cell_size > 0);
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Adds the desired inset.  It must be less than half the cell size, so that the
+    /// floor of each cell is visible.
+    pub fn inset(&mut self, inset: usize) -> &mut Self {
+        assert!(inset > 0);
+        self.inset = inset;
+        self
+    }
+
+    /// Adds the desired border width.
+    pub fn border_width(&mut self, border_width: usize) -> &mut Self {
+        assert!(border_width > 0);
+        self.border_width = border_width;
+        self
+    }
+
+    /// Render the grid using the current parameters.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = WeaveGrid::new(5, 8);
+    /// recursive_backtracker_rng(&mut grid, &mut rand::thread_rng());
+    ///
+    /// let image = WeaveGridRenderer::new().cell_size(10).inset(3).render(&grid);
+    /// assert_eq!(image.dimensions(), (81, 51));
+    /// ```
+    pub fn render(&self, grid: &WeaveGrid) -> RgbaImage {
+        assert!(
+            2 * self.inset < self.cell_size,
+            "inset {} is too large for cell size {}",
+            self.inset,
+            self.cell_size
+        );
+
+        // FIRST, size and create the image, and clear it to white.
+        let cs = self.cell_size;
+        let width = (grid.num_cols() * cs + self.border_width) as u32;
+        let height = (grid.num_rows() * cs + self.border_width) as u32;
+
+        let white = Rgba([255, 255, 255, 255]);
+        let black = Rgba([0, 0, 0, 255]);
+        let mut image: RgbaImage = ImageBuffer::from_pixel(width, height, white);
+
+        // NEXT, draw each cell's walls.  (x1, y1) and (x2, y2) are the corners of the
+        // cell's floor; the floor's sides are extended to the cell's sides along each
+        // passage, including the passages that tunnel under a neighbor.
+        let over = grid.grid();
+        let gap = 2 * self.border_width + 1;
+
+        for cell in 0..over.num_cells() {
+            let (i, j) = over.ij(cell);
+            let (x, y) = (j * cs, i * cs);
+            let (x1, y1) = (x + self.inset, y + self.inset);
+            let (x2, y2) = (x + cs - self.inset, y + cs - self.inset);
+
+            if over.is_isolated(cell) {
+                continue;
+            }
+
+            let open = |dir| over.is_linked_to(cell, dir) || grid.under_to(cell, dir).is_some();
+
+            if open(GridDirection::North) {
+                self.vline(&mut image, x1, y, y1, black);
+                self.vline(&mut image, x2, y, y1, black);
+            } else {
+                self.hline(&mut image, x1, x2, y1, black);
+            }
+
+            if open(GridDirection::South) {
+                self.vline(&mut image, x1, y2, y + cs, black);
+                self.vline(&mut image, x2, y2, y + cs, black);
+            } else {
+                self.hline(&mut image, x1, x2, y2, black);
+            }
+
+            if open(GridDirection::West) {
+                self.hline(&mut image, x, x1, y1, black);
+                self.hline(&mut image, x, x1, y2, black);
+            } else {
+                self.vline(&mut image, x1, y1, y2, black);
+            }
+
+            if open(GridDirection::East) {
+                self.hline(&mut image, x2, x + cs, y1, black);
+                self.hline(&mut image, x2, x + cs, y2, black);
+            } else {
+                self.vline(&mut image, x2, y1, y2, black);
+            }
+
+            // FINALLY, draw the walls of the passage running under the cell, if any,
+            // leaving a gap on either side of the passage above.
+            if let Some(under) = grid.under_cell(cell) {
+                if grid.is_horizontal(under) {
+                    for &wy in &[y1, y2] {
+                        self.hline(&mut image, x, x1.saturating_sub(gap), wy, black);
+                        self.hline(&mut image, (x2 + gap).min(x + cs), x + cs, wy, black);
+                    }
+                } else {
+                    for &wx in &[x1, x2] {
+                        self.vline(&mut image, wx, y, y1.saturating_sub(gap), black);
+                        self.vline(&mut image, wx, (y2 + gap).min(y + cs), y + cs, black);
+                    }
+                }
+            }
+        }
+
+        image
+    }
+
+    /// Draws a horizontal wall from xa to xb at y.
+    fn hline(&self, image: &mut RgbaImage, xa: usize, xb: usize, y: usize, color: Rgba<u8>) {
+        let bw = self.border_width;
+        fill(image, (xa, y), (xb + bw, y + bw), color);
+    }
+
+    /// Draws a vertical wall from ya to yb at x.
+    fn vline(&self, image: &mut RgbaImage, x: usize, ya: usize, yb: usize, color: Rgba<u8>) {
+        let bw = self.border_width;
+        fill(image, (x, ya), (x + bw, yb + bw), color);
+    }
+}
+
+/// Fills the rectangle from (xa, ya) up to but not including (xb, yb), clipping it to the
+/// image.
+fn fill(
+    image: &mut RgbaImage,
+    (xa, ya): (usize, usize),
+    (xb, yb): (usize, usize),
+    color: Rgba<u8>,
+) {
+    let xb = xb.min(image.width() as usize);
+    let yb = yb.min(image.height() as usize);
+
+    for py in ya..yb {
+        for px in xa..xb {
+            image.put_pixel(px as u32, py as u32, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weave_render() {
+        // A 3x3 grid with a north-south corridor through the center, and a tunnel under it
+        // from west to east.
+        let mut grid = WeaveGrid::new(3, 3);
+        grid.link(1, 4);
+        grid.link(4, 7);
+        grid.link(3, 5);
+
+        let image = WeaveGridRenderer::new()
+            .cell_size(20)
+            .inset(5)
+            .border_width(1)
+            .render(&grid);
+        assert_eq!(image.dimensions(), (61, 61));
+
+        let white = Rgba([255, 255, 255, 255]);
+        let black = Rgba([0, 0, 0, 255]);
+
+        // The corridor's walls run unbroken through the center cell.
+        assert_eq!(*image.get_pixel(25, 30), black);
+        assert_eq!(*image.get_pixel(35, 30), black);
+        assert_eq!(*image.get_pixel(30, 30), white);
+
+        // The tunnel's walls are drawn on either side of the corridor, with a gap.
+        assert_eq!(*image.get_pixel(21, 25), black);
+        assert_eq!(*image.get_pixel(23, 25), white);
+        assert_eq!(*image.get_pixel(39, 35), black);
+        assert_eq!(*image.get_pixel(37, 35), white);
+
+        // The tunnel's ends are open into the cells on either side.
+        assert_eq!(*image.get_pixel(15, 30), white);
+        assert_eq!(*image.get_pixel(45, 30), white);
+    }
+}
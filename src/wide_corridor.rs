@@ -0,0 +1,229 @@
+//! Mazes whose corridors are two cells wide everywhere, e.g., for accessible garden
+//! mazes.  The maze is generated on a coarse grid at half the resolution, and each
+//! coarse cell becomes a 2x2 block of fine cells.
+
+use crate::Cell;
+use crate::CellPath;
+use crate::Grid;
+use rand::{thread_rng, Rng};
+
+/// The coarse maze behind a wide-corridor maze, as returned by `wide_corridor_maze`.
+/// Coarse cell (i,j) is the 2x2 block of fine cells whose upper-left cell is (2i,2j).
+#[derive(Debug, Clone)]
+pub struct WideCorridorMaze {
+    coarse: Grid,
+}
+
+impl WideCorridorMaze {
+    /// Gets the coarse grid.
+    pub fn coarse(&self) -> &Grid {
+        &self.coarse
+    }
+
+    /// Gets the coarse cell that contains the fine cell.
+    pub fn coarse_cell(&self, fine: Cell) -> Cell {
+        let (i, j) = self.fine_ij(fine);
+        self.coarse.cell(i / 2, j / 2)
+    }
+
+    /// Solves the maze on the coarse grid, and maps the solution back to a path through
+    /// the fine grid from the start cell to the goal cell.  The path follows the coarse
+    /// solution block by block, so it's short but not necessarily the shortest.  The
+    /// path is empty if there's no coarse path.
+    ///
+    /// ```
+    /// use mazegen::*;
+    ///
+    /// let mut grid = Grid::new(8, 10);
+    /// let maze = wide_corridor_maze(&mut grid).unwrap();
+    /// let path = maze.solve_coarse(0, grid.num_cells() - 1);
+    ///
+    /// assert_eq!(path.first(), Some(0));
+    /// assert_eq!(path.last(), Some(grid.num_cells() - 1));
+    /// assert!(CellPath::new(&grid, path.into_cells()).is_ok());
+    /// ```
+    pub fn solve_coarse(&self, start: Cell, goal: Cell) -> CellPath {
+        let rows = 2 * self.coarse.num_rows();
+        let cols = 2 * self.coarse.num_cols();
+        let blocks = self
+            .coarse
+            .shortest_path(self.coarse_cell(start), self.coarse_cell(goal));
+
+        if blocks.is_empty() {
+            return CellPath::adjacent(rows, cols, Vec::new()).expect("empty path");
+        }
+
+        // FIRST, cross from each block to the next, staying in the current row or
+        // column.
+        let mut path = vec![start];
+
+        for step in blocks.cells().windows(2) {
+            let (i, j) = self.fine_ij(*path.last().expect("non-empty path"));
+            let (bi, bj) = self.coarse.ij(step[0]);
+            let (ni, nj) = self.coarse.ij(step[1]);
+
+            // The last cell in this block, and the first in the next.
+            let (exit, entry) = if ni > bi {
+                ((2 * bi + 1, j), (2 * ni, j))
+            } else if ni < bi {
+                ((2 * bi, j), (2 * ni + 1, j))
+            } else if nj > bj {
+                ((i, 2 * bj + 1), (i, 2 * nj))
+            } else {
+                ((i, 2 * bj), (i, 2 * nj + 1))
+            };
+
+            self.walk_block(&mut path, exit);
+            path.push(entry.0 * cols + entry.1);
+        }
+
+        // FINALLY, walk to the goal within the last block.
+        self.walk_block(&mut path, self.fine_ij(goal));
+
+        CellPath::adjacent(rows, cols, path).expect("valid path")
+    }
+
+    /// Extends the path within the current block to the given fine cell, going around the
+    /// block's loop rather than diagonally.
+    fn walk_block(&self, path: &mut Vec<Cell>, (ti, tj): (usize, usize)) {
+        let cols = 2 * self.coarse.num_cols();
+        let (i, j) = self.fine_ij(*path.last().expect("non-empty path"));
+
+        if j != tj {
+            path.push(i * cols + tj);
+        }
+
+        if i != ti {
+            path.push(ti * cols + tj);
+        }
+    }
+
+    /// Computes the fine row and column from the fine cell ID.
+    fn fine_ij(&self, fine: Cell) -> (usize, usize) {
+        let cols = 2 * self.coarse.num_cols();
+        (fine / cols, fine % cols)
+    }
+}
+
+/// Wide corridor maze algorithm: generates a maze whose corridors are at least two cells
+/// wide everywhere.  A maze is carved with the Recursive Backtracker into a coarse grid
+/// of half the grid's dimensions; then each coarse cell's 2x2 block of cells is linked
+/// internally, and each coarse passage opens a two-cell-wide connection between blocks.
+/// Returns the coarse maze, which can be used to solve the fine one.  Any existing links
+/// are cleared first.  Returns an error, leaving the grid unchanged, if either of the
+/// grid's dimensions is odd.
+///
+/// ```
+/// use mazegen::*;
+///
+/// let mut grid = Grid::new(6, 8);
+/// let maze = wide_corridor_maze(&mut grid).unwrap();
+/// assert!(maze.coarse().is_perfect_maze());
+/// assert!(grid.is_connected());
+///
+/// // Every cell has at least two links: there are no one-cell-wide pinch points.
+/// assert!((0..grid.num_cells()).all(|c| grid.links(c).len() >= 2));
+///
+/// assert!(wide_corridor_maze(&mut Grid::new(5, 8)).is_err());
+/// ```
+pub fn wide_corridor_maze(grid: &mut Grid) -> Result<WideCorridorMaze, String> {
+    wide_corridor_maze_rng(grid, &mut thread_rng())
+}
+
+/// Wide corridor maze algorithm, using the given random number generator.  See
+/// `wide_corridor_maze`.
+pub fn wide_corridor_maze_rng(
+    grid: &mut Grid,
+    rng: &mut impl Rng,
+) -> Result<WideCorridorMaze, String> {
+    let (rows, cols) = (grid.num_rows(), grid.num_cols());
+
+    if rows % 2 == 1 || cols % 2 == 1 {
+        return Err(format!(
+            "wide corridor mazes require even dimensions, got {}x{}",
+            rows, cols
+        ));
+    }
+
+    // FIRST, carve the coarse maze.
+    let mut coarse = Grid::new(rows / 2, cols / 2);
+    crate::recursive_backtracker_rng(&mut coarse, rng);
+
+    // NEXT, link each block internally, and open each coarse passage to the east or
+    // south across the full width of the block.
+    grid.clear();
+
+    for block in 0..coarse.num_cells() {
+        let (bi, bj) = coarse.ij(block);
+        let (i, j) = (2 * bi, 2 * bj);
+
+        grid.link(grid.cell(i, j), grid.cell(i, j + 1));
+        grid.link(grid.cell(i + 1, j), grid.cell(i + 1, j + 1));
+        grid.link(grid.cell(i, j), grid.cell(i + 1, j));
+        grid.link(grid.cell(i, j + 1), grid.cell(i + 1, j + 1));
+
+        if coarse.is_linked_east(block) {
+            grid.link(grid.cell(i, j + 1), grid.cell(i, j + 2));
+            grid.link(grid.cell(i + 1, j + 1), grid.cell(i + 1, j + 2));
+        }
+
+        if coarse.is_linked_south(block) {
+            grid.link(grid.cell(i + 1, j), grid.cell(i + 2, j));
+            grid.link(grid.cell(i + 1, j + 1), grid.cell(i + 2, j + 1));
+        }
+    }
+
+    Ok(WideCorridorMaze { coarse })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_wide_corridor_maze() {
+        let mut grid = Grid::new(10, 14);
+        let maze = wide_corridor_maze_rng(&mut grid, &mut StdRng::seed_from_u64(8)).unwrap();
+
+        assert!(grid.is_connected());
+        assert!((0..grid.num_cells()).all(|c| grid.links(c).len() >= 2));
+        assert_eq!(maze.coarse().num_rows(), 5);
+        assert_eq!(maze.coarse().num_cols(), 7);
+        assert_eq!(maze.coarse_cell(grid.cell(3, 5)), maze.coarse().cell(1, 2));
+
+        // Each coarse link opens two fine links; each block has four of its own.
+        assert_eq!(
+            grid.num_links(),
+            4 * maze.coarse().num_cells() + 2 * maze.coarse().num_links()
+        );
+    }
+
+    #[test]
+    fn test_wide_corridor_maze_odd() {
+        let mut grid = Grid::new(6, 7);
+        grid.link(0, 1);
+
+        let err = wide_corridor_maze(&mut grid).unwrap_err();
+        assert!(err.contains("6x7"), "{}", err);
+        assert!(grid.is_linked(0, 1));
+    }
+
+    #[test]
+    fn test_wide_corridor_solve_coarse() {
+        let mut rng = StdRng::seed_from_u64(11);
+
+        for _ in 0..10 {
+            let mut grid = Grid::new(12, 16);
+            let maze = wide_corridor_maze_rng(&mut grid, &mut rng).unwrap();
+            let start = grid.random_cell_with(&mut rng);
+            let goal = grid.random_cell_with(&mut rng);
+
+            let path = maze.solve_coarse(start, goal);
+            assert_eq!(path.first(), Some(start));
+            assert_eq!(path.last(), Some(goal));
+            assert!(CellPath::new(&grid, path.into_cells()).is_ok());
+        }
+    }
+}